@@ -0,0 +1,21 @@
+//! Serves a database over the binary protocol in `core_engine::server`.
+//!
+//! Usage: `duck-server [--path <dir>] [serve --addr <addr> --http <addr>]`,
+//! by default on `127.0.0.1:4800` from `./tmp`, until Ctrl-C or SIGTERM. The
+//! JSON API is only served when `--http` is given. Any other command of
+//! `cli_interface` works too.
+
+use clap::Parser;
+use cli_interface::commands::{self, Cli, Command, DEFAULT_ADDR};
+use core_engine::log_file::COMPACTION_CHECK_INTERVAL;
+
+fn main() {
+  commands::main(
+    Cli::parse(),
+    Command::Serve {
+      addr: DEFAULT_ADDR.to_string(),
+      http: None,
+      compaction_interval: COMPACTION_CHECK_INTERVAL,
+    },
+  );
+}
@@ -0,0 +1,381 @@
+//! Command line parsing shared by `cli_interface` and `duck-server`.
+//!
+//! Every command opens the database in `--path`, does one thing and exits,
+//! so the database can be driven from shell scripts. Values and snapshots
+//! go to stdout and errors to stderr with a non-zero exit status.
+
+pub(crate) mod stats;
+
+use std::{
+  fs::{self, File},
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  path::{Path, PathBuf},
+  process,
+  sync::{mpsc, Arc},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Parser, Subcommand};
+use core_engine::{
+  log_file::{LogFile, COMPACTION_CHECK_INTERVAL},
+  options::Options,
+  server::{Client, HttpServer, Server},
+  watch::Event,
+};
+use ttlog::{
+  event::LogLevel, file_listener::FileListener, stdout_listener::StdoutListener, trace::Trace,
+  ttlog_macros::info,
+};
+
+use crate::{repl, tui};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:4800";
+
+#[derive(Debug, Parser)]
+#[command(version, about = "An embedded key-value store")]
+pub struct Cli {
+  /// Data directory of the database.
+  #[arg(long, global = true, default_value = "./tmp")]
+  pub path: PathBuf,
+
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+  /// Print the value of KEY.
+  Get { key: String },
+  /// Store VALUE under KEY, reading it from stdin if omitted.
+  Put { key: String, value: Option<String> },
+  /// Delete KEY.
+  Delete { key: String },
+  /// Print the keys starting with PREFIX and their values, tab-separated, in
+  /// key order.
+  Scan {
+    #[arg(default_value = "")]
+    prefix: String,
+    #[arg(long)]
+    limit: Option<usize>,
+  },
+  /// Compact the log, wait for it to finish and print the sizes before and
+  /// after.
+  Compact,
+  /// Print a summary of the store and a table of its segments.
+  Stats {
+    /// Print the raw statistics as JSON instead.
+    #[arg(long)]
+    json: bool,
+  },
+  /// Serve the database over the network, compacting it in the background,
+  /// until interrupted with Ctrl-C or SIGTERM.
+  Serve {
+    /// Address of the binary protocol listener.
+    #[arg(long, default_value = DEFAULT_ADDR)]
+    addr: String,
+    /// Also serve the JSON API on this address.
+    #[arg(long)]
+    http: Option<String>,
+    /// Seconds between checks whether the log needs compacting.
+    #[arg(long, default_value_t = COMPACTION_CHECK_INTERVAL)]
+    compaction_interval: u64,
+  },
+  /// Write every live key to a JSON lines snapshot, which any version of
+  /// the store can load.
+  Dump {
+    /// Snapshot file, stdout if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+  },
+  /// Rebuild the database in `--path`, which must be empty, from a snapshot
+  /// written by `dump`.
+  Restore {
+    /// Snapshot file, stdin if omitted.
+    #[arg(long = "in")]
+    input: Option<PathBuf>,
+  },
+  /// Print metrics in the Prometheus text format.
+  Metrics,
+  /// Check every segment and hint file; exits with 1 on problems.
+  Fsck,
+  /// Salvage what can be read from damaged segments.
+  Repair,
+  /// Open the interactive shell.
+  Shell,
+  /// Browse, edit and delete keys in a full-screen terminal UI.
+  Tui,
+  /// Print the changes a running server makes to keys starting with PREFIX
+  /// as they happen, until interrupted.
+  Watch {
+    #[arg(default_value = "")]
+    prefix: String,
+    /// Address of the server.
+    #[arg(long, default_value = DEFAULT_ADDR)]
+    addr: String,
+  },
+}
+
+/// Runs `cli`, or `default` when no command was given, and exits with 1 if
+/// it fails.
+pub fn main(cli: Cli, default: Command) {
+  match run(cli.path, cli.command.unwrap_or(default)) {
+    // The reader of our output went away, as `head` does.
+    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+    Err(e) => {
+      eprintln!("error: {e}");
+      process::exit(1);
+    }
+    Ok(()) => {}
+  }
+}
+
+fn run(path: PathBuf, command: Command) -> Result<(), io::Error> {
+  // The server holds the database, so `watch` doesn't open it.
+  if let Command::Watch { prefix, addr } = &command {
+    return watch(addr, prefix);
+  }
+
+  // Only the server logs to stdout, where it doesn't get in the way of
+  // values, snapshots or the shell.
+  fs::create_dir_all(&path)?;
+  let trace = Trace::init(2, 64, "duck", path.to_str());
+  trace.add_listener(Arc::new(FileListener::new(
+    &path.join("ttlog.log").to_string_lossy(),
+  )?));
+  if let Command::Serve { .. } = command {
+    trace.add_listener(Arc::new(StdoutListener::new()));
+    trace.set_level(LogLevel::INFO);
+  } else {
+    trace.set_level(LogLevel::TRACE);
+  }
+
+  let log = LogFile::with_options(Options {
+    dir: path,
+    ..Options::default()
+  })?;
+
+  // `fsck` and `repair` look at the files as they are, before `start`
+  // recovers anything.
+  match command {
+    Command::Fsck => return fsck(&log),
+    Command::Repair => return repair(&log),
+    _ => log.start()?,
+  }
+
+  let mut stdout = io::stdout().lock();
+  match command {
+    Command::Get { key } => {
+      if !log.contains_key(&key) {
+        return Err(not_found(&key));
+      }
+      writeln!(stdout, "{}", log.read(&key)?)?;
+    }
+    Command::Put { key, value } => {
+      let value = match value {
+        Some(value) => value,
+        None => {
+          let mut value = String::new();
+          io::stdin().read_to_string(&mut value)?;
+          value
+        }
+      };
+      log.append(&key, &value)?;
+    }
+    Command::Delete { key } => {
+      if !log.contains_key(&key) {
+        return Err(not_found(&key));
+      }
+      log.delete(&key)?;
+    }
+    Command::Scan { prefix, limit } => {
+      for entry in log.scan(&prefix).take(limit.unwrap_or(usize::MAX)) {
+        let (key, value) = entry?;
+        writeln!(stdout, "{key}\t{value}")?;
+      }
+    }
+    Command::Compact => stats::compact(&mut stdout, &log)?,
+    Command::Stats { json: false } => stats::print_stats(&mut stdout, &log.stats()?)?,
+    Command::Stats { json: true } => {
+      serde_json::to_writer_pretty(&mut stdout, &log.stats()?)?;
+      writeln!(stdout)?;
+    }
+    Command::Serve {
+      addr,
+      http,
+      compaction_interval,
+    } => {
+      drop(stdout);
+      serve(&log, &addr, http.as_deref(), compaction_interval)?;
+    }
+    Command::Dump { out } => {
+      let count = match out {
+        Some(out) => dump(&log, &out)?,
+        None => log.export_jsonl(&mut stdout)?,
+      };
+      eprintln!("Dumped {count} records");
+    }
+    Command::Restore { input } => {
+      // Restoring on top of existing keys would mix two databases.
+      if !log.is_empty() {
+        return Err(io::Error::new(
+          io::ErrorKind::AlreadyExists,
+          format!(
+            "The database already holds {} keys, restore into an empty --path",
+            log.len()
+          ),
+        ));
+      }
+      let reader: Box<dyn BufRead> = match input {
+        Some(input) => Box::new(BufReader::new(File::open(input)?)),
+        None => Box::new(io::stdin().lock()),
+      };
+      let count = log.import_jsonl(reader)?;
+      log.sync()?;
+      eprintln!("Restored {count} records");
+    }
+    Command::Metrics => write!(stdout, "{}", log.render_metrics()?)?,
+    Command::Shell => {
+      drop(stdout);
+      let _compaction =
+        log.start_background_compaction(Duration::from_secs(COMPACTION_CHECK_INTERVAL));
+      repl::run(&log)?;
+    }
+    Command::Tui => {
+      drop(stdout);
+      tui::run(&log)?;
+    }
+    Command::Fsck | Command::Repair | Command::Watch { .. } => {
+      unreachable!("run before the log is started")
+    }
+  }
+  Ok(())
+}
+
+/// Serves `log` until the process is asked to stop, then shuts down in the
+/// reverse order: the listeners finish their open connections, a running
+/// compaction completes and the log is synced.
+fn serve(
+  log: &LogFile,
+  addr: &str,
+  http: Option<&str>,
+  compaction_interval: u64,
+) -> Result<(), io::Error> {
+  let (stop, stopped) = mpsc::channel();
+  ctrlc::set_handler(move || {
+    let _ = stop.send(());
+  })
+  .map_err(io::Error::other)?;
+
+  let compaction = log.start_background_compaction(Duration::from_secs(compaction_interval));
+  let server = Server::start(log, addr)?;
+  let http = http.map(|http| HttpServer::start(log, http)).transpose()?;
+  let stats = log.stats()?;
+  info!(
+    "[SERVE] Ready.",
+    addr = server.local_addr().to_string(),
+    http = http
+      .as_ref()
+      .map_or_else(|| "off".to_string(), |http| http.local_addr().to_string()),
+    live_keys = stats.live_keys,
+    segments = stats.segment_count()
+  );
+
+  let _ = stopped.recv();
+  info!("[SERVE] Shutting down.");
+  drop(http);
+  drop(server);
+  compaction.stop();
+  log.sync()?;
+  info!("[SERVE] Stopped.");
+  Ok(())
+}
+
+/// Writes the snapshot to a temporary file next to `out` and renames it into
+/// place once complete, so `out` is never a partial snapshot.
+fn dump(log: &LogFile, out: &Path) -> Result<usize, io::Error> {
+  let mut partial = out.as_os_str().to_owned();
+  partial.push(".partial");
+  let partial = PathBuf::from(partial);
+
+  let dumped = File::create(&partial).and_then(|file| {
+    let mut writer = BufWriter::new(file);
+    let count = log.export_jsonl(&mut writer)?;
+    writer.into_inner()?.sync_all()?;
+    Ok(count)
+  });
+  match dumped {
+    Ok(count) => {
+      fs::rename(&partial, out)?;
+      Ok(count)
+    }
+    Err(e) => {
+      let _ = fs::remove_file(&partial);
+      Err(e)
+    }
+  }
+}
+
+/// Prints one line per change, stamped with the time it arrived in seconds
+/// since the epoch, like `redis-cli monitor`. Keys and values are quoted.
+fn watch(addr: &str, prefix: &str) -> Result<(), io::Error> {
+  let changes = Client::connect(addr)?.watch(prefix)?;
+  let mut stdout = io::stdout().lock();
+  for change in changes {
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+    write!(stdout, "{}.{:06} ", now.as_secs(), now.subsec_micros())?;
+    match change? {
+      Event::Put { key, value } => writeln!(stdout, "PUT {key:?} {value:?}")?,
+      Event::Delete { key } => writeln!(stdout, "DELETE {key:?}")?,
+      Event::Merge { key, operand } => writeln!(stdout, "MERGE {key:?} {operand:?}")?,
+    }
+    stdout.flush()?;
+  }
+  Ok(())
+}
+
+fn fsck(log: &LogFile) -> Result<(), io::Error> {
+  let report = log.verify()?;
+  for problem in &report.problems {
+    println!("{problem}");
+  }
+  let records = report
+    .segments
+    .iter()
+    .map(|segment| segment.records)
+    .sum::<u64>();
+  println!(
+    "Checked {} segments, {records} records: {} problems",
+    report.segments.len(),
+    report.problems.len()
+  );
+  if !report.is_ok() {
+    process::exit(1);
+  }
+  Ok(())
+}
+
+fn repair(log: &LogFile) -> Result<(), io::Error> {
+  let report = log.repair()?;
+  for range in &report.quarantined {
+    println!(
+      "Quarantined {} bytes of segment {} at offset {} to {}",
+      range.len,
+      range.file_id,
+      range.offset,
+      range.path.display()
+    );
+  }
+  println!(
+    "Rewrote {} segments keeping {} records, wrote {} hint files",
+    report.rewritten.len(),
+    report.salvaged,
+    report.hints
+  );
+  Ok(())
+}
+
+fn not_found(key: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::NotFound, format!("No such key: {key}"))
+}
@@ -0,0 +1,109 @@
+//! Human-readable output of the `stats` and `compact` commands.
+
+use std::{
+  io::{self, Write},
+  time::Instant,
+};
+
+use core_engine::{log_file::LogFile, stats::Stats};
+
+/// Writes `stats` as a summary followed by a table of the segments.
+pub(crate) fn print_stats(out: &mut impl Write, stats: &Stats) -> Result<(), io::Error> {
+  let cache_hit_rate = match stats.cache_hit_rate() {
+    Some(rate) => format!(
+      "{} ({} of {} lookups)",
+      percent(rate),
+      stats.cache_hits,
+      stats.cache_hits + stats.cache_misses
+    ),
+    None => "n/a".to_string(),
+  };
+  let last_compaction = stats.last_compaction.map_or_else(
+    || "never".to_string(),
+    |at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+  );
+
+  writeln!(out, "Live keys        {}", stats.live_keys)?;
+  writeln!(
+    out,
+    "Segments         {} ({})",
+    stats.segment_count(),
+    format_bytes(stats.total_bytes)
+  )?;
+  writeln!(
+    out,
+    "Dead bytes       {} ({})",
+    format_bytes(stats.dead_bytes),
+    percent(ratio(stats.dead_bytes, stats.total_bytes))
+  )?;
+  writeln!(out, "Reads            {}", stats.reads)?;
+  writeln!(out, "Writes           {}", stats.writes)?;
+  writeln!(out, "Cache hit rate   {cache_hit_rate}")?;
+  writeln!(out, "Last compaction  {last_compaction}")?;
+
+  writeln!(out)?;
+  writeln!(
+    out,
+    "{:>8}  {:>10}  {:>10}  {:>6}",
+    "Segment", "Size", "Dead", "Dead %"
+  )?;
+  for segment in &stats.segments {
+    writeln!(
+      out,
+      "{:>8}  {:>10}  {:>10}  {:>6}{}",
+      segment.file_id,
+      format_bytes(segment.size),
+      format_bytes(segment.dead_bytes),
+      percent(ratio(segment.dead_bytes, segment.size)),
+      if segment.active { "  active" } else { "" }
+    )?;
+  }
+  Ok(())
+}
+
+/// Compacts `log`, blocking until it is done, and reports how much smaller
+/// it got.
+pub(crate) fn compact(out: &mut impl Write, log: &LogFile) -> Result<(), io::Error> {
+  let before = log.stats()?;
+  let started = Instant::now();
+  log.compact()?;
+  let elapsed = started.elapsed();
+  let after = log.stats()?;
+
+  writeln!(
+    out,
+    "Compacted {} segments ({}) into {} ({}) in {elapsed:.2?}, reclaiming {}",
+    before.segment_count(),
+    format_bytes(before.total_bytes),
+    after.segment_count(),
+    format_bytes(after.total_bytes),
+    format_bytes(before.total_bytes.saturating_sub(after.total_bytes))
+  )
+}
+
+/// `bytes` in the largest binary unit that keeps it at least 1.
+fn format_bytes(bytes: u64) -> String {
+  const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+  let mut value = bytes as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit + 1 < UNITS.len() {
+    value /= 1024.0;
+    unit += 1;
+  }
+
+  match unit {
+    0 => format!("{bytes} B"),
+    _ => format!("{value:.1} {}", UNITS[unit]),
+  }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+  match whole {
+    0 => 0.0,
+    whole => part as f64 / whole as f64,
+  }
+}
+
+fn percent(ratio: f64) -> String {
+  format!("{:.1}%", ratio * 100.0)
+}
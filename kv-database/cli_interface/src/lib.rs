@@ -0,0 +1,8 @@
+//! The command line front ends of the engine: `cli_interface`, which opens
+//! an interactive shell by default, and `duck-server`, which serves the
+//! database by default. Both accept the same commands, including a terminal
+//! UI for browsing keys.
+
+pub mod commands;
+mod repl;
+mod tui;
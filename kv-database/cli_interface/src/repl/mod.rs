@@ -0,0 +1,129 @@
+//! The interactive shell `cli_interface` opens when run without a command.
+//!
+//! Lines are edited with rustyline, and the history is kept in
+//! `~/.duck_history` across sessions.
+
+use std::{env, io, path::PathBuf};
+
+use core_engine::log_file::LogFile;
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::commands::stats;
+
+const PROMPT: &str = "duck> ";
+const HISTORY_FILE: &str = ".duck_history";
+
+const HELP: &str = "\
+get <key>              print the value of <key>
+put <key> <value>      store <value>, which may contain spaces
+del <key>              delete <key>
+scan [prefix] [limit]  list the keys starting with <prefix> in order
+stats                  show keys, segments, dead bytes and cache hits
+compact                compact the log and show the space reclaimed
+help                   show this message
+exit                   leave the shell, as does Ctrl-D";
+
+/// Reads and runs commands against `log` until `exit` or end of input.
+pub fn run(log: &LogFile) -> Result<(), io::Error> {
+  let mut editor = DefaultEditor::new().map_err(io::Error::other)?;
+  let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+  if let Some(history) = &history {
+    // There is no history yet on first use.
+    let _ = editor.load_history(history);
+  }
+
+  println!("Type `help` for the list of commands.");
+  loop {
+    let line = match editor.readline(PROMPT) {
+      Ok(line) => line,
+      // Ctrl-C drops the line being typed, like in a shell.
+      Err(ReadlineError::Interrupted) => continue,
+      Err(ReadlineError::Eof) => break,
+      Err(e) => return Err(io::Error::other(e)),
+    };
+    let line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    let _ = editor.add_history_entry(line);
+    match execute(log, line) {
+      Ok(true) => {}
+      Ok(false) => break,
+      Err(e) => println!("error: {e}"),
+    }
+  }
+
+  if let Some(history) = &history {
+    editor.save_history(history).map_err(io::Error::other)?;
+  }
+  Ok(())
+}
+
+/// Runs one command line. Returns `false` once the shell should exit.
+fn execute(log: &LogFile, line: &str) -> Result<bool, io::Error> {
+  let (command, rest) = split_word(line);
+  match (command, rest) {
+    ("get", key) if !key.is_empty() => {
+      if log.contains_key(key) {
+        println!("{}", log.read(key)?);
+      } else {
+        println!("(not found)");
+      }
+    }
+    ("put", rest) => match split_word(rest) {
+      (key, value) if !key.is_empty() && !value.is_empty() => {
+        log.append(key, value)?;
+        println!("OK");
+      }
+      _ => return Err(usage("put <key> <value>")),
+    },
+    ("del", key) if !key.is_empty() => {
+      if log.contains_key(key) {
+        log.delete(key)?;
+        println!("OK");
+      } else {
+        println!("(not found)");
+      }
+    }
+    ("scan", rest) => {
+      let (prefix, limit) = split_word(rest);
+      let limit = match limit {
+        "" => usize::MAX,
+        limit => limit.parse().map_err(|_| usage("scan [prefix] [limit]"))?,
+      };
+      let mut count = 0;
+      for entry in log.scan(prefix).take(limit) {
+        let (key, value) = entry?;
+        println!("{key}\t{value}");
+        count += 1;
+      }
+      println!("({count} keys)");
+    }
+    ("stats", "") => stats::print_stats(&mut io::stdout(), &log.stats()?)?,
+    ("compact", "") => stats::compact(&mut io::stdout(), log)?,
+    ("help", "") => println!("{HELP}"),
+    ("exit" | "quit", "") => return Ok(false),
+    ("get" | "del", _) => return Err(usage(&format!("{command} <key>"))),
+    _ => {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unknown command `{line}`, try `help`"),
+      ))
+    }
+  }
+  Ok(true)
+}
+
+/// Splits off the first whitespace-separated word of `line`.
+fn split_word(line: &str) -> (&str, &str) {
+  let line = line.trim_start();
+  match line.split_once(char::is_whitespace) {
+    Some((word, rest)) => (word, rest.trim_start()),
+    None => (line, ""),
+  }
+}
+
+fn usage(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, format!("usage: {message}"))
+}
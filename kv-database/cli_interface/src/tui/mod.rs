@@ -0,0 +1,311 @@
+//! A full-screen key browser for `cli_interface tui`.
+//!
+//! The keys starting with the prefix typed after `/` are listed on the left
+//! and the selected key's value on the right. The list is loaded with
+//! [`LogFile::scan`] whenever the prefix changes and kept current with
+//! [`LogFile::watch`], so writes made while browsing show up right away.
+
+use std::{io, sync::mpsc::Receiver, time::Duration};
+
+use core_engine::{log_file::LogFile, watch::Event as Change};
+use ratatui::{
+  crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+  layout::{Constraint, Layout},
+  style::{Modifier, Style},
+  text::Line,
+  widgets::{Block, List, ListState, Paragraph, Wrap},
+  DefaultTerminal, Frame,
+};
+
+/// At most this many keys are listed; narrow the prefix to see the rest.
+const MAX_KEYS: usize = 10_000;
+/// How long to wait for a key press before looking for changes.
+const TICK: Duration = Duration::from_millis(100);
+
+const HELP: &str = "/ filter  e edit  d delete  r reload  q quit";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+  Browse,
+  /// Typing the prefix, which filters the list as it changes.
+  Filter,
+  /// Typing a new value for the selected key.
+  Edit(String),
+  ConfirmDelete,
+}
+
+struct App<'a> {
+  log: &'a LogFile,
+  changes: Receiver<Change>,
+  prefix: String,
+  /// Listed keys and their values in ascending key order.
+  entries: Vec<(String, String)>,
+  /// Whether keys past [`MAX_KEYS`] were left out.
+  truncated: bool,
+  list: ListState,
+  mode: Mode,
+  /// The outcome of the last action, shown in place of the help line until
+  /// the next key press.
+  status: Option<String>,
+}
+
+/// Runs the browser on `log` until the user quits.
+pub fn run(log: &LogFile) -> Result<(), io::Error> {
+  let mut app = App {
+    log,
+    changes: log.watch(""),
+    prefix: String::new(),
+    entries: Vec::new(),
+    truncated: false,
+    list: ListState::default(),
+    mode: Mode::Browse,
+    status: None,
+  };
+  app.reload()?;
+
+  let mut terminal = ratatui::init();
+  let result = app.run(&mut terminal);
+  ratatui::restore();
+  result
+}
+
+impl App<'_> {
+  fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), io::Error> {
+    loop {
+      terminal.draw(|frame| self.draw(frame))?;
+
+      if event::poll(TICK)? {
+        if let Event::Key(key) = event::read()? {
+          // Windows reports releases too.
+          if key.kind == KeyEventKind::Press && !self.handle_key(key)? {
+            return Ok(());
+          }
+        }
+      }
+      while let Ok(change) = self.changes.try_recv() {
+        self.apply(change)?;
+      }
+    }
+  }
+
+  /// Rescans the keys under the current prefix, keeping the selected key
+  /// selected if it is still there.
+  fn reload(&mut self) -> Result<(), io::Error> {
+    let selected = self.selected().map(|(key, _)| key.clone());
+    let mut entries = self
+      .log
+      .scan(&self.prefix)
+      .take(MAX_KEYS + 1)
+      .collect::<Result<Vec<_>, io::Error>>()?;
+    self.truncated = entries.len() > MAX_KEYS;
+    entries.truncate(MAX_KEYS);
+    self.entries = entries;
+
+    let index = match selected {
+      Some(key) => self.position(&key).unwrap_or_else(|index| index),
+      None => 0,
+    };
+    self.select(index);
+    Ok(())
+  }
+
+  /// Updates the list for a write, made here or elsewhere in the process.
+  fn apply(&mut self, change: Change) -> Result<(), io::Error> {
+    let key = change.key().to_string();
+    if !key.starts_with(&self.prefix) {
+      return Ok(());
+    }
+    // Past the last listed key when truncated, so not listed either.
+    if self.truncated && self.entries.last().is_some_and(|(last, _)| key > *last) {
+      return Ok(());
+    }
+
+    let value = match change {
+      Change::Put { value, .. } => Some(value),
+      Change::Delete { .. } => None,
+      // The event only has the operand, the folded value has to be read.
+      Change::Merge { .. } => match self.log.contains_key(&key) {
+        true => Some(self.log.read(&key)?),
+        false => None,
+      },
+    };
+    let selected = self.list.selected();
+    match (self.position(&key), value) {
+      (Ok(index), Some(value)) => self.entries[index].1 = value,
+      (Ok(index), None) => {
+        self.entries.remove(index);
+        if selected.is_some_and(|selected| selected > index) {
+          self.list.select_previous();
+        }
+      }
+      (Err(index), Some(value)) => {
+        self.entries.insert(index, (key, value));
+        if selected.is_some_and(|selected| selected >= index) {
+          self.list.select_next();
+        }
+        if self.entries.len() > MAX_KEYS {
+          self.entries.pop();
+          self.truncated = true;
+        }
+      }
+      (Err(_), None) => {}
+    }
+    self.select(self.list.selected().unwrap_or(0));
+    Ok(())
+  }
+
+  /// Handles a key press. Returns `false` once the browser should exit.
+  fn handle_key(&mut self, key: KeyEvent) -> Result<bool, io::Error> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+      return Ok(false);
+    }
+    self.status = None;
+
+    match &mut self.mode {
+      Mode::Browse => match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+        KeyCode::Char('/') => self.mode = Mode::Filter,
+        KeyCode::Char('e') | KeyCode::Enter => {
+          if let Some((_, value)) = self.selected() {
+            self.mode = Mode::Edit(value.clone());
+          }
+        }
+        KeyCode::Char('d') | KeyCode::Delete if self.selected().is_some() => {
+          self.mode = Mode::ConfirmDelete
+        }
+        KeyCode::Char('r') => {
+          self.reload()?;
+          self.status = Some(format!("Reloaded {} keys", self.entries.len()));
+        }
+        KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+        KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+        KeyCode::PageDown => self.move_by(20),
+        KeyCode::PageUp => self.move_by(-20),
+        KeyCode::Home => self.select(0),
+        KeyCode::End => self.select(self.entries.len().saturating_sub(1)),
+        _ => {}
+      },
+      Mode::Filter => match key.code {
+        KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+        KeyCode::Backspace => {
+          self.prefix.pop();
+          self.reload()?;
+        }
+        KeyCode::Char(c) => {
+          self.prefix.push(c);
+          self.reload()?;
+        }
+        _ => {}
+      },
+      Mode::Edit(value) => match key.code {
+        KeyCode::Esc => self.mode = Mode::Browse,
+        KeyCode::Enter => {
+          let value = std::mem::take(value);
+          self.mode = Mode::Browse;
+          self.save(value)?;
+        }
+        KeyCode::Backspace => {
+          value.pop();
+        }
+        KeyCode::Char(c) => value.push(c),
+        _ => {}
+      },
+      Mode::ConfirmDelete => {
+        self.mode = Mode::Browse;
+        if key.code == KeyCode::Char('y') {
+          if let Some((key, _)) = self.selected() {
+            let key = key.clone();
+            self.log.delete(&key)?;
+            self.status = Some(format!("Deleted {key}"));
+          }
+        }
+      }
+    }
+    Ok(true)
+  }
+
+  /// Stores `value` under the selected key. The list picks it up from the
+  /// watch like any other write.
+  fn save(&mut self, value: String) -> Result<(), io::Error> {
+    let key = match self.selected() {
+      Some((key, _)) => key.clone(),
+      None => return Ok(()),
+    };
+    match self.log.append(&key, &value) {
+      Ok(_) => self.status = Some(format!("Saved {key}")),
+      // An empty or oversized value; the log is untouched.
+      Err(e) if e.kind() == io::ErrorKind::InvalidInput => self.status = Some(e.to_string()),
+      Err(e) => return Err(e),
+    }
+    Ok(())
+  }
+
+  fn draw(&mut self, frame: &mut Frame) {
+    let [filter, body, footer] = Layout::vertical([
+      Constraint::Length(1),
+      Constraint::Min(0),
+      Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [keys, value] =
+      Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+
+    let cursor = if self.mode == Mode::Filter { "_" } else { "" };
+    frame.render_widget(
+      Line::from(format!("Prefix: {}{cursor}", self.prefix)),
+      filter,
+    );
+
+    let title = match self.truncated {
+      true => format!(" Keys (first {MAX_KEYS}) "),
+      false => format!(" Keys ({}) ", self.entries.len()),
+    };
+    let list = List::new(self.entries.iter().map(|(key, _)| key.as_str()))
+      .block(Block::bordered().title(title))
+      .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, keys, &mut self.list);
+
+    let (title, text) = match (&self.mode, self.selected()) {
+      (Mode::Edit(value), Some((key, _))) => (format!(" Editing {key} "), format!("{value}_")),
+      (_, Some((key, value))) => (format!(" {key} "), value.clone()),
+      (_, None) => (" Value ".to_string(), String::new()),
+    };
+    frame.render_widget(
+      Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(title)),
+      value,
+    );
+
+    let footer_text = match &self.mode {
+      Mode::Filter => "Type a prefix, Enter to browse".to_string(),
+      Mode::Edit(_) => "Enter to save, Esc to cancel".to_string(),
+      Mode::ConfirmDelete => "Delete this key? y/n".to_string(),
+      Mode::Browse => self.status.clone().unwrap_or_else(|| HELP.to_string()),
+    };
+    frame.render_widget(Line::from(footer_text), footer);
+  }
+
+  fn selected(&self) -> Option<&(String, String)> {
+    self.entries.get(self.list.selected()?)
+  }
+
+  fn position(&self, key: &str) -> Result<usize, usize> {
+    self
+      .entries
+      .binary_search_by(|(entry, _)| entry.as_str().cmp(key))
+  }
+
+  fn move_by(&mut self, offset: isize) {
+    let index = self.list.selected().unwrap_or(0);
+    self.select(index.saturating_add_signed(offset));
+  }
+
+  /// Selects `index`, clamped to the list, or nothing when it's empty.
+  fn select(&mut self, index: usize) {
+    match self.entries.len() {
+      0 => self.list.select(None),
+      len => self.list.select(Some(index.min(len - 1))),
+    }
+  }
+}
@@ -0,0 +1,129 @@
+//! A [`LogFile`] for async code, behind the `async` feature.
+//!
+//! Every operation that can block — reads that miss the page cache, the
+//! fsync behind each durable write, compaction — runs on tokio's blocking
+//! pool, so awaiting it never stalls the reactor. The futures need a tokio
+//! runtime to run on.
+
+use std::{io, path::PathBuf, time::Duration};
+
+use tokio::task;
+
+use crate::{log_file::LogFile, options::Options, stats::Stats, write_batch::WriteBatch};
+
+/// Async handle to a [`LogFile`]. Clones share the same log.
+#[derive(Debug, Clone)]
+pub struct AsyncLogFile {
+  log: LogFile,
+}
+
+impl AsyncLogFile {
+  /// Opens and starts the log in `options.dir`.
+  pub async fn open(options: Options) -> Result<Self, io::Error> {
+    let log = task::spawn_blocking(move || {
+      let log = LogFile::with_options(options)?;
+      log.start()?;
+      Ok::<_, io::Error>(log)
+    })
+    .await
+    .map_err(io::Error::other)??;
+    Ok(Self { log })
+  }
+
+  /// The blocking log underneath, for the operations without an async
+  /// counterpart.
+  pub fn log(&self) -> &LogFile {
+    &self.log
+  }
+
+  pub async fn read(&self, key: impl Into<String>) -> Result<String, io::Error> {
+    let key = key.into();
+    self.run(move |log| log.read(&key)).await
+  }
+
+  pub async fn multi_get(&self, keys: Vec<String>) -> Result<Vec<Option<Vec<u8>>>, io::Error> {
+    self.run(move |log| log.multi_get(&keys)).await
+  }
+
+  /// Resolves once the write is durable.
+  pub async fn append(
+    &self,
+    key: impl Into<String>,
+    value: impl Into<String>,
+  ) -> Result<(), io::Error> {
+    let (key, value) = (key.into(), value.into());
+    self
+      .run(move |log| log.append(&key, &value).map(|_| ()))
+      .await
+  }
+
+  pub async fn put_with_ttl(
+    &self,
+    key: impl Into<String>,
+    value: impl Into<String>,
+    ttl: Duration,
+  ) -> Result<(), io::Error> {
+    let (key, value) = (key.into(), value.into());
+    self
+      .run(move |log| log.put_with_ttl(&key, &value, ttl).map(|_| ()))
+      .await
+  }
+
+  /// Deletes `key` and returns the value it had.
+  pub async fn delete(&self, key: impl Into<String>) -> Result<String, io::Error> {
+    let key = key.into();
+    self.run(move |log| log.delete(&key)).await
+  }
+
+  pub async fn merge(
+    &self,
+    key: impl Into<String>,
+    operand: impl Into<String>,
+  ) -> Result<u64, io::Error> {
+    let (key, operand) = (key.into(), operand.into());
+    self.run(move |log| log.merge(&key, &operand)).await
+  }
+
+  /// Commits `batch` atomically, see [`LogFile::write`].
+  pub async fn write(&self, batch: WriteBatch) -> Result<u64, io::Error> {
+    self.run(move |log| log.write(&batch)).await
+  }
+
+  pub async fn flush(&self) -> Result<(), io::Error> {
+    self.run(LogFile::flush).await
+  }
+
+  pub async fn sync(&self) -> Result<(), io::Error> {
+    self.run(LogFile::sync).await
+  }
+
+  pub async fn compact(&self) -> Result<(), io::Error> {
+    self.run(LogFile::compact).await
+  }
+
+  pub async fn stats(&self) -> Result<Stats, io::Error> {
+    self.run(LogFile::stats).await
+  }
+
+  pub async fn checkpoint(&self, dest_dir: impl Into<PathBuf>) -> Result<(), io::Error> {
+    let dest_dir = dest_dir.into();
+    self.run(move |log| log.checkpoint(&dest_dir)).await
+  }
+
+  /// Runs `f` on the blocking pool with a handle to the log.
+  async fn run<T: Send + 'static>(
+    &self,
+    f: impl FnOnce(&LogFile) -> Result<T, io::Error> + Send + 'static,
+  ) -> Result<T, io::Error> {
+    let log = self.log.clone();
+    task::spawn_blocking(move || f(&log))
+      .await
+      .map_err(io::Error::other)?
+  }
+}
+
+impl From<LogFile> for AsyncLogFile {
+  fn from(log: LogFile) -> Self {
+    Self { log }
+  }
+}
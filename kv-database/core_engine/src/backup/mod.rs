@@ -0,0 +1,228 @@
+//! Full and incremental backups of a [`LogFile`](crate::log_file::LogFile).
+//!
+//! Sealed segments and their hint files never change once written, so a
+//! backup hardlinks them and only pays for a real copy when the destination
+//! is on another filesystem. The active segment is still being appended to
+//! and is always copied.
+//!
+//! Every backup directory carries a [`BackupManifest`]. Segment ids are never
+//! reused, so an incremental backup only has to store the segments the
+//! previous manifest did not know about, plus the one that was still active
+//! then. [`restore`] walks a chain of backups and picks the newest copy of
+//! every segment the last one needs.
+
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{self, Read, Write},
+  path::Path,
+};
+
+use crate::{column_family, file_names, logging::info, storage, value_log};
+
+/// File name of the manifest inside a backup directory.
+pub const MANIFEST: &str = "BACKUP";
+
+/// What a backup directory holds, stored as text: `segments <id>...`,
+/// `active <id>` and `copied <id>...`, one per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupManifest {
+  /// Every segment the database consisted of when the backup was taken.
+  pub segments: Vec<u64>,
+  /// The segment that was still being appended to.
+  pub active: u64,
+  /// Segments whose files are stored in this backup. A full backup copies
+  /// all of `segments`.
+  pub copied: Vec<u64>,
+}
+
+impl BackupManifest {
+  /// Reads the manifest at `path`.
+  pub fn read(path: &Path) -> Result<Self, io::Error> {
+    let contents = fs::read_to_string(path)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Corrupted backup manifest");
+    let ids = |field: &str| {
+      field
+        .split_whitespace()
+        .map(|id| id.parse().map_err(|_| invalid()))
+        .collect::<Result<Vec<u64>, _>>()
+    };
+
+    let mut lines = contents.lines();
+    let mut field = |name: &str| {
+      lines
+        .next()
+        .and_then(|line| line.strip_prefix(name))
+        .ok_or_else(invalid)
+    };
+    let segments = ids(field("segments")?)?;
+    let active = field("active")?.trim().parse().map_err(|_| invalid())?;
+    let copied = ids(field("copied")?)?;
+
+    Ok(Self {
+      segments,
+      active,
+      copied,
+    })
+  }
+
+  /// Atomically replaces the manifest at `path`.
+  pub(crate) fn write(&self, path: &Path) -> Result<(), io::Error> {
+    let ids = |ids: &[u64]| ids.iter().map(u64::to_string).collect::<Vec<_>>().join(" ");
+    let contents = format!(
+      "segments {}\nactive {}\ncopied {}\n",
+      ids(&self.segments),
+      self.active,
+      ids(&self.copied)
+    );
+
+    let temp_path = path.with_extension("tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+  }
+
+  /// Whether a backup taken after this one has to store `file_id`: either
+  /// it is new, or it was still growing when this backup was taken.
+  pub(crate) fn needs_copy(&self, file_id: u64) -> bool {
+    file_id >= self.active || !self.segments.contains(&file_id)
+  }
+}
+
+/// Rebuilds a database in `dest_dir` from a full backup followed by the
+/// incremental backups taken on top of it, oldest first. `dest_dir` must not
+/// exist or be empty, and can be opened with a `LogFile` afterwards.
+///
+/// The result matches the last backup in `backups`. Column families are
+/// restored from the matching subdirectories of each backup.
+pub fn restore(backups: &[impl AsRef<Path>], dest_dir: impl AsRef<Path>) -> Result<(), io::Error> {
+  let backups = backups.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+  restore_dirs(&backups, dest_dir.as_ref())?;
+
+  info!(
+    "[BACKUP] Backup has been restored successfully.",
+    dest_dir = dest_dir.as_ref().display().to_string()
+  );
+  Ok(())
+}
+
+fn restore_dirs(backups: &[&Path], dest_dir: &Path) -> Result<(), io::Error> {
+  let Some(&last) = backups.last() else {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "Nothing to restore from",
+    ));
+  };
+  let manifests = backups
+    .iter()
+    .map(|dir| BackupManifest::read(&dir.join(MANIFEST)))
+    .collect::<Result<Vec<_>, _>>()?;
+  create_dest(dest_dir)?;
+
+  for &file_id in &manifests[manifests.len() - 1].segments {
+    let Some(source) = (0..backups.len())
+      .rev()
+      .find(|&i| manifests[i].copied.contains(&file_id))
+      .map(|i| backups[i])
+    else {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("No backup in the set holds segment {file_id}"),
+      ));
+    };
+
+    // Restored files are copied, never linked: the restored database may
+    // truncate or delete them, and that must not reach the backup.
+    // Backups taken by older versions use the old file names, which are
+    // restored under the current ones.
+    for (name, legacy) in [
+      (
+        file_names::segment(file_id),
+        file_names::legacy_segment(file_id),
+      ),
+      (file_names::hint(file_id), file_names::legacy_hint(file_id)),
+    ] {
+      for src in [source.join(&name), source.join(legacy)] {
+        if fs::exists(&src)? {
+          copy(&src, &dest_dir.join(&name))?;
+          break;
+        }
+      }
+    }
+  }
+  // Every backup holds all blob files of the value log.
+  for entry in fs::read_dir(last)? {
+    let file_name = entry?.file_name();
+    if value_log::parse_file_name(&file_name.to_string_lossy()).is_some() {
+      copy(&last.join(&file_name), &dest_dir.join(&file_name))?;
+    }
+  }
+  sync_dir(dest_dir)?;
+
+  for entry in fs::read_dir(last)? {
+    let entry = entry?;
+    let file_name = entry.file_name();
+    let Some(family) = file_name.to_str().and_then(column_family::name_from_dir) else {
+      continue;
+    };
+
+    // The family's chain starts at the full copy taken when it first
+    // showed up in a backup.
+    let family_dir = column_family::dir_name(family);
+    let chain = backups
+      .iter()
+      .map(|dir| dir.join(&family_dir))
+      .filter(|dir| dir.join(MANIFEST).exists())
+      .collect::<Vec<_>>();
+    let chain = chain.iter().map(|dir| dir.as_path()).collect::<Vec<_>>();
+    restore_dirs(&chain, &dest_dir.join(family_dir))?;
+  }
+  Ok(())
+}
+
+/// Creates `dir` for a backup or restore. It may already exist as long as
+/// it is empty, so nothing in it gets mixed up with the copied segments.
+pub(crate) fn create_dest(dir: &Path) -> Result<(), io::Error> {
+  fs::create_dir_all(dir)?;
+  if fs::read_dir(dir)?.next().is_some() {
+    return Err(io::Error::new(
+      io::ErrorKind::AlreadyExists,
+      format!("Directory {} is not empty", dir.display()),
+    ));
+  }
+  Ok(())
+}
+
+/// Hardlinks the immutable file `src` to `dest`, falling back to a copy.
+pub(crate) fn link_or_copy(src: &Path, dest: &Path) -> Result<(), io::Error> {
+  if fs::hard_link(src, dest).is_ok() {
+    return Ok(());
+  }
+  copy(src, dest)
+}
+
+/// Copies `src` to `dest` and fsyncs the copy.
+pub(crate) fn copy(src: &Path, dest: &Path) -> Result<(), io::Error> {
+  fs::copy(src, dest)?;
+  // Windows only syncs handles open for writing.
+  OpenOptions::new().write(true).open(dest)?.sync_all()
+}
+
+/// Copies the first `len` bytes of `src` to `dest` and fsyncs the copy.
+pub(crate) fn copy_prefix(src: &Path, dest: &Path, len: u64) -> Result<(), io::Error> {
+  let mut file = File::create_new(dest)?;
+  io::copy(&mut File::open(src)?.take(len), &mut file)?;
+  file.sync_all()
+}
+
+/// Writes `bytes` to the new file `dest` and fsyncs it.
+pub(crate) fn write(dest: &Path, bytes: &[u8]) -> Result<(), io::Error> {
+  let mut file = File::create_new(dest)?;
+  file.write_all(bytes)?;
+  file.sync_all()
+}
+
+/// Makes the entries created in `dir` durable.
+pub(crate) fn sync_dir(dir: &Path) -> Result<(), io::Error> {
+  storage::sync_dir(dir)
+}
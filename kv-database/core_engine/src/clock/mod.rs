@@ -0,0 +1,11 @@
+//! The monotonic clock behind latency metrics and the rate limiter.
+//!
+//! `std::time::Instant::now` panics on `wasm32-unknown-unknown`, which has no
+//! clock of its own, so there time comes from the JS host through `web-time`.
+//! Wall-clock timestamps go through chrono, whose `wasmbind` feature does the
+//! same on that target.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
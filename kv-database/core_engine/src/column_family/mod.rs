@@ -0,0 +1,69 @@
+//! Named keyspaces opened from a single [`LogFile`].
+//!
+//! Each [`ColumnFamily`] is a complete log stored in a `cf-<name>`
+//! subdirectory of its parent's data directory. Keys in different families
+//! never collide, and every family rotates, compacts and reports stats on its
+//! own, so callers no longer have to encode a namespace into each key.
+
+use std::{io, ops::Deref};
+
+use crate::log_file::LogFile;
+
+const DIR_PREFIX: &str = "cf-";
+
+/// Handle to one keyspace, returned by [`LogFile::column_family`].
+///
+/// It dereferences to the family's own [`LogFile`], so the whole read, write
+/// and maintenance API is available on it.
+#[derive(Debug, Clone)]
+pub struct ColumnFamily {
+  name: String,
+  log: LogFile,
+}
+
+impl ColumnFamily {
+  pub(crate) fn new(name: &str, log: LogFile) -> Self {
+    Self {
+      name: name.to_string(),
+      log,
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+}
+
+impl Deref for ColumnFamily {
+  type Target = LogFile;
+
+  fn deref(&self) -> &LogFile {
+    &self.log
+  }
+}
+
+/// Family names become directory names, so keep them to a safe alphabet.
+pub(crate) fn validate_name(name: &str) -> Result<(), io::Error> {
+  let valid = !name.is_empty()
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+  if !valid {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "Column family names may only contain letters, digits, '_' and '-'",
+    ));
+  }
+  Ok(())
+}
+
+pub(crate) fn dir_name(name: &str) -> String {
+  format!("{DIR_PREFIX}{name}")
+}
+
+pub(crate) fn name_from_dir(dir_name: &str) -> Option<&str> {
+  dir_name
+    .strip_prefix(DIR_PREFIX)
+    .filter(|name| validate_name(name).is_ok())
+}
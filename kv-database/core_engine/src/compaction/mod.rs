@@ -0,0 +1,186 @@
+//! Background maintenance owned by the engine.
+//!
+//! [`LogFile::start_background_compaction`] spawns a thread that wakes up on a
+//! fixed interval and compacts the log once enough of it is garbage (see
+//! [`LogFile::needs_compaction`]). The returned [`CompactionHandle`] stops it: either
+//! explicitly through [`CompactionHandle::stop`] or implicitly when dropped, so
+//! the thread never outlives the code that started it.
+//!
+//! A compaction itself is made crash safe by a [`Manifest`]: it names the
+//! output and the segments it replaces, and is written before the output is
+//! renamed into place. That rename is the commit point.
+
+use std::{
+  fs::{self, File},
+  io::{self, Write},
+  sync::mpsc::Sender,
+  thread::JoinHandle,
+};
+// Only the background thread needs these, and wasm32 has no threads.
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+  sync::mpsc::{self, RecvTimeoutError},
+  thread,
+  time::Duration,
+};
+
+use crate::logging::info;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+  error::DbError,
+  log_file::LogFile,
+  logging::{error, trace},
+};
+
+/// Owner of a background compaction thread.
+#[derive(Debug)]
+pub struct CompactionHandle {
+  stop: Option<Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl CompactionHandle {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) fn spawn(log: LogFile, interval: Duration) -> Self {
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    // Wakes up every `interval`; a message or a dropped sender means stop.
+    let thread = thread::spawn(move || {
+      while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let compacted = log
+          .needs_compaction()
+          .and_then(|due| if due { log.compact() } else { Ok(()) });
+        if let Err(e) = compacted {
+          // Open cursors pause compaction; try again next time.
+          if let Some(DbError::SnapshotsPinned { cursors }) = DbError::from_io(&e) {
+            trace!(
+              "[COMPACT] Background compaction skipped.",
+              cursors = *cursors as u64
+            );
+            continue;
+          }
+          error!(
+            "[COMPACT] Background compaction failed.",
+            error = e.to_string()
+          );
+        }
+      }
+    });
+
+    info!(
+      "[COMPACT] Background compaction started.",
+      interval_secs = interval.as_secs()
+    );
+    Self {
+      stop: Some(stop),
+      thread: Some(thread),
+    }
+  }
+
+  /// Signals the thread to exit and waits for it. A compaction that is
+  /// already running is allowed to finish first.
+  pub fn stop(mut self) {
+    self.shutdown();
+  }
+
+  /// Blocks until the thread exits without asking it to. Since only the
+  /// handle can stop it, this waits for the lifetime of the process.
+  pub fn join(mut self) {
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+
+  fn shutdown(&mut self) {
+    if let Some(stop) = self.stop.take() {
+      let _ = stop.send(());
+    }
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+      info!("[COMPACT] Background compaction stopped.");
+    }
+  }
+}
+
+impl Drop for CompactionHandle {
+  fn drop(&mut self) {
+    self.shutdown();
+  }
+}
+
+/// Record of a compaction in flight, written before its output is renamed
+/// into place so an interrupted run can be finished or undone on startup.
+///
+/// Stored as text: `output <id>`, `temp <file name>`, `inputs <id>...` and
+/// `blobs <id>...`, one per line.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Manifest {
+  /// Segment id the compacted output is renamed to.
+  pub(crate) output_id: u64,
+  /// File name the output is written under until then.
+  pub(crate) temp_name: String,
+  /// Segments the output replaces.
+  pub(crate) inputs: Vec<u64>,
+  /// Blob files of the value log the output no longer points into.
+  pub(crate) blobs: Vec<u64>,
+}
+
+impl Manifest {
+  /// Atomically replaces the manifest at `path`.
+  pub(crate) fn write(&self, path: &str) -> Result<(), io::Error> {
+    let ids = |ids: &[u64]| ids.iter().map(u64::to_string).collect::<Vec<_>>().join(" ");
+    let contents = format!(
+      "output {}\ntemp {}\ninputs {}\nblobs {}\n",
+      self.output_id,
+      self.temp_name,
+      ids(&self.inputs),
+      ids(&self.blobs)
+    );
+
+    let temp_path = format!("{path}.tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)
+  }
+
+  /// Reads the manifest at `path`, if there is one.
+  pub(crate) fn read(path: &str) -> Result<Option<Self>, io::Error> {
+    let contents = match fs::read_to_string(path) {
+      Ok(contents) => contents,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Corrupted compaction manifest");
+
+    let mut lines = contents.lines();
+    let mut field = |name: &str| {
+      lines
+        .next()
+        .and_then(|line| line.strip_prefix(name))
+        .map(str::trim)
+        .ok_or_else(invalid)
+    };
+    let output_id = field("output")?.parse().map_err(|_| invalid())?;
+    let temp_name = field("temp")?.to_string();
+    let ids = |line: &str| {
+      line
+        .split_whitespace()
+        .map(|id| id.parse().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()
+    };
+    let inputs = ids(field("inputs")?)?;
+    // Manifests written before the value log existed stop here.
+    let blobs = match field("blobs") {
+      Ok(line) => ids(line)?,
+      Err(_) => Vec::new(),
+    };
+
+    Ok(Some(Self {
+      output_id,
+      temp_name,
+      inputs,
+      blobs,
+    }))
+  }
+}
@@ -0,0 +1,37 @@
+//! User-supplied key orderings.
+//!
+//! Keys are listed in ascending byte order by default, which is wrong for
+//! many encodings: `item:10` sorts before `item:9`, and a timestamp suffix
+//! that should be newest first comes out oldest first. Setting
+//! `Options::comparator` changes the order used by every ordered read:
+//! [`LogFile::sorted_keys`](crate::log_file::LogFile::sorted_keys),
+//! [`scan`](crate::log_file::LogFile::scan), exports and
+//! [`Cursor`](crate::cursor::Cursor)s, including where
+//! [`seek`](crate::cursor::Cursor::seek) lands.
+//!
+//! The comparator must be a total order and stay the same for the lifetime
+//! of the data, or seeks land in the wrong place.
+
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+type CompareFn = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+/// `fn(a, b) -> Ordering`, deciding whether key `a` sorts before `b`.
+#[derive(Clone)]
+pub struct KeyComparator(Arc<CompareFn>);
+
+impl KeyComparator {
+  pub fn new(compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> Self {
+    Self(Arc::new(compare))
+  }
+
+  pub(crate) fn compare(&self, a: &str, b: &str) -> Ordering {
+    (self.0)(a, b)
+  }
+}
+
+impl fmt::Debug for KeyComparator {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("KeyComparator(..)")
+  }
+}
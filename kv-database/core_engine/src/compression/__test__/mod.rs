@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod compression_test {
+  use crate::compression::*;
+
+  fn round_trip(input: &[u8]) -> Vec<u8> {
+    let compressed = compress(input);
+    assert_eq!(decompress(&compressed).unwrap(), input);
+    compressed
+  }
+
+  /// Bytes without any repeated 4-byte prefix worth matching.
+  fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+      .map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+      })
+      .collect()
+  }
+
+  // ---------------------------------------------------------
+  // round trip tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn empty_input() {
+    let compressed = round_trip(b"");
+    assert_eq!(compressed, 0u32.to_le_bytes());
+  }
+
+  #[test]
+  fn inputs_shorter_than_a_match() {
+    for len in 1..MIN_MATCH * 2 {
+      round_trip(&b"abcabcab"[..len]);
+    }
+  }
+
+  #[test]
+  fn incompressible_input_grows_by_its_headers_only() {
+    let input = noise(10_000);
+    let compressed = round_trip(&input);
+    let runs = input.len().div_ceil(MAX_LITERALS);
+    assert!(compressed.len() <= 4 + input.len() + runs);
+  }
+
+  #[test]
+  fn repetitive_input_shrinks() {
+    let input = br#"{"id":1,"name":"duck","tags":["a","b"]},"#.repeat(1000);
+    let compressed = round_trip(&input);
+    assert!(compressed.len() * 10 < input.len());
+
+    let zeros = vec![0; 100_000];
+    let compressed = round_trip(&zeros);
+    assert!(compressed.len() * 30 < zeros.len());
+  }
+
+  #[test]
+  fn runs_at_the_token_limits() {
+    for len in [MAX_LITERALS - 1, MAX_LITERALS, MAX_LITERALS + 1] {
+      round_trip(&noise(len));
+    }
+    for len in [MAX_MATCH - 1, MAX_MATCH, MAX_MATCH + 1, MAX_MATCH * 3] {
+      let mut input = b"abcd".to_vec();
+      input.extend(std::iter::repeat_n(b'x', len));
+      round_trip(&input);
+    }
+  }
+
+  #[test]
+  fn matches_at_the_maximum_distance() {
+    let block = noise(64);
+    let with_gap = |gap: usize| {
+      let mut input = block.clone();
+      input.extend(std::iter::repeat_n(0xff, gap));
+      input.extend(&block);
+      round_trip(&input).len()
+    };
+
+    // The second block starts exactly `MAX_DISTANCE` after the first and is
+    // copied; one byte further it has to be stored as literals again.
+    let reachable = with_gap(MAX_DISTANCE - block.len());
+    let too_far = with_gap(MAX_DISTANCE - block.len() + 1);
+    assert!(reachable + block.len() / 2 < too_far);
+  }
+
+  #[test]
+  fn large_input() {
+    let mut input = Vec::new();
+    for i in 0..64 {
+      input.extend(noise(16 * 1024 + i));
+      input.extend(format!("{{\"row\":{i}}}").repeat(1024).as_bytes());
+    }
+    let compressed = round_trip(&input);
+    assert!(compressed.len() < input.len());
+  }
+
+  // ---------------------------------------------------------
+  // corruption tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn rejects_corrupt_input() {
+    let compressed = compress(&b"hello hello hello hello".repeat(4));
+
+    for bad in [
+      &compressed[..3],
+      &compressed[..compressed.len() - 1],
+      // A copy reaching back before the start of the output.
+      &[4, 0, 0, 0, 0x80, 1, 0][..],
+      // A copy with distance zero.
+      &[5, 0, 0, 0, 0, b'a', 0x80, 0, 0][..],
+    ] {
+      let err = decompress(bad).unwrap_err();
+      assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    let mut wrong_len = compressed.clone();
+    wrong_len[0] ^= 1;
+    assert!(decompress(&wrong_len).is_err());
+  }
+}
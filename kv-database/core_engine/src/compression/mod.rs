@@ -0,0 +1,112 @@
+//! A small dependency-free LZ77 codec for record values.
+//!
+//! The output starts with the uncompressed length (u32, little endian)
+//! followed by a stream of tokens:
+//!
+//! - `0xxxxxxx`: a run of `x + 1` literal bytes follows.
+//! - `1xxxxxxx` + u16 distance: copy `x + 4` bytes starting `distance` bytes
+//!   back in the output. Copies may overlap what they produce.
+//!
+//! Matches are found greedily through a hash table of 4-byte prefixes, which
+//! is cheap and does well on repetitive data such as JSON documents.
+
+use std::io;
+
+mod __test__;
+
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = 0x7f + MIN_MATCH;
+const MAX_LITERALS: usize = 0x80;
+const MAX_DISTANCE: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+
+pub(crate) fn compress(input: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(input.len() / 2 + 8);
+  out.extend_from_slice(&(input.len() as u32).to_le_bytes());
+
+  // Last position (plus one) each hashed prefix was seen at; zero is empty.
+  let mut table = vec![0usize; 1 << HASH_BITS];
+  let mut literals = 0;
+  let mut i = 0;
+  while i + MIN_MATCH <= input.len() {
+    let prefix = &input[i..i + MIN_MATCH];
+    let slot = hash(prefix);
+    let candidate = table[slot].checked_sub(1);
+    table[slot] = i + 1;
+
+    let Some(start) = candidate
+      .filter(|&start| i - start <= MAX_DISTANCE && &input[start..start + MIN_MATCH] == prefix)
+    else {
+      i += 1;
+      continue;
+    };
+
+    let mut len = MIN_MATCH;
+    while i + len < input.len() && len < MAX_MATCH && input[start + len] == input[i + len] {
+      len += 1;
+    }
+
+    push_literals(&mut out, &input[literals..i]);
+    out.push(0x80 | (len - MIN_MATCH) as u8);
+    out.extend_from_slice(&((i - start) as u16).to_le_bytes());
+    i += len;
+    literals = i;
+  }
+  push_literals(&mut out, &input[literals..]);
+
+  out
+}
+
+pub(crate) fn decompress(input: &[u8]) -> Result<Vec<u8>, io::Error> {
+  let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Corrupted compressed value");
+
+  let len = input
+    .get(..4)
+    .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    .ok_or_else(invalid)?;
+  let mut out = Vec::with_capacity(len);
+
+  let mut i = 4;
+  while i < input.len() {
+    let token = input[i] as usize;
+    i += 1;
+
+    if token & 0x80 == 0 {
+      let run = input.get(i..i + token + 1).ok_or_else(invalid)?;
+      out.extend_from_slice(run);
+      i += run.len();
+      continue;
+    }
+
+    let distance = input
+      .get(i..i + 2)
+      .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+      .ok_or_else(invalid)?;
+    i += 2;
+    if distance == 0 || distance > out.len() {
+      return Err(invalid());
+    }
+
+    let start = out.len() - distance;
+    for k in 0..(token & 0x7f) + MIN_MATCH {
+      out.push(out[start + k]);
+    }
+  }
+
+  if out.len() != len {
+    return Err(invalid());
+  }
+  Ok(out)
+}
+
+fn push_literals(out: &mut Vec<u8>, literals: &[u8]) {
+  for run in literals.chunks(MAX_LITERALS) {
+    out.push((run.len() - 1) as u8);
+    out.extend_from_slice(run);
+  }
+}
+
+fn hash(prefix: &[u8]) -> usize {
+  let word = u32::from_le_bytes(prefix.try_into().unwrap());
+  (word.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
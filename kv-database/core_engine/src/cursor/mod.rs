@@ -0,0 +1,139 @@
+//! Positioned reads over a pinned snapshot, opened with
+//! [`LogFile::cursor`](crate::log_file::LogFile::cursor).
+//!
+//! A [`Cursor`] lists the keys visible at the sequence number it was opened
+//! at and reads each value as of that sequence number, so pages fetched
+//! minutes apart still come from the same point in time. Older versions are
+//! only kept until compaction drops them, so compaction is paused while any
+//! cursor is open.
+
+use std::{
+  io,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  },
+};
+
+use crate::log_file::LogFile;
+
+/// A position between two keys of a snapshot. [`next`](Iterator::next)
+/// returns the entry after it and [`prev`](Self::prev) the one before it,
+/// moving the position past what they return.
+///
+/// Keys that expire after the cursor was opened are skipped.
+#[derive(Debug)]
+pub struct Cursor {
+  log: LogFile,
+  seq: u64,
+  /// Keys visible at `seq`, in ascending order under the log's comparator.
+  keys: Vec<String>,
+  /// Index into `keys` of the entry `next` returns.
+  position: usize,
+  _pin: Pin,
+}
+
+impl Cursor {
+  pub(crate) fn new(log: LogFile, seq: u64, keys: Vec<String>, pin: Pin) -> Self {
+    Self {
+      log,
+      seq,
+      keys,
+      position: 0,
+      _pin: pin,
+    }
+  }
+
+  /// Sequence number of the snapshot the cursor reads.
+  pub fn seq(&self) -> u64 {
+    self.seq
+  }
+
+  /// Moves the cursor before the first key at or after `key`, which
+  /// doesn't have to exist.
+  pub fn seek(&mut self, key: &str) {
+    self.position = self
+      .keys
+      .partition_point(|listed| self.log.compare_keys(listed, key).is_lt());
+  }
+
+  /// Moves the cursor back to before the first key.
+  pub fn rewind(&mut self) {
+    self.position = 0;
+  }
+
+  /// Returns the entry before the cursor and moves the cursor before it.
+  pub fn prev(&mut self) -> Option<Result<(String, String), io::Error>> {
+    while self.position > 0 {
+      self.position -= 1;
+      if let Some(entry) = self.entry(self.position).transpose() {
+        return Some(entry);
+      }
+    }
+    None
+  }
+
+  /// Returns up to `n` entries after the cursor and moves past them. Fewer
+  /// than `n` means the end was reached.
+  pub fn next_page(&mut self, n: usize) -> Result<Vec<(String, String)>, io::Error> {
+    self.take(n).collect()
+  }
+
+  /// The value of the key at `index` in the snapshot, or `None` if it has
+  /// expired since.
+  fn entry(&self, index: usize) -> Result<Option<(String, String)>, io::Error> {
+    let key = &self.keys[index];
+    match self.log.read_at(key, self.seq) {
+      Ok(value) => Ok(Some((key.clone(), value))),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+}
+
+impl Iterator for Cursor {
+  type Item = Result<(String, String), io::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.position < self.keys.len() {
+      self.position += 1;
+      if let Some(entry) = self.entry(self.position - 1).transpose() {
+        return Some(entry);
+      }
+    }
+    None
+  }
+}
+
+/// Number of open cursors. Compaction refuses to run unless it is zero.
+#[derive(Debug, Default)]
+pub(crate) struct Snapshots {
+  pinned: AtomicUsize,
+}
+
+impl Snapshots {
+  /// Must be called under the log's writer lock, so a compaction that
+  /// already holds it can't miss the pin.
+  pub(crate) fn pin(self: &Arc<Self>) -> Pin {
+    self.pinned.fetch_add(1, Ordering::SeqCst);
+    Pin {
+      snapshots: self.clone(),
+    }
+  }
+
+  pub(crate) fn pinned(&self) -> usize {
+    self.pinned.load(Ordering::SeqCst)
+  }
+}
+
+/// Keeps a snapshot's versions from being compacted away until dropped.
+#[derive(Debug)]
+pub(crate) struct Pin {
+  snapshots: Arc<Snapshots>,
+}
+
+impl Drop for Pin {
+  fn drop(&mut self) {
+    self.snapshots.pinned.fetch_sub(1, Ordering::SeqCst);
+  }
+}
@@ -0,0 +1,92 @@
+//! Engine-specific failures.
+//!
+//! The public API keeps returning `io::Error`; a [`DbError`] travels inside
+//! it as the custom payload so callers that care can match on it with
+//! [`DbError::from_io`] while everyone else just sees an I/O error.
+
+use std::{error::Error, fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum DbError {
+  /// Another process, or another open `LogFile` in this one, holds the
+  /// data directory's `LOCK` file.
+  AlreadyLocked { dir: PathBuf },
+  /// Compaction can't run while cursors are reading older versions.
+  SnapshotsPinned { cursors: usize },
+  /// A write with an empty key.
+  EmptyKey,
+  /// A put with an empty value, which the log would read back as a delete.
+  EmptyValue,
+  /// A key longer than `Options::max_key_size`.
+  KeyTooLarge { size: usize, max: usize },
+  /// A value longer than `Options::max_value_size`.
+  ValueTooLarge { size: usize, max: usize },
+  /// A record on disk claims a key or value longer than the limits, so its
+  /// header is corrupt or the limits were lowered since it was written.
+  RecordTooLarge { key_size: usize, value_size: usize },
+  /// A segment in a format this version can't read: one with a newer
+  /// header, or from the first release, whose records had no checksums.
+  UnsupportedFormat { file: String, version: u32 },
+}
+
+impl DbError {
+  /// Returns the `DbError` carried by `error`, if it has one.
+  pub fn from_io(error: &io::Error) -> Option<&DbError> {
+    error.get_ref()?.downcast_ref()
+  }
+
+  fn kind(&self) -> io::ErrorKind {
+    match self {
+      DbError::AlreadyLocked { .. } | DbError::SnapshotsPinned { .. } => {
+        io::ErrorKind::ResourceBusy
+      }
+      DbError::EmptyKey
+      | DbError::EmptyValue
+      | DbError::KeyTooLarge { .. }
+      | DbError::ValueTooLarge { .. } => io::ErrorKind::InvalidInput,
+      DbError::RecordTooLarge { .. } => io::ErrorKind::InvalidData,
+      DbError::UnsupportedFormat { .. } => io::ErrorKind::Unsupported,
+    }
+  }
+}
+
+impl fmt::Display for DbError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DbError::AlreadyLocked { dir } => write!(
+        f,
+        "The data directory {} is already in use by another instance",
+        dir.display()
+      ),
+      DbError::SnapshotsPinned { cursors } => {
+        write!(f, "Compaction is paused while {cursors} cursors are open")
+      }
+      DbError::EmptyKey => write!(f, "The key must not be empty"),
+      DbError::EmptyValue => write!(f, "The value must not be empty, delete the key instead"),
+      DbError::KeyTooLarge { size, max } => {
+        write!(f, "The key is {size} bytes, more than the limit of {max}")
+      }
+      DbError::ValueTooLarge { size, max } => {
+        write!(f, "The value is {size} bytes, more than the limit of {max}")
+      }
+      DbError::RecordTooLarge {
+        key_size,
+        value_size,
+      } => write!(
+        f,
+        "Corrupted record: a {key_size} byte key or {value_size} byte value exceeds the limits"
+      ),
+      DbError::UnsupportedFormat { file, version } => {
+        write!(f, "{file} uses unsupported format version {version}")
+      }
+    }
+  }
+}
+
+impl Error for DbError {}
+
+impl From<DbError> for io::Error {
+  fn from(error: DbError) -> Self {
+    io::Error::new(error.kind(), error)
+  }
+}
@@ -0,0 +1,135 @@
+//! Names of the segment and hint files in a data directory.
+//!
+//! Segment 42 is `segment-000042.duck` and its hint file
+//! `segment-000042.hint`. Ids are zero-padded to six digits so a directory
+//! listing shows segments in write order; larger ids just get longer names.
+//! Directories written before this scheme used `log-file-<id>` and
+//! `hint-<id>`, which [`migrate`] renames when the log is started, unless
+//! the segments are from the first release and too old to read.
+
+use std::{fs, io, path::Path};
+
+use crate::logging::info;
+
+const LEGACY_SEGMENT_PREFIX: &str = "log-file-";
+const LEGACY_HINT_PREFIX: &str = "hint-";
+/// Compaction output before the rename, as older versions named it.
+const LEGACY_TEMP_PREFIX: &str = "temp-log-file-";
+
+pub(crate) fn segment(file_id: u64) -> String {
+  format!("segment-{file_id:06}.duck")
+}
+
+pub(crate) fn hint(file_id: u64) -> String {
+  format!("segment-{file_id:06}.hint")
+}
+
+/// The id of the segment named `name`, which must be spelled exactly as
+/// [`segment`] spells it.
+pub(crate) fn parse_segment(name: &str) -> Option<u64> {
+  parse(name, ".duck").filter(|&file_id| segment(file_id) == name)
+}
+
+/// The id of the segment whose hint file is named `name`.
+pub(crate) fn parse_hint(name: &str) -> Option<u64> {
+  parse(name, ".hint").filter(|&file_id| hint(file_id) == name)
+}
+
+/// Whether `name` is a segment under either naming scheme.
+pub(crate) fn is_segment(name: &str) -> bool {
+  parse_segment(name).is_some() || parse_legacy_segment(name).is_some()
+}
+
+/// The id of the segment older versions named `name`.
+pub(crate) fn parse_legacy_segment(name: &str) -> Option<u64> {
+  name.strip_prefix(LEGACY_SEGMENT_PREFIX)?.parse().ok()
+}
+
+/// Whether `name` is a file written under a temporary name and renamed
+/// over a segment or hint file once complete: compaction output, repaired
+/// segments and hint files. One that still exists was cut short by a crash.
+pub(crate) fn is_temp(name: &str) -> bool {
+  if name.starts_with(LEGACY_TEMP_PREFIX) {
+    return true;
+  }
+  match name.rsplit_once('.') {
+    Some((base, "tmp")) => parse_segment(base).is_some() || parse_hint(base).is_some(),
+    Some((base, "repair")) => parse_segment(base).is_some(),
+    _ => false,
+  }
+}
+
+fn parse(name: &str, extension: &str) -> Option<u64> {
+  name
+    .strip_prefix("segment-")?
+    .strip_suffix(extension)?
+    .parse()
+    .ok()
+}
+
+/// The name older versions gave segment `file_id`.
+pub(crate) fn legacy_segment(file_id: u64) -> String {
+  format!("{LEGACY_SEGMENT_PREFIX}{file_id}")
+}
+
+/// The name older versions gave the hint file of segment `file_id`.
+pub(crate) fn legacy_hint(file_id: u64) -> String {
+  format!("{LEGACY_HINT_PREFIX}{file_id}")
+}
+
+/// The current name of a file that used the old naming scheme.
+pub(crate) fn from_legacy(name: &str) -> Option<String> {
+  if let Some(file_id) = parse_legacy_segment(name) {
+    return Some(segment(file_id));
+  }
+  name
+    .strip_prefix(LEGACY_HINT_PREFIX)?
+    .parse()
+    .ok()
+    .map(hint)
+}
+
+/// Whether `dir` still has files named the old way.
+pub(crate) fn has_legacy(dir: &Path) -> Result<bool, io::Error> {
+  for entry in fs::read_dir(dir)? {
+    if entry?.file_name().to_str().and_then(from_legacy).is_some() {
+      return Ok(true);
+    }
+  }
+  Ok(false)
+}
+
+/// Renames the files in `dir` that use the old naming scheme and returns
+/// how many there were. The caller must hold the directory's lock and sync
+/// the directory afterwards. A crash part way leaves some files renamed,
+/// and the next call renames the rest.
+pub(crate) fn migrate(dir: &Path) -> Result<usize, io::Error> {
+  let mut renamed = 0;
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let Some(name) = entry.file_name().to_str().and_then(from_legacy) else {
+      continue;
+    };
+    let dest = dir.join(&name);
+    if fs::exists(&dest)? {
+      return Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!(
+          "Both {} and {name} exist in {}",
+          entry.file_name().to_string_lossy(),
+          dir.display()
+        ),
+      ));
+    }
+    fs::rename(entry.path(), dest)?;
+    renamed += 1;
+  }
+
+  if renamed > 0 {
+    info!(
+      "[RECOVERY] Renamed files to the current naming scheme.",
+      files = renamed as u64
+    );
+  }
+  Ok(renamed)
+}
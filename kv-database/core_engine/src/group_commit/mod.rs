@@ -0,0 +1,91 @@
+//! Group commit: many writers, one fsync.
+//!
+//! Writers append their records without syncing and then call
+//! [`GroupCommit::wait`] with the sequence number they need on disk. The first
+//! waiter becomes the leader: it lingers for the configured window so other
+//! writers can queue up behind it, runs a single sync that covers everything
+//! written so far, and wakes every follower whose record that sync covered.
+
+use std::{
+  io,
+  sync::{Condvar, Mutex},
+  thread,
+  time::Duration,
+};
+
+#[derive(Debug)]
+struct SyncState {
+  durable_seq: u64,
+  leader: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct GroupCommit {
+  window: Duration,
+  state: Mutex<SyncState>,
+  synced: Condvar,
+}
+
+impl GroupCommit {
+  pub(crate) fn new(window: Duration) -> Self {
+    Self {
+      window,
+      state: Mutex::new(SyncState {
+        durable_seq: 0,
+        leader: false,
+      }),
+      synced: Condvar::new(),
+    }
+  }
+
+  /// Highest sequence number known to be on disk.
+  pub(crate) fn durable_seq(&self) -> u64 {
+    self.state.lock().unwrap().durable_seq
+  }
+
+  /// Records that everything up to `seq` is durable without syncing, e.g.
+  /// after recovery or after a write path that synced on its own.
+  pub(crate) fn mark_durable(&self, seq: u64) {
+    let mut state = self.state.lock().unwrap();
+    state.durable_seq = state.durable_seq.max(seq);
+    self.synced.notify_all();
+  }
+
+  /// Blocks until `seq` is durable.
+  ///
+  /// `sync` flushes the log to disk and returns the highest sequence number
+  /// the flush covered. It is called by at most one waiter at a time; if it
+  /// fails, the leader gets the error and the next waiter retries.
+  pub(crate) fn wait(&self, seq: u64, sync: impl Fn() -> io::Result<u64>) -> io::Result<()> {
+    let mut state = self.state.lock().unwrap();
+
+    loop {
+      if state.durable_seq >= seq {
+        return Ok(());
+      }
+
+      if state.leader {
+        state = self.synced.wait(state).unwrap();
+        continue;
+      }
+
+      state.leader = true;
+      drop(state);
+
+      // wasm32 can't sleep, and with a single thread nobody could queue up
+      // behind the leader anyway.
+      if !self.window.is_zero() && cfg!(not(target_arch = "wasm32")) {
+        thread::sleep(self.window);
+      }
+      let result = sync();
+
+      state = self.state.lock().unwrap();
+      state.leader = false;
+      if let Ok(synced) = result {
+        state.durable_seq = state.durable_seq.max(synced);
+      }
+      self.synced.notify_all();
+      result?;
+    }
+  }
+}
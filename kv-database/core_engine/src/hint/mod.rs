@@ -0,0 +1,112 @@
+//! Hint files: a compact copy of a sealed segment's record headers.
+//!
+//! Every sealed `segment-<id>.duck` gets a `segment-<id>.hint` next to it listing each
+//! record's key and location but not its value, so startup can rebuild the
+//! keydir without reading whole segments. Entries are stored in log order so
+//! replaying them gives the same result as scanning the segment.
+//!
+//! The file starts with `MAGIC` and a format version byte. Each entry is a
+//! CRC32 of the rest of the entry followed by: key_size, key, seq, kind,
+//! offset, len, expires_at. Any mismatch makes [`read`] fail with
+//! `InvalidData` so the caller can fall back to scanning the segment.
+
+use std::{
+  fs::{self, File},
+  io::{self, Write},
+};
+
+use utils::codec::{RecordReader, RecordWriter};
+
+use crate::log_file::RecordKind;
+
+const MAGIC: &[u8; 4] = b"DKVH";
+const VERSION: u8 = 1;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct HintEntry {
+  pub(crate) key: String,
+  pub(crate) seq: u64,
+  pub(crate) kind: RecordKind,
+  pub(crate) offset: u64,
+  pub(crate) len: u64,
+  pub(crate) expires_at: i64,
+}
+
+/// Writes `entries` to `path`, replacing any previous hint file. The entries
+/// go to a temporary file first so a crash never leaves a half-written hint.
+pub(crate) fn write(path: &str, entries: &[HintEntry]) -> Result<(), io::Error> {
+  let mut buf = RecordWriter::new();
+  buf.put_bytes(MAGIC);
+  buf.put_u8(VERSION);
+  for entry in entries {
+    let mut body = RecordWriter::new();
+    body.put_prefixed(entry.key.as_bytes());
+    body.put_u64(entry.seq);
+    body.put_u64(kind_code(entry.kind));
+    body.put_u64(entry.offset);
+    body.put_u64(entry.len);
+    body.put_i64(entry.expires_at);
+
+    buf.put_u32(crc32fast::hash(body.as_bytes()));
+    buf.put_bytes(body.as_bytes());
+  }
+
+  let temp_path = format!("{path}.tmp");
+  let mut file = File::create(&temp_path)?;
+  file.write_all(buf.as_bytes())?;
+  file.sync_all()?;
+  fs::rename(&temp_path, path)
+}
+
+/// Reads every entry of the hint file at `path`.
+pub(crate) fn read(path: &str) -> Result<Vec<HintEntry>, io::Error> {
+  let buf = fs::read(path)?;
+  let mut reader = RecordReader::new(&buf);
+  if reader.get_bytes(MAGIC.len())? != MAGIC || reader.get_u8()? != VERSION {
+    return Err(invalid("Unrecognized hint file header"));
+  }
+
+  let mut entries = Vec::new();
+  while !reader.is_empty() {
+    let crc = reader.get_u32()?;
+    let start = reader.position();
+    let key = String::from_utf8(reader.get_prefixed(usize::MAX)?.to_vec())
+      .map_err(|_| invalid("Corrupted hint key"))?;
+    let entry = HintEntry {
+      key,
+      seq: reader.get_u64()?,
+      kind: kind_from_code(reader.get_u64()?)?,
+      offset: reader.get_u64()?,
+      len: reader.get_u64()?,
+      expires_at: reader.get_i64()?,
+    };
+
+    if crc32fast::hash(reader.read_since(start)) != crc {
+      return Err(invalid("Corrupted hint entry: checksum mismatch"));
+    }
+    entries.push(entry);
+  }
+
+  Ok(entries)
+}
+
+fn kind_code(kind: RecordKind) -> u64 {
+  match kind {
+    RecordKind::Put => 0,
+    RecordKind::Delete => 1,
+    RecordKind::Merge => 2,
+  }
+}
+
+fn kind_from_code(code: u64) -> Result<RecordKind, io::Error> {
+  match code {
+    0 => Ok(RecordKind::Put),
+    1 => Ok(RecordKind::Delete),
+    2 => Ok(RecordKind::Merge),
+    _ => Err(invalid("Unknown record kind in hint file")),
+  }
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,160 @@
+//! Maintenance event hooks, registered with `LogFile::on_flush`,
+//! `LogFile::on_compaction_start`, `LogFile::on_compaction_finish` and
+//! `LogFile::on_segment_sealed`.
+//!
+//! Events are raised wherever the engine happens to be, often with its locks
+//! held, so they are handed to a dispatcher thread that runs the hooks in
+//! order. Hooks therefore never slow down a write and may call back into the
+//! log, but run shortly after the event rather than during it. Until the
+//! first hook is registered nothing is dispatched at all.
+//!
+//! wasm32 has no threads, so there hooks run inline when the event is
+//! raised and must not call back into the log.
+//!
+//! Hooks live as long as the log. One that owns a clone of the log keeps it
+//! open for the rest of the process.
+
+use std::{
+  fmt,
+  sync::{Arc, RwLock},
+  time::Duration,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+  sync::{
+    mpsc::{self, Sender},
+    OnceLock,
+  },
+  thread,
+};
+
+/// An fsync of the active segment, from group commit, the periodic syncer or
+/// `LogFile::sync`.
+#[derive(Debug, Clone)]
+pub struct FlushInfo {
+  pub file_id: u64,
+  /// Every write up to this sequence number is now durable.
+  pub seq: u64,
+  pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompactionStartInfo {
+  /// Segments about to be rewritten, the active one included.
+  pub segments: usize,
+  pub total_bytes: u64,
+  /// Estimated bytes the compaction will reclaim.
+  pub dead_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompactionInfo {
+  /// The segment now holding every live record.
+  pub output_id: u64,
+  /// Segments that were rewritten and removed.
+  pub segments: usize,
+  pub bytes_before: u64,
+  pub bytes_after: u64,
+  pub duration: Duration,
+}
+
+/// The active segment reached its size limit and was synced and closed.
+#[derive(Debug, Clone)]
+pub struct SegmentSealedInfo {
+  pub file_id: u64,
+  pub size: u64,
+  /// Bytes of the segment already overwritten or deleted.
+  pub dead_bytes: u64,
+}
+
+#[derive(Debug)]
+pub(crate) enum HookEvent {
+  Flush(FlushInfo),
+  CompactionStart(CompactionStartInfo),
+  CompactionFinish(CompactionInfo),
+  SegmentSealed(SegmentSealedInfo),
+}
+
+type Callbacks<T> = Vec<Box<dyn Fn(&T) + Send + Sync>>;
+
+#[derive(Default)]
+pub(crate) struct Registry {
+  pub(crate) flush: Callbacks<FlushInfo>,
+  pub(crate) compaction_start: Callbacks<CompactionStartInfo>,
+  pub(crate) compaction_finish: Callbacks<CompactionInfo>,
+  pub(crate) segment_sealed: Callbacks<SegmentSealedInfo>,
+}
+
+impl Registry {
+  #[cfg(target_arch = "wasm32")]
+  fn is_empty(&self) -> bool {
+    self.flush.is_empty()
+      && self.compaction_start.is_empty()
+      && self.compaction_finish.is_empty()
+      && self.segment_sealed.is_empty()
+  }
+
+  fn dispatch(&self, event: &HookEvent) {
+    match event {
+      HookEvent::Flush(info) => self.flush.iter().for_each(|hook| hook(info)),
+      HookEvent::CompactionStart(info) => self.compaction_start.iter().for_each(|hook| hook(info)),
+      HookEvent::CompactionFinish(info) => {
+        self.compaction_finish.iter().for_each(|hook| hook(info))
+      }
+      HookEvent::SegmentSealed(info) => self.segment_sealed.iter().for_each(|hook| hook(info)),
+    }
+  }
+}
+
+/// The hooks registered on one log and the thread running them.
+#[derive(Default)]
+pub(crate) struct Hooks {
+  registry: Arc<RwLock<Registry>>,
+  /// Feeds the dispatcher thread, which exits once this is dropped with the
+  /// log.
+  #[cfg(not(target_arch = "wasm32"))]
+  sender: OnceLock<Sender<HookEvent>>,
+}
+
+impl Hooks {
+  /// Adds a hook through `register` and starts the dispatcher if this is
+  /// the first one.
+  pub(crate) fn register(&self, register: impl FnOnce(&mut Registry)) {
+    register(&mut self.registry.write().unwrap());
+    #[cfg(not(target_arch = "wasm32"))]
+    self.sender.get_or_init(|| {
+      let (sender, events) = mpsc::channel::<HookEvent>();
+      let registry = self.registry.clone();
+      thread::spawn(move || {
+        for event in events {
+          registry.read().unwrap().dispatch(&event);
+        }
+      });
+      sender
+    });
+  }
+
+  /// Queues the event built by `event` if any hook is registered.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) fn emit(&self, event: impl FnOnce() -> HookEvent) {
+    if let Some(sender) = self.sender.get() {
+      let _ = sender.send(event());
+    }
+  }
+
+  /// Runs the hooks for the event built by `event` right away, there being
+  /// no thread to hand it to.
+  #[cfg(target_arch = "wasm32")]
+  pub(crate) fn emit(&self, event: impl FnOnce() -> HookEvent) {
+    let registry = self.registry.read().unwrap();
+    if !registry.is_empty() {
+      registry.dispatch(&event());
+    }
+  }
+}
+
+impl fmt::Debug for Hooks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("Hooks(..)")
+  }
+}
@@ -0,0 +1,44 @@
+//! The JSON-lines format used by
+//! [`LogFile::export_jsonl`](crate::log_file::LogFile::export_jsonl) and
+//! [`LogFile::import_jsonl`](crate::log_file::LogFile::import_jsonl).
+//!
+//! One object per line: `{"key":…,"value":…,"ts":…}`, where `ts` is the
+//! record's write time in Unix nanoseconds. Imports accept lines without a
+//! `ts`.
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Record {
+  pub(crate) key: String,
+  pub(crate) value: String,
+  #[serde(default)]
+  pub(crate) ts: i64,
+}
+
+impl Record {
+  pub(crate) fn write_line(&self, writer: &mut impl Write) -> Result<(), io::Error> {
+    serde_json::to_writer(&mut *writer, self)?;
+    writer.write_all(b"\n")
+  }
+
+  /// Parses line `line_number` (1-based, for the error message).
+  pub(crate) fn parse(line: &str, line_number: usize) -> Result<Self, io::Error> {
+    let record = serde_json::from_str::<Self>(line).map_err(|e| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid record on line {line_number}: {e}"),
+      )
+    })?;
+
+    if record.key.is_empty() || record.value.is_empty() {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Empty key or value on line {line_number}"),
+      ));
+    }
+    Ok(record)
+  }
+}
@@ -0,0 +1,149 @@
+//! The in-memory index from keys to record locations, split into shards.
+//!
+//! Every key hashes to one [`Shard`] with its own `RwLock`, so threads working
+//! on different keys never contend. Readers keep their shard locked while
+//! they read a record from disk, which is what lets compaction delete
+//! segments safely once it holds every shard for writing.
+//!
+//! Locks are always taken in this order: the log's writer lock, shards in
+//! ascending index order, then the segment table.
+
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  hash::{BuildHasher, RandomState},
+  sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{
+  log_file::{Index, Version},
+  segment_writer::SegmentWriter,
+};
+
+/// One partition of the keydir.
+#[derive(Debug, Default)]
+pub(crate) struct Shard {
+  pub(crate) data_index: HashMap<String, Index>,
+  /// Merge operands not yet folded into the value in `data_index`.
+  pub(crate) merges: HashMap<String, Vec<Index>>,
+  pub(crate) history: HashMap<String, Vec<Version>>,
+}
+
+impl Shard {
+  pub(crate) fn contains(&self, key: &str) -> bool {
+    self.data_index.contains_key(key) || self.merges.contains_key(key)
+  }
+
+  /// Whether `key` is one of the [`live_keys`](Self::live_keys).
+  pub(crate) fn is_live(&self, key: &str) -> bool {
+    self.merges.contains_key(key)
+      || self
+        .data_index
+        .get(key)
+        .is_some_and(|index| !index.is_expired())
+  }
+
+  /// Keys with an unexpired value or pending merge operands.
+  pub(crate) fn live_keys(&self) -> impl Iterator<Item = &String> {
+    self
+      .data_index
+      .iter()
+      .filter(|(key, index)| !index.is_expired() && !self.merges.contains_key(*key))
+      .map(|(key, _)| key)
+      .chain(self.merges.keys())
+  }
+}
+
+/// Where each segment lives on disk.
+#[derive(Debug, Default)]
+pub(crate) struct Segments {
+  pub(crate) file_index: HashMap<u64, String>,
+  /// Segment currently being appended to. Mirrors the writer's
+  /// `current_file_id` so readers can tell it apart from sealed segments.
+  pub(crate) active_file_id: u64,
+  /// Writer for the active segment, which readers flush before reading it.
+  pub(crate) active_writer: Option<Arc<SegmentWriter>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct KeyDir {
+  shards: Box<[RwLock<Shard>]>,
+  hasher: RandomState,
+  segments: RwLock<Segments>,
+}
+
+impl KeyDir {
+  pub(crate) fn new(shards: usize) -> Self {
+    Self {
+      shards: (0..shards.max(1)).map(|_| RwLock::default()).collect(),
+      hasher: RandomState::new(),
+      segments: RwLock::default(),
+    }
+  }
+
+  pub(crate) fn shard_of(&self, key: &str) -> usize {
+    (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+  }
+
+  pub(crate) fn read(&self, key: &str) -> RwLockReadGuard<'_, Shard> {
+    self.shards[self.shard_of(key)].read().unwrap()
+  }
+
+  pub(crate) fn write(&self, key: &str) -> RwLockWriteGuard<'_, Shard> {
+    self.shards[self.shard_of(key)].write().unwrap()
+  }
+
+  /// Read-locks the shards owning `keys`, in lock order.
+  pub(crate) fn read_many<'a>(
+    &self,
+    keys: impl IntoIterator<Item = &'a str>,
+  ) -> BTreeMap<usize, RwLockReadGuard<'_, Shard>> {
+    let ids = keys
+      .into_iter()
+      .map(|key| self.shard_of(key))
+      .collect::<BTreeSet<_>>();
+    ids
+      .into_iter()
+      .map(|id| (id, self.shards[id].read().unwrap()))
+      .collect()
+  }
+
+  /// Write-locks the shards owning `keys`, in lock order.
+  pub(crate) fn write_many<'a>(
+    &self,
+    keys: impl IntoIterator<Item = &'a str>,
+  ) -> BTreeMap<usize, RwLockWriteGuard<'_, Shard>> {
+    let ids = keys
+      .into_iter()
+      .map(|key| self.shard_of(key))
+      .collect::<BTreeSet<_>>();
+    ids
+      .into_iter()
+      .map(|id| (id, self.shards[id].write().unwrap()))
+      .collect()
+  }
+
+  /// Write-locks every shard, which waits out all in-flight reads.
+  pub(crate) fn write_all(&self) -> Vec<RwLockWriteGuard<'_, Shard>> {
+    self
+      .shards
+      .iter()
+      .map(|shard| shard.write().unwrap())
+      .collect()
+  }
+
+  /// Visits the shards one at a time; each is locked only while `f` runs, so
+  /// the aggregate is not a point-in-time snapshot.
+  pub(crate) fn for_each_shard(&self, mut f: impl FnMut(&Shard)) {
+    for shard in self.shards.iter() {
+      f(&shard.read().unwrap());
+    }
+  }
+
+  pub(crate) fn segments(&self) -> RwLockReadGuard<'_, Segments> {
+    self.segments.read().unwrap()
+  }
+
+  pub(crate) fn segments_mut(&self) -> RwLockWriteGuard<'_, Segments> {
+    self.segments.write().unwrap()
+  }
+}
@@ -1 +1,34 @@
+#[cfg(feature = "async")]
+pub mod async_log_file;
+pub mod backup;
+mod clock;
+pub mod column_family;
+pub mod compaction;
+pub mod comparator;
+mod compression;
+pub mod csv_format;
+pub mod cursor;
+pub mod error;
+mod file_names;
+mod group_commit;
+mod hint;
+pub mod hooks;
+mod jsonl;
+mod keydir;
 pub mod log_file;
+pub mod logging;
+pub mod merge;
+mod metrics;
+pub mod options;
+mod rate_limiter;
+pub mod repair;
+pub mod replication;
+mod segment_writer;
+pub mod server;
+pub mod stats;
+mod storage;
+mod syncer;
+pub mod value_log;
+pub mod verify;
+pub mod watch;
+pub mod write_batch;
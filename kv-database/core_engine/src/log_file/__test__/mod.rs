@@ -0,0 +1,651 @@
+#[cfg(test)]
+mod log_file_test {
+  use std::path::PathBuf;
+
+  use crate::{log_file::*, value_log};
+
+  /// An empty data directory, unique to `name`.
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kv-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+  }
+
+  fn open(dir: &Path) -> LogFile {
+    let log = LogFile::with_options(Options {
+      dir: dir.to_path_buf(),
+      ..Options::default()
+    })
+    .unwrap();
+    log.start().unwrap();
+    log
+  }
+
+  /// Every live key with its value, sorted by key.
+  fn contents(log: &LogFile) -> Vec<(String, String)> {
+    log
+      .sorted_keys()
+      .map(|key| {
+        let value = log.read(&key).unwrap();
+        (key, value)
+      })
+      .collect()
+  }
+
+  /// Writes enough puts, updates and deletes to seal a few segments.
+  fn fill(log: &LogFile) {
+    for i in 0..40 {
+      log
+        .append(&format!("key-{i:02}"), &format!("value-{i}"))
+        .unwrap();
+    }
+    for i in (0..40).step_by(3) {
+      log.delete(&format!("key-{i:02}")).unwrap();
+    }
+    for i in (1..40).step_by(5).filter(|i| i % 3 != 0) {
+      log
+        .update(&format!("key-{i:02}"), &format!("updated-{i}"))
+        .unwrap();
+    }
+  }
+
+  fn segment_ids(dir: &Path) -> Vec<u64> {
+    let mut file_ids = fs::read_dir(dir)
+      .unwrap()
+      .filter_map(|entry| file_names::parse_segment(entry.unwrap().file_name().to_str()?))
+      .collect::<Vec<_>>();
+    file_ids.sort();
+    file_ids
+  }
+
+  fn set_len(path: &Path, len: u64) {
+    OpenOptions::new()
+      .write(true)
+      .open(path)
+      .unwrap()
+      .set_len(len)
+      .unwrap();
+  }
+
+  // ---------------------------------------------------------
+  // batch tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn torn_batch_is_discarded_as_a_whole() {
+    let dir = temp_dir("torn-batch");
+    let log = open(&dir);
+    log.append("before", "kept").unwrap();
+    let batch_offset = log.stats().unwrap().total_bytes;
+    let mut batch = WriteBatch::new();
+    batch.put("a", "1").put("b", "2").delete("before");
+    log.write(&batch).unwrap();
+    drop(log);
+
+    // Cut the batch's last record short, as a crash mid-write would.
+    let path = dir.join(file_names::segment(1));
+    set_len(&path, fs::metadata(&path).unwrap().len() - 3);
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), [("before".to_string(), "kept".to_string())]);
+    assert_eq!(fs::metadata(&path).unwrap().len(), batch_offset);
+  }
+
+  // ---------------------------------------------------------
+  // recovery tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn reopening_rebuilds_the_same_keydir() {
+    let dir = temp_dir("reopen");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    let last_seq = log.last_seq();
+    drop(log);
+
+    // From the hint files of the sealed segments.
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    assert_eq!(log.last_seq(), last_seq);
+    drop(log);
+
+    // From the segments alone.
+    let hints = fs::read_dir(&dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .filter(|path| {
+        path
+          .extension()
+          .is_some_and(|extension| extension == "hint")
+      })
+      .collect::<Vec<_>>();
+    assert!(!hints.is_empty());
+    for path in hints {
+      fs::remove_file(path).unwrap();
+    }
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    assert_eq!(log.last_seq(), last_seq);
+  }
+
+  #[test]
+  fn sequence_numbers_survive_compacting_away_the_newest_records() {
+    let dir = temp_dir("compacted-seq");
+    let log = open(&dir);
+    for key in ["a", "b", "c"] {
+      log.append(key, "value").unwrap();
+    }
+    log.delete("b").unwrap();
+    log.delete("c").unwrap();
+    log.compact().unwrap();
+    assert_eq!(log.last_seq(), 5);
+    drop(log);
+
+    let log = open(&dir);
+    assert_eq!(log.last_seq(), 5);
+    assert_eq!(contents(&log), [("a".to_string(), "value".to_string())]);
+    assert_eq!(log.append_deferred("d", "value").unwrap().seq(), 6);
+    drop(log);
+
+    // Compacting the kept tombstone again keeps it.
+    let log = open(&dir);
+    log.delete("d").unwrap();
+    log.compact().unwrap();
+    log.compact().unwrap();
+    drop(log);
+    let log = open(&dir);
+    assert_eq!(log.last_seq(), 7);
+    assert_eq!(log.len(), 1);
+  }
+
+  /// Appends `count` records to a fresh log in `dir` and returns the offset
+  /// each one starts at, plus the end of the last one.
+  fn append_records(dir: &Path, count: usize) -> Vec<u64> {
+    let log = open(dir);
+    let mut offsets = vec![0];
+    for i in 0..count {
+      log.append(&format!("key-{i}"), "value").unwrap();
+      offsets.push(log.stats().unwrap().total_bytes);
+    }
+    offsets
+  }
+
+  #[test]
+  fn torn_tail_is_truncated() {
+    let dir = temp_dir("torn-tail");
+    let offsets = append_records(&dir, 8);
+    let path = dir.join(file_names::segment(1));
+    set_len(&path, offsets[8] - 3);
+
+    let log = open(&dir);
+    assert_eq!(log.len(), 7);
+    assert!(!log.contains_key("key-7"));
+    assert_eq!(fs::metadata(&path).unwrap().len(), offsets[7]);
+  }
+
+  #[test]
+  fn corrupt_record_before_intact_ones_refuses_to_start() {
+    let dir = temp_dir("corrupt-middle");
+    let offsets = append_records(&dir, 8);
+    let path = dir.join(file_names::segment(1));
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[(offsets[1] + HEADER_SIZE) as usize] ^= 0xff;
+    fs::write(&path, &bytes).unwrap();
+
+    let log = LogFile::with_options(Options {
+      dir: dir.clone(),
+      ..Options::default()
+    })
+    .unwrap();
+    let err = log.start().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("repair"));
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+  }
+
+  #[test]
+  fn repair_quarantines_a_corrupt_record() {
+    let dir = temp_dir("repair");
+    let offsets = append_records(&dir, 8);
+    let path = dir.join(file_names::segment(1));
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[(offsets[1] + HEADER_SIZE) as usize] ^= 0xff;
+    fs::write(&path, &bytes).unwrap();
+
+    let log = LogFile::with_options(Options {
+      dir: dir.clone(),
+      ..Options::default()
+    })
+    .unwrap();
+    let report = log.repair().unwrap();
+    assert_eq!(report.rewritten, [1]);
+    assert_eq!(report.salvaged, 7);
+    assert_eq!(report.quarantined.len(), 1);
+    let quarantined = &report.quarantined[0];
+    assert_eq!(quarantined.offset, offsets[1]);
+    assert_eq!(quarantined.len, offsets[2] - offsets[1]);
+    assert_eq!(
+      fs::read(&quarantined.path).unwrap(),
+      &bytes[offsets[1] as usize..offsets[2] as usize]
+    );
+    assert_eq!(
+      fs::metadata(&path).unwrap().len(),
+      offsets[8] - quarantined.len
+    );
+
+    log.start().unwrap();
+    assert_eq!(log.len(), 7);
+    assert!(!log.contains_key("key-1"));
+    assert_eq!(log.read("key-2").unwrap(), "value");
+  }
+
+  // ---------------------------------------------------------
+  // compaction recovery tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn compaction_without_output_is_rolled_back() {
+    let dir = temp_dir("compaction-rollback");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    drop(log);
+
+    let inputs = segment_ids(&dir);
+    let output_id = inputs.last().unwrap() + 1;
+    let temp_name = format!("{}.tmp", file_names::segment(output_id));
+    fs::write(dir.join(&temp_name), b"half a compaction").unwrap();
+    Manifest {
+      output_id,
+      temp_name: temp_name.clone(),
+      inputs: inputs.clone(),
+      blobs: Vec::new(),
+    }
+    .write(dir.join(COMPACTION_MANIFEST).to_str().unwrap())
+    .unwrap();
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    assert!(!dir.join(&temp_name).exists());
+    assert!(!dir.join(COMPACTION_MANIFEST).exists());
+    for file_id in inputs {
+      assert!(dir.join(file_names::segment(file_id)).exists());
+    }
+  }
+
+  #[test]
+  fn compaction_with_renamed_output_is_finished() {
+    let dir = temp_dir("compaction-finish");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    drop(log);
+
+    // The output holds every record of its inputs, in order.
+    let inputs = segment_ids(&dir);
+    let output_id = inputs.last().unwrap() + 1;
+    let mut output = LogFile::segment_header().to_vec();
+    for &file_id in &inputs {
+      let segment = fs::read(dir.join(file_names::segment(file_id))).unwrap();
+      output.extend(&segment[SEGMENT_HEADER_SIZE as usize..]);
+    }
+    fs::write(dir.join(file_names::segment(output_id)), output).unwrap();
+    Manifest {
+      output_id,
+      temp_name: format!("{}.tmp", file_names::segment(output_id)),
+      inputs: inputs.clone(),
+      blobs: Vec::new(),
+    }
+    .write(dir.join(COMPACTION_MANIFEST).to_str().unwrap())
+    .unwrap();
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    assert!(!dir.join(COMPACTION_MANIFEST).exists());
+    for file_id in inputs {
+      assert!(!dir.join(file_names::segment(file_id)).exists());
+      assert!(!dir.join(file_names::hint(file_id)).exists());
+    }
+    assert!(dir.join(file_names::segment(output_id)).exists());
+  }
+
+  #[test]
+  fn files_left_behind_by_a_crash_are_removed() {
+    let dir = temp_dir("orphans");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    drop(log);
+
+    let segments = segment_ids(&dir);
+    let missing = segments.last().unwrap() + 1;
+    let orphans = [
+      format!("{}.tmp", file_names::segment(missing)),
+      format!("{}.repair", file_names::segment(1)),
+      format!("{}.tmp", file_names::hint(1)),
+      file_names::hint(missing),
+      format!("{COMPACTION_MANIFEST}.tmp"),
+    ];
+    for name in &orphans {
+      fs::write(dir.join(name), b"left behind").unwrap();
+    }
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    for name in &orphans {
+      assert!(!dir.join(name).exists(), "{name} was not removed");
+    }
+    assert!(dir.join(file_names::hint(1)).exists());
+    assert!(dir.join(file_names::segment(1)).exists());
+  }
+
+  // ---------------------------------------------------------
+  // format tests
+  // ---------------------------------------------------------
+
+  /// Names the segments in `dir` the way older versions did, which wrote
+  /// them without a header.
+  fn make_legacy(dir: &Path) {
+    for file_id in segment_ids(dir) {
+      let path = dir.join(file_names::segment(file_id));
+      let segment = fs::read(&path).unwrap();
+      fs::write(
+        dir.join(file_names::legacy_segment(file_id)),
+        &segment[SEGMENT_HEADER_SIZE as usize..],
+      )
+      .unwrap();
+      fs::remove_file(path).unwrap();
+      let _ = fs::remove_file(dir.join(file_names::hint(file_id)));
+    }
+  }
+
+  #[test]
+  fn segments_without_a_header_are_migrated() {
+    let dir = temp_dir("headerless");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    drop(log);
+    make_legacy(&dir);
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+    log.compact().unwrap();
+    drop(log);
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+  }
+
+  #[test]
+  fn segments_of_the_first_release_are_refused_before_renaming() {
+    let dir = temp_dir("version-0");
+    fs::create_dir_all(&dir).unwrap();
+    // Timestamp, key size and value size, then the key and value.
+    let mut segment = Vec::new();
+    for (key, value) in [("a", "first"), ("b", "second")] {
+      segment.extend(1_700_000_000_i64.to_le_bytes());
+      segment.extend((key.len() as u64).to_le_bytes());
+      segment.extend((value.len() as u64).to_le_bytes());
+      segment.extend(key.as_bytes());
+      segment.extend(value.as_bytes());
+    }
+    let legacy = dir.join(file_names::legacy_segment(1));
+    fs::write(&legacy, &segment).unwrap();
+
+    let log = LogFile::with_options(Options {
+      dir: dir.clone(),
+      ..Options::default()
+    })
+    .unwrap();
+    let err = log.start().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    assert!(matches!(
+      DbError::from_io(&err),
+      Some(DbError::UnsupportedFormat { version: 0, .. })
+    ));
+    assert_eq!(fs::read(&legacy).unwrap(), segment);
+    assert!(segment_ids(&dir).is_empty());
+  }
+
+  #[test]
+  fn segments_of_a_newer_version_are_refused() {
+    let dir = temp_dir("version-2");
+    let log = open(&dir);
+    fill(&log);
+    drop(log);
+
+    // Even with a hint file, which would spare reading the segment.
+    let path = dir.join(file_names::segment(1));
+    assert!(dir.join(file_names::hint(1)).exists());
+    let mut segment = fs::read(&path).unwrap();
+    segment[4..8].copy_from_slice(&(SEGMENT_VERSION + 1).to_le_bytes());
+    fs::write(&path, segment).unwrap();
+
+    let log = LogFile::with_options(Options {
+      dir: dir.clone(),
+      ..Options::default()
+    })
+    .unwrap();
+    let err = log.start().unwrap_err();
+    assert!(matches!(
+      DbError::from_io(&err),
+      Some(DbError::UnsupportedFormat { version, .. }) if *version == SEGMENT_VERSION + 1
+    ));
+  }
+
+  // ---------------------------------------------------------
+  // hint tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn invalid_hint_falls_back_to_the_segment() {
+    let dir = temp_dir("invalid-hint");
+    let log = open(&dir);
+    fill(&log);
+    let expected = contents(&log);
+    drop(log);
+
+    // A truncated hint and one with a flipped byte in its last entry.
+    let truncated = dir.join(file_names::hint(1));
+    set_len(&truncated, fs::metadata(&truncated).unwrap().len() - 1);
+    let corrupt = dir.join(file_names::hint(2));
+    let mut bytes = fs::read(&corrupt).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xff;
+    fs::write(&corrupt, bytes).unwrap();
+
+    let log = open(&dir);
+    assert_eq!(contents(&log), expected);
+  }
+
+  // ---------------------------------------------------------
+  // compression tests
+  // ---------------------------------------------------------
+
+  fn open_with_compression(dir: &Path, compression: Compression) -> LogFile {
+    let log = LogFile::with_options(Options {
+      dir: dir.to_path_buf(),
+      compression,
+      ..Options::default()
+    })
+    .unwrap();
+    log.start().unwrap();
+    log
+  }
+
+  fn document(i: usize) -> String {
+    format!(r#"{{"id":{i},"name":"duck","tags":["a","b","c"]}}"#).repeat(8)
+  }
+
+  #[test]
+  fn compressed_records_stay_readable_with_compression_off() {
+    let dir = temp_dir("compression-off");
+    let log = open_with_compression(&dir, Compression::Lz);
+    for i in 0..10 {
+      log.append(&format!("doc-{i}"), &document(i)).unwrap();
+    }
+    log.append("short", "value").unwrap();
+    let plain = (0..10).map(|i| document(i).len() as u64).sum::<u64>();
+    assert!(log.stats().unwrap().total_bytes < plain);
+    drop(log);
+
+    let log = open_with_compression(&dir, Compression::None);
+    assert_eq!(log.read("doc-3").unwrap(), document(3));
+    log.update("doc-4", &document(40)).unwrap();
+    let expected = contents(&log);
+    assert_eq!(expected.len(), 11);
+
+    // Compaction copies the compressed records as they are.
+    log.compact().unwrap();
+    assert_eq!(contents(&log), expected);
+    drop(log);
+    let log = open_with_compression(&dir, Compression::None);
+    assert_eq!(contents(&log), expected);
+  }
+
+  // ---------------------------------------------------------
+  // value log tests
+  // ---------------------------------------------------------
+
+  /// A value long enough to go to the value log, one of two per blob file.
+  fn large(i: usize) -> String {
+    format!("{i:0>100}")
+  }
+
+  fn open_with_blobs(dir: &Path, gc_ratio: f64) -> LogFile {
+    let log = LogFile::with_options(Options {
+      dir: dir.to_path_buf(),
+      value_log_threshold: Some(64),
+      value_log_file_size: 200,
+      value_log_gc_ratio: gc_ratio,
+      ..Options::default()
+    })
+    .unwrap();
+    log.start().unwrap();
+    log
+  }
+
+  fn blob_ids(dir: &Path) -> Vec<u64> {
+    let mut file_ids = fs::read_dir(dir)
+      .unwrap()
+      .filter_map(|entry| value_log::parse_file_name(entry.unwrap().file_name().to_str()?))
+      .collect::<Vec<_>>();
+    file_ids.sort();
+    file_ids
+  }
+
+  /// Fills blob file 1 half with garbage, 2 with nothing but garbage, 3
+  /// with live values only, and starts 4.
+  fn fill_blobs(log: &LogFile) -> Vec<(String, String)> {
+    for i in 1..=7 {
+      log.append(&format!("key-{i}"), &large(i)).unwrap();
+    }
+    log.update("key-1", "small").unwrap();
+    log.delete("key-3").unwrap();
+    log.delete("key-4").unwrap();
+    contents(log)
+  }
+
+  #[test]
+  fn large_values_are_read_through_the_value_log() {
+    let dir = temp_dir("blob-read");
+    let log = open_with_blobs(&dir, 1.0);
+    let expected = fill_blobs(&log);
+    assert_eq!(blob_ids(&dir), [1, 2, 3, 4]);
+    assert_eq!(log.read("key-2").unwrap(), large(2));
+    assert_eq!(log.read("key-1").unwrap(), "small");
+
+    // Segments only hold pointers to the values.
+    let segment_bytes = segment_ids(&dir)
+      .into_iter()
+      .map(|file_id| fs::read(dir.join(file_names::segment(file_id))).unwrap())
+      .collect::<Vec<_>>()
+      .concat();
+    assert!(!segment_bytes
+      .windows(100)
+      .any(|window| window == large(2).as_bytes()));
+    drop(log);
+
+    let log = open_with_blobs(&dir, 1.0);
+    assert_eq!(contents(&log), expected);
+  }
+
+  #[test]
+  fn compaction_collects_blob_files_past_the_gc_ratio() {
+    let dir = temp_dir("blob-gc");
+    let log = open_with_blobs(&dir, 1.0);
+    let expected = fill_blobs(&log);
+    let kept = fs::read(dir.join("blob-3")).unwrap();
+
+    log.compact().unwrap();
+    // 1 was half garbage and its live value moved to the active file, 2 had
+    // nothing live left and 3 nothing dead.
+    assert_eq!(blob_ids(&dir), [3, 4]);
+    assert_eq!(fs::read(dir.join("blob-3")).unwrap(), kept);
+    assert_eq!(contents(&log), expected);
+    drop(log);
+
+    let log = open_with_blobs(&dir, 1.0);
+    assert_eq!(contents(&log), expected);
+  }
+
+  #[test]
+  fn compaction_keeps_blob_files_below_the_gc_ratio() {
+    let dir = temp_dir("blob-gc-ratio");
+    let log = open_with_blobs(&dir, 2.0);
+    let expected = fill_blobs(&log);
+    let half_dead = fs::read(dir.join("blob-1")).unwrap();
+
+    log.compact().unwrap();
+    assert_eq!(blob_ids(&dir), [1, 3, 4]);
+    assert_eq!(fs::read(dir.join("blob-1")).unwrap(), half_dead);
+    assert_eq!(contents(&log), expected);
+  }
+
+  // ---------------------------------------------------------
+  // input tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn empty_keys_and_values_are_rejected() {
+    let log = LogFile::in_memory().unwrap();
+    log.append("key", "value").unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch.put("other", "value").put("key", "");
+    for err in [
+      log.append("key", "").unwrap_err(),
+      log.update("key", "").unwrap_err(),
+      log
+        .put_with_ttl("key", "", Duration::from_secs(1))
+        .unwrap_err(),
+      log.write(&batch).unwrap_err(),
+    ] {
+      assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+      assert!(matches!(DbError::from_io(&err), Some(DbError::EmptyValue)));
+    }
+    let err = log.append("", "value").unwrap_err();
+    assert!(matches!(DbError::from_io(&err), Some(DbError::EmptyKey)));
+
+    // Nothing of the rejected batch made it in either.
+    assert_eq!(contents(&log), [("key".to_string(), "value".to_string())]);
+  }
+
+  // ---------------------------------------------------------
+  // ttl tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn update_does_not_revive_an_expired_key() {
+    let log = LogFile::in_memory().unwrap();
+    log
+      .put_with_ttl("session", "token", Duration::from_millis(10))
+      .unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    let err = log.update("session", "revived").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert!(!log.contains_key("session"));
+    assert!(log.read("session").is_err());
+  }
+}
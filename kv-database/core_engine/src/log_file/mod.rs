@@ -1,227 +1,857 @@
 use std::{
-  collections::HashMap,
-  fs::{self, File, OpenOptions},
-  io::{self, Write},
-  os::unix::fs::{FileExt, MetadataExt},
-  sync::{Arc, Mutex, MutexGuard},
+  collections::{BTreeSet, HashMap, HashSet},
+  fmt,
+  fs::{self, File, OpenOptions, TryLockError},
+  io::{self, BufRead, Read, Write},
+  ops::Range,
+  path::Path,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    mpsc::Receiver,
+    Arc, Mutex, MutexGuard, OnceLock, RwLock, RwLockWriteGuard,
+  },
+  time::Duration,
 };
 
-use chrono::Utc;
-use serde;
-use ttlog::{
-  file_listener::FileListener,
-  stdout_listener::StdoutListener,
-  trace::Trace,
-  ttlog_macros::{error, info, trace},
+use chrono::{DateTime, Utc};
+use utils::codec::{RecordReader, RecordWriter};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::compaction::CompactionHandle;
+use crate::{
+  backup::{self, BackupManifest},
+  clock::Instant,
+  column_family::{self, ColumnFamily},
+  compaction::Manifest,
+  compression,
+  csv_format::{self, CsvOptions},
+  cursor::{Cursor, Snapshots},
+  error::DbError,
+  file_names,
+  group_commit::GroupCommit,
+  hint::{self, HintEntry},
+  hooks::{CompactionInfo, CompactionStartInfo, FlushInfo, HookEvent, Hooks, SegmentSealedInfo},
+  jsonl,
+  keydir::{KeyDir, Segments, Shard},
+  logging::{error, info, trace},
+  merge::MergeOperator,
+  metrics::Metrics,
+  options::{Compression, Options, SyncPolicy},
+  rate_limiter::RateLimiter,
+  repair::{QuarantinedRange, RepairReport, QUARANTINE_DIR},
+  replication::{Change, Feed},
+  segment_writer::{MemorySegment, SegmentWriter},
+  stats::{SegmentStats, Stats},
+  storage::{self, ReadAt},
+  syncer::Syncer,
+  value_log::{BlobPointer, ValueLog},
+  verify::{Problem, ProblemKind, SegmentReport, VerifyReport},
+  watch::{Event, Watchers},
+  write_batch::{BatchOp, WriteBatch},
 };
 
+mod __test__;
+
 const FILE_THRESHOLD: u64 = 1024; // 1KB
+const MAX_OPEN_READERS: usize = 64;
+/// Records an import hands to the log in one batch.
+const IMPORT_BATCH_SIZE: usize = 1024;
+const COMPACTION_MANIFEST: &str = "COMPACTION";
+const HEADER_SIZE: u64 = 4 + 8 * 6; // crc, timestamp, sequence, record type, expiry, key size, value size
+/// Every segment starts with `SEGMENT_MAGIC` and `SEGMENT_VERSION` as a
+/// u32. Segments written before the header existed start with their first
+/// record, laid out as in version 1. The first release wrote records
+/// without sequence numbers or checksums, version 0, which isn't readable.
+const SEGMENT_MAGIC: &[u8; 4] = b"DKVS";
+const SEGMENT_VERSION: u32 = 1;
+const SEGMENT_HEADER_SIZE: u64 = 8;
+const NO_EXPIRY: i64 = 0;
+const RECORD_VALUE: u64 = 0;
+const RECORD_MERGE: u64 = 1;
+/// Low bits of `record_type` hold the type, higher bits are flags.
+const RECORD_TYPE_MASK: u64 = 0xff;
+/// The value is stored compressed (see [`crate::compression`]).
+const RECORD_COMPRESSED: u64 = 1 << 8;
+/// The value is a [`BlobPointer`] into the value log.
+const RECORD_BLOB: u64 = 1 << 9;
+/// Values shorter than this are never worth compressing.
+const MIN_COMPRESSED_VALUE: usize = 32;
 pub const PERIODIC_COMPACTION_INTERVAL: u64 = 60 * 10; // 10 minutes
+pub const COMPACTION_CHECK_INTERVAL: u64 = 30; // 30 seconds
 
 #[derive(Debug)]
 struct MetaIndex {
   timestamp: i64,
+  seq: u64,
+  /// `RECORD_VALUE` (a put, or a delete when the value is empty) or
+  /// `RECORD_MERGE`, possibly with `RECORD_COMPRESSED` or `RECORD_BLOB`
+  /// set.
+  record_type: u64,
+  /// Unix time in nanoseconds after which the record reads as missing, or
+  /// `NO_EXPIRY`.
+  expires_at: i64,
   key_size: usize,
   key_buf: Vec<u8>,
   value_size: usize,
   value_buf: Vec<u8>,
 }
 
-#[derive(Debug)]
-struct Index {
+impl MetaIndex {
+  fn is_expired(&self) -> bool {
+    is_expired(self.expires_at)
+  }
+
+  fn kind(&self) -> RecordKind {
+    if self.record_type & RECORD_TYPE_MASK == RECORD_MERGE {
+      RecordKind::Merge
+    } else if self.value_buf.is_empty() {
+      RecordKind::Delete
+    } else {
+      RecordKind::Put
+    }
+  }
+
+  /// Where the value lives in the value log, if it was stored there.
+  fn blob(&self) -> Result<Option<BlobPointer>, io::Error> {
+    if self.record_type & RECORD_BLOB == 0 {
+      return Ok(None);
+    }
+    BlobPointer::decode(&self.value_buf).map(Some)
+  }
+
+  /// The value as written by the user, decompressed if needed. Values in the
+  /// value log have to go through [`LogFile::value_of`] instead.
+  fn into_value(self) -> Result<Vec<u8>, io::Error> {
+    if self.record_type & RECORD_COMPRESSED == 0 {
+      return Ok(self.value_buf);
+    }
+    compression::decompress(&self.value_buf)
+  }
+
+  /// Size of the whole record on disk, header included.
+  fn len(&self) -> u64 {
+    HEADER_SIZE + (self.key_size + self.value_size) as u64
+  }
+}
+
+/// What a record does to its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecordKind {
+  Put,
+  Delete,
+  Merge,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Index {
   file_id: u64,
   offset: u64,
+  seq: u64,
+  /// Size of the whole record on disk, header included.
+  len: u64,
+  expires_at: i64,
+}
+
+impl Index {
+  pub(crate) fn is_expired(&self) -> bool {
+    is_expired(self.expires_at)
+  }
+}
+
+fn is_expired(expires_at: i64) -> bool {
+  expires_at != NO_EXPIRY && expires_at <= Utc::now().timestamp_nanos_opt().unwrap()
+}
+
+/// Keys, merge operands and the values they fold into are written from
+/// strings, so bytes that aren't UTF-8 mean the record is corrupt.
+fn utf8(bytes: Vec<u8>) -> Result<String, io::Error> {
+  String::from_utf8(bytes).map_err(|_| {
+    io::Error::new(
+      io::ErrorKind::InvalidData,
+      "Corrupted record: expected UTF-8 text",
+    )
+  })
+}
+
+/// A superseded version of a key, kept visible to older sequence numbers
+/// until compaction drops it. `index` is the base value (`None` for a delete
+/// or a key built only from merges) and `operands` the merges on top of it.
+#[derive(Debug, Clone)]
+pub(crate) struct Version {
+  seq: u64,
+  index: Option<Index>,
+  operands: Vec<Index>,
+}
+
+/// What compaction keeps of a key: its newest value and the merge operands
+/// written after it.
+#[derive(Debug, Default)]
+struct Survivor {
+  base: Option<MetaIndex>,
+  operands: Vec<MetaIndex>,
+}
+
+/// The segment a compaction produced and where its records ended up.
+#[derive(Debug)]
+struct Compacted {
+  output_id: u64,
+  size: u64,
+  data_index: HashMap<String, Index>,
+  merges: HashMap<String, Vec<Index>>,
+}
+
+/// `Options::max_key_size` and `Options::max_value_size`, which every
+/// record parsed from a segment is checked against before its key and value
+/// are read.
+#[derive(Debug, Clone, Copy)]
+struct SizeLimits {
+  key: usize,
+  value: usize,
+}
+
+impl SizeLimits {
+  fn check(&self, key_size: usize, value_size: usize) -> Result<(), io::Error> {
+    if key_size > self.key || value_size > self.value {
+      return Err(
+        DbError::RecordTooLarge {
+          key_size,
+          value_size,
+        }
+        .into(),
+      );
+    }
+    Ok(())
+  }
+}
+
+/// Carried by the `UnexpectedEof` error for a record header of all zeros,
+/// which is the preallocated space past the last record of a segment.
+#[derive(Debug)]
+struct Unwritten;
+
+impl fmt::Display for Unwritten {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Unwritten space at the end of the segment")
+  }
+}
+
+impl std::error::Error for Unwritten {}
+
+/// Where records are parsed from: a segment file, or the contents of an
+/// in-memory segment.
+trait RecordSource {
+  /// Parses the record at `offset`, which must end by `end`.
+  fn read_record(
+    &self,
+    offset: &mut u64,
+    end: u64,
+    limits: SizeLimits,
+  ) -> Result<MetaIndex, io::Error>;
+  fn size(&self) -> Result<u64, io::Error>;
+  /// The first `len` bytes, or all of them if there are fewer.
+  fn prefix(&self, len: u64) -> Result<Vec<u8>, io::Error>;
+}
+
+impl RecordSource for File {
+  fn read_record(
+    &self,
+    offset: &mut u64,
+    end: u64,
+    limits: SizeLimits,
+  ) -> Result<MetaIndex, io::Error> {
+    LogFile::get_index_from_file(offset, end, self, limits)
+  }
+
+  fn size(&self) -> Result<u64, io::Error> {
+    Ok(self.metadata()?.len())
+  }
+
+  fn prefix(&self, len: u64) -> Result<Vec<u8>, io::Error> {
+    let mut buf = vec![0; self.size()?.min(len) as usize];
+    self.read_exact_at(&mut buf, 0)?;
+    Ok(buf)
+  }
+}
+
+impl RecordSource for [u8] {
+  fn read_record(
+    &self,
+    offset: &mut u64,
+    _end: u64,
+    limits: SizeLimits,
+  ) -> Result<MetaIndex, io::Error> {
+    LogFile::get_index_from_slice(offset, self, limits)
+  }
+
+  fn size(&self) -> Result<u64, io::Error> {
+    Ok(self.len() as u64)
+  }
+
+  fn prefix(&self, len: u64) -> Result<Vec<u8>, io::Error> {
+    Ok(self[..self.len().min(len as usize)].to_vec())
+  }
 }
 
+/// Writer state lives in `inner` and the index readers need lives in the
+/// sharded `keydir`, so reads only ever take a shared lock on one shard and
+/// never wait on each other or on an in-flight append. Lock order is `inner`,
+/// then the keydir (see [`crate::keydir`]), then `mmaps`, `readers` or
+/// `memory`.
 #[derive(Debug, Clone)]
 pub struct LogFile {
   inner: Arc<Mutex<Inner>>,
+  keydir: Arc<KeyDir>,
+  mmaps: Arc<Mutex<HashMap<u64, Arc<storage::Map>>>>,
+  /// Read handles per segment, shared since all reads are positional.
+  readers: Arc<Mutex<HashMap<u64, Arc<File>>>>,
+  /// Every segment's contents under `Options::in_memory`, which has no files.
+  memory: Arc<Mutex<HashMap<u64, MemorySegment>>>,
+  options: Arc<Options>,
+  reads: Arc<AtomicU64>,
+  commit: Arc<GroupCommit>,
+  compaction_limiter: Arc<RateLimiter>,
+  /// Held for the whole of a compaction, which only takes the writer lock
+  /// to start and to swap its output in.
+  compacting: Arc<Mutex<()>>,
+  families: Arc<Mutex<HashMap<String, LogFile>>>,
+  /// The flocked `LOCK` file, held until the last clone is dropped.
+  dir_lock: Arc<OnceLock<File>>,
+  /// Background fsync, only running under `SyncPolicy::Interval`.
+  syncer: Arc<OnceLock<Syncer>>,
+  /// Recent changes for replication followers, recorded from the moment a
+  /// [`Primary`](crate::replication::Primary) is started on this log.
+  feed: Arc<OnceLock<Arc<Feed>>>,
+  /// Subscribers of [`watch`](Self::watch) and the events they still wait
+  /// for.
+  watchers: Arc<Watchers>,
+  /// Snapshots pinned by open [`Cursor`]s.
+  snapshots: Arc<Snapshots>,
+  hooks: Arc<Hooks>,
+  metrics: Arc<Metrics>,
+  value_log: Arc<ValueLog>,
+}
+
+/// A write that is in the log but may not be on disk yet.
+///
+/// Returned by the `*_deferred` methods. [`wait`](Self::wait) blocks until the
+/// group commit covering it has synced, so many writers share one fsync.
+#[derive(Debug)]
+pub struct PendingWrite {
+  seq: u64,
+  log: LogFile,
+}
+
+impl PendingWrite {
+  /// Sequence number stamped on the write.
+  pub fn seq(&self) -> u64 {
+    self.seq
+  }
+
+  pub fn is_durable(&self) -> bool {
+    self.log.commit.durable_seq() >= self.seq
+  }
+
+  /// Blocks until the write is durable and returns its sequence number.
+  /// Under `SyncPolicy::Interval` and `SyncPolicy::Os` this returns right
+  /// away and the write becomes durable whenever the next sync happens.
+  pub fn wait(self) -> Result<u64, io::Error> {
+    if self.log.options.sync_policy == SyncPolicy::Always {
+      self.log.commit.wait(self.seq, || self.log.sync_active())?;
+      self.log.watchers.release(self.log.commit.durable_seq());
+    }
+    Ok(self.seq)
+  }
 }
 
+/// State owned by writers: where the next record goes and what compaction
+/// needs to know.
 #[derive(Debug)]
 struct Inner {
   byte_offset: u64,
   current_file_id: u64,
   path: String,
-  data_index: HashMap<String, Index>,
-  file_index: HashMap<u64, String>,
+  /// Append handle for the active segment, only reopened on rotation.
+  file: Option<Arc<SegmentWriter>>,
+  last_seq: u64,
+  /// Bytes per segment taken up by overwritten records and tombstones.
+  dead_bytes: HashMap<u64, u64>,
+  /// On-disk size of every sealed segment. The active one is `byte_offset`.
+  segment_sizes: HashMap<u64, u64>,
+  last_compaction: Option<DateTime<Utc>>,
+  writes: u64,
+}
+
+impl Inner {
+  fn active(&self) -> Result<Arc<SegmentWriter>, io::Error> {
+    self
+      .file
+      .clone()
+      .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "The log has not been started"))
+  }
 }
 
 impl LogFile {
   pub fn new() -> Result<Self, std::io::Error> {
+    Self::with_options(Options::default())
+  }
+
+  pub fn with_options(options: Options) -> Result<Self, std::io::Error> {
     Ok(Self {
       inner: Arc::new(Mutex::new(Inner {
         path: "".to_string(),
+        file: None,
         byte_offset: 0x1,
         current_file_id: 0x1,
-        data_index: HashMap::new(),
-        file_index: HashMap::new(),
+        last_seq: 0,
+        dead_bytes: HashMap::new(),
+        segment_sizes: HashMap::new(),
+        last_compaction: None,
+        writes: 0,
       })),
+      keydir: Arc::new(KeyDir::new(options.keydir_shards)),
+      mmaps: Arc::new(Mutex::new(HashMap::new())),
+      readers: Arc::new(Mutex::new(HashMap::new())),
+      memory: Arc::new(Mutex::new(HashMap::new())),
+      commit: Arc::new(GroupCommit::new(options.group_commit_window)),
+      compaction_limiter: Arc::new(RateLimiter::new(options.compaction_rate_limit)),
+      compacting: Arc::new(Mutex::new(())),
+      families: Arc::new(Mutex::new(HashMap::new())),
+      dir_lock: Arc::new(OnceLock::new()),
+      syncer: Arc::new(OnceLock::new()),
+      feed: Arc::new(OnceLock::new()),
+      watchers: Arc::new(Watchers::default()),
+      snapshots: Arc::new(Snapshots::default()),
+      hooks: Arc::new(Hooks::default()),
+      metrics: Arc::new(Metrics::default()),
+      reads: Arc::new(AtomicU64::new(0)),
+      value_log: Arc::new(ValueLog::new(
+        options.dir.clone(),
+        options.value_log_file_size,
+      )),
+      options: Arc::new(options),
     })
   }
 
-  fn read_hint_file(&self, inner: &mut MutexGuard<'_, Inner>) -> Result<(), std::io::Error> {
-    let path = format!("./tmp/hint-{}", inner.current_file_id);
-    if !fs::exists(&path)? {
-      return Ok(());
-    }
-
-    let hint_file = OpenOptions::new().read(true).open(&path)?;
-    let mut offset = 0;
-
-    loop {
-      if fs::metadata(&path)?.size() <= offset {
-        break;
-      }
-
-      let mut key_size_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut key_size_buf, offset)?;
-      let key_size = u64::from_le_bytes(key_size_buf);
-      offset += 8;
-
-      let mut key_buf = vec![0u8; key_size as usize];
-      hint_file.read_exact_at(&mut key_buf, offset)?;
-      let key_value = String::from_utf8(key_buf.clone()).unwrap();
-      offset += key_size;
-
-      // adding because here we read the timestamp
-      offset += 8;
-
-      let mut file_id_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut file_id_buf, offset)?;
-      let file_id = u64::from_le_bytes(file_id_buf);
-      offset += 8;
-
-      let mut offset_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut offset_buf, offset)?;
-      let offset_value = u64::from_le_bytes(offset_buf);
-      offset += 8;
-
-      inner.data_index.insert(
-        key_value,
-        Index {
-          offset: offset_value,
-          file_id,
-        },
-      );
-    }
+  /// Opens and starts a log that lives entirely in memory, see
+  /// `Options::in_memory`. It supports the whole API; checkpoints and backups
+  /// of it are written to disk as usual.
+  pub fn in_memory() -> Result<Self, io::Error> {
+    let log = Self::with_options(Options {
+      in_memory: true,
+      ..Options::default()
+    })?;
+    log.start()?;
+    Ok(log)
+  }
 
-    Ok(())
+  /// Path of `name` inside the data directory.
+  fn file_path(&self, name: &str) -> String {
+    self.options.dir.join(name).to_string_lossy().into_owned()
   }
 
   pub fn start(&self) -> Result<(), std::io::Error> {
-    fs::create_dir_all("tmp")?;
+    if cfg!(target_arch = "wasm32") && matches!(self.options.sync_policy, SyncPolicy::Interval(_)) {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SyncPolicy::Interval needs a background thread, which wasm32 doesn't have",
+      ));
+    }
 
-    // rebuild index from hint
-    {
+    if self.options.in_memory {
       let mut inner = self.inner.lock().unwrap();
-      self.read_hint_file(&mut inner)?;
+      if inner.file.is_none() {
+        self.create(&mut inner)?;
+      }
+      return Ok(());
     }
 
-    // rebuild from log files
-    {
-      let mut inner = self.inner.lock().unwrap();
+    let dir = &self.options.dir;
+    fs::create_dir_all(dir)?;
+
+    let mut inner = self.inner.lock().unwrap();
+    self.lock_dir()?;
+    self.migrate_file_names()?;
+    self.recover_compaction()?;
+    self.remove_orphans()?;
 
-      let mut files = fs::read_dir("./tmp")?
+    // rebuild from hint files where a segment has one, else from the log
+    {
+      // Segments are replayed oldest first so later records win.
+      let mut files = fs::read_dir(dir)?
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
           let path = entry.path();
-          let file_name = path.file_name()?.to_str()?;
-          if let Some(number_str) = file_name.strip_prefix("log-file-") {
-            if number_str.parse::<u64>().is_ok() {
-              return Some(path);
-            }
-          }
-          None
+          let file_id = file_names::parse_segment(path.file_name()?.to_str()?)?;
+          Some((file_id, path.to_str()?.to_string()))
         })
         .collect::<Vec<_>>();
+      files.sort_by_key(|&(file_id, _)| file_id);
 
-      files.sort_by_key(|path| {
-        path
-          .file_name()
-          .unwrap()
-          .to_str()
-          .unwrap()
-          .strip_prefix("log-file-")
-          .unwrap()
-          .parse::<u64>()
-          .unwrap()
-      });
-
-      for file_path in &files {
+      for (position, (file_id, file_path)) in files.iter().enumerate() {
+        let file_id = *file_id;
         let file = File::open(file_path)?;
-        let file_id = file_path
-          .file_name()
-          .unwrap()
-          .to_str()
-          .unwrap()
-          .strip_prefix("log-file-")
-          .unwrap()
-          .parse::<u64>()
-          .unwrap();
-        let metadata = fs::metadata(file_path)?;
-
-        inner
+        let size = file.metadata()?.len();
+
+        self
+          .keydir
+          .segments_mut()
           .file_index
-          .insert(file_id, file_path.to_str().unwrap().to_string());
+          .insert(file_id, file_path.clone());
+        inner.segment_sizes.insert(file_id, size);
+        let mut offset = Self::segment_start(file_id, &file)?;
+
+        if let Some(entries) = self.read_hint_file(file_id)? {
+          for entry in entries {
+            let record = Index {
+              offset: entry.offset,
+              file_id,
+              seq: entry.seq,
+              len: entry.len,
+              expires_at: entry.expires_at,
+            };
+            inner.last_seq = inner.last_seq.max(entry.seq);
+            let mut shard = self.keydir.write(&entry.key);
+            Self::install_version(&mut inner, &mut shard, &entry.key, record, entry.kind);
+          }
+          continue;
+        }
 
-        let mut offset = 0;
         loop {
-          if metadata.size() <= offset {
+          if size <= offset {
             break;
           }
 
-          let index = Index { offset, file_id };
-
-          let meta = match self.get_index_from_file(&mut offset, &file) {
-            Ok(meta) => meta,
+          let entry_offset = offset;
+          let records = match Self::read_entry(&mut offset, size, &file, self.limits()) {
+            // The rest was preallocated but never written before a crash.
+            Ok(records) if records.is_empty() => {
+              OpenOptions::new()
+                .write(true)
+                .open(file_path)?
+                .set_len(entry_offset)?;
+              inner.segment_sizes.insert(file_id, entry_offset);
+              break;
+            }
+            Ok(records) => records,
+            // Only the newest segment can end in a torn write. Cut it back
+            // to the last whole record so nothing reads the garbage again.
+            Err(e)
+              if position + 1 == files.len()
+                && matches!(
+                  e.kind(),
+                  io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+                )
+                // A torn write can't have a complete header with sizes the
+                // writer would have refused; don't truncate over a limit.
+                && DbError::from_io(&e).is_none() =>
+            {
+              // A crash only tears the last write. Damage with intact
+              // records after it is corruption, and truncating would throw
+              // those acknowledged writes away.
+              let mut tail = vec![0; (size - entry_offset) as usize];
+              file.read_exact_at(&mut tail, entry_offset)?;
+              if Self::intact_after_damage(&tail, self.limits()) {
+                return Err(io::Error::new(
+                  io::ErrorKind::InvalidData,
+                  format!(
+                    "Segment {file_id} is damaged at offset {entry_offset} but has intact records after it, run repair to salvage them: {e}"
+                  ),
+                ));
+              }
+              error!(
+                "[RECOVERY] Truncating torn write at the end of the log.",
+                file_id = file_id,
+                offset = entry_offset,
+                error = e.to_string()
+              );
+              OpenOptions::new()
+                .write(true)
+                .open(file_path)?
+                .set_len(entry_offset)?;
+              inner.segment_sizes.insert(file_id, entry_offset);
+              break;
+            }
             Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
             Err(e) => return Err(e),
           };
 
-          let key = String::from_utf8(meta.key_buf.clone()).unwrap();
-          if meta.value_buf.is_empty() {
-            inner.data_index.remove(&key);
-          } else {
-            inner.data_index.insert(key, index);
+          for (record_offset, meta) in records {
+            let key = utf8(meta.key_buf.clone())?;
+            let record = Index {
+              offset: record_offset,
+              file_id,
+              seq: meta.seq,
+              len: meta.len(),
+              expires_at: meta.expires_at,
+            };
+            inner.last_seq = inner.last_seq.max(meta.seq);
+            let mut shard = self.keydir.write(&key);
+            Self::install_version(&mut inner, &mut shard, &key, record, meta.kind());
           }
         }
       }
 
-      let id = files
-        .last()
-        .map(|path| {
-          path
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .strip_prefix("log-file-")
-            .unwrap()
-            .parse::<u64>()
-            .unwrap()
-        })
-        .unwrap_or(0x1);
+      // Never append to a recovered segment: the next id starts a new one.
+      inner.current_file_id = files.last().map_or(1, |&(file_id, _)| file_id + 1);
+      self.create(&mut inner)?;
+
+      // Everything recovered from disk is already durable.
+      self.commit.mark_durable(inner.last_seq);
+    }
 
-      inner.current_file_id = id + 1;
+    if let SyncPolicy::Interval(interval) = self.options.sync_policy {
+      self.syncer.get_or_init(|| self.spawn_syncer(interval));
     }
+    Ok(())
+  }
+
+  /// Starts the `SyncPolicy::Interval` thread. It only holds a weak handle
+  /// on the writer state and stops once the log is gone.
+  fn spawn_syncer(&self, interval: Duration) -> Syncer {
+    let inner = Arc::downgrade(&self.inner);
+    let commit = self.commit.clone();
+    let watchers = self.watchers.clone();
+    let hooks = self.hooks.clone();
+    let metrics = self.metrics.clone();
+    let value_log = self.value_log.clone();
+    Syncer::spawn(interval, move || {
+      let Some(inner) = inner.upgrade() else {
+        return false;
+      };
+      match Self::sync_inner(&inner, &value_log, &hooks, &metrics) {
+        Ok(seq) => {
+          commit.mark_durable(seq);
+          watchers.release(seq);
+        }
+        Err(e) => error!("[SYNC] Periodic sync failed.", error = e.to_string()),
+      }
+      true
+    })
+  }
 
-    // we drop the lock BEFORE calling create()
-    self.create()?;
+  /// Hands buffered appends to the OS without waiting for them to reach
+  /// the disk. See `Options::write_buffer_size`.
+  pub fn flush(&self) -> Result<(), io::Error> {
+    let file = self.inner.lock().unwrap().active()?;
+    file.flush()
+  }
 
+  /// Flushes everything written so far to disk, whatever the sync policy.
+  pub fn sync(&self) -> Result<(), io::Error> {
+    let seq = self.sync_active()?;
+    self.commit.mark_durable(seq);
+    self.watchers.release(seq);
     Ok(())
   }
 
-  fn create(&self) -> Result<(), std::io::Error> {
-    let mut inner = self.inner.lock().unwrap();
-    let path = format!("./tmp/log-file-{}", inner.current_file_id);
+  /// Subscribes to changes of keys starting with `prefix` (`""` for every
+  /// key). Each put, delete and merge is delivered once it is durable under
+  /// the configured `Options::sync_policy`, in write order. Dropping the
+  /// receiver unsubscribes.
+  pub fn watch(&self, prefix: &str) -> Receiver<Event> {
+    self.watchers.subscribe(prefix)
+  }
+
+  /// Writes a consistent copy of the database to `dest_dir`, which must not
+  /// exist or be empty. The copy can be opened directly with its own
+  /// `LogFile`, and serves as the full backup later
+  /// [`backup_incremental`](Self::backup_incremental) calls build on.
+  ///
+  /// Writes and compaction wait while the segments are captured. Sealed
+  /// segments and hint files are hardlinked where possible; the active
+  /// segment is flushed and copied. Column families are checkpointed into
+  /// matching subdirectories afterwards, each consistent on its own.
+  pub fn checkpoint(&self, dest_dir: impl AsRef<Path>) -> Result<(), io::Error> {
+    self.backup_to(dest_dir.as_ref(), None)?;
+    Ok(())
+  }
+
+  /// Like [`checkpoint`](Self::checkpoint), but only stores the segments
+  /// created since the backup whose manifest is at `since_manifest`, plus
+  /// the segment that was still active then. The result is not usable on its
+  /// own: [`backup::restore`] reassembles it with the backups before it.
+  pub fn backup_incremental(
+    &self,
+    dest_dir: impl AsRef<Path>,
+    since_manifest: impl AsRef<Path>,
+  ) -> Result<BackupManifest, io::Error> {
+    self.backup_to(dest_dir.as_ref(), Some(since_manifest.as_ref()))
+  }
+
+  fn backup_to(
+    &self,
+    dest_dir: &Path,
+    since_manifest: Option<&Path>,
+  ) -> Result<BackupManifest, io::Error> {
+    let since = since_manifest.map(BackupManifest::read).transpose()?;
+    backup::create_dest(dest_dir)?;
+
+    let manifest = {
+      let inner = self.inner.lock().unwrap();
+      inner.active()?.flush()?;
+
+      let file_index = self.keydir.segments().file_index.clone();
+      let mut manifest = BackupManifest {
+        segments: file_index.keys().copied().collect(),
+        active: inner.current_file_id,
+        copied: Vec::new(),
+      };
+      manifest.segments.sort();
+
+      for &file_id in &manifest.segments {
+        if since
+          .as_ref()
+          .is_some_and(|since| !since.needs_copy(file_id))
+        {
+          continue;
+        }
+        manifest.copied.push(file_id);
+
+        let path = Path::new(&file_index[&file_id]);
+        let name = file_names::segment(file_id);
+        if self.options.in_memory {
+          let segment = self.memory_segment(file_id)?;
+          backup::write(&dest_dir.join(name), &segment.read().unwrap())?;
+          continue;
+        }
+        if file_id == inner.current_file_id {
+          // Leaves out the preallocated space past the last record.
+          backup::copy_prefix(path, &dest_dir.join(name), inner.byte_offset)?;
+          continue;
+        }
+
+        backup::link_or_copy(path, &dest_dir.join(name))?;
+        let hint = file_names::hint(file_id);
+        let hint_path = self.options.dir.join(&hint);
+        if fs::exists(&hint_path)? {
+          backup::link_or_copy(&hint_path, &dest_dir.join(hint))?;
+        }
+      }
+
+      // Blob files aren't tracked by the manifest; every backup has them all.
+      if !self.options.in_memory {
+        let active = self.value_log.active_id();
+        for file_id in self.value_log.file_ids()? {
+          let path = self.value_log.path(file_id);
+          let dest = dest_dir.join(path.file_name().unwrap());
+          match Some(file_id) == active {
+            true => backup::copy(&path, &dest)?,
+            false => backup::link_or_copy(&path, &dest)?,
+          }
+        }
+      }
 
-    OpenOptions::new().create(true).append(true).open(&path)?;
+      manifest.write(&dest_dir.join(backup::MANIFEST))?;
+      backup::sync_dir(dest_dir)?;
+      manifest
+    };
+
+    for name in self.column_families()? {
+      let family_dir = column_family::dir_name(&name);
+      // A family created after the previous backup gets a full copy.
+      let family_since = since_manifest
+        .and_then(Path::parent)
+        .map(|dir| dir.join(&family_dir).join(backup::MANIFEST))
+        .filter(|path| path.exists());
+      self
+        .column_family(&name)?
+        .backup_to(&dest_dir.join(&family_dir), family_since.as_deref())?;
+    }
+
+    info!(
+      "[BACKUP] Backup has been written successfully.",
+      dest_dir = dest_dir.display().to_string(),
+      segments = manifest.copied.len() as u64
+    );
+    Ok(manifest)
+  }
+
+  /// Loads the hint file of segment `file_id` if there is one. A hint that fails validation
+  /// is ignored so the segment gets scanned instead: a stale or truncated
+  /// hint must never put wrong offsets in the keydir.
+  fn read_hint_file(&self, file_id: u64) -> Result<Option<Vec<HintEntry>>, io::Error> {
+    let path = self.file_path(&file_names::hint(file_id));
+    if !fs::exists(&path)? {
+      return Ok(None);
+    }
+
+    match hint::read(&path) {
+      Ok(entries) => Ok(Some(entries)),
+      Err(e)
+        if matches!(
+          e.kind(),
+          io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+        ) =>
+      {
+        error!(
+          "[HINT] Ignoring invalid hint file.",
+          file_id = file_id,
+          error = e.to_string()
+        );
+        Ok(None)
+      }
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Takes an exclusive lock on the data directory so no other instance can
+  /// append to the same segments. Starting twice keeps the lock we have.
+  fn lock_dir(&self) -> Result<(), io::Error> {
+    if self.dir_lock.get().is_some() {
+      return Ok(());
+    }
+
+    let file = OpenOptions::new()
+      .create(true)
+      .truncate(false)
+      .write(true)
+      .open(self.file_path("LOCK"))?;
+    match file.try_lock() {
+      Ok(()) => {}
+      Err(TryLockError::WouldBlock) => {
+        return Err(
+          DbError::AlreadyLocked {
+            dir: self.options.dir.clone(),
+          }
+          .into(),
+        )
+      }
+      Err(TryLockError::Error(e)) => return Err(e),
+    }
+
+    let _ = self.dir_lock.set(file);
+    Ok(())
+  }
+
+  fn create(&self, inner: &mut Inner) -> Result<(), std::io::Error> {
+    let path = self.file_path(&file_names::segment(inner.current_file_id));
+
+    let writer = if self.options.in_memory {
+      let segment = MemorySegment::default();
+      self
+        .memory
+        .lock()
+        .unwrap()
+        .insert(inner.current_file_id, segment.clone());
+      Arc::new(SegmentWriter::in_memory(segment))
+    } else {
+      Arc::new(SegmentWriter::open(
+        &path,
+        self.options.write_buffer_size,
+        FILE_THRESHOLD,
+      )?)
+    };
+    inner.file = Some(writer.clone());
     inner.path = path;
     let path = inner.path.clone();
     let id = inner.current_file_id;
-    inner.file_index.insert(id, path);
-    inner.byte_offset = 0;
+    let mut segments = self.keydir.segments_mut();
+    segments.file_index.insert(id, path);
+    segments.active_file_id = id;
+    segments.active_writer = Some(writer);
+    drop(segments);
+    inner.active()?.write_all(&Self::segment_header())?;
+    inner.byte_offset = SEGMENT_HEADER_SIZE;
 
     trace!(
       "[LOGFILE] Log file has been created successfully.",
@@ -231,213 +861,2002 @@ impl LogFile {
   }
 
   pub fn append<'a>(&self, key: &str, value: &'a str) -> Result<&'a str, io::Error> {
-    let mut inner = self.inner.lock().unwrap();
-    if key.is_empty() {
-      error!("The index length should be at least 1 character");
-      return Err(io::Error::other(""));
-    }
+    let _timer = self.metrics.write_latency.start_timer();
+    self.append_deferred(key, value)?.wait()?;
 
-    let data_size = (value.len() + key.len() + 8 * 3) as u64;
-    let index_value = Index {
-      offset: inner.byte_offset,
-      file_id: inner.current_file_id,
-    };
+    info!("[WRITE]", index_value = value.to_string());
+    Ok(value)
+  }
 
-    inner.data_index.insert(key.to_string(), index_value);
-    inner.byte_offset += data_size;
+  /// Like [`append`](Self::append), but returns once the record is in the
+  /// log instead of waiting for it to be durable.
+  pub fn append_deferred(&self, key: &str, value: &str) -> Result<PendingWrite, io::Error> {
+    self.check_put(key, value)?;
+    let mut inner = self.inner.lock().unwrap();
 
-    let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
+    let seq = self.write_record(&mut inner, key, value, RECORD_VALUE, NO_EXPIRY)?;
+    Ok(self.pending(seq))
+  }
 
-    self.insert_index_value(
-      MetaIndex {
-        timestamp,
-        key_size: key.len(),
-        key_buf: key.as_bytes().to_vec(),
-        value_size: value.len(),
-        value_buf: value.as_bytes().to_vec(),
-      },
-      &mut inner,
-    )?;
+  /// Like [`append`](Self::append), but the key reads as missing once `ttl`
+  /// has elapsed and the next compaction drops it.
+  pub fn put_with_ttl<'a>(
+    &self,
+    key: &str,
+    value: &'a str,
+    ttl: Duration,
+  ) -> Result<&'a str, io::Error> {
+    let _timer = self.metrics.write_latency.start_timer();
+    self.check_put(key, value)?;
+    let mut inner = self.inner.lock().unwrap();
 
-    info!("[WRITE]", index_value = value.to_string());
+    let expires_at = chrono::Duration::from_std(ttl)
+      .ok()
+      .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+      .and_then(|t| t.timestamp_nanos_opt())
+      .ok_or_else(|| {
+        io::Error::new(
+          io::ErrorKind::InvalidInput,
+          "The ttl puts the expiry past the representable time range",
+        )
+      })?;
+    let seq = self.write_record(&mut inner, key, value, RECORD_VALUE, expires_at)?;
+    drop(inner);
+    self.pending(seq).wait()?;
+
+    info!(
+      "[WRITE]",
+      index_value = value.to_string(),
+      expires_at = expires_at
+    );
     Ok(value)
   }
 
   pub fn read(&self, id: &str) -> Result<String, io::Error> {
-    if !self.inner.lock().unwrap().data_index.contains_key(id) {
-      return Err(io::Error::other("This key does not exist in the index"));
+    let _timer = self.metrics.read_latency.start_timer();
+    self.reads.fetch_add(1, Ordering::Relaxed);
+    let shard = self.keydir.read(id);
+    if !shard.contains(id) {
+      return Err(Self::missing());
     }
 
-    let index = self.get_index_value(id)?;
+    let value = self.get_value(&shard, id)?;
+    drop(shard);
 
-    // let timestamp = Utc.timestamp_opt(index.timestamp, 0);
-    // let timestamp = timestamp.unwrap().to_string();
-    // let index_key_value = String::from_utf8(index.key_buf).unwrap().to_string();
-    let value = String::from_utf8(index.value_buf).unwrap().to_string();
     info!("[READ]", key = id.to_string(), value = value);
     Ok(value)
   }
 
-  pub fn update(&self, key: &str, value: &str) -> Result<String, io::Error> {
-    let mut inner = self.inner.lock().unwrap();
-    if key.is_empty() {
-      error!("The index length should be at least 1 character");
-      return Err(io::Error::other(""));
-    }
+  /// Reads `key` as it was visible right after the write stamped with `seq`.
+  ///
+  /// Older versions stay readable until the next compaction drops them.
+  pub fn read_at(&self, key: &str, seq: u64) -> Result<String, io::Error> {
+    let _timer = self.metrics.read_latency.start_timer();
+    self.reads.fetch_add(1, Ordering::Relaxed);
+    let shard = self.keydir.read(key);
+    let Some((base, operands)) = Self::version_at(&shard, key, seq) else {
+      return Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "This key does not exist at the requested sequence",
+      ));
+    };
 
-    if !inner.data_index.contains_key(key) {
-      return Err(io::Error::other("This key does not exist in the index"));
-    }
+    let value = self.resolve(base.as_ref(), &operands)?;
+    drop(shard);
+    info!("[READ]", key = key.to_string(), seq = seq, value = value);
+    Ok(value)
+  }
 
-    let index_value = Index {
-      offset: inner.byte_offset,
-      file_id: inner.current_file_id,
+  /// The records making up `key` right after the write stamped `seq`: the
+  /// base value and the merge operands on top of it, or `None` if it had no
+  /// value then.
+  fn version_at(shard: &Shard, key: &str, seq: u64) -> Option<(Option<Index>, Vec<Index>)> {
+    let base = shard.data_index.get(key);
+    let operands = shard.merges.get(key);
+    let live_since = base
+      .or(operands.and_then(|operands| operands.first()))
+      .map(|index| index.seq);
+
+    let (base, mut operands) = if live_since.is_some_and(|since| since <= seq) {
+      (base.cloned(), operands.cloned().unwrap_or_default())
+    } else {
+      shard
+        .history
+        .get(key)
+        .and_then(|versions| versions.iter().rev().find(|version| version.seq <= seq))
+        .map(|version| (version.index.clone(), version.operands.clone()))
+        .unwrap_or_default()
     };
 
-    let data_size = (value.len() + key.len() + 8 * 2) as u64;
+    operands.retain(|operand| operand.seq <= seq);
+    match base.is_none() && operands.is_empty() {
+      true => None,
+      false => Some((base, operands)),
+    }
+  }
 
-    inner.data_index.insert(key.to_string(), index_value);
-    inner.byte_offset += data_size;
+  /// Opens a [`Cursor`] over the keys starting with `prefix` as they are
+  /// now, positioned before the first of them. Later writes don't show up
+  /// in it, and compaction is paused until it is dropped.
+  pub fn cursor(&self, prefix: &str) -> Cursor {
+    let inner = self.inner.lock().unwrap();
+    let seq = inner.last_seq;
+    let pin = self.snapshots.pin();
+    drop(inner);
 
-    let timestamp = Utc::now().timestamp();
+    let mut keys = Vec::new();
+    self.keydir.for_each_shard(|shard| {
+      let candidates = shard
+        .data_index
+        .keys()
+        .chain(shard.merges.keys())
+        .chain(shard.history.keys())
+        .filter(|key| key.starts_with(prefix))
+        .collect::<BTreeSet<_>>();
+      keys.extend(
+        candidates
+          .into_iter()
+          .filter(|key| Self::version_at(shard, key, seq).is_some())
+          .cloned(),
+      );
+    });
+    self.sort_keys(&mut keys);
+    Cursor::new(self.clone(), seq, keys, pin)
+  }
 
-    // drop(inner);
-    self.insert_index_value(
-      MetaIndex {
-        timestamp,
-        key_size: key.len(),
-        key_buf: key.as_bytes().to_vec(),
-        value_size: value.len(),
-        value_buf: value.as_bytes().to_vec(),
-      },
-      &mut inner,
-    )?;
+  /// Whether `key` has a live value, answered from the in-memory index
+  /// without reading anything from disk.
+  pub fn contains_key(&self, key: &str) -> bool {
+    self.keydir.read(key).is_live(key)
+  }
 
-    info!("[UPDATE]", key = key.to_string(), value = value.to_string());
+  /// Iterates over a snapshot of every live key, in no particular order.
+  /// Writes made while iterating are not reflected.
+  pub fn keys(&self) -> impl Iterator<Item = String> {
+    let mut live = Vec::new();
+    self
+      .keydir
+      .for_each_shard(|shard| live.extend(shard.live_keys().cloned()));
+    live.into_iter()
+  }
 
-    Ok(value.to_string())
+  /// Number of live keys.
+  pub fn len(&self) -> usize {
+    let mut len = 0;
+    self
+      .keydir
+      .for_each_shard(|shard| len += shard.live_keys().count());
+    len
   }
 
-  pub fn delete(&self, id: &str) -> Result<String, io::Error> {
-    let mut inner = self.inner.lock().unwrap();
-    let mut index = self.get_index_value(id)?;
-    let value = String::from_utf8(index.value_buf.clone())
-      .unwrap()
-      .to_string();
-    index.value_size = 0;
-    index.value_buf.clear();
-    self.insert_index_value(index, &mut inner)?;
-    inner.data_index.remove(id);
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
 
-    info!("[DELETE]", key = id.to_string(), value = value);
-    Ok(value.to_string())
+  /// Like [`keys`](Self::keys), but in ascending order under
+  /// `Options::comparator`, byte order by default.
+  pub fn sorted_keys(&self) -> impl Iterator<Item = String> {
+    let mut keys = self.keys().collect::<Vec<_>>();
+    self.sort_keys(&mut keys);
+    keys.into_iter()
   }
 
-  pub fn compact(&self) -> Result<(), io::Error> {
-    let new_hash = std::mem::take(&mut self.inner.lock().unwrap().file_index);
-    let mut end_file = HashMap::<String, MetaIndex>::new();
-    let mut sorted_file_ids = new_hash.keys().collect::<Vec<_>>();
-    sorted_file_ids.sort();
+  fn sort_keys(&self, keys: &mut [String]) {
+    match &self.options.comparator {
+      Some(comparator) => keys.sort_unstable_by(|a, b| comparator.compare(a, b)),
+      None => keys.sort_unstable(),
+    }
+  }
 
-    for &file_id in sorted_file_ids {
-      let file_idx = new_hash.get(&file_id).unwrap();
-      self.compact_file(&mut end_file, file_idx)?;
+  /// Orders `a` and `b` like [`sorted_keys`](Self::sorted_keys) does.
+  pub(crate) fn compare_keys(&self, a: &str, b: &str) -> std::cmp::Ordering {
+    match &self.options.comparator {
+      Some(comparator) => comparator.compare(a, b),
+      None => a.cmp(b),
     }
+  }
 
-    let mut inner = self.inner.lock().unwrap();
-    let _ = core::mem::replace(&mut inner.file_index, new_hash);
+  /// Live keys starting with `prefix` with their values, in the order of
+  /// [`sorted_keys`](Self::sorted_keys). The keys are listed up front and each value is read when the
+  /// iterator gets to it, so keys deleted in between are skipped.
+  pub fn scan(
+    &self,
+    prefix: &str,
+  ) -> impl Iterator<Item = Result<(String, String), io::Error>> + '_ {
+    let prefix = prefix.to_string();
+    self
+      .sorted_keys()
+      .filter(move |key| key.starts_with(&prefix))
+      .filter_map(|key| match self.live_entry(&key) {
+        Ok(entry) => entry.map(|(value, _)| Ok((key, value))),
+        Err(e) => Some(Err(e)),
+      })
+  }
 
-    let temp_file_path = format!(
-      "./tmp/temp-log-file-{}",
-      Utc::now().timestamp_nanos_opt().unwrap()
-    );
-    let mut temp_file = File::create(&temp_file_path)?;
+  /// Looks up every key in `keys`, returning the values in the same order
+  /// (`None` for missing or expired keys).
+  ///
+  /// Lookups are sorted by segment and offset so each segment is opened once
+  /// and read front to back, instead of once per key as with `read`.
+  pub fn multi_get(&self, keys: &[impl AsRef<[u8]>]) -> Result<Vec<Option<Vec<u8>>>, io::Error> {
+    let _timer = self.metrics.read_latency.start_timer();
+    let mut values = vec![None; keys.len()];
+    let mut plain = Vec::new();
+    let mut merged = Vec::new();
+
+    self.reads.fetch_add(keys.len() as u64, Ordering::Relaxed);
+    let keys = keys
+      .iter()
+      .enumerate()
+      .filter_map(|(slot, key)| Some((slot, std::str::from_utf8(key.as_ref()).ok()?)))
+      .collect::<Vec<_>>();
+    let shards = self.keydir.read_many(keys.iter().map(|(_, key)| *key));
+
+    for &(slot, key) in &keys {
+      let shard = &shards[&self.keydir.shard_of(key)];
+      let base = shard.data_index.get(key);
+      match shard.merges.get(key) {
+        Some(operands) => merged.push((slot, base, operands)),
+        None => plain.extend(base.map(|index| (slot, index))),
+      }
+    }
 
-    let mut offset = 0;
-    let mut final_data_index = HashMap::<String, Index>::new();
+    plain.sort_by_key(|(_, index)| (index.file_id, index.offset));
+    let mut open: Option<(u64, Arc<File>)> = None;
+    for (slot, index) in plain {
+      if self.options.in_memory {
+        let meta = self.read_index(index)?;
+        if !meta.is_expired() {
+          values[slot] = Some(self.value_of(meta)?);
+        }
+        continue;
+      }
 
-    // Keep record layout identical to append: ts, key_size, value_size, key, value.
-    for (key, value) in end_file.into_iter() {
-      final_data_index.insert(key, Index { offset, file_id: 1 });
+      if open
+        .as_ref()
+        .is_none_or(|(file_id, _)| *file_id != index.file_id)
+      {
+        self.flush_if_active(index.file_id)?;
+        open = Some((
+          index.file_id,
+          self.reader(
+            index.file_id,
+            &self.keydir.segments().file_index[&index.file_id],
+          )?,
+        ));
+      }
+      let (_, file) = open.as_ref().unwrap();
 
-      temp_file.write_all(&value.timestamp.to_le_bytes())?;
-      temp_file.write_all(&value.key_size.to_le_bytes())?;
-      temp_file.write_all(&value.value_size.to_le_bytes())?;
-      temp_file.write_all(&value.key_buf)?;
-      temp_file.write_all(&value.value_buf)?;
+      let mut offset = index.offset;
+      let meta =
+        Self::get_index_from_file(&mut offset, index.offset + index.len, file, self.limits())?;
+      if !meta.is_expired() {
+        values[slot] = Some(self.value_of(meta)?);
+      }
+    }
 
-      // CRASH SAFETY HERE
-      temp_file.sync_all()?; // durability guarantee
-      offset += (value.key_size + value.value_size) as u64 + 8 * 3;
+    // Keys with pending merge operands need the operator applied.
+    for (slot, base, operands) in merged {
+      match self.resolve(base, operands) {
+        Ok(value) => values[slot] = Some(value.into_bytes()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+      }
     }
 
-    temp_file.flush()?;
+    Ok(values)
+  }
+
+  /// Streams every live key to `writer` as JSON lines, sorted by key, and
+  /// returns how many were written. Each line is an object
+  /// `{"key":…,"value":…,"ts":…}`, with `ts` the write time of the record in
+  /// Unix nanoseconds.
+  pub fn export_jsonl(&self, mut writer: impl Write) -> Result<usize, io::Error> {
+    let count = self
+      .export_with(|key, value, ts| jsonl::Record { key, value, ts }.write_line(&mut writer))?;
+    writer.flush()?;
+    Ok(count)
+  }
+
+  /// Bulk-loads JSON lines as produced by [`export_jsonl`](Self::export_jsonl)
+  /// and returns how many records were imported. Records are written in
+  /// batches and stamped with the import time; `ts` is not carried over.
+  /// Blank lines are skipped. A malformed line fails the import after the
+  /// records before it have been written.
+  pub fn import_jsonl(&self, reader: impl BufRead) -> Result<usize, io::Error> {
+    let records = reader
+      .lines()
+      .enumerate()
+      .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+      .map(|(index, line)| {
+        let record = jsonl::Record::parse(&line?, index + 1)?;
+        Ok((record.key, record.value))
+      });
+    self.import_with(records)
+  }
+
+  /// Streams every live key to `writer` as CSV rows of key, value and write
+  /// time in Unix nanoseconds, sorted by key, and returns how many were
+  /// written. See [`CsvOptions`] for the dialect.
+  pub fn export_csv(&self, writer: impl Write, options: &CsvOptions) -> Result<usize, io::Error> {
+    let mut writer = csv_format::Writer::new(writer, options)?;
+    let count = self.export_with(|key, value, ts| writer.write(&key, &value, ts))?;
+    writer.flush()?;
+    Ok(count)
+  }
 
-    inner.current_file_id = 1;
-    let path = format!("./tmp/log-file-{}", inner.current_file_id);
+  /// Bulk-loads CSV as produced by [`export_csv`](Self::export_csv) with the
+  /// same `options`, and returns how many records were imported. Only the
+  /// key and value columns are read. Like
+  /// [`import_jsonl`](Self::import_jsonl), a malformed row fails the import
+  /// after the rows before it have been written.
+  pub fn import_csv(&self, reader: impl Read, options: &CsvOptions) -> Result<usize, io::Error> {
+    self.import_with(csv_format::Reader::new(reader, options))
+  }
+
+  /// The value of `key` and the write time of its newest record, or `None`
+  /// if it has no live value.
+  fn live_entry(&self, key: &str) -> Result<Option<(String, i64)>, io::Error> {
+    let shard = self.keydir.read(key);
+    let base = shard.data_index.get(key);
+    let operands = shard.merges.get(key).map(Vec::as_slice).unwrap_or_default();
+    let Some(newest) = operands.last().or(base) else {
+      return Ok(None);
+    };
 
-    // Clear the index file and remove the old files
-    for (_, path) in inner.file_index.iter() {
-      fs::remove_file(path)?;
+    let ts = self.read_index(newest)?.timestamp;
+    match self.resolve(base, operands) {
+      Ok(value) => Ok(Some((value, ts))),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e),
     }
-    inner.file_index.clear();
+  }
 
-    drop(temp_file);
-    fs::rename(&temp_file_path, &path)?;
+  /// Hands every live key with its value and write time to `emit`, sorted
+  /// by key, and returns how many there were.
+  fn export_with(
+    &self,
+    mut emit: impl FnMut(String, String, i64) -> Result<(), io::Error>,
+  ) -> Result<usize, io::Error> {
+    let mut count = 0;
+    for key in self.sorted_keys() {
+      // The key may have been deleted or expired since it was listed.
+      let Some((value, ts)) = self.live_entry(&key)? else {
+        continue;
+      };
 
-    let current_file_id = inner.current_file_id;
-    inner.path = path.clone();
-    inner.file_index.insert(current_file_id, path);
-    inner.data_index = final_data_index;
-    info!("[COMPACT] Compaction has been completed successfully.");
+      emit(key, value, ts)?;
+      count += 1;
+    }
 
-    drop(inner);
-    self.write_hint_file()?;
+    info!(
+      "[EXPORT] Export has been completed.",
+      records = count as u64
+    );
+    Ok(count)
+  }
+
+  /// Writes `records` in batches of `IMPORT_BATCH_SIZE`. The first error
+  /// stops the import once everything before it has been written.
+  fn import_with(
+    &self,
+    records: impl Iterator<Item = Result<(String, String), io::Error>>,
+  ) -> Result<usize, io::Error> {
+    let mut batch = WriteBatch::new();
+    let mut count = 0;
+    for record in records {
+      let (key, value) = match record {
+        Ok(record) => record,
+        Err(e) => {
+          self.write(&batch)?;
+          return Err(e);
+        }
+      };
+
+      batch.put(&key, &value);
+      if batch.len() == IMPORT_BATCH_SIZE {
+        self.write(&batch)?;
+        count += batch.len();
+        batch.clear();
+      }
+    }
+    self.write(&batch)?;
+    count += batch.len();
+
+    info!(
+      "[IMPORT] Import has been completed.",
+      records = count as u64
+    );
+    Ok(count)
+  }
+
+  /// Takes a snapshot of the store's size and activity counters.
+  pub fn stats(&self) -> Result<Stats, io::Error> {
+    let inner = self.inner.lock().unwrap();
+    let live_keys = self.len();
+    let file_ids = self
+      .keydir
+      .segments()
+      .file_index
+      .keys()
+      .copied()
+      .collect::<Vec<_>>();
+
+    let mut segments = Vec::with_capacity(file_ids.len());
+    for file_id in file_ids {
+      let active = file_id == inner.current_file_id;
+      let size = if active {
+        inner.byte_offset
+      } else {
+        inner.segment_sizes.get(&file_id).copied().unwrap_or(0)
+      };
+
+      segments.push(SegmentStats {
+        file_id,
+        size,
+        dead_bytes: inner.dead_bytes.get(&file_id).copied().unwrap_or(0),
+        active,
+      });
+    }
+    segments.sort_by_key(|segment| segment.file_id);
+
+    Ok(Stats {
+      live_keys,
+      total_bytes: segments.iter().map(|segment| segment.size).sum(),
+      dead_bytes: segments.iter().map(|segment| segment.dead_bytes).sum(),
+      segments,
+      last_compaction: inner.last_compaction,
+      reads: self.reads.load(Ordering::Relaxed),
+      writes: inner.writes,
+      cache_hits: self.metrics.cache_hits.load(Ordering::Relaxed),
+      cache_misses: self.metrics.cache_misses.load(Ordering::Relaxed),
+    })
+  }
+
+  /// Renders the log's counters, latency histograms and [`stats`](Self::stats)
+  /// in the Prometheus text exposition format, ready to be scraped.
+  pub fn render_metrics(&self) -> Result<String, io::Error> {
+    Ok(self.metrics.render(&self.stats()?))
+  }
+
+  /// Checks every segment and hint file without changing anything, see
+  /// [`crate::verify`]. It works on a log that was never started, which
+  /// leaves torn writes in place for it to find, as well as on a running
+  /// one, whose writers wait until it is done.
+  pub fn verify(&self) -> Result<VerifyReport, io::Error> {
+    let inner = self.inner.lock().unwrap();
+    if let Some(file) = &inner.file {
+      file.flush()?;
+    }
+    let mut report = VerifyReport::default();
+
+    if self.options.in_memory {
+      let memory = self.memory.lock().unwrap();
+      let mut file_ids = memory.keys().copied().collect::<Vec<_>>();
+      file_ids.sort();
+      for file_id in file_ids {
+        let bytes = memory[&file_id].read().unwrap();
+        let (segment, _) = self.verify_segment(file_id, bytes.as_slice(), &mut report.problems)?;
+        report.segments.push(segment);
+      }
+      return Ok(report);
+    }
+
+    // Only `start` takes the lock needed to rename them.
+    if inner.file.is_none() && file_names::has_legacy(&self.options.dir)? {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "The data directory uses the file names of an older version, start it once to rename them",
+      ));
+    }
+    let (file_ids, hint_ids) = self.list_files()?;
+    for &file_id in &file_ids {
+      let file = File::open(self.file_path(&file_names::segment(file_id)))?;
+      let (mut segment, records) = self.verify_segment(file_id, &file, &mut report.problems)?;
+      segment.has_hint = hint_ids.contains(&file_id);
+      if segment.has_hint {
+        self.verify_hint(file_id, records.as_deref(), &mut report.problems)?;
+      }
+      report.segments.push(segment);
+    }
+
+    for file_id in hint_ids.difference(&file_ids) {
+      report.problems.push(Problem {
+        file: file_names::hint(*file_id),
+        offset: None,
+        kind: ProblemKind::OrphanHint,
+      });
+    }
+    Ok(report)
+  }
+
+  /// Reads every record of segment `file_id`. Returns the hint entries the
+  /// segment should have, or `None` if it has problems.
+  fn verify_segment(
+    &self,
+    file_id: u64,
+    source: &(impl RecordSource + ?Sized),
+    problems: &mut Vec<Problem>,
+  ) -> Result<(SegmentReport, Option<Vec<HintEntry>>), io::Error> {
+    let file = file_names::segment(file_id);
+    let size = source.size()?;
+    let mut segment = SegmentReport {
+      file_id,
+      size,
+      records: 0,
+      has_hint: false,
+    };
+    let mut records = Some(Vec::new());
+    let mut offset = Self::segment_start(file_id, source)?;
+
+    while offset < size {
+      let entry_offset = offset;
+      let kind = match Self::read_entry(&mut offset, size, source, self.limits()) {
+        Ok(entries) if entries.is_empty() => break,
+        Ok(entries) => {
+          for (record_offset, meta) in entries {
+            segment.records += 1;
+            let entry = HintEntry {
+              seq: meta.seq,
+              kind: meta.kind(),
+              offset: record_offset,
+              len: meta.len(),
+              expires_at: meta.expires_at,
+              key: match String::from_utf8(meta.key_buf) {
+                Ok(key) => key,
+                Err(_) => {
+                  problems.push(Problem {
+                    file: file.clone(),
+                    offset: Some(record_offset),
+                    kind: ProblemKind::InvalidKey,
+                  });
+                  records = None;
+                  continue;
+                }
+              },
+            };
+            if let Some(records) = &mut records {
+              records.push(entry);
+            }
+          }
+          continue;
+        }
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => ProblemKind::TruncatedRecord,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+          ProblemKind::CorruptRecord(e.to_string())
+        }
+        Err(e) => return Err(e),
+      };
+
+      problems.push(Problem {
+        file,
+        offset: Some(entry_offset),
+        kind,
+      });
+      return Ok((segment, None));
+    }
+
+    Ok((segment, records))
+  }
+
+  /// Checks the hint file of segment `file_id` against the `records` of its segment, if the
+  /// segment could be read.
+  fn verify_hint(
+    &self,
+    file_id: u64,
+    records: Option<&[HintEntry]>,
+    problems: &mut Vec<Problem>,
+  ) -> Result<(), io::Error> {
+    let file = file_names::hint(file_id);
+    let kind = match hint::read(&self.file_path(&file)) {
+      Ok(entries) => {
+        let Some(records) = records else {
+          return Ok(());
+        };
+        match entries
+          .iter()
+          .zip(records)
+          .position(|(entry, record)| entry != record)
+        {
+          Some(i) => ProblemKind::HintMismatch(format!(
+            "entry {i} disagrees with the record at offset {}",
+            records[i].offset
+          )),
+          None if entries.len() != records.len() => ProblemKind::HintMismatch(format!(
+            "{} entries for {} records",
+            entries.len(),
+            records.len()
+          )),
+          None => return Ok(()),
+        }
+      }
+      Err(e)
+        if matches!(
+          e.kind(),
+          io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData
+        ) =>
+      {
+        ProblemKind::InvalidHint(e.to_string())
+      }
+      Err(e) => return Err(e),
+    };
+
+    problems.push(Problem {
+      file,
+      offset: None,
+      kind,
+    });
     Ok(())
   }
 
-  fn write_hint_file(&self) -> Result<(), io::Error> {
+  /// Salvages every intact record from damaged segments, see
+  /// [`crate::repair`]. The log must not have been started; it can be once
+  /// this returns.
+  pub fn repair(&self) -> Result<RepairReport, io::Error> {
+    let inner = self.inner.lock().unwrap();
+    if self.options.in_memory || inner.file.is_some() {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Only a log on disk that has not been started can be repaired",
+      ));
+    }
+    self.lock_dir()?;
+    self.migrate_file_names()?;
+    self.recover_compaction()?;
+    self.remove_orphans()?;
+
+    let mut report = RepairReport::default();
+    let (file_ids, hint_ids) = self.list_files()?;
+    let quarantine_dir = self.options.dir.join(QUARANTINE_DIR);
+    for &file_id in &file_ids {
+      let path = self.file_path(&file_names::segment(file_id));
+      let bytes = fs::read(&path)?;
+      let start = Self::segment_start(file_id, bytes.as_slice())?;
+      let (intact, records, damaged) = Self::salvage(&bytes, start, self.limits());
+      if damaged.is_empty() {
+        continue;
+      }
+
+      fs::create_dir_all(&quarantine_dir)?;
+      for range in damaged {
+        let quarantined = QuarantinedRange {
+          file_id,
+          offset: range.start as u64,
+          len: range.len() as u64,
+          path: quarantine_dir.join(format!("{}.{}", file_names::segment(file_id), range.start)),
+        };
+        fs::write(&quarantined.path, &bytes[range])?;
+        error!(
+          "[REPAIR] Quarantined damaged bytes.",
+          file_id = file_id,
+          offset = quarantined.offset,
+          len = quarantined.len
+        );
+        report.quarantined.push(quarantined);
+      }
+
+      let temp_path = format!("{path}.repair");
+      let mut temp_file = File::create(&temp_path)?;
+      temp_file.write_all(&Self::segment_header())?;
+      for range in intact {
+        temp_file.write_all(&bytes[range])?;
+      }
+      temp_file.sync_all()?;
+      fs::rename(&temp_path, &path)?;
+      report.rewritten.push(file_id);
+      report.salvaged += records;
+    }
+
+    // Hints of rewritten segments are stale, and any other may be damaged.
+    for file_id in &hint_ids {
+      fs::remove_file(self.file_path(&file_names::hint(*file_id)))?;
+    }
+    for &file_id in &file_ids {
+      self.write_hint_file(file_id, &self.file_path(&file_names::segment(file_id)))?;
+      report.hints += 1;
+    }
+    self.sync_dir()?;
+
+    info!(
+      "[REPAIR] Repair has been completed.",
+      rewritten = report.rewritten.len() as u64,
+      salvaged = report.salvaged
+    );
+    Ok(report)
+  }
+
+  /// Splits a segment whose records begin at `start` into the byte ranges
+  /// of intact entries, counting their records, and the damaged ranges
+  /// between them.
+  fn salvage(
+    bytes: &[u8],
+    start: u64,
+    limits: SizeLimits,
+  ) -> (Vec<Range<usize>>, u64, Vec<Range<usize>>) {
+    let size = bytes.len() as u64;
+    let mut intact = Vec::new();
+    let mut records = 0;
+    let mut damaged = Vec::new();
+    let mut damaged_since = None;
+    let mut offset = start;
+
+    while offset < size {
+      let start = offset;
+      match Self::read_entry(&mut offset, bytes.len() as u64, bytes, limits) {
+        Ok(entries) if entries.is_empty() => break,
+        // `start` can't load a key that isn't UTF-8.
+        Ok(entries)
+          if entries
+            .iter()
+            .all(|(_, meta)| std::str::from_utf8(&meta.key_buf).is_ok()) =>
+        {
+          if let Some(since) = damaged_since.take() {
+            damaged.push(since as usize..start as usize);
+          }
+          intact.push(start as usize..offset as usize);
+          records += entries.len() as u64;
+        }
+        Ok(_) => {
+          damaged_since.get_or_insert(start);
+        }
+        Err(_) => {
+          damaged_since.get_or_insert(start);
+          offset = Self::broken_batch_end(bytes, start, limits).unwrap_or(start + 1);
+        }
+      }
+    }
+
+    if let Some(since) = damaged_since {
+      damaged.push(since as usize..bytes.len());
+    }
+    (intact, records, damaged)
+  }
+
+  /// Whether an intact record starts anywhere past the damaged record of
+  /// the entry at the start of `tail`. The records of a batch before the
+  /// damaged one don't count, since they are lost along with it.
+  fn intact_after_damage(tail: &[u8], limits: SizeLimits) -> bool {
+    let mut offset = 0;
+    let count = match Self::get_index_from_slice(&mut offset, tail, limits) {
+      Ok(header) if header.key_buf.is_empty() => {
+        <[u8; 8]>::try_from(header.value_buf.as_slice()).map_or(0, u64::from_le_bytes)
+      }
+      _ => 0,
+    };
+    for _ in 0..count {
+      if Self::get_index_from_slice(&mut offset, tail, limits).is_err() {
+        break;
+      }
+    }
+
+    (offset + 1..tail.len() as u64)
+      .any(|mut start| Self::get_index_from_slice(&mut start, tail, limits).is_ok())
+  }
+
+  /// If a write batch with damaged records starts at `offset`, returns where
+  /// it ends. Its records are contiguous, so a damaged one ends where the
+  /// next intact record starts.
+  fn broken_batch_end(bytes: &[u8], offset: u64, limits: SizeLimits) -> Option<u64> {
+    let intact = |mut start: u64| Self::get_index_from_slice(&mut start, bytes, limits).is_ok();
+    let size = bytes.len() as u64;
+    let mut end = offset;
+    let header = Self::get_index_from_slice(&mut end, bytes, limits).ok()?;
+    if !header.key_buf.is_empty() {
+      return None;
+    }
+
+    let count = <[u8; 8]>::try_from(header.value_buf.as_slice()).map_or(0, u64::from_le_bytes);
+    for _ in 0..count {
+      if end >= size {
+        break;
+      }
+      let mut next = end;
+      end = match Self::get_index_from_slice(&mut next, bytes, limits) {
+        Ok(_) => next,
+        Err(_) => (end + 1..size).find(|&start| intact(start)).unwrap_or(size),
+      };
+    }
+    Some(end)
+  }
+
+  /// Ids of the segments and of the hint files in the data directory.
+  fn list_files(&self) -> Result<(BTreeSet<u64>, BTreeSet<u64>), io::Error> {
+    let mut file_ids = BTreeSet::new();
+    let mut hint_ids = BTreeSet::new();
+    for entry in fs::read_dir(&self.options.dir)? {
+      let name = entry?.file_name();
+      let Some(name) = name.to_str() else {
+        continue;
+      };
+      if let Some(file_id) = file_names::parse_segment(name) {
+        file_ids.insert(file_id);
+      } else if let Some(file_id) = file_names::parse_hint(name) {
+        hint_ids.insert(file_id);
+      }
+    }
+    Ok((file_ids, hint_ids))
+  }
+
+  /// Opens the column family `name`, creating it on first use.
+  ///
+  /// The family is a separate log in its own subdirectory, so its keys,
+  /// compaction and stats are independent of this log and of other families.
+  pub fn column_family(&self, name: &str) -> Result<ColumnFamily, io::Error> {
+    column_family::validate_name(name)?;
+
+    let mut families = self.families.lock().unwrap();
+    if let Some(log) = families.get(name) {
+      return Ok(ColumnFamily::new(name, log.clone()));
+    }
+
+    let mut options = Options::clone(&self.options);
+    options.dir = options.dir.join(column_family::dir_name(name));
+    let log = LogFile::with_options(options)?;
+    log.start()?;
+
+    families.insert(name.to_string(), log.clone());
+    info!("[CF] Column family opened.", name = name.to_string());
+    Ok(ColumnFamily::new(name, log))
+  }
+
+  /// Names of every column family that exists on disk, sorted. In memory,
+  /// those opened so far.
+  pub fn column_families(&self) -> Result<Vec<String>, io::Error> {
+    if self.options.in_memory {
+      let mut names = self
+        .families
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+      names.sort();
+      return Ok(names);
+    }
+
+    let dir = &self.options.dir;
+    if !fs::exists(dir)? {
+      return Ok(Vec::new());
+    }
+
+    let mut names = fs::read_dir(dir)?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().is_dir())
+      .filter_map(|entry| {
+        let file_name = entry.file_name();
+        column_family::name_from_dir(file_name.to_str()?).map(str::to_string)
+      })
+      .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+  }
+
+  /// Returns the sequence number of the most recent write.
+  pub fn last_seq(&self) -> u64 {
+    self.inner.lock().unwrap().last_seq
+  }
+
+  pub fn update(&self, key: &str, value: &str) -> Result<String, io::Error> {
+    let _timer = self.metrics.write_latency.start_timer();
+    self.check_put(key, value)?;
+    let mut inner = self.inner.lock().unwrap();
+
+    // An expired key is gone; updating it must not bring it back.
+    if !self.keydir.read(key).is_live(key) {
+      return Err(Self::missing());
+    }
+
+    let seq = self.write_record(&mut inner, key, value, RECORD_VALUE, NO_EXPIRY)?;
+    drop(inner);
+    self.pending(seq).wait()?;
+
+    info!("[UPDATE]", key = key.to_string(), value = value.to_string());
+
+    Ok(value.to_string())
+  }
+
+  pub fn delete(&self, id: &str) -> Result<String, io::Error> {
+    let _timer = self.metrics.write_latency.start_timer();
+    let value = self.get_value(&self.keydir.read(id), id)?;
+
+    let mut inner = self.inner.lock().unwrap();
+    let seq = self.write_record(&mut inner, id, "", RECORD_VALUE, NO_EXPIRY)?;
+    drop(inner);
+    self.pending(seq).wait()?;
+
+    info!("[DELETE]", key = id.to_string(), value = value);
+    Ok(value.to_string())
+  }
+
+  /// Queues `operand` for `key` and returns the write's sequence number.
+  ///
+  /// The configured `Options::merge_operator` folds every queued operand into
+  /// the existing value whenever the key is read, and compaction replaces the
+  /// operands with the folded result, so concurrent read-modify-writes need no
+  /// external locking. A folded value of `""` is a delete, like any empty
+  /// value.
+  pub fn merge(&self, key: &str, operand: &str) -> Result<u64, io::Error> {
+    let _timer = self.metrics.write_latency.start_timer();
+    if key.is_empty() {
+      return Err(DbError::EmptyKey.into());
+    }
+    let mut inner = self.inner.lock().unwrap();
+    if self.options.merge_operator.is_none() {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "No merge operator is configured",
+      ));
+    }
+
+    let seq = self.write_record(&mut inner, key, operand, RECORD_MERGE, NO_EXPIRY)?;
+    drop(inner);
+    self.pending(seq).wait()?;
+
+    info!(
+      "[MERGE]",
+      key = key.to_string(),
+      operand = operand.to_string()
+    );
+    Ok(seq)
+  }
+
+  /// Commits every operation in `batch` atomically and returns the sequence
+  /// number of its last write.
+  ///
+  /// The records are preceded by a batch header (an empty key whose value is
+  /// the record count) and hit the disk with a single write and fsync, so a
+  /// crash mid-batch leaves a short batch that recovery discards entirely.
+  pub fn write(&self, batch: &WriteBatch) -> Result<u64, io::Error> {
+    let _timer = self.metrics.write_latency.start_timer();
+    self.write_deferred(batch)?.wait()
+  }
+
+  /// Like [`write`](Self::write), but returns once the batch is in the log
+  /// instead of waiting for it to be durable.
+  pub fn write_deferred(&self, batch: &WriteBatch) -> Result<PendingWrite, io::Error> {
+    let mut inner = self.inner.lock().unwrap();
+    if batch.is_empty() {
+      let seq = inner.last_seq;
+      return Ok(self.pending(seq));
+    }
+    for op in &batch.ops {
+      match op {
+        BatchOp::Put { key, value } => self.check_put(key, value)?,
+        BatchOp::Delete { key } if key.is_empty() => return Err(DbError::EmptyKey.into()),
+        BatchOp::Delete { key } => self.check_sizes(key, "")?,
+      }
+    }
+
+    let count = batch.len() as u64;
+    let mut buf = Vec::new();
+    Self::write_meta(
+      &mut buf,
+      &MetaIndex {
+        timestamp: Utc::now().timestamp_nanos_opt().unwrap(),
+        seq: 0,
+        record_type: RECORD_VALUE,
+        expires_at: NO_EXPIRY,
+        key_size: 0,
+        key_buf: Vec::new(),
+        value_size: 8,
+        value_buf: count.to_le_bytes().to_vec(),
+      },
+    )?;
+
+    let mut offset = inner.byte_offset + buf.len() as u64;
+    let mut seq = inner.last_seq;
+    let mut versions = Vec::with_capacity(batch.len());
+    for op in &batch.ops {
+      seq += 1;
+      let (key, value) = (op.key(), op.value());
+      let meta = self.new_record(seq, RECORD_VALUE, NO_EXPIRY, key, value.as_bytes())?;
+      let record = Index {
+        offset,
+        file_id: inner.current_file_id,
+        seq,
+        len: meta.len(),
+        expires_at: meta.expires_at,
+      };
+
+      Self::write_meta(&mut buf, &meta)?;
+      offset += record.len;
+      versions.push((key, value, record, meta.kind()));
+    }
+
+    inner.active()?.write_all(&buf)?;
+
+    // Only publish the batch once all of it is in the log.
+    inner.byte_offset = offset;
+    inner.last_seq = seq;
+    inner.writes += count;
+    let mut shards = self
+      .keydir
+      .write_many(versions.iter().map(|(key, _, _, _)| *key));
+    for (key, value, record, kind) in versions {
+      let shard = shards.get_mut(&self.keydir.shard_of(key)).unwrap();
+      let seq = record.seq;
+      Self::install_version(&mut inner, shard, key, record, kind);
+      self.publish(seq, kind, key, value, NO_EXPIRY);
+    }
+    drop(shards);
+
+    // FILE SEGMENTATION HERE
+    self.split(&mut inner)?;
+
+    self.metrics.batches.fetch_add(1, Ordering::Relaxed);
+    info!("[BATCH]", records = count, seq = seq);
+    Ok(self.pending(seq))
+  }
+
+  fn pending(&self, seq: u64) -> PendingWrite {
+    PendingWrite {
+      seq,
+      log: self.clone(),
+    }
+  }
+
+  /// Syncs the active segment and returns the last sequence number it covers.
+  /// Sealed segments are synced when `split()` rotates them out.
+  fn sync_active(&self) -> Result<u64, io::Error> {
+    Self::sync_inner(&self.inner, &self.value_log, &self.hooks, &self.metrics)
+  }
+
+  fn sync_inner(
+    inner: &Mutex<Inner>,
+    value_log: &ValueLog,
+    hooks: &Hooks,
+    metrics: &Metrics,
+  ) -> Result<u64, io::Error> {
+    let (seq, file_id, file) = {
+      let inner = inner.lock().unwrap();
+      (inner.last_seq, inner.current_file_id, inner.active()?)
+    };
+
+    // CRASH SAFETY HERE
+    let started = Instant::now();
+    // Values before the records pointing at them.
+    value_log.sync()?;
+    file.sync()?; // durability guarantee
+    metrics.sync_latency.observe(started.elapsed());
+    hooks.emit(|| {
+      HookEvent::Flush(FlushInfo {
+        file_id,
+        seq,
+        duration: started.elapsed(),
+      })
+    });
+    Ok(seq)
+  }
+
+  /// Hands a committed record to replication, if a primary is running, and
+  /// to watchers of its key. Called with `inner` held, so both see changes in
+  /// sequence order.
+  fn publish(&self, seq: u64, kind: RecordKind, key: &str, value: &str, expires_at: i64) {
+    let counter = match kind {
+      RecordKind::Put => &self.metrics.puts,
+      RecordKind::Delete => &self.metrics.deletes,
+      RecordKind::Merge => &self.metrics.merges,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+
+    if let Some(feed) = self.feed.get() {
+      feed.push(Change {
+        seq,
+        kind,
+        key: key.to_string(),
+        value: value.to_string(),
+        expires_at,
+      });
+    }
+
+    self.watchers.push(seq, key, || {
+      let key = key.to_string();
+      match kind {
+        RecordKind::Put => Event::Put {
+          key,
+          value: value.to_string(),
+        },
+        RecordKind::Delete => Event::Delete { key },
+        RecordKind::Merge => Event::Merge {
+          key,
+          operand: value.to_string(),
+        },
+      }
+    });
+    // Nothing ever syncs explicitly, so the write is as durable as it gets.
+    if self.options.sync_policy == SyncPolicy::Os {
+      self.watchers.release(seq);
+    }
+  }
+
+  /// Starts recording changes for replication and returns the feed.
+  pub(crate) fn replication_feed(&self) -> Arc<Feed> {
     let inner = self.inner.lock().unwrap();
-    let path = format!("./tmp/hint-{}", inner.current_file_id);
-    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    self
+      .feed
+      .get_or_init(|| Arc::new(Feed::new(inner.last_seq)))
+      .clone()
+  }
+
+  /// Applies a change shipped by a replication primary under the primary's
+  /// sequence number. Changes this log already has are skipped.
+  pub(crate) fn apply_replicated(&self, change: &Change) -> Result<(), io::Error> {
+    let mut inner = self.inner.lock().unwrap();
+    if change.seq <= inner.last_seq {
+      return Ok(());
+    }
+
+    inner.last_seq = change.seq - 1;
+    let record_type = match change.kind {
+      RecordKind::Merge => RECORD_MERGE,
+      RecordKind::Put | RecordKind::Delete => RECORD_VALUE,
+    };
+    let seq = self.write_record(
+      &mut inner,
+      &change.key,
+      &change.value,
+      record_type,
+      change.expires_at,
+    )?;
+    drop(inner);
+    self.pending(seq).wait()?;
+    Ok(())
+  }
+
+  /// Appends a record for `key` stamped with the next sequence number and
+  /// applies it to the key's live state. An empty `value` is written as a
+  /// tombstone unless the record is a merge operand.
+  fn write_record(
+    &self,
+    inner: &mut MutexGuard<'_, Inner>,
+    key: &str,
+    value: &str,
+    record_type: u64,
+    expires_at: i64,
+  ) -> Result<u64, io::Error> {
+    self.check_sizes(key, value)?;
+    inner.last_seq += 1;
+    let seq = inner.last_seq;
+
+    let meta = self.new_record(seq, record_type, expires_at, key, value.as_bytes())?;
+    let record = Index {
+      offset: inner.byte_offset,
+      file_id: inner.current_file_id,
+      seq,
+      len: meta.len(),
+      expires_at: meta.expires_at,
+    };
+    let kind = meta.kind();
+    let expires_at = meta.expires_at;
+    self.insert_index_value(meta, inner)?;
+
+    // Readers don't hold `inner`, so the record has to be in the file before
+    // the keydir points at it.
+    inner.byte_offset += record.len;
+    inner.writes += 1;
+    Self::install_version(inner, &mut self.keydir.write(key), key, record, kind);
+    self.publish(seq, kind, key, value, expires_at);
+
+    // FILE SEGMENTATION HERE
+    self.split(inner)?;
+
+    Ok(seq)
+  }
+
+  /// Applies `record` to the live state of `key`: a put replaces it, a
+  /// delete removes it and a merge queues an operand on top of it. Whatever a
+  /// put or delete replaces moves into the key's version history and its
+  /// bytes are counted as dead.
+  fn install_version(
+    inner: &mut Inner,
+    shard: &mut Shard,
+    key: &str,
+    record: Index,
+    kind: RecordKind,
+  ) {
+    let seq = record.seq;
+    let previous = match kind {
+      RecordKind::Merge => {
+        shard
+          .merges
+          .entry(key.to_string())
+          .or_default()
+          .push(record);
+        return;
+      }
+      RecordKind::Delete => {
+        *inner.dead_bytes.entry(record.file_id).or_default() += record.len;
+        shard.data_index.remove(key)
+      }
+      RecordKind::Put => shard.data_index.insert(key.to_string(), record),
+    };
+
+    // Replaying a record we already know about (e.g. from the hint file).
+    if previous
+      .as_ref()
+      .is_some_and(|previous| previous.seq == seq)
+    {
+      return;
+    }
+
+    let operands = shard.merges.remove(key).unwrap_or_default();
+    for replaced in previous.iter().chain(&operands) {
+      *inner.dead_bytes.entry(replaced.file_id).or_default() += replaced.len;
+    }
+
+    let history = shard.history.entry(key.to_string()).or_default();
+    let since = previous
+      .as_ref()
+      .or(operands.first())
+      .map(|index| index.seq);
+    if let Some(since) = since {
+      history.push(Version {
+        seq: since,
+        index: previous,
+        operands,
+      });
+    }
+    if kind == RecordKind::Delete {
+      history.push(Version {
+        seq,
+        index: None,
+        operands: Vec::new(),
+      });
+    }
+    if history.is_empty() {
+      shard.history.remove(key);
+    }
+  }
+
+  /// Rewrites every sealed segment into a single new one holding only live
+  /// records.
+  ///
+  /// The active segment is sealed first and writes carry on in a fresh one,
+  /// so the (throttled) copy runs without the writer lock: writers only wait
+  /// for the final swap. Whatever they wrote in the meantime wins over the
+  /// compacted records. Segment ids are never reused: the output gets the id
+  /// between its inputs and the segment writes moved on to.
+  ///
+  /// Fails with [`DbError::SnapshotsPinned`] while a [`Cursor`] is open.
+  pub fn compact(&self) -> Result<(), io::Error> {
+    let _compacting = self.compacting.lock().unwrap();
+    let mut inner = self.inner.lock().unwrap();
+    self.check_unpinned()?;
+    let output_id = inner.current_file_id + 1;
+    self.seal_active(&mut inner, output_id + 1)?;
+    let segments = self.keydir.segments().file_index.clone();
+    let mut end_file = HashMap::<String, Survivor>::new();
+    let mut newest = None;
+    let mut inputs = segments
+      .keys()
+      .copied()
+      .filter(|&file_id| file_id < output_id)
+      .collect::<Vec<_>>();
+    inputs.sort();
+
+    let started = Instant::now();
+    let bytes_before = inputs
+      .iter()
+      .map(|file_id| inner.segment_sizes.get(file_id).copied().unwrap_or(0))
+      .sum();
+    self.hooks.emit(|| {
+      HookEvent::CompactionStart(CompactionStartInfo {
+        segments: inputs.len(),
+        total_bytes: bytes_before,
+        dead_bytes: inputs
+          .iter()
+          .filter_map(|file_id| inner.dead_bytes.get(file_id))
+          .sum(),
+      })
+    });
+    // Values only reach the value log under the writer lock, so nothing but
+    // the inputs can point into the blob files that are sealed by now.
+    let sealed_blobs = self.sealed_blobs()?;
+    drop(inner);
+
+    for &file_id in &inputs {
+      self.compact_file(&mut end_file, &mut newest, file_id, &segments[&file_id])?;
+    }
+
+    let path = self.file_path(&file_names::segment(output_id));
+    let bytes_after;
+    if self.options.in_memory {
+      let mut output = Vec::new();
+      let compacted = self.write_survivors(end_file, newest, output_id, &mut output)?;
+      bytes_after = compacted.size;
+
+      let mut inner = self.inner.lock().unwrap();
+      self.check_unpinned()?;
+      let mut shards = self.keydir.write_all();
+      let mut keydir_segments = self.keydir.segments_mut();
+      let mut memory = self.memory.lock().unwrap();
+      for file_id in &inputs {
+        memory.remove(file_id);
+      }
+      memory.insert(output_id, Arc::new(RwLock::new(output)));
+      drop(memory);
+      self.install_compaction(
+        &mut inner,
+        &mut shards,
+        &mut keydir_segments,
+        &inputs,
+        path,
+        compacted,
+      );
+    } else {
+      let temp_name = format!("{}.tmp", file_names::segment(output_id));
+      let temp_file_path = self.file_path(&temp_name);
+      let dropped_blobs = self.collect_blobs(sealed_blobs, &mut end_file)?;
+      let mut temp_file = File::create(&temp_file_path)?;
+      let compacted = self.write_survivors(end_file, newest, output_id, &mut temp_file)?;
+      bytes_after = compacted.size;
+
+      // CRASH SAFETY HERE
+      temp_file.sync_all()?; // durability guarantee
+      drop(temp_file);
+
+      let mut inner = self.inner.lock().unwrap();
+      if let Err(e) = self.check_unpinned() {
+        fs::remove_file(&temp_file_path)?;
+        return Err(e);
+      }
+      let manifest_path = self.file_path(COMPACTION_MANIFEST);
+      Manifest {
+        output_id,
+        temp_name,
+        inputs: inputs.clone(),
+        blobs: dropped_blobs.clone(),
+      }
+      .write(&manifest_path)?;
+      self.sync_dir()?;
+
+      // Readers hold their shard while they read, so none is left pointing at
+      // a file once every shard is ours.
+      let mut shards = self.keydir.write_all();
+      let mut keydir_segments = self.keydir.segments_mut();
+
+      // The commit point: from here on recovery finishes the compaction.
+      fs::rename(&temp_file_path, &path)?;
+      self.sync_dir()?;
+      // Windows won't delete files that are still mapped.
+      self.mmaps.lock().unwrap().clear();
+      self.readers.lock().unwrap().clear();
+      for &file_id in &inputs {
+        self.remove_segment(file_id)?;
+      }
+      for &file_id in &dropped_blobs {
+        self.value_log.remove(file_id)?;
+      }
+
+      self.install_compaction(
+        &mut inner,
+        &mut shards,
+        &mut keydir_segments,
+        &inputs,
+        path.clone(),
+        compacted,
+      );
+      drop(keydir_segments);
+      drop(shards);
+      drop(inner);
+
+      // The compacted segment is sealed like any other.
+      self.write_hint_file(output_id, &path)?;
+      fs::remove_file(&manifest_path)?;
+    }
+
+    self.metrics.compactions.fetch_add(1, Ordering::Relaxed);
+    self
+      .metrics
+      .compaction_bytes_read
+      .fetch_add(bytes_before, Ordering::Relaxed);
+    self
+      .metrics
+      .compaction_bytes_written
+      .fetch_add(bytes_after, Ordering::Relaxed);
+    self.metrics.compaction_latency.observe(started.elapsed());
+    self.hooks.emit(|| {
+      HookEvent::CompactionFinish(CompactionInfo {
+        output_id,
+        segments: inputs.len(),
+        bytes_before,
+        bytes_after,
+        duration: started.elapsed(),
+      })
+    });
+    Ok(())
+  }
+
+  /// Fails with [`DbError::SnapshotsPinned`] while a [`Cursor`] is open.
+  /// Cursors pin under the writer lock, so the caller must hold it.
+  fn check_unpinned(&self) -> Result<(), io::Error> {
+    match self.snapshots.pinned() {
+      0 => Ok(()),
+      cursors => Err(DbError::SnapshotsPinned { cursors }.into()),
+    }
+  }
+
+  /// Ids of the blob files writes no longer append to.
+  fn sealed_blobs(&self) -> Result<Vec<u64>, io::Error> {
+    if self.options.in_memory {
+      return Ok(Vec::new());
+    }
+    let active = self.value_log.active_id();
+    Ok(
+      self
+        .value_log
+        .file_ids()?
+        .into_iter()
+        .filter(|&file_id| Some(file_id) != active)
+        .collect(),
+    )
+  }
+
+  /// Collects the garbage of `sealed`, blob files only the inputs of a
+  /// compaction point into, ahead of writing `end_file`, the records
+  /// surviving it. The live values of blob files that crossed
+  /// `Options::value_log_gc_ratio` move to the active blob file and their
+  /// survivors get new pointers. Returns the blob files nothing will point
+  /// into once the compaction is installed.
+  fn collect_blobs(
+    &self,
+    sealed: Vec<u64>,
+    end_file: &mut HashMap<String, Survivor>,
+  ) -> Result<Vec<u64>, io::Error> {
+    if sealed.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let mut live = HashMap::<u64, u64>::new();
+    for survivor in end_file.values() {
+      if let Some(pointer) = survivor
+        .base
+        .as_ref()
+        .map(MetaIndex::blob)
+        .transpose()?
+        .flatten()
+      {
+        *live.entry(pointer.file_id).or_default() += pointer.entry_len();
+      }
+    }
+
+    let mut relocated = BTreeSet::new();
+    for &file_id in &sealed {
+      let Some(&live) = live.get(&file_id) else {
+        continue;
+      };
+      let dead = self.value_log.file_size(file_id)?.saturating_sub(live);
+      if dead as f64 / live as f64 >= self.options.value_log_gc_ratio {
+        relocated.insert(file_id);
+      }
+    }
+    for base in end_file
+      .values_mut()
+      .filter_map(|survivor| survivor.base.as_mut())
+    {
+      let Some(pointer) = base.blob()? else {
+        continue;
+      };
+      if relocated.contains(&pointer.file_id) {
+        self.compaction_limiter.request(pointer.entry_len() * 2);
+        let value = self.value_log.read(&pointer)?;
+        base.value_buf = self.value_log.append(&value, false)?.encode();
+      }
+    }
+    // The output is about to point at the moved values.
+    self.value_log.sync()?;
+
+    if !relocated.is_empty() {
+      info!(
+        "[VALUE LOG] Live values have been moved out of mostly dead blob files.",
+        files = relocated.len() as u64
+      );
+    }
+    Ok(
+      sealed
+        .into_iter()
+        .filter(|file_id| relocated.contains(file_id) || !live.contains_key(file_id))
+        .collect(),
+    )
+  }
+
+  /// Writes the newest version of every key in `end_file` to `output`, the
+  /// future segment `output_id`, and returns where each record went.
+  ///
+  /// `newest` is the sequence number and key of the newest record in the
+  /// inputs. Startup recovers `last_seq` from the records on disk, so if that
+  /// record is a tombstone or an expired put, a tombstone carrying its
+  /// sequence number is kept at the end of the output; otherwise sequence
+  /// numbers would go backwards on the next start.
+  fn write_survivors(
+    &self,
+    end_file: HashMap<String, Survivor>,
+    newest: Option<(u64, String)>,
+    output_id: u64,
+    output: &mut impl Write,
+  ) -> Result<Compacted, io::Error> {
+    output.write_all(&Self::segment_header())?;
+    let mut compacted = Compacted {
+      output_id,
+      size: SEGMENT_HEADER_SIZE,
+      data_index: HashMap::new(),
+      merges: HashMap::new(),
+    };
+    let mut high_water = 0;
+
+    for (key, survivor) in end_file.into_iter() {
+      let records = match &self.options.merge_operator {
+        Some(operator) if !survivor.operands.is_empty() => {
+          vec![self.fold_operands(operator, &key, survivor)?]
+        }
+        // Without an operator the operands are carried over untouched.
+        _ => survivor.base.into_iter().chain(survivor.operands).collect(),
+      };
+
+      for meta in records {
+        let record = Index {
+          offset: compacted.size,
+          file_id: output_id,
+          seq: meta.seq,
+          len: meta.len(),
+          expires_at: meta.expires_at,
+        };
+        match meta.kind() {
+          RecordKind::Delete => continue,
+          RecordKind::Put => {
+            compacted.data_index.insert(key.clone(), record);
+          }
+          RecordKind::Merge => compacted
+            .merges
+            .entry(key.clone())
+            .or_default()
+            .push(record),
+        }
+
+        self.compaction_limiter.request(meta.len());
+        Self::write_meta(output, &meta)?;
+        compacted.size += meta.len();
+        high_water = high_water.max(meta.seq);
+      }
+    }
+
+    if let Some((seq, key)) = newest.filter(|&(seq, _)| seq > high_water) {
+      // The key has no record in the output, so the tombstone deletes
+      // nothing.
+      let meta = self.new_record(seq, RECORD_VALUE, NO_EXPIRY, &key, b"")?;
+      Self::write_meta(output, &meta)?;
+      compacted.size += meta.len();
+    }
+    Ok(compacted)
+  }
+
+  /// Points the keydir at the compacted segment, which replaces `inputs`.
+  /// The caller holds every shard and the segment table.
+  ///
+  /// A put or delete drops every older version of its key, so a key's
+  /// compacted state only still applies while the keydir points into the
+  /// inputs. Keys written to since keep what they have; their compacted
+  /// records are dead weight in the output.
+  fn install_compaction(
+    &self,
+    inner: &mut Inner,
+    shards: &mut [RwLockWriteGuard<'_, Shard>],
+    keydir_segments: &mut Segments,
+    inputs: &[u64],
+    path: String,
+    mut compacted: Compacted,
+  ) {
+    let inputs = inputs.iter().copied().collect::<HashSet<_>>();
+    let in_inputs = |index: &Index| inputs.contains(&index.file_id);
+
+    for shard in shards.iter_mut() {
+      // Older versions lived in the inputs. No snapshot can still want them.
+      shard.history.clear();
+
+      let stale = shard
+        .data_index
+        .iter()
+        .filter(|(_, index)| in_inputs(index))
+        .map(|(key, _)| key)
+        .chain(
+          shard
+            .merges
+            .iter()
+            .filter(|(_, operands)| operands.iter().any(in_inputs))
+            .map(|(key, _)| key),
+        )
+        .cloned()
+        .collect::<HashSet<_>>();
+      for key in stale {
+        match compacted.data_index.remove(&key) {
+          Some(index) => shard.data_index.insert(key.clone(), index),
+          None => shard.data_index.remove(&key),
+        };
+        let newer = shard.merges.remove(&key).unwrap_or_default();
+        let operands = compacted
+          .merges
+          .remove(&key)
+          .unwrap_or_default()
+          .into_iter()
+          .chain(newer.into_iter().filter(|operand| !in_inputs(operand)))
+          .collect::<Vec<_>>();
+        if !operands.is_empty() {
+          shard.merges.insert(key, operands);
+        }
+      }
+    }
+
+    let superseded = compacted
+      .data_index
+      .values()
+      .chain(compacted.merges.values().flatten())
+      .map(|index| index.len)
+      .sum::<u64>();
+    for file_id in &inputs {
+      keydir_segments.file_index.remove(file_id);
+      inner.dead_bytes.remove(file_id);
+      inner.segment_sizes.remove(file_id);
+    }
+    keydir_segments.file_index.insert(compacted.output_id, path);
+    if superseded > 0 {
+      inner.dead_bytes.insert(compacted.output_id, superseded);
+    }
+    inner
+      .segment_sizes
+      .insert(compacted.output_id, compacted.size);
+    self.mmaps.lock().unwrap().clear();
+    self.readers.lock().unwrap().clear();
+    inner.last_compaction = Some(Utc::now());
+    info!("[COMPACT] Compaction has been completed successfully.");
+  }
+
+  /// Renames files named the way older versions named them. Runs before
+  /// compaction recovery, whose manifest refers to segments by id. The
+  /// segments of the first release are refused before anything is renamed.
+  fn migrate_file_names(&self) -> Result<(), io::Error> {
+    for entry in fs::read_dir(&self.options.dir)? {
+      let entry = entry?;
+      let name = entry.file_name().to_string_lossy().into_owned();
+      if file_names::parse_legacy_segment(&name).is_some()
+        && Self::is_version_0(&fs::read(entry.path())?, self.limits())
+      {
+        return Err(
+          DbError::UnsupportedFormat {
+            file: name,
+            version: 0,
+          }
+          .into(),
+        );
+      }
+    }
+
+    if file_names::migrate(&self.options.dir)? > 0 {
+      self.sync_dir()?;
+    }
+    Ok(())
+  }
+
+  /// Finishes or rolls back a compaction a crash interrupted, depending on
+  /// whether its output was already renamed into place.
+  fn recover_compaction(&self) -> Result<(), io::Error> {
+    let manifest_path = self.file_path(COMPACTION_MANIFEST);
+    let Some(manifest) = Manifest::read(&manifest_path)? else {
+      return Ok(());
+    };
+
+    let output = self.file_path(&file_names::segment(manifest.output_id));
+    if fs::exists(&output)? {
+      for &file_id in &manifest.inputs {
+        self.remove_segment(file_id)?;
+      }
+      for &file_id in &manifest.blobs {
+        self.value_log.remove(file_id)?;
+      }
+      info!(
+        "[RECOVERY] Finished an interrupted compaction.",
+        output_id = manifest.output_id
+      );
+    } else {
+      let temp_path = self.file_path(&manifest.temp_name);
+      if fs::exists(&temp_path)? {
+        fs::remove_file(temp_path)?;
+      }
+      info!("[RECOVERY] Rolled back an interrupted compaction.");
+    }
+
+    fs::remove_file(&manifest_path)?;
+    self.sync_dir()
+  }
+
+  /// Deletes what crashes left behind: temporary files that were never
+  /// renamed into place, and hint files whose segment is gone. Runs after
+  /// [`recover_compaction`](Self::recover_compaction), which decides what
+  /// to do with the temporary output of the compaction it recovers.
+  fn remove_orphans(&self) -> Result<(), io::Error> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&self.options.dir)? {
+      if let Ok(name) = entry?.file_name().into_string() {
+        names.push(name);
+      }
+    }
+    let segments = names
+      .iter()
+      .filter_map(|name| file_names::parse_segment(name))
+      .collect::<HashSet<_>>();
+    let manifest_temp = format!("{COMPACTION_MANIFEST}.tmp");
+
+    let mut removed = 0;
+    for name in &names {
+      let orphaned = file_names::is_temp(name)
+        || *name == manifest_temp
+        || file_names::parse_hint(name).is_some_and(|file_id| !segments.contains(&file_id));
+      if orphaned {
+        fs::remove_file(self.file_path(name))?;
+        removed += 1;
+      }
+    }
+
+    if removed > 0 {
+      info!(
+        "[RECOVERY] Removed files left behind by a crash.",
+        files = removed as u64
+      );
+      self.sync_dir()?;
+    }
+    Ok(())
+  }
 
-    for (key, value) in inner.data_index.iter() {
-      let timestamp = Utc::now().timestamp();
-      file.write_all(&key.len().to_le_bytes())?;
-      file.write_all(key.as_bytes())?;
-      file.write_all(&timestamp.to_le_bytes())?;
-      file.write_all(&value.file_id.to_le_bytes())?;
-      file.write_all(&value.offset.to_le_bytes())?;
+  /// Deletes segment `file_id` and its hint file, whichever still exist.
+  fn remove_segment(&self, file_id: u64) -> Result<(), io::Error> {
+    for name in [file_names::segment(file_id), file_names::hint(file_id)] {
+      let path = self.file_path(&name);
+      if fs::exists(&path)? {
+        fs::remove_file(path)?;
+      }
     }
+    Ok(())
+  }
+
+  /// Makes renames and deletions in the data directory durable.
+  fn sync_dir(&self) -> Result<(), io::Error> {
+    storage::sync_dir(&self.options.dir)
+  }
+
+  /// Checks the log every `interval` on a background thread owned by the
+  /// returned handle and compacts it when [`needs_compaction`](Self::needs_compaction)
+  /// says so. Dropping the handle stops the thread. Not available on wasm32,
+  /// which has no threads; call [`compact`](Self::compact) instead.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start_background_compaction(&self, interval: Duration) -> CompactionHandle {
+    CompactionHandle::spawn(self.clone(), interval)
+  }
+
+  /// Whether some segment has crossed `Options::compaction_dead_ratio`.
+  /// Always true when no ratio is configured.
+  pub fn needs_compaction(&self) -> Result<bool, io::Error> {
+    let threshold = match self.options.compaction_dead_ratio {
+      Some(threshold) => threshold,
+      None => return Ok(true),
+    };
+
+    Ok(
+      self
+        .stats()?
+        .segments
+        .iter()
+        .any(|segment| segment.dead_ratio() >= threshold),
+    )
+  }
+
+  /// Changes the compaction I/O budget (bytes per second) at runtime,
+  /// including for compactions already in progress. `None` removes the cap.
+  pub fn set_compaction_rate_limit(&self, bytes_per_sec: Option<u64>) {
+    self.compaction_limiter.set_rate(bytes_per_sec);
+  }
+
+  pub fn compaction_rate_limit(&self) -> Option<u64> {
+    self.compaction_limiter.rate()
+  }
+
+  /// Calls `hook` after every fsync of the active segment. See
+  /// [`crate::hooks`] for when hooks run.
+  pub fn on_flush(&self, hook: impl Fn(&FlushInfo) + Send + Sync + 'static) {
+    self
+      .hooks
+      .register(|registry| registry.flush.push(Box::new(hook)));
+  }
+
+  /// Calls `hook` when a compaction begins.
+  pub fn on_compaction_start(&self, hook: impl Fn(&CompactionStartInfo) + Send + Sync + 'static) {
+    self
+      .hooks
+      .register(|registry| registry.compaction_start.push(Box::new(hook)));
+  }
+
+  /// Calls `hook` when a compaction has replaced its input segments.
+  pub fn on_compaction_finish(&self, hook: impl Fn(&CompactionInfo) + Send + Sync + 'static) {
+    self
+      .hooks
+      .register(|registry| registry.compaction_finish.push(Box::new(hook)));
+  }
+
+  /// Calls `hook` whenever the active segment is sealed and writes move on
+  /// to a new one.
+  pub fn on_segment_sealed(&self, hook: impl Fn(&SegmentSealedInfo) + Send + Sync + 'static) {
+    self
+      .hooks
+      .register(|registry| registry.segment_sealed.push(Box::new(hook)));
+  }
 
-    info!("[HINT] Hint file has been written successfully.");
+  /// Writes the hint file of the sealed segment `file_id` at `path`, listing the
+  /// header of every record in it.
+  fn write_hint_file(&self, file_id: u64, path: &str) -> Result<(), io::Error> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut offset = Self::segment_start(file_id, &file)?;
+    let mut entries = Vec::new();
+    while offset < size {
+      let records = Self::read_entry(&mut offset, size, &file, self.limits())?;
+      if records.is_empty() {
+        break;
+      }
+      for (record_offset, meta) in records {
+        entries.push(HintEntry {
+          seq: meta.seq,
+          kind: meta.kind(),
+          offset: record_offset,
+          len: meta.len(),
+          expires_at: meta.expires_at,
+          key: utf8(meta.key_buf)?,
+        });
+      }
+    }
 
+    hint::write(&self.file_path(&file_names::hint(file_id)), &entries)?;
+    info!(
+      "[HINT] Hint file has been written successfully.",
+      file_id = file_id
+    );
     Ok(())
   }
 
+  /// Collapses a key's value and merge operands into a single put stamped
+  /// with the newest operand's sequence number.
+  fn fold_operands(
+    &self,
+    operator: &MergeOperator,
+    key: &str,
+    survivor: Survivor,
+  ) -> Result<MetaIndex, io::Error> {
+    let existing = survivor
+      .base
+      .map(|base| utf8(self.value_of(base)?))
+      .transpose()?;
+    let seq = survivor.operands.last().unwrap().seq;
+    let operands = survivor
+      .operands
+      .into_iter()
+      .map(|operand| utf8(operand.into_value()?))
+      .collect::<Result<Vec<_>, io::Error>>()?;
+    let operands = operands.iter().map(String::as_str).collect::<Vec<_>>();
+    let value = operator.apply(existing.as_deref(), &operands);
+    // Anything longer would be rejected as corrupt when read back.
+    self.check_sizes(key, &value)?;
+
+    self.new_record(seq, RECORD_VALUE, NO_EXPIRY, key, value.as_bytes())
+  }
+
+  /// Builds a record for `key`, compressing `value` when
+  /// `Options::compression` asks for it and it actually saves space.
+  fn new_record(
+    &self,
+    seq: u64,
+    record_type: u64,
+    expires_at: i64,
+    key: &str,
+    value: &[u8],
+  ) -> Result<MetaIndex, io::Error> {
+    let blob = record_type == RECORD_VALUE
+      && !self.options.in_memory
+      && self
+        .options
+        .value_log_threshold
+        .is_some_and(|threshold| !value.is_empty() && value.len() >= threshold);
+    let compressed = match self.options.compression {
+      Compression::Lz if !blob && value.len() >= MIN_COMPRESSED_VALUE => {
+        Some(compression::compress(value)).filter(|compressed| compressed.len() < value.len())
+      }
+      _ => None,
+    };
+    let (record_type, value_buf) = match compressed {
+      _ if blob => {
+        // Under `SyncPolicy::Always` the value is made durable before any
+        // record points at it; otherwise `sync_inner` syncs it first.
+        let sync = self.options.sync_policy == SyncPolicy::Always;
+        let pointer = self.value_log.append(value, sync)?;
+        (record_type | RECORD_BLOB, pointer.encode())
+      }
+      Some(compressed) => (record_type | RECORD_COMPRESSED, compressed),
+      None => (record_type, value.to_vec()),
+    };
+
+    Ok(MetaIndex {
+      timestamp: Utc::now().timestamp_nanos_opt().unwrap(),
+      seq,
+      record_type,
+      expires_at,
+      key_size: key.len(),
+      key_buf: key.as_bytes().to_vec(),
+      value_size: value_buf.len(),
+      value_buf,
+    })
+  }
+
   fn compact_file(
     &self,
-    end_file: &mut HashMap<String, MetaIndex>,
-    file_idx: &String,
+    end_file: &mut HashMap<String, Survivor>,
+    newest: &mut Option<(u64, String)>,
+    file_id: u64,
+    path: &str,
   ) -> Result<(), io::Error> {
-    let mut offset = 0;
-    let file = File::open(file_idx)?;
-    let meta_data = fs::metadata(file_idx)?;
+    if self.options.in_memory {
+      let segment = self.memory_segment(file_id)?;
+      let bytes = segment.read().unwrap();
+      return self.compact_records(end_file, newest, file_id, bytes.as_slice());
+    }
+    self.compact_records(end_file, newest, file_id, &File::open(path)?)
+  }
+
+  /// Collects the survivors of `source` into `end_file`, and keeps track of
+  /// the newest record seen in `newest` (see
+  /// [`write_survivors`](Self::write_survivors)).
+  fn compact_records(
+    &self,
+    end_file: &mut HashMap<String, Survivor>,
+    newest: &mut Option<(u64, String)>,
+    file_id: u64,
+    source: &(impl RecordSource + ?Sized),
+  ) -> Result<(), io::Error> {
+    let size = source.size()?;
+    let mut offset = Self::segment_start(file_id, source)?;
 
     loop {
-      if meta_data.size() <= offset {
+      if size <= offset {
         break;
       }
 
-      let meta = self.get_index_from_file(&mut offset, &file)?;
-      let key = String::from_utf8(meta.key_buf.clone()).unwrap();
+      // A short tail (torn record or batch) was never acknowledged; skip it.
+      let entry_offset = offset;
+      let records = match Self::read_entry(&mut offset, size, source, self.limits()) {
+        Ok(records) if records.is_empty() => break,
+        Ok(records) => records,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+        Err(e) => return Err(e),
+      };
+      self.compaction_limiter.request(offset - entry_offset);
+
+      for (_, meta) in records {
+        let key = utf8(meta.key_buf.clone())?;
+        if newest.as_ref().is_none_or(|&(seq, _)| meta.seq > seq) {
+          *newest = Some((meta.seq, key.clone()));
+        }
 
-      if meta.value_buf.is_empty() {
-        end_file.remove(&key);
-        continue;
+        match meta.kind() {
+          RecordKind::Merge => end_file.entry(key).or_default().operands.push(meta),
+          RecordKind::Put if !meta.is_expired() => {
+            end_file.insert(
+              key,
+              Survivor {
+                base: Some(meta),
+                operands: Vec::new(),
+              },
+            );
+          }
+          RecordKind::Put | RecordKind::Delete => {
+            end_file.remove(&key);
+          }
+        }
       }
-
-      end_file.insert(key, meta);
     }
 
     Ok(())
@@ -448,91 +2867,459 @@ impl LogFile {
     meta: MetaIndex,
     inner: &mut MutexGuard<'_, Inner>,
   ) -> Result<(), io::Error> {
-    let mut file = OpenOptions::new().append(true).open(&inner.path)?;
+    let mut buf = Vec::with_capacity(meta.len() as usize);
+    Self::write_meta(&mut buf, &meta)?;
+    inner.active()?.write_all(&buf)?;
 
-    file.write_all(&meta.timestamp.to_le_bytes())?;
-    file.write_all(&meta.key_size.to_le_bytes())?;
-    file.write_all(&meta.value_size.to_le_bytes())?;
-    file.write_all(&meta.key_buf)?;
-    file.write_all(&meta.value_buf)?;
+    Ok(())
+  }
 
-    // CRASH SAFETY HERE
-    file.sync_all()?; // durability guarantee
+  /// Writes one record: crc, ts, seq, record_type, expires_at, key_size,
+  /// value_size, key, value. The CRC32 covers everything after itself.
+  fn write_meta(file: &mut impl Write, meta: &MetaIndex) -> Result<(), io::Error> {
+    let mut body = RecordWriter::with_capacity(meta.len() as usize - 4);
+    body.put_i64(meta.timestamp);
+    body.put_u64(meta.seq);
+    body.put_u64(meta.record_type);
+    body.put_i64(meta.expires_at);
+    body.put_u64(meta.key_size as u64);
+    body.put_u64(meta.value_size as u64);
+    body.put_bytes(&meta.key_buf);
+    body.put_bytes(&meta.value_buf);
+
+    file.write_all(&crc32fast::hash(body.as_bytes()).to_le_bytes())?;
+    file.write_all(body.as_bytes())?;
+    Ok(())
+  }
 
-    // FILE SEGMENTATION HERE
-    self.split(inner)?;
+  /// Parses the fields [`write_meta`](Self::write_meta) puts between the
+  /// crc and the key. The key and value are left empty for the caller.
+  fn parse_header(header: &[u8]) -> Result<MetaIndex, io::Error> {
+    let mut fields = RecordReader::new(header);
+    Ok(MetaIndex {
+      timestamp: fields.get_i64()?,
+      seq: fields.get_u64()?,
+      record_type: fields.get_u64()?,
+      expires_at: fields.get_i64()?,
+      key_size: fields.get_u64()? as usize,
+      key_buf: Vec::new(),
+      value_size: fields.get_u64()? as usize,
+      value_buf: Vec::new(),
+    })
+  }
 
+  /// Fails with `InvalidData` unless `crc` matches the record's contents.
+  /// Fails with [`Unwritten`] for the header of a record that was never
+  /// written. Every real record has a timestamp, so its header isn't zero.
+  fn check_written(crc: u32, header: &[u8]) -> Result<(), io::Error> {
+    if crc == 0 && header.iter().all(|&byte| byte == 0) {
+      return Err(io::Error::new(io::ErrorKind::UnexpectedEof, Unwritten));
+    }
     Ok(())
   }
 
-  fn get_index_value(&self, id: &str) -> Result<MetaIndex, io::Error> {
-    let inner = self.inner.lock().unwrap();
-    if !inner.data_index.contains_key(id) {
-      return Err(io::Error::other(""));
+  fn verify_crc(
+    crc: u32,
+    header: &[u8],
+    key_buf: &[u8],
+    value_buf: &[u8],
+  ) -> Result<(), io::Error> {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(header);
+    hasher.update(key_buf);
+    hasher.update(value_buf);
+    if hasher.finalize() != crc {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Corrupted record: checksum mismatch",
+      ));
+    }
+    Ok(())
+  }
+
+  /// Resolves the live value of `key`, folding in pending merge operands.
+  fn get_value(&self, shard: &Shard, key: &str) -> Result<String, io::Error> {
+    let base = shard.data_index.get(key);
+    let operands = shard.merges.get(key).map(Vec::as_slice).unwrap_or_default();
+    if base.is_none() && operands.is_empty() {
+      return Err(Self::missing());
+    }
+
+    self.resolve(base, operands)
+  }
+
+  /// Reads `base` (if any and not expired) and applies the merge operator to
+  /// it and `operands`.
+  fn resolve(&self, base: Option<&Index>, operands: &[Index]) -> Result<String, io::Error> {
+    let existing = match base {
+      Some(index) => {
+        let meta = self.read_index(index)?;
+        if meta.is_expired() {
+          None
+        } else {
+          Some(utf8(self.value_of(meta)?)?)
+        }
+      }
+      None => None,
+    };
+    if operands.is_empty() {
+      return existing.ok_or_else(Self::expired);
+    }
+
+    let Some(operator) = &self.options.merge_operator else {
+      return Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "This key has merge operands but no merge operator is configured",
+      ));
+    };
+
+    let operands = operands
+      .iter()
+      .map(|operand| utf8(self.read_index(operand)?.into_value()?))
+      .collect::<Result<Vec<_>, io::Error>>()?;
+    let operands = operands.iter().map(String::as_str).collect::<Vec<_>>();
+    Ok(operator.apply(existing.as_deref(), &operands))
+  }
+
+  /// The value of `meta` as written by the user, read from the value log if
+  /// it was stored there.
+  fn value_of(&self, meta: MetaIndex) -> Result<Vec<u8>, io::Error> {
+    match meta.blob()? {
+      Some(pointer) => self.value_log.read(&pointer),
+      None => meta.into_value(),
+    }
+  }
+
+  fn limits(&self) -> SizeLimits {
+    SizeLimits {
+      key: self.options.max_key_size,
+      value: self.options.max_value_size,
+    }
+  }
+
+  /// Rejects a put with an empty key or value, or one over the configured
+  /// limits. An empty value is how a delete is stored, so a put can't have
+  /// one.
+  fn check_put(&self, key: &str, value: &str) -> Result<(), io::Error> {
+    if key.is_empty() {
+      return Err(DbError::EmptyKey.into());
+    }
+    if value.is_empty() {
+      return Err(DbError::EmptyValue.into());
+    }
+    self.check_sizes(key, value)
+  }
+
+  /// Rejects a write whose key or value is over the configured limits.
+  fn check_sizes(&self, key: &str, value: &str) -> Result<(), io::Error> {
+    if key.len() > self.options.max_key_size {
+      return Err(
+        DbError::KeyTooLarge {
+          size: key.len(),
+          max: self.options.max_key_size,
+        }
+        .into(),
+      );
+    }
+    if value.len() > self.options.max_value_size {
+      return Err(
+        DbError::ValueTooLarge {
+          size: value.len(),
+          max: self.options.max_value_size,
+        }
+        .into(),
+      );
+    }
+    Ok(())
+  }
+
+  fn expired() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "This key has expired")
+  }
+
+  /// `NotFound`, so callers can tell a missing key from a failed read
+  /// without checking for it first.
+  fn missing() -> io::Error {
+    io::Error::new(
+      io::ErrorKind::NotFound,
+      "This key does not exist in the index",
+    )
+  }
+
+  /// Loads the record `index` points at, through a cached memory map when
+  /// the segment is sealed and `mmap_sealed_segments` is enabled. The caller
+  /// must hold the shard owning `index` so compaction can't remove the file.
+  fn read_index(&self, index: &Index) -> Result<MetaIndex, io::Error> {
+    if self.options.in_memory {
+      let segment = self.memory_segment(index.file_id)?;
+      let bytes = segment.read().unwrap();
+      let mut offset = index.offset;
+      return Self::get_index_from_slice(&mut offset, &bytes, self.limits());
     }
 
-    let index = inner.data_index.get(id).unwrap();
-    let file = File::open(inner.file_index.get(&index.file_id).unwrap())?;
+    let segments = self.keydir.segments();
+    let path = segments.file_index.get(&index.file_id).unwrap().clone();
+    let sealed = index.file_id != segments.active_file_id;
+    drop(segments);
+    self.flush_if_active(index.file_id)?;
     let mut offset = index.offset;
 
-    drop(inner);
-    self.get_index_from_file(&mut offset, &file)
+    if self.options.mmap_sealed_segments && sealed {
+      let mut mmaps = self.mmaps.lock().unwrap();
+      let map = match mmaps.get(&index.file_id) {
+        Some(map) => {
+          self.metrics.cache_hits.fetch_add(1, Ordering::Relaxed);
+          map.clone()
+        }
+        None => {
+          self.metrics.cache_misses.fetch_add(1, Ordering::Relaxed);
+          let map = Arc::new(storage::map(&File::open(path)?)?);
+          mmaps.insert(index.file_id, map.clone());
+          map
+        }
+      };
+      drop(mmaps);
+
+      return Self::get_index_from_slice(&mut offset, &map, self.limits());
+    }
+
+    let file = self.reader(index.file_id, &path)?;
+    Self::get_index_from_file(&mut offset, index.offset + index.len, &file, self.limits())
+  }
+
+  /// The contents of in-memory segment `file_id`.
+  fn memory_segment(&self, file_id: u64) -> Result<MemorySegment, io::Error> {
+    self
+      .memory
+      .lock()
+      .unwrap()
+      .get(&file_id)
+      .cloned()
+      .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such segment"))
+  }
+
+  /// Makes appends still buffered for segment `file_id` readable.
+  fn flush_if_active(&self, file_id: u64) -> Result<(), io::Error> {
+    let segments = self.keydir.segments();
+    let writer = segments
+      .active_writer
+      .clone()
+      .filter(|_| segments.active_file_id == file_id);
+    drop(segments);
+
+    match writer {
+      Some(writer) => writer.flush(),
+      None => Ok(()),
+    }
+  }
+
+  /// Shared read handle for segment `file_id`, opened on first use. At most
+  /// `MAX_OPEN_READERS` stay open; an evicted one is simply reopened.
+  fn reader(&self, file_id: u64, path: &str) -> Result<Arc<File>, io::Error> {
+    let mut readers = self.readers.lock().unwrap();
+    if let Some(file) = readers.get(&file_id) {
+      self.metrics.cache_hits.fetch_add(1, Ordering::Relaxed);
+      return Ok(file.clone());
+    }
+    self.metrics.cache_misses.fetch_add(1, Ordering::Relaxed);
+
+    if readers.len() >= MAX_OPEN_READERS {
+      let evicted = *readers.keys().next().unwrap();
+      readers.remove(&evicted);
+    }
+    let file = Arc::new(File::open(path)?);
+    readers.insert(file_id, file.clone());
+    Ok(file)
   }
 
-  fn get_index_from_file(&self, offset: &mut u64, file: &File) -> Result<MetaIndex, io::Error> {
-    let mut ts_buff = [0u8; 8];
-    file.read_exact_at(&mut ts_buff, *offset)?;
-    let timestamp = i64::from_le_bytes(ts_buff);
-    *offset += 8;
+  /// Same as [`get_index_from_file`](Self::get_index_from_file) but parses
+  /// the record out of a mapped or in-memory segment.
+  fn get_index_from_slice(
+    offset: &mut u64,
+    buf: &[u8],
+    limits: SizeLimits,
+  ) -> Result<MetaIndex, io::Error> {
+    let mut reader = RecordReader::new(buf.get(*offset as usize..).unwrap_or_default());
+    let crc = reader.get_u32()?;
+    let header = reader.get_bytes(HEADER_SIZE as usize - 4)?;
+    Self::check_written(crc, header)?;
+    let mut meta = Self::parse_header(header)?;
+    limits.check(meta.key_size, meta.value_size)?;
+    meta.key_buf = reader.get_bytes(meta.key_size)?.to_vec();
+    meta.value_buf = reader.get_bytes(meta.value_size)?.to_vec();
+    Self::verify_crc(crc, header, &meta.key_buf, &meta.value_buf)?;
+
+    *offset += reader.position() as u64;
+    Ok(meta)
+  }
+
+  fn segment_header() -> [u8; SEGMENT_HEADER_SIZE as usize] {
+    let mut header = [0; SEGMENT_HEADER_SIZE as usize];
+    header[..4].copy_from_slice(SEGMENT_MAGIC);
+    header[4..].copy_from_slice(&SEGMENT_VERSION.to_le_bytes());
+    header
+  }
+
+  /// Where the records of segment `file_id` start: past its header, or at
+  /// the beginning for a segment written before segments had one. A header
+  /// of another version fails with `DbError::UnsupportedFormat`.
+  fn segment_start(file_id: u64, source: &(impl RecordSource + ?Sized)) -> Result<u64, io::Error> {
+    let prefix = source.prefix(SEGMENT_HEADER_SIZE)?;
+    let Some(version) = prefix.strip_prefix(SEGMENT_MAGIC) else {
+      return Ok(0);
+    };
+    match <[u8; 4]>::try_from(version).map(u32::from_le_bytes) {
+      Ok(SEGMENT_VERSION) => Ok(SEGMENT_HEADER_SIZE),
+      Ok(version) => Err(
+        DbError::UnsupportedFormat {
+          file: file_names::segment(file_id),
+          version,
+        }
+        .into(),
+      ),
+      Err(_) => Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Corrupted segment: truncated header",
+      )),
+    }
+  }
+
+  /// Whether `bytes`, a segment without a header, holds version 0 records
+  /// rather than current ones: its first record doesn't pass as a current
+  /// one, and the whole segment splits into version 0 records.
+  fn is_version_0(bytes: &[u8], limits: SizeLimits) -> bool {
+    if bytes.is_empty() || Self::read_entry(&mut 0, bytes.len() as u64, bytes, limits).is_ok() {
+      return false;
+    }
+
+    let mut reader = RecordReader::new(bytes);
+    while !reader.is_empty() {
+      if Self::skip_version_0_record(&mut reader, limits).is_err() {
+        return false;
+      }
+    }
+    true
+  }
+
+  /// Reads past a version 0 record: timestamp, key size and value size
+  /// followed by the key and value, without a checksum.
+  fn skip_version_0_record(reader: &mut RecordReader, limits: SizeLimits) -> Result<(), io::Error> {
+    let _timestamp = reader.get_i64()?;
+    let key_size = reader.get_u64()? as usize;
+    let value_size = reader.get_u64()? as usize;
+    limits.check(key_size, value_size)?;
+    utf8(reader.get_bytes(key_size)?.to_vec())?;
+    reader.get_bytes(value_size)?;
+    Ok(())
+  }
+
+  /// Reads the entry starting at `offset` along with each record's offset:
+  /// either a single record or every record of a write batch. A batch that
+  /// is not entirely on disk fails with `UnexpectedEof`, like a torn record.
+  /// Returns no records at all where the segment's unwritten space starts.
+  fn read_entry(
+    offset: &mut u64,
+    end: u64,
+    source: &(impl RecordSource + ?Sized),
+    limits: SizeLimits,
+  ) -> Result<Vec<(u64, MetaIndex)>, io::Error> {
+    let record_offset = *offset;
+    let meta = match source.read_record(offset, end, limits) {
+      Ok(meta) => meta,
+      Err(e) if e.get_ref().is_some_and(|e| e.is::<Unwritten>()) => {
+        *offset = record_offset;
+        return Ok(Vec::new());
+      }
+      Err(e) => return Err(e),
+    };
+    if !meta.key_buf.is_empty() {
+      return Ok(vec![(record_offset, meta)]);
+    }
 
-    let mut key_size_buf = [0u8; 8];
-    file.read_exact_at(&mut key_size_buf, *offset)?;
-    let key_size = u64::from_le_bytes(key_size_buf) as usize;
-    *offset += 8;
+    let count = <[u8; 8]>::try_from(meta.value_buf.as_slice())
+      .map(u64::from_le_bytes)
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Corrupted batch header"))?;
 
-    let mut value_size_buf = [0u8; 8];
-    file.read_exact_at(&mut value_size_buf, *offset)?;
-    let value_size = u64::from_le_bytes(value_size_buf) as usize;
-    *offset += 8;
+    let mut records = Vec::new();
+    for _ in 0..count {
+      let record_offset = *offset;
+      records.push((record_offset, source.read_record(offset, end, limits)?));
+    }
+    Ok(records)
+  }
 
-    let file_size = file.metadata()?.size();
-    if *offset + key_size as u64 + value_size as u64 > file_size {
+  fn get_index_from_file(
+    offset: &mut u64,
+    end: u64,
+    file: &File,
+    limits: SizeLimits,
+  ) -> Result<MetaIndex, io::Error> {
+    let mut head = [0u8; HEADER_SIZE as usize];
+    file.read_exact_at(&mut head, *offset)?;
+    let (crc, header) = head.split_at(4);
+    let crc = u32::from_le_bytes(crc.try_into().unwrap());
+    Self::check_written(crc, header)?;
+    let mut meta = Self::parse_header(header)?;
+    *offset += HEADER_SIZE;
+    limits.check(meta.key_size, meta.value_size)?;
+
+    // Check against the known end of the record or segment before
+    // allocating, so a corrupt header can't claim up to the size limits.
+    let record_end = offset
+      .saturating_add(meta.key_size as u64)
+      .saturating_add(meta.value_size as u64);
+    if record_end > end {
       return Err(io::Error::new(
         io::ErrorKind::UnexpectedEof,
         "Corrupted record: claimed size exceeds file",
       ));
     }
 
-    let mut key_buf = vec![0u8; key_size];
-    file.read_exact_at(&mut key_buf, *offset)?;
-    *offset += key_size as u64;
+    meta.key_buf = vec![0u8; meta.key_size];
+    file.read_exact_at(&mut meta.key_buf, *offset)?;
+    *offset += meta.key_size as u64;
 
-    let mut value_buf = vec![0u8; value_size];
-    file.read_exact_at(&mut value_buf, *offset)?;
-    *offset += value_size as u64;
+    meta.value_buf = vec![0u8; meta.value_size];
+    file.read_exact_at(&mut meta.value_buf, *offset)?;
+    *offset += meta.value_size as u64;
+    Self::verify_crc(crc, header, &meta.key_buf, &meta.value_buf)?;
 
-    Ok(MetaIndex {
-      timestamp,
-      key_size,
-      key_buf,
-      value_size,
-      value_buf,
-    })
+    Ok(meta)
   }
 
   fn split(&self, inner: &mut MutexGuard<'_, Inner>) -> Result<(), io::Error> {
-    let metadata = fs::metadata(&inner.path)?;
-
-    if metadata.size() > FILE_THRESHOLD {
+    if inner.byte_offset > FILE_THRESHOLD {
       trace!(
         "[LOGFILE] File has exceeded the threshold",
         threshold = FILE_THRESHOLD,
-        file_size = metadata.size()
+        file_size = inner.byte_offset
       );
-
-      inner.current_file_id += 1;
-      self.create()?;
+      let next_id = inner.current_file_id + 1;
+      self.seal_active(inner, next_id)?;
     }
     Ok(())
   }
+
+  /// Seals the active segment and carries on writing in segment `next_id`.
+  fn seal_active(&self, inner: &mut Inner, next_id: u64) -> Result<(), io::Error> {
+    // Group commits only sync the active segment, so seal this one first.
+    inner.active()?.seal()?;
+    if !self.options.in_memory {
+      self.write_hint_file(inner.current_file_id, &inner.path)?;
+    }
+    let file_id = inner.current_file_id;
+    let size = inner.byte_offset;
+    inner.segment_sizes.insert(file_id, size);
+    self.metrics.segments_sealed.fetch_add(1, Ordering::Relaxed);
+    self.hooks.emit(|| {
+      HookEvent::SegmentSealed(SegmentSealedInfo {
+        file_id,
+        size,
+        dead_bytes: inner.dead_bytes.get(&file_id).copied().unwrap_or(0),
+      })
+    });
+
+    inner.current_file_id = next_id;
+    self.create(inner)
+  }
 }
@@ -0,0 +1,97 @@
+//! The engine's logging facade.
+//!
+//! Every event is a short message plus `key = value` fields. With the
+//! default `ttlog` feature they go to ttlog, as they always have, so the
+//! application sets up a `ttlog::trace::Trace` to see them. Applications
+//! that prefer their own logger install it once with [`set_logger`], and
+//! building without the feature drops ttlog entirely. With neither, logging
+//! costs nothing.
+
+use std::{fmt, io, sync::OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+  Trace,
+  Debug,
+  Info,
+  Warn,
+  Error,
+}
+
+impl fmt::Display for Level {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Level::Trace => "TRACE",
+      Level::Debug => "DEBUG",
+      Level::Info => "INFO",
+      Level::Warn => "WARN",
+      Level::Error => "ERROR",
+    })
+  }
+}
+
+/// A field of an event, such as `("key", &"user:1")`.
+pub type Field<'a> = (&'static str, &'a dyn fmt::Display);
+
+/// Receives the engine's log events. Any
+/// `Fn(Level, &str, &[Field]) + Send + Sync` closure is a logger too.
+pub trait Logger: Send + Sync {
+  fn log(&self, level: Level, message: &str, fields: &[Field<'_>]);
+}
+
+impl<F> Logger for F
+where
+  F: Fn(Level, &str, &[Field<'_>]) + Send + Sync,
+{
+  fn log(&self, level: Level, message: &str, fields: &[Field<'_>]) {
+    self(level, message, fields)
+  }
+}
+
+static LOGGER: OnceLock<Box<dyn Logger>> = OnceLock::new();
+
+/// Sends every log event of the process to `logger`. It can be installed
+/// once; later calls fail with `AlreadyExists`.
+pub fn set_logger(logger: impl Logger + 'static) -> Result<(), io::Error> {
+  LOGGER.set(Box::new(logger)).map_err(|_| {
+    io::Error::new(
+      io::ErrorKind::AlreadyExists,
+      "A logger has already been installed",
+    )
+  })
+}
+
+pub(crate) fn logger() -> Option<&'static dyn Logger> {
+  LOGGER.get().map(|logger| &**logger)
+}
+
+/// Sends an event to ttlog, if enabled, and to the installed logger. Field
+/// values are only evaluated for a logger that is there, so they should be
+/// cheap and free of side effects.
+macro_rules! emit {
+  ($level:ident, $ttlog:ident, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+    #[cfg(feature = "ttlog")]
+    ::ttlog::ttlog_macros::$ttlog!($message $(, $key = $value)*);
+    if let Some(logger) = $crate::logging::logger() {
+      logger.log(
+        $crate::logging::Level::$level,
+        $message,
+        &[$((stringify!($key), &$value as &dyn ::std::fmt::Display)),*],
+      );
+    }
+  }};
+}
+
+macro_rules! trace {
+  ($($args:tt)*) => { $crate::logging::emit!(Trace, trace, $($args)*) };
+}
+
+macro_rules! info {
+  ($($args:tt)*) => { $crate::logging::emit!(Info, info, $($args)*) };
+}
+
+macro_rules! error {
+  ($($args:tt)*) => { $crate::logging::emit!(Error, error, $($args)*) };
+}
+
+pub(crate) use {emit, error, info, trace};
@@ -0,0 +1,32 @@
+//! User-supplied merge operators.
+//!
+//! [`LogFile::merge`](crate::log_file::LogFile::merge) appends an operand
+//! instead of a full value. The operator combines the key's existing value
+//! with every operand written since, both when the key is read and when
+//! compaction rewrites it, so counters, appends and similar read-modify-write
+//! updates happen inside the engine.
+
+use std::{fmt, sync::Arc};
+
+type MergeFn = dyn Fn(Option<&str>, &[&str]) -> String + Send + Sync;
+
+/// `fn(existing, operands) -> value`, where `existing` is `None` if the key
+/// had no value and `operands` are in write order.
+#[derive(Clone)]
+pub struct MergeOperator(Arc<MergeFn>);
+
+impl MergeOperator {
+  pub fn new(merge: impl Fn(Option<&str>, &[&str]) -> String + Send + Sync + 'static) -> Self {
+    Self(Arc::new(merge))
+  }
+
+  pub(crate) fn apply(&self, existing: Option<&str>, operands: &[&str]) -> String {
+    (self.0)(existing, operands)
+  }
+}
+
+impl fmt::Debug for MergeOperator {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("MergeOperator(..)")
+  }
+}
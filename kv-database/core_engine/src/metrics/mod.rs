@@ -0,0 +1,239 @@
+//! Operation counters and latency histograms, rendered in the Prometheus
+//! text exposition format by [`LogFile::render_metrics`](crate::log_file::LogFile::render_metrics).
+//!
+//! Everything here is a relaxed atomic, so recording costs a few uncontended
+//! increments and never takes a lock. Point-in-time gauges such as the
+//! segment count come from [`Stats`] when the metrics are rendered.
+
+use std::{
+  fmt::Write,
+  sync::atomic::{AtomicU64, Ordering},
+  time::Duration,
+};
+
+use crate::{clock::Instant, stats::Stats};
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 10] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// A Prometheus histogram with fixed [`BUCKETS`].
+#[derive(Debug, Default)]
+pub(crate) struct Histogram {
+  /// Observations per bucket, not cumulative; the last one is `+Inf`.
+  buckets: [AtomicU64; BUCKETS.len() + 1],
+  sum_nanos: AtomicU64,
+  count: AtomicU64,
+}
+
+impl Histogram {
+  pub(crate) fn observe(&self, duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    let bucket = BUCKETS
+      .iter()
+      .position(|&bound| seconds <= bound)
+      .unwrap_or(BUCKETS.len());
+    self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    self
+      .sum_nanos
+      .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    self.count.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Observes the time until the returned guard is dropped.
+  pub(crate) fn start_timer(&self) -> Timer<'_> {
+    Timer {
+      histogram: self,
+      started: Instant::now(),
+    }
+  }
+
+  fn render(&self, out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    let mut cumulative = 0;
+    for (i, bucket) in self.buckets.iter().enumerate() {
+      cumulative += bucket.load(Ordering::Relaxed);
+      let bound = BUCKETS
+        .get(i)
+        .map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+      let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+    }
+    let sum = self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+    let _ = writeln!(out, "{name}_sum {sum}");
+    let _ = writeln!(out, "{name}_count {}", self.count.load(Ordering::Relaxed));
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct Timer<'a> {
+  histogram: &'a Histogram,
+  started: Instant,
+}
+
+impl Drop for Timer<'_> {
+  fn drop(&mut self) {
+    self.histogram.observe(self.started.elapsed());
+  }
+}
+
+/// Everything a log counts while it runs. Counters start at zero when the
+/// log is opened.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+  pub(crate) puts: AtomicU64,
+  pub(crate) deletes: AtomicU64,
+  pub(crate) merges: AtomicU64,
+  pub(crate) batches: AtomicU64,
+  /// Calls to the reading API, as opposed to `Stats::reads` which counts
+  /// keys.
+  pub(crate) read_latency: Histogram,
+  /// Time until a write is acknowledged, fsync included.
+  pub(crate) write_latency: Histogram,
+  pub(crate) sync_latency: Histogram,
+  pub(crate) segments_sealed: AtomicU64,
+  pub(crate) compactions: AtomicU64,
+  pub(crate) compaction_bytes_read: AtomicU64,
+  pub(crate) compaction_bytes_written: AtomicU64,
+  pub(crate) compaction_latency: Histogram,
+  /// Segment lookups served by the open file and memory map caches, and
+  /// those that had to open the segment. Reported through `Stats`.
+  pub(crate) cache_hits: AtomicU64,
+  pub(crate) cache_misses: AtomicU64,
+}
+
+impl Metrics {
+  /// Renders every metric, with the gauges taken from `stats`.
+  pub(crate) fn render(&self, stats: &Stats) -> String {
+    let mut out = String::new();
+    let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+      let _ = writeln!(out, "# HELP {name} {help}");
+      let _ = writeln!(out, "# TYPE {name} counter");
+      let _ = writeln!(out, "{name} {value}");
+    };
+    let gauge = |out: &mut String, name: &str, help: &str, value: f64| {
+      let _ = writeln!(out, "# HELP {name} {help}");
+      let _ = writeln!(out, "# TYPE {name} gauge");
+      let _ = writeln!(out, "{name} {value}");
+    };
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+    let _ = writeln!(
+      out,
+      "# HELP duck_records_written_total Records appended to the log, by kind."
+    );
+    let _ = writeln!(out, "# TYPE duck_records_written_total counter");
+    for (kind, value) in [
+      ("put", &self.puts),
+      ("delete", &self.deletes),
+      ("merge", &self.merges),
+    ] {
+      let _ = writeln!(
+        out,
+        "duck_records_written_total{{kind=\"{kind}\"}} {}",
+        load(value)
+      );
+    }
+    counter(
+      &mut out,
+      "duck_batches_written_total",
+      "Write batches committed.",
+      load(&self.batches),
+    );
+    counter(
+      &mut out,
+      "duck_keys_read_total",
+      "Keys looked up.",
+      stats.reads,
+    );
+    counter(
+      &mut out,
+      "duck_segment_cache_hits_total",
+      "Reads that found their segment already open or mapped.",
+      stats.cache_hits,
+    );
+    counter(
+      &mut out,
+      "duck_segment_cache_misses_total",
+      "Reads that had to open or map their segment.",
+      stats.cache_misses,
+    );
+    self.read_latency.render(
+      &mut out,
+      "duck_read_duration_seconds",
+      "Latency of read calls.",
+    );
+    self.write_latency.render(
+      &mut out,
+      "duck_write_duration_seconds",
+      "Latency of durable write calls.",
+    );
+    self.sync_latency.render(
+      &mut out,
+      "duck_sync_duration_seconds",
+      "Latency of fsyncs of the active segment.",
+    );
+    counter(
+      &mut out,
+      "duck_segments_sealed_total",
+      "Segments filled up and sealed.",
+      load(&self.segments_sealed),
+    );
+    counter(
+      &mut out,
+      "duck_compactions_total",
+      "Compactions completed.",
+      load(&self.compactions),
+    );
+    counter(
+      &mut out,
+      "duck_compaction_read_bytes_total",
+      "Segment bytes rewritten by compaction.",
+      load(&self.compaction_bytes_read),
+    );
+    counter(
+      &mut out,
+      "duck_compaction_written_bytes_total",
+      "Bytes compaction wrote back.",
+      load(&self.compaction_bytes_written),
+    );
+    self.compaction_latency.render(
+      &mut out,
+      "duck_compaction_duration_seconds",
+      "Duration of compactions.",
+    );
+
+    gauge(
+      &mut out,
+      "duck_live_keys",
+      "Keys that currently have a value.",
+      stats.live_keys as f64,
+    );
+    gauge(
+      &mut out,
+      "duck_segments",
+      "Segments on disk.",
+      stats.segment_count() as f64,
+    );
+    gauge(
+      &mut out,
+      "duck_segment_bytes",
+      "Total size of all segments.",
+      stats.total_bytes as f64,
+    );
+    gauge(
+      &mut out,
+      "duck_dead_bytes",
+      "Estimated bytes a compaction would reclaim.",
+      stats.dead_bytes as f64,
+    );
+    if let Some(last_compaction) = stats.last_compaction {
+      gauge(
+        &mut out,
+        "duck_last_compaction_timestamp_seconds",
+        "Unix time of the last compaction.",
+        last_compaction.timestamp() as f64,
+      );
+    }
+    out
+  }
+}
@@ -0,0 +1,137 @@
+//! Tunables accepted by [`LogFile::with_options`](crate::log_file::LogFile::with_options).
+
+use std::{path::PathBuf, time::Duration};
+
+use crate::{comparator::KeyComparator, merge::MergeOperator};
+
+/// Configuration for a [`LogFile`](crate::log_file::LogFile).
+///
+/// Every field has a sensible default, so callers usually start from
+/// `Options::default()` and override what they need.
+#[derive(Debug, Clone)]
+pub struct Options {
+  /// Directory holding the segments and hint files. Column families live in
+  /// subdirectories of it.
+  pub dir: PathBuf,
+  /// How long the leader of a group commit waits for more writers to queue
+  /// up before issuing the shared fsync. Zero still batches every writer that
+  /// arrives while a sync is already in flight. Ignored on wasm32, where
+  /// there is only one writer.
+  pub group_commit_window: Duration,
+  /// Serve reads from sealed (no longer written) segments through a memory
+  /// map instead of a positional read per record. The active segment keeps
+  /// using regular reads since it is still growing. wasm32 has no memory
+  /// maps, so there sealed segments are read into memory whole instead.
+  pub mmap_sealed_segments: bool,
+  /// Upper bound, in bytes per second, on the I/O compaction performs. The
+  /// budget is shared by every handle of the same log and can be changed
+  /// later with `LogFile::set_compaction_rate_limit`, even mid-compaction.
+  /// `None` leaves compaction unthrottled. wasm32 can't sleep, so
+  /// compaction is never throttled there.
+  pub compaction_rate_limit: Option<u64>,
+  /// Background compaction only runs once some segment's dead-to-live byte
+  /// ratio reaches this value, so a mostly-live dataset is not rewritten on
+  /// every tick. `None` compacts unconditionally on each tick.
+  pub compaction_dead_ratio: Option<f64>,
+  /// Folds the operands written by `LogFile::merge` into a value. Merging is
+  /// rejected while this is `None`.
+  pub merge_operator: Option<MergeOperator>,
+  /// Order of keys in scans, exports and cursors. `None` sorts them byte by
+  /// byte.
+  pub comparator: Option<KeyComparator>,
+  /// Number of independently locked partitions the in-memory index is split
+  /// into. More shards mean less contention between threads touching
+  /// different keys.
+  pub keydir_shards: usize,
+  /// When writes are fsynced. See [`SyncPolicy`].
+  pub sync_policy: SyncPolicy,
+  /// Bytes of appends collected in memory before they are written to the
+  /// active segment, so many small records become one large write. Pair it
+  /// with a relaxed `sync_policy` and call `LogFile::flush`/`LogFile::sync`
+  /// when needed. Zero writes every record straight through.
+  pub write_buffer_size: usize,
+  /// Codec applied to values on disk. Records written with one setting stay
+  /// readable after it changes.
+  pub compression: Compression,
+  /// Values at least this many bytes long are written to the value log and
+  /// only a pointer to them to the segment, so compaction doesn't copy them.
+  /// `None` keeps every value inline. Ignored with `in_memory`. See
+  /// [`crate::value_log`].
+  pub value_log_threshold: Option<usize>,
+  /// Size in bytes at which the value log moves on to a new blob file.
+  pub value_log_file_size: u64,
+  /// Compaction copies the live values out of a blob file once its
+  /// dead-to-live byte ratio reaches this value, so the file can be deleted.
+  /// Files without any live values are always deleted.
+  pub value_log_gc_ratio: f64,
+  /// Longest key, in bytes, a write accepts. Records read back with a
+  /// longer key are treated as corrupt.
+  pub max_key_size: usize,
+  /// Longest value, in bytes, a write or merge operand accepts, and the
+  /// longest value a merge operator may fold into. Like `max_key_size`, it
+  /// is also checked against every record read, so a damaged header fails
+  /// fast instead of allocating whatever size it claims.
+  pub max_value_size: usize,
+  /// Keep every segment in memory and never touch `dir`. Nothing survives
+  /// the last handle being dropped, which suits tests and caches. It is
+  /// also the backend for targets without a file system, such as
+  /// `wasm32-unknown-unknown`. See `LogFile::in_memory`.
+  ///
+  /// That target has no threads and no clock of its own. Timestamps and
+  /// TTLs read the clock of the JS host, so the module has to run under
+  /// `wasm-bindgen`. `SyncPolicy::Interval` and
+  /// `LogFile::start_background_compaction` are unavailable, and hooks run
+  /// inline instead of on a dispatcher thread.
+  pub in_memory: bool,
+}
+
+/// How much durability a write gets before `append`, `update`, `delete` and
+/// friends return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncPolicy {
+  /// Every write waits for an fsync, shared with concurrent writers through
+  /// group commit. Nothing acknowledged is lost on a crash.
+  #[default]
+  Always,
+  /// Writes return immediately and a background thread fsyncs the active
+  /// segment on this interval, bounding what a crash can lose. `LogFile::start`
+  /// rejects it on wasm32, which has no threads.
+  Interval(Duration),
+  /// Writes are never fsynced explicitly; the OS flushes them whenever it
+  /// likes.
+  Os,
+}
+
+/// How record values are stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+  #[default]
+  None,
+  /// A built-in LZ77 codec. Only used for a value when it makes the value
+  /// smaller, so short or incompressible values are stored as is.
+  Lz,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self {
+      dir: PathBuf::from("./tmp"),
+      group_commit_window: Duration::ZERO,
+      mmap_sealed_segments: false,
+      compaction_rate_limit: None,
+      compaction_dead_ratio: Some(1.0),
+      merge_operator: None,
+      comparator: None,
+      keydir_shards: 16,
+      sync_policy: SyncPolicy::Always,
+      write_buffer_size: 0,
+      compression: Compression::None,
+      value_log_threshold: None,
+      value_log_file_size: 64 * 1024 * 1024,
+      value_log_gc_ratio: 1.0,
+      max_key_size: 64 * 1024,
+      max_value_size: 64 * 1024 * 1024,
+      in_memory: false,
+    }
+  }
+}
@@ -0,0 +1,103 @@
+//! A token bucket used to cap background I/O.
+//!
+//! Callers report how many bytes they are about to read or write through
+//! [`RateLimiter::request`], which sleeps just long enough to keep the overall
+//! throughput at or under the configured rate. One limiter is shared by every
+//! handle of a log, and a changed rate also applies to requests that are
+//! already sleeping.
+
+use std::{sync::Mutex, thread, time::Duration};
+
+use crate::clock::Instant;
+
+/// Longest a throttled request sleeps before it looks at the rate again, so
+/// a changed limit applies to requests that are already waiting.
+const MAX_SLEEP: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct Bucket {
+  bytes_per_sec: Option<u64>,
+  /// Bytes that may go through right now. Negative while callers are
+  /// sleeping off a request larger than what was available.
+  available: f64,
+  last_refill: Instant,
+}
+
+impl Bucket {
+  fn refill(&mut self, rate: u64) {
+    let now = Instant::now();
+    let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+    // At most one second worth of burst.
+    self.available = (self.available + elapsed * rate as f64).min(rate as f64);
+    self.last_refill = now;
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+  bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+  /// `None` disables throttling.
+  pub(crate) fn new(bytes_per_sec: Option<u64>) -> Self {
+    Self {
+      bucket: Mutex::new(Bucket {
+        bytes_per_sec: bytes_per_sec.filter(|&rate| rate > 0),
+        available: bytes_per_sec.unwrap_or(0) as f64,
+        last_refill: Instant::now(),
+      }),
+    }
+  }
+
+  pub(crate) fn rate(&self) -> Option<u64> {
+    self.bucket.lock().unwrap().bytes_per_sec
+  }
+
+  /// Changes the limit, including for requests already waiting on it.
+  pub(crate) fn set_rate(&self, bytes_per_sec: Option<u64>) {
+    let mut bucket = self.bucket.lock().unwrap();
+    if let Some(rate) = bucket.bytes_per_sec {
+      bucket.refill(rate);
+    }
+    bucket.bytes_per_sec = bytes_per_sec.filter(|&rate| rate > 0);
+    match bucket.bytes_per_sec {
+      Some(rate) => bucket.available = bucket.available.min(rate as f64),
+      // Nothing is owed once the cap is gone.
+      None => bucket.available = 0.0,
+    }
+    bucket.last_refill = Instant::now();
+  }
+
+  /// Blocks until `bytes` fit within the configured rate. wasm32 can't
+  /// block, so nothing is throttled there.
+  pub(crate) fn request(&self, bytes: u64) {
+    if cfg!(target_arch = "wasm32") {
+      return;
+    }
+    {
+      let mut bucket = self.bucket.lock().unwrap();
+      let Some(rate) = bucket.bytes_per_sec else {
+        return;
+      };
+      bucket.refill(rate);
+      bucket.available -= bytes as f64;
+    }
+
+    // Sleep off the debt in slices, at whatever the rate is by then.
+    loop {
+      let wait = {
+        let mut bucket = self.bucket.lock().unwrap();
+        let Some(rate) = bucket.bytes_per_sec else {
+          return;
+        };
+        bucket.refill(rate);
+        if bucket.available >= 0.0 {
+          return;
+        }
+        Duration::from_secs_f64(-bucket.available / rate as f64).min(MAX_SLEEP)
+      };
+      thread::sleep(wait);
+    }
+  }
+}
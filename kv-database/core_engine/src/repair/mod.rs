@@ -0,0 +1,42 @@
+//! Results of [`LogFile::repair`](crate::log_file::LogFile::repair), which
+//! salvages what it can from a damaged data directory.
+//!
+//! Segments are scanned like [`crate::verify`] does, but instead of giving
+//! up at the first bad record the scan looks for the next offset where an
+//! intact record starts and carries on from there. The bytes in between are
+//! moved to [`QUARANTINE_DIR`] for inspection and the segment is rewritten
+//! with only the intact records. A write batch is kept only if all of it is
+//! intact, so it stays atomic. Every hint file is regenerated afterwards.
+
+use std::path::PathBuf;
+
+/// Subdirectory of the data directory holding quarantined bytes, one file
+/// per range named after its segment and offset, like `segment-000042.duck.512`.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+  /// Segments that were damaged and have been rewritten.
+  pub rewritten: Vec<u64>,
+  /// Records kept in the rewritten segments.
+  pub salvaged: u64,
+  pub quarantined: Vec<QuarantinedRange>,
+  /// Hint files written, one per segment.
+  pub hints: usize,
+}
+
+impl RepairReport {
+  /// Whether anything had to be changed besides the hint files.
+  pub fn repaired(&self) -> bool {
+    !self.rewritten.is_empty()
+  }
+}
+
+/// Bytes cut out of segment `file_id` and saved to `path`.
+#[derive(Debug, Clone)]
+pub struct QuarantinedRange {
+  pub file_id: u64,
+  pub offset: u64,
+  pub len: u64,
+  pub path: PathBuf,
+}
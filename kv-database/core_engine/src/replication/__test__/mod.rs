@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod replication_test {
+  use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+  };
+
+  use crate::{log_file::LogFile, options::Options, replication::*};
+
+  /// An empty data directory, unique to `name`.
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("replication-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+  }
+
+  /// Values of 64 bytes and more go to the value log, which a bootstrap has
+  /// to ship as well.
+  fn options(dir: &Path) -> Options {
+    Options {
+      dir: dir.to_path_buf(),
+      value_log_threshold: Some(64),
+      ..Options::default()
+    }
+  }
+
+  fn open(dir: &Path) -> LogFile {
+    let log = LogFile::with_options(options(dir)).unwrap();
+    log.start().unwrap();
+    log
+  }
+
+  fn large(i: usize) -> String {
+    format!("{i:0>100}")
+  }
+
+  /// Every live key with its value, sorted by key.
+  fn contents(log: &LogFile) -> Vec<(String, String)> {
+    log
+      .sorted_keys()
+      .map(|key| {
+        let value = log.read(&key).unwrap();
+        (key, value)
+      })
+      .collect()
+  }
+
+  /// Waits until the follower has applied everything up to `seq`.
+  fn wait_for(follower: &Follower, seq: u64) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while follower.applied_seq() < seq {
+      assert!(
+        Instant::now() < deadline,
+        "the follower is stuck at {} of {seq}",
+        follower.applied_seq()
+      );
+      thread::sleep(Duration::from_millis(10));
+    }
+  }
+
+  #[test]
+  fn follower_bootstraps_and_streams_writes() {
+    let primary_log = open(&temp_dir("bootstrap-primary"));
+    for i in 0..10 {
+      primary_log.append(&format!("small-{i}"), "value").unwrap();
+      primary_log
+        .append(&format!("large-{i}"), &large(i))
+        .unwrap();
+    }
+    primary_log.delete("small-3").unwrap();
+    let primary = Primary::start(&primary_log, "127.0.0.1:0").unwrap();
+
+    let follower = Follower::start(
+      options(&temp_dir("bootstrap-follower")),
+      primary.local_addr(),
+    )
+    .unwrap();
+    assert_eq!(contents(follower.log()), contents(&primary_log));
+
+    primary_log.append("small-10", "value").unwrap();
+    primary_log.update("large-2", &large(20)).unwrap();
+    primary_log.delete("large-4").unwrap();
+    wait_for(&follower, primary_log.last_seq());
+    assert_eq!(contents(follower.log()), contents(&primary_log));
+    assert_eq!(follower.log().read("large-2").unwrap(), large(20));
+  }
+
+  #[test]
+  fn restarted_follower_resumes_where_it_stopped() {
+    let primary_log = open(&temp_dir("resume-primary"));
+    primary_log.append("a", &large(1)).unwrap();
+    let primary = Primary::start(&primary_log, "127.0.0.1:0").unwrap();
+
+    let follower_dir = temp_dir("resume-follower");
+    let follower = Follower::start(options(&follower_dir), primary.local_addr()).unwrap();
+    primary_log.append("b", "value").unwrap();
+    wait_for(&follower, primary_log.last_seq());
+    drop(follower);
+
+    // Writes made while it was away come from the backlog, not a bootstrap.
+    primary_log.append("c", &large(3)).unwrap();
+    primary_log.delete("a").unwrap();
+    let follower = Follower::start(options(&follower_dir), primary.local_addr()).unwrap();
+    wait_for(&follower, primary_log.last_seq());
+    assert_eq!(contents(follower.log()), contents(&primary_log));
+  }
+
+  #[test]
+  fn follower_reconnects_to_a_restarted_primary() {
+    let primary_dir = temp_dir("restart-primary");
+    let primary_log = open(&primary_dir);
+    for key in ["a", "b", "c"] {
+      primary_log.append(key, "value").unwrap();
+    }
+    let primary = Primary::start(&primary_log, "127.0.0.1:0").unwrap();
+    let addr = primary.local_addr();
+    let follower = Follower::start(options(&temp_dir("restart-follower")), addr).unwrap();
+
+    // Compaction drops the newest records of the primary, which must not
+    // take their sequence numbers with them.
+    primary_log.delete("b").unwrap();
+    primary_log.delete("c").unwrap();
+    primary_log.compact().unwrap();
+    wait_for(&follower, primary_log.last_seq());
+    drop(primary);
+    drop(primary_log);
+
+    let primary_log = open(&primary_dir);
+    let _primary = Primary::start(&primary_log, addr).unwrap();
+    primary_log.append("d", "value").unwrap();
+    wait_for(&follower, primary_log.last_seq());
+    assert_eq!(contents(follower.log()), contents(&primary_log));
+    assert_eq!(follower.log().read("d").unwrap(), "value");
+  }
+}
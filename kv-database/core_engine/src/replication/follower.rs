@@ -0,0 +1,265 @@
+use std::{
+  fs::{self, File},
+  io::{self, BufReader, Read, Write},
+  net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+use super::{
+  invalid, is_snapshot_file, read_change, read_u64, read_u8, HEARTBEAT_INTERVAL, MSG_CHANGE,
+  MSG_FILE, MSG_HEARTBEAT, MSG_RESUME, MSG_SNAPSHOT_END,
+};
+use crate::{
+  file_names,
+  log_file::LogFile,
+  logging::{error, info},
+  options::Options,
+  storage,
+};
+
+/// Wait before reconnecting after the primary went away.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+/// A replica of a [`Primary`](super::Primary)'s log.
+///
+/// The replica is an ordinary [`LogFile`] in its own directory that a
+/// background thread keeps applying the primary's changes to. Serve reads
+/// from [`log`](Self::log); writing to it directly makes it diverge from the
+/// primary.
+#[derive(Debug)]
+pub struct Follower {
+  log: LogFile,
+  stopped: Arc<AtomicBool>,
+  /// The connection being streamed from, shut down to stop the thread.
+  stream: Arc<Mutex<Option<TcpStream>>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Follower {
+  /// Opens the replica in `options.dir` and catches it up with the primary
+  /// at `primary`, first copying the primary's segments if the replica is
+  /// empty or too far behind to resume. Returns once the replica is open;
+  /// changes keep streaming in on a background thread after that, which
+  /// reconnects whenever the connection drops.
+  pub fn start(options: Options, primary: impl ToSocketAddrs) -> Result<Self, io::Error> {
+    let primary = primary
+      .to_socket_addrs()?
+      .next()
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No primary address"))?;
+    let (log, stream) = catch_up(options, primary)?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let current = Arc::new(Mutex::new(Some(stream.get_ref().try_clone()?)));
+    let thread = {
+      let log = log.clone();
+      let stopped = stopped.clone();
+      let current = current.clone();
+      thread::spawn(move || follow(&log, primary, stream, &stopped, &current))
+    };
+
+    Ok(Self {
+      log,
+      stopped,
+      stream: current,
+      thread: Some(thread),
+    })
+  }
+
+  /// The replica's log.
+  pub fn log(&self) -> &LogFile {
+    &self.log
+  }
+
+  /// Sequence number of the newest change applied from the primary.
+  pub fn applied_seq(&self) -> u64 {
+    self.log.last_seq()
+  }
+}
+
+impl Drop for Follower {
+  fn drop(&mut self) {
+    self.stopped.store(true, Ordering::Relaxed);
+    if let Some(stream) = self.stream.lock().unwrap().take() {
+      let _ = stream.shutdown(Shutdown::Both);
+    }
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+/// Opens the replica, bootstrapping it if the primary asks for that, and
+/// returns it with a connection that is ready to stream changes.
+fn catch_up(
+  options: Options,
+  primary: SocketAddr,
+) -> Result<(LogFile, BufReader<TcpStream>), io::Error> {
+  let mut log = None;
+  if has_segments(&options)? {
+    let opened = LogFile::with_options(options.clone())?;
+    opened.start()?;
+    log = Some(opened);
+  }
+
+  let seq = log.as_ref().map_or(0, LogFile::last_seq);
+  let mut stream = connect(primary, seq)?;
+  match read_u8(&mut stream)? {
+    MSG_RESUME if log.is_some() => Ok((log.unwrap(), stream)),
+    tag @ (MSG_FILE | MSG_SNAPSHOT_END) => {
+      // Let go of the directory lock before replacing the segments.
+      drop(log);
+      receive_snapshot(&options, &mut stream, tag)?;
+      let log = LogFile::with_options(options)?;
+      log.start()?;
+      Ok((log, stream))
+    }
+    _ => Err(invalid("Unexpected reply from the primary")),
+  }
+}
+
+/// Sends the handshake. Everything the primary sends back is read through
+/// the returned buffer, so nothing read ahead gets lost.
+fn connect(primary: SocketAddr, seq: u64) -> Result<BufReader<TcpStream>, io::Error> {
+  let mut stream = TcpStream::connect(primary)?;
+  stream.set_nodelay(true)?;
+  stream.set_read_timeout(Some(HEARTBEAT_INTERVAL * 5))?;
+  stream.write_all(&seq.to_le_bytes())?;
+  Ok(BufReader::new(stream))
+}
+
+fn has_segments(options: &Options) -> Result<bool, io::Error> {
+  if !fs::exists(&options.dir)? {
+    return Ok(false);
+  }
+  Ok(
+    fs::read_dir(&options.dir)?
+      .filter_map(|entry| entry.ok())
+      .any(|entry| {
+        entry
+          .file_name()
+          .to_str()
+          .is_some_and(file_names::is_segment)
+      }),
+  )
+}
+
+/// Replaces the replica's segments with the snapshot being streamed, whose
+/// first message `tag` has already been read.
+fn receive_snapshot(
+  options: &Options,
+  reader: &mut BufReader<TcpStream>,
+  mut tag: u8,
+) -> Result<(), io::Error> {
+  fs::create_dir_all(&options.dir)?;
+  for entry in fs::read_dir(&options.dir)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if is_snapshot_file(&name) || name == "COMPACTION" {
+      fs::remove_file(entry.path())?;
+    }
+  }
+
+  let mut files = 0;
+  while tag == MSG_FILE {
+    let mut len = [0; 2];
+    reader.read_exact(&mut len)?;
+    let mut name = vec![0; u16::from_le_bytes(len) as usize];
+    reader.read_exact(&mut name)?;
+    let name = String::from_utf8(name).map_err(|_| invalid("Invalid snapshot file name"))?;
+    if !is_snapshot_file(&name) {
+      return Err(invalid("Invalid snapshot file name"));
+    }
+
+    let size = read_u64(reader)?;
+    let mut file = File::create(options.dir.join(&name))?;
+    let copied = io::copy(&mut reader.take(size), &mut file)?;
+    if copied != size {
+      return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    file.sync_all()?;
+    files += 1;
+    tag = read_u8(reader)?;
+  }
+  if tag != MSG_SNAPSHOT_END {
+    return Err(invalid("Unexpected message in snapshot"));
+  }
+  let seq = read_u64(reader)?;
+  storage::sync_dir(&options.dir)?;
+
+  info!(
+    "[REPLICATION] Bootstrapped from the primary.",
+    files = files as u64,
+    seq = seq
+  );
+  Ok(())
+}
+
+/// Applies changes until the follower is stopped, reconnecting as needed.
+fn follow(
+  log: &LogFile,
+  primary: SocketAddr,
+  mut stream: BufReader<TcpStream>,
+  stopped: &AtomicBool,
+  current: &Mutex<Option<TcpStream>>,
+) {
+  loop {
+    if let Err(e) = apply_stream(log, &mut stream) {
+      if stopped.load(Ordering::Relaxed) {
+        return;
+      }
+      error!("[REPLICATION] Lost the primary.", error = e.to_string());
+    }
+
+    stream = loop {
+      thread::sleep(RECONNECT_DELAY);
+      if stopped.load(Ordering::Relaxed) {
+        return;
+      }
+      match reconnect(log, primary) {
+        Ok(stream) => break stream,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+          error!("[REPLICATION] Replication stopped.", error = e.to_string());
+          return;
+        }
+        Err(_) => continue,
+      }
+    };
+    match stream.get_ref().try_clone() {
+      Ok(clone) => *current.lock().unwrap() = Some(clone),
+      Err(_) => continue,
+    }
+    // Dropped while reconnecting: the shutdown missed this stream.
+    if stopped.load(Ordering::Relaxed) {
+      return;
+    }
+  }
+}
+
+fn reconnect(log: &LogFile, primary: SocketAddr) -> Result<BufReader<TcpStream>, io::Error> {
+  let mut stream = connect(primary, log.last_seq())?;
+  match read_u8(&mut stream)? {
+    MSG_RESUME => Ok(stream),
+    // The replica is already serving reads, so its segments can't be
+    // swapped out from under it.
+    MSG_FILE | MSG_SNAPSHOT_END => Err(io::Error::new(
+      io::ErrorKind::Unsupported,
+      "The follower fell too far behind to resume; restart it to bootstrap again",
+    )),
+    _ => Err(invalid("Unexpected reply from the primary")),
+  }
+}
+
+fn apply_stream(log: &LogFile, reader: &mut BufReader<TcpStream>) -> Result<(), io::Error> {
+  loop {
+    match read_u8(reader)? {
+      MSG_CHANGE => log.apply_replicated(&read_change(reader)?)?,
+      MSG_HEARTBEAT => {}
+      _ => return Err(invalid("Unexpected message in replication stream")),
+    }
+  }
+}
@@ -0,0 +1,185 @@
+use std::{
+  fs::{self, File},
+  io::{self, BufWriter, Write},
+  net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+use super::{
+  is_snapshot_file, read_u64, write_change, Feed, HEARTBEAT_INTERVAL, MSG_FILE, MSG_HEARTBEAT,
+  MSG_RESUME, MSG_SNAPSHOT_END,
+};
+use crate::{
+  log_file::LogFile,
+  logging::{error, info},
+};
+use chrono::Utc;
+
+/// How often the accept loop checks whether the primary was stopped.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct Shared {
+  log: LogFile,
+  feed: Arc<Feed>,
+  stopped: AtomicBool,
+}
+
+/// Ships a log's committed records to followers connecting on a TCP port.
+///
+/// Replication runs on background threads until the `Primary` is dropped.
+#[derive(Debug)]
+pub struct Primary {
+  local_addr: SocketAddr,
+  shared: Arc<Shared>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Primary {
+  /// Starts serving followers of the started `log` on `addr`.
+  pub fn start(log: &LogFile, addr: impl ToSocketAddrs) -> Result<Self, io::Error> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+
+    let shared = Arc::new(Shared {
+      feed: log.replication_feed(),
+      log: log.clone(),
+      stopped: AtomicBool::new(false),
+    });
+    let thread = {
+      let shared = shared.clone();
+      thread::spawn(move || accept_loop(&shared, listener))
+    };
+
+    info!(
+      "[REPLICATION] Primary is listening.",
+      addr = local_addr.to_string()
+    );
+    Ok(Self {
+      local_addr,
+      shared,
+      thread: Some(thread),
+    })
+  }
+
+  /// The address followers connect to, useful when bound to port 0.
+  pub fn local_addr(&self) -> SocketAddr {
+    self.local_addr
+  }
+}
+
+impl Drop for Primary {
+  fn drop(&mut self) {
+    self.shared.stopped.store(true, Ordering::Relaxed);
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn accept_loop(shared: &Arc<Shared>, listener: TcpListener) {
+  let mut followers = Vec::new();
+  while !shared.stopped.load(Ordering::Relaxed) {
+    match listener.accept() {
+      Ok((stream, peer)) => {
+        let shared = shared.clone();
+        followers.push(thread::spawn(move || {
+          if let Err(e) = serve(&shared, stream) {
+            error!(
+              "[REPLICATION] Follower disconnected.",
+              peer = peer.to_string(),
+              error = e.to_string()
+            );
+          }
+        }));
+      }
+      Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+      Err(e) => {
+        error!("[REPLICATION] Accept failed.", error = e.to_string());
+        thread::sleep(ACCEPT_POLL_INTERVAL);
+      }
+    }
+    followers.retain(|follower| !follower.is_finished());
+  }
+
+  for follower in followers {
+    let _ = follower.join();
+  }
+}
+
+fn serve(shared: &Shared, stream: TcpStream) -> Result<(), io::Error> {
+  stream.set_nonblocking(false)?;
+  stream.set_nodelay(true)?;
+  let mut seq = read_u64(&mut &stream)?;
+  let mut writer = BufWriter::new(stream);
+
+  if seq == 0 || !shared.feed.covers(seq) {
+    seq = send_snapshot(&shared.log, &mut writer)?;
+  } else {
+    writer.write_all(&[MSG_RESUME])?;
+  }
+  writer.flush()?;
+
+  while !shared.stopped.load(Ordering::Relaxed) {
+    let Some(changes) = shared.feed.since(seq, HEARTBEAT_INTERVAL) else {
+      return Err(io::Error::other(
+        "The follower fell behind the replication backlog",
+      ));
+    };
+    if changes.is_empty() {
+      writer.write_all(&[MSG_HEARTBEAT])?;
+    }
+    for change in &changes {
+      write_change(&mut writer, change)?;
+      seq = change.seq;
+    }
+    writer.flush()?;
+  }
+  Ok(())
+}
+
+/// Streams a checkpoint of the log's segments and returns the sequence
+/// number changes have to be shipped from afterwards.
+fn send_snapshot(log: &LogFile, writer: &mut impl Write) -> Result<u64, io::Error> {
+  // Everything up to here is in the checkpoint. It may hold a few later
+  // records too; the follower skips those when they are shipped again.
+  let seq = log.last_seq();
+  let dir = std::env::temp_dir().join(format!(
+    "duck-replication-{}-{}",
+    std::process::id(),
+    Utc::now().timestamp_nanos_opt().unwrap()
+  ));
+  let result = log.checkpoint(&dir).and_then(|()| send_files(&dir, writer));
+  let _ = fs::remove_dir_all(&dir);
+  result?;
+
+  writer.write_all(&[MSG_SNAPSHOT_END])?;
+  writer.write_all(&seq.to_le_bytes())?;
+  info!("[REPLICATION] Follower bootstrapped.", seq = seq);
+  Ok(seq)
+}
+
+fn send_files(dir: &Path, writer: &mut impl Write) -> Result<(), io::Error> {
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().into_owned();
+    if !is_snapshot_file(&name) {
+      continue;
+    }
+
+    let mut file = File::open(entry.path())?;
+    writer.write_all(&[MSG_FILE])?;
+    writer.write_all(&(name.len() as u16).to_le_bytes())?;
+    writer.write_all(name.as_bytes())?;
+    writer.write_all(&file.metadata()?.len().to_le_bytes())?;
+    io::copy(&mut file, writer)?;
+  }
+  Ok(())
+}
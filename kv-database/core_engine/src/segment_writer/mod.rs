@@ -0,0 +1,126 @@
+//! Buffered appends to the active segment.
+//!
+//! Records are collected in a `BufWriter` sized by
+//! `Options::write_buffer_size` and reach the file in large sequential
+//! writes. Because the keydir may already point at a buffered record, readers
+//! of the active segment call [`SegmentWriter::flush`] first. The buffer lock
+//! is the innermost lock in the engine, so that is safe from any context.
+//!
+//! New segment files are preallocated to the size at which they are sealed,
+//! so appends don't extend the file and an fsync doesn't have to persist a
+//! new file size each time. The space past the last record reads as zeros
+//! until it is written; [`SegmentWriter::seal`] and dropping the writer cut
+//! the file back to what was written.
+//!
+//! Under `Options::in_memory` the segment is a [`MemorySegment`] instead and
+//! appends land in it directly, so flushing and syncing have nothing to do.
+
+use std::{
+  fs::{File, OpenOptions},
+  io::{self, BufWriter, Seek, Write},
+  sync::{Arc, Mutex, RwLock},
+};
+
+/// The contents of a segment kept in memory rather than in a file.
+pub(crate) type MemorySegment = Arc<RwLock<Vec<u8>>>;
+
+#[derive(Debug)]
+pub(crate) struct SegmentWriter {
+  sink: Sink,
+}
+
+#[derive(Debug)]
+enum Sink {
+  File {
+    buf: Mutex<BufWriter<File>>,
+    /// A second handle on the same file so fsync doesn't hold up appends.
+    file: File,
+  },
+  Memory(MemorySegment),
+}
+
+impl SegmentWriter {
+  /// Opens `path` for appending, preallocating it to `preallocate` bytes.
+  pub(crate) fn open(path: &str, capacity: usize, preallocate: u64) -> Result<Self, io::Error> {
+    // Not opened in append mode, which would write past the preallocated
+    // space; the position starts at the end of whatever is there instead.
+    let mut file = OpenOptions::new()
+      .create(true)
+      .write(true)
+      .truncate(false)
+      .open(path)?;
+    let len = file.seek(io::SeekFrom::End(0))?;
+    if len < preallocate {
+      file.set_len(preallocate)?;
+    }
+    Ok(Self {
+      sink: Sink::File {
+        file: file.try_clone()?,
+        buf: Mutex::new(BufWriter::with_capacity(capacity, file)),
+      },
+    })
+  }
+
+  /// Appends to `segment` instead of a file.
+  pub(crate) fn in_memory(segment: MemorySegment) -> Self {
+    Self {
+      sink: Sink::Memory(segment),
+    }
+  }
+
+  pub(crate) fn write_all(&self, bytes: &[u8]) -> Result<(), io::Error> {
+    match &self.sink {
+      Sink::File { buf, .. } => buf.lock().unwrap().write_all(bytes),
+      Sink::Memory(segment) => {
+        segment.write().unwrap().extend_from_slice(bytes);
+        Ok(())
+      }
+    }
+  }
+
+  /// Hands everything buffered to the OS.
+  pub(crate) fn flush(&self) -> Result<(), io::Error> {
+    match &self.sink {
+      Sink::File { buf, .. } => buf.lock().unwrap().flush(),
+      Sink::Memory(_) => Ok(()),
+    }
+  }
+
+  /// Flushes the buffer and fsyncs the file's data. The file size is only
+  /// synced along with it when appends grew the file past its preallocation.
+  pub(crate) fn sync(&self) -> Result<(), io::Error> {
+    match &self.sink {
+      Sink::File { file, .. } => {
+        self.flush()?;
+        file.sync_data()
+      }
+      Sink::Memory(_) => Ok(()),
+    }
+  }
+
+  /// Syncs the segment and truncates the unused preallocated space, once no
+  /// more appends will come.
+  pub(crate) fn seal(&self) -> Result<(), io::Error> {
+    match &self.sink {
+      Sink::File { buf, file } => {
+        let mut buf = buf.lock().unwrap();
+        buf.flush()?;
+        let len = buf.get_mut().stream_position()?;
+        file.set_len(len)?;
+        file.sync_all()
+      }
+      Sink::Memory(_) => Ok(()),
+    }
+  }
+}
+
+impl Drop for SegmentWriter {
+  fn drop(&mut self) {
+    if let Sink::File { buf, file } = &self.sink {
+      let mut buf = buf.lock().unwrap();
+      if let (Ok(()), Ok(len)) = (buf.flush(), buf.get_mut().stream_position()) {
+        let _ = file.set_len(len);
+      }
+    }
+  }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod server_test {
+  use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+  };
+
+  use crate::{log_file::LogFile, options::Options, server::*};
+
+  fn serve_in_memory() -> (LogFile, Server) {
+    let log = LogFile::in_memory().unwrap();
+    let server = Server::start(&log, "127.0.0.1:0").unwrap();
+    (log, server)
+  }
+
+  #[test]
+  fn put_rejects_an_empty_value() {
+    let (log, server) = serve_in_memory();
+    let mut client = Client::connect(server.local_addr()).unwrap();
+    client.put("key", "value").unwrap();
+
+    let err = client.put("key", "").unwrap_err();
+    assert!(err.to_string().contains("value must not be empty"));
+    assert_eq!(client.get("key").unwrap().as_deref(), Some("value"));
+    assert!(log.contains_key("key"));
+  }
+
+  #[test]
+  fn put_rejects_an_empty_key_with_a_message() {
+    let (_log, server) = serve_in_memory();
+    let mut client = Client::connect(server.local_addr()).unwrap();
+
+    let err = client.put("", "value").unwrap_err();
+    assert_eq!(err.to_string(), "The key must not be empty");
+  }
+
+  #[test]
+  fn concurrent_deletes_of_a_key_succeed_once() {
+    let (log, server) = serve_in_memory();
+    let addr = server.local_addr();
+    for round in 0..20 {
+      let key = format!("key-{round}");
+      log.append(&key, "value").unwrap();
+
+      let deleted = std::thread::scope(|scope| {
+        let clients = (0..4)
+          .map(|_| {
+            let key = &key;
+            scope.spawn(move || Client::connect(addr).unwrap().delete(key).unwrap())
+          })
+          .collect::<Vec<_>>();
+        clients
+          .into_iter()
+          .map(|client| client.join().unwrap())
+          .filter(|&deleted| deleted)
+          .count()
+      });
+      assert_eq!(deleted, 1);
+    }
+  }
+
+  // ---------------------------------------------------------
+  // http tests
+  // ---------------------------------------------------------
+
+  /// Sends one request and returns the status with the body.
+  fn http(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+      stream,
+      "{method} {path} HTTP/1.1\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{body}",
+      body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, body.to_string())
+  }
+
+  #[test]
+  fn http_put_maps_rejected_input_to_client_errors() {
+    let dir = std::env::temp_dir().join(format!("server-http-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let log = LogFile::with_options(Options {
+      dir: PathBuf::from(&dir),
+      max_key_size: 8,
+      max_value_size: 8,
+      ..Options::default()
+    })
+    .unwrap();
+    log.start().unwrap();
+    let server = HttpServer::start(&log, "127.0.0.1:0").unwrap();
+    let addr = server.local_addr();
+
+    assert_eq!(http(addr, "PUT", "/keys/key", r#"{"value":"v"}"#).0, 204);
+    let (status, body) = http(addr, "PUT", "/keys/key", r#"{"value":""}"#);
+    assert_eq!(status, 400);
+    assert!(body.contains("value must not be empty"));
+    assert_eq!(
+      http(addr, "PUT", "/keys/key", r#"{"value":"too large"}"#).0,
+      413
+    );
+    assert_eq!(
+      http(addr, "PUT", "/keys/longer-key", r#"{"value":"v"}"#).0,
+      413
+    );
+    assert_eq!(log.read("key").unwrap(), "v");
+  }
+}
@@ -0,0 +1,146 @@
+use std::{
+  io::{self, BufReader, BufWriter, Write},
+  net::{TcpStream, ToSocketAddrs},
+};
+
+use super::{
+  invalid, put_bytes, read_frame, take_str, take_u32, take_u8, write_frame, EVENT_DELETE,
+  EVENT_MERGE, EVENT_PUT, OP_DELETE, OP_GET, OP_METRICS, OP_PUT, OP_SCAN, OP_WATCH, STATUS_ERROR,
+  STATUS_NOT_FOUND, STATUS_OK,
+};
+use crate::watch::Event;
+
+/// A blocking connection to a [`Server`](super::Server). Requests are sent
+/// one at a time.
+#[derive(Debug)]
+pub struct Client {
+  reader: BufReader<TcpStream>,
+  writer: BufWriter<TcpStream>,
+}
+
+impl Client {
+  pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, io::Error> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    Ok(Self {
+      reader: BufReader::new(stream.try_clone()?),
+      writer: BufWriter::new(stream),
+    })
+  }
+
+  /// The value of `key`, or `None` if it has none.
+  pub fn get(&mut self, key: &str) -> Result<Option<String>, io::Error> {
+    let mut request = vec![OP_GET];
+    put_bytes(&mut request, key.as_bytes());
+    let Some(response) = self.call(&request)? else {
+      return Ok(None);
+    };
+    take_str(&mut response.as_slice()).map(Some)
+  }
+
+  pub fn put(&mut self, key: &str, value: &str) -> Result<(), io::Error> {
+    let mut request = vec![OP_PUT];
+    put_bytes(&mut request, key.as_bytes());
+    put_bytes(&mut request, value.as_bytes());
+    self.call(&request)?;
+    Ok(())
+  }
+
+  /// Deletes `key` and reports whether it had a value.
+  pub fn delete(&mut self, key: &str) -> Result<bool, io::Error> {
+    let mut request = vec![OP_DELETE];
+    put_bytes(&mut request, key.as_bytes());
+    Ok(self.call(&request)?.is_some())
+  }
+
+  /// Up to `limit` entries whose key starts with `prefix`, in ascending key
+  /// order. A `limit` of 0 returns them all.
+  pub fn scan(&mut self, prefix: &str, limit: u32) -> Result<Vec<(String, String)>, io::Error> {
+    let mut request = vec![OP_SCAN];
+    put_bytes(&mut request, prefix.as_bytes());
+    request.extend_from_slice(&limit.to_le_bytes());
+    let Some(response) = self.call(&request)? else {
+      return Ok(Vec::new());
+    };
+
+    let mut response = response.as_slice();
+    let count = take_u32(&mut response)?;
+    (0..count)
+      .map(|_| Ok((take_str(&mut response)?, take_str(&mut response)?)))
+      .collect()
+  }
+
+  /// The server's metrics in the Prometheus text format.
+  pub fn metrics(&mut self) -> Result<String, io::Error> {
+    let response = self.call(&[OP_METRICS])?.unwrap_or_default();
+    take_str(&mut response.as_slice())
+  }
+
+  /// Turns the connection into a stream of the server's changes to keys
+  /// starting with `prefix`, as [`LogFile::watch`](crate::log_file::LogFile::watch)
+  /// reports them.
+  pub fn watch(mut self, prefix: &str) -> Result<Watch, io::Error> {
+    let mut request = vec![OP_WATCH];
+    put_bytes(&mut request, prefix.as_bytes());
+    self.call(&request)?;
+    Ok(Watch {
+      reader: self.reader,
+    })
+  }
+
+  /// Sends `request` and returns the response payload, or `None` for
+  /// `NOT_FOUND`. An `ERROR` response becomes an error.
+  fn call(&mut self, request: &[u8]) -> Result<Option<Vec<u8>>, io::Error> {
+    write_frame(&mut self.writer, request)?;
+    self.writer.flush()?;
+    let response = read_frame(&mut self.reader, None)?
+      .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    let mut payload = &response[..];
+    match take_u8(&mut payload)? {
+      STATUS_OK => Ok(Some(payload.to_vec())),
+      STATUS_NOT_FOUND => Ok(None),
+      STATUS_ERROR => Err(io::Error::other(take_str(&mut payload)?)),
+      _ => Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Unknown response status",
+      )),
+    }
+  }
+}
+
+/// Changes streamed by [`Client::watch`]. Blocks until the next one arrives
+/// and ends when the server stops; dropping it hangs up.
+#[derive(Debug)]
+pub struct Watch {
+  reader: BufReader<TcpStream>,
+}
+
+impl Iterator for Watch {
+  type Item = Result<Event, io::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let frame = match read_frame(&mut self.reader, None) {
+      Ok(frame) => frame?,
+      Err(e) => return Some(Err(e)),
+    };
+
+    let mut frame = frame.as_slice();
+    let event = take_u8(&mut frame).and_then(|event| {
+      let key = take_str(&mut frame)?;
+      match event {
+        EVENT_PUT => Ok(Event::Put {
+          key,
+          value: take_str(&mut frame)?,
+        }),
+        EVENT_DELETE => Ok(Event::Delete { key }),
+        EVENT_MERGE => Ok(Event::Merge {
+          key,
+          operand: take_str(&mut frame)?,
+        }),
+        _ => Err(invalid("Unknown event")),
+      }
+    });
+    Some(event)
+  }
+}
@@ -0,0 +1,358 @@
+use std::{
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  net::{SocketAddr, TcpStream, ToSocketAddrs},
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::{fill, invalid, Listener, Shared, MAX_FRAME_SIZE, POLL_INTERVAL};
+use crate::{error::DbError, log_file::LogFile, logging::info};
+use serde_json::{json, Value};
+
+/// Longest request or header line accepted.
+const MAX_LINE_SIZE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+/// Serves a log as a JSON API over HTTP/1.1 until dropped.
+///
+/// - `GET /keys/{key}`: `{"key": …, "value": …}`, or 404.
+/// - `PUT /keys/{key}` with a `{"value": …}` body: stores the value.
+/// - `DELETE /keys/{key}`: removes the key, or 404.
+/// - `GET /scan?prefix=…&limit=…`: `[{"key": …, "value": …}, …]` in
+///   ascending key order. Both parameters are optional.
+/// - `GET /stats`: the log's [`Stats`](crate::stats::Stats).
+/// - `GET /metrics`: [`LogFile::render_metrics`] for Prometheus to scrape.
+///
+/// Keys in the path and query parameters are percent-decoded. Errors come
+/// back as `{"error": …}`: 400 for invalid input, 413 for a key or value
+/// over the configured limits and 500 for failures of the log itself.
+#[derive(Debug)]
+pub struct HttpServer {
+  listener: Listener,
+}
+
+impl HttpServer {
+  /// Starts serving the started `log` on `addr`.
+  pub fn start(log: &LogFile, addr: impl ToSocketAddrs) -> Result<Self, io::Error> {
+    let listener = Listener::start(log, addr, serve)?;
+    info!(
+      "[HTTP] Server is listening.",
+      addr = listener.local_addr.to_string()
+    );
+    Ok(Self { listener })
+  }
+
+  /// The address clients connect to, useful when bound to port 0.
+  pub fn local_addr(&self) -> SocketAddr {
+    self.listener.local_addr
+  }
+
+  /// Blocks for as long as the server runs, which is until the process
+  /// exits.
+  pub fn join(self) {
+    self.listener.join();
+  }
+}
+
+#[derive(Debug)]
+struct Request {
+  method: String,
+  path: String,
+  query: Vec<(String, String)>,
+  body: Vec<u8>,
+  keep_alive: bool,
+}
+
+#[derive(Debug)]
+struct Response {
+  status: u16,
+  content_type: Option<&'static str>,
+  body: Vec<u8>,
+}
+
+impl Response {
+  fn ok(body: Value) -> Self {
+    Self::json(200, body)
+  }
+
+  fn json(status: u16, body: Value) -> Self {
+    Self {
+      status,
+      content_type: Some("application/json"),
+      body: body.to_string().into_bytes(),
+    }
+  }
+
+  /// Metrics in the Prometheus text format.
+  fn metrics(body: String) -> Self {
+    Self {
+      status: 200,
+      content_type: Some("text/plain; version=0.0.4"),
+      body: body.into_bytes(),
+    }
+  }
+
+  fn no_content() -> Self {
+    Self {
+      status: 204,
+      content_type: None,
+      body: Vec::new(),
+    }
+  }
+
+  fn error(status: u16, message: &str) -> Self {
+    Self::json(status, json!({ "error": message }))
+  }
+
+  fn write(&self, writer: &mut impl Write, keep_alive: bool) -> Result<(), io::Error> {
+    let reason = match self.status {
+      200 => "OK",
+      204 => "No Content",
+      400 => "Bad Request",
+      404 => "Not Found",
+      405 => "Method Not Allowed",
+      413 => "Payload Too Large",
+      _ => "Internal Server Error",
+    };
+    write!(writer, "HTTP/1.1 {} {reason}\r\n", self.status)?;
+    if let Some(content_type) = self.content_type {
+      write!(writer, "Content-Type: {content_type}\r\n")?;
+    }
+    write!(writer, "Content-Length: {}\r\n", self.body.len())?;
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    write!(writer, "Connection: {connection}\r\n\r\n")?;
+    writer.write_all(&self.body)
+  }
+}
+
+fn serve(shared: &Shared, stream: TcpStream) -> Result<(), io::Error> {
+  stream.set_nonblocking(false)?;
+  stream.set_nodelay(true)?;
+  stream.set_read_timeout(Some(POLL_INTERVAL))?;
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let mut writer = BufWriter::new(stream);
+
+  loop {
+    let request = match read_request(&mut reader, &shared.stopped) {
+      Ok(Some(request)) => request,
+      Ok(None) => return Ok(()),
+      Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+        Response::error(400, &e.to_string()).write(&mut writer, false)?;
+        return writer.flush();
+      }
+      Err(e) => return Err(e),
+    };
+
+    let response = route(&shared.log, &request)
+      .unwrap_or_else(|e| Response::error(status_of(&e), &e.to_string()));
+    response.write(&mut writer, request.keep_alive)?;
+    writer.flush()?;
+    if !request.keep_alive {
+      return Ok(());
+    }
+  }
+}
+
+fn route(log: &LogFile, request: &Request) -> Result<Response, io::Error> {
+  let method = request.method.as_str();
+  if let Some(key) = request.path.strip_prefix("/keys/") {
+    let key = percent_decode(key, false)?;
+    if key.is_empty() {
+      return Ok(Response::error(400, "The key must not be empty"));
+    }
+
+    return match method {
+      "GET" => Ok(match log.multi_get(&[&key])?.pop().flatten() {
+        Some(value) => Response::ok(json!({
+          "key": key,
+          "value": String::from_utf8_lossy(&value),
+        })),
+        None => Response::error(404, "Key not found"),
+      }),
+      "PUT" => {
+        let body = serde_json::from_slice::<Value>(&request.body).ok();
+        let Some(value) = body.as_ref().and_then(|body| body["value"].as_str()) else {
+          return Ok(Response::error(
+            400,
+            "Expected a body like {\"value\": \"...\"}",
+          ));
+        };
+        log.append(&key, value)?;
+        Ok(Response::no_content())
+      }
+      "DELETE" => match log.delete(&key) {
+        Ok(_) => Ok(Response::no_content()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Response::error(404, "Key not found")),
+        Err(e) => Err(e),
+      },
+      _ => Ok(Response::error(405, "Method not allowed")),
+    };
+  }
+
+  match (method, request.path.as_str()) {
+    ("GET", "/scan") => {
+      let param = |name: &str| {
+        request
+          .query
+          .iter()
+          .find(|(key, _)| key == name)
+          .map(|(_, value)| value.as_str())
+      };
+      let limit = match param("limit").map(str::parse::<usize>) {
+        None => usize::MAX,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => return Ok(Response::error(400, "Invalid limit")),
+      };
+
+      let entries = log
+        .scan(param("prefix").unwrap_or_default())
+        .take(limit)
+        .map(|entry| entry.map(|(key, value)| json!({ "key": key, "value": value })))
+        .collect::<Result<Vec<_>, _>>()?;
+      Ok(Response::ok(Value::Array(entries)))
+    }
+    ("GET", "/stats") => Ok(Response::ok(serde_json::to_value(log.stats()?)?)),
+    ("GET", "/metrics") => Ok(Response::metrics(log.render_metrics()?)),
+    (_, "/scan" | "/stats" | "/metrics") => Ok(Response::error(405, "Method not allowed")),
+    _ => Ok(Response::error(404, "No such endpoint")),
+  }
+}
+
+/// The status for an error of the log.
+fn status_of(error: &io::Error) -> u16 {
+  match DbError::from_io(error) {
+    Some(DbError::KeyTooLarge { .. } | DbError::ValueTooLarge { .. }) => 413,
+    _ => match error.kind() {
+      io::ErrorKind::NotFound => 404,
+      io::ErrorKind::InvalidInput => 400,
+      _ => 500,
+    },
+  }
+}
+
+/// Reads one request, or `None` when the connection closed or the server
+/// stopped between requests. Malformed requests fail with `InvalidData`.
+fn read_request(
+  reader: &mut BufReader<TcpStream>,
+  stopped: &AtomicBool,
+) -> Result<Option<Request>, io::Error> {
+  let Some(request_line) = read_line(reader, stopped)? else {
+    return Ok(None);
+  };
+  let mut parts = request_line.split(' ');
+  let (Some(method), Some(target), Some(version), None) =
+    (parts.next(), parts.next(), parts.next(), parts.next())
+  else {
+    return Err(invalid("Malformed request line"));
+  };
+
+  let mut content_length = 0;
+  let mut keep_alive = version == "HTTP/1.1";
+  for _ in 0..=MAX_HEADERS {
+    let Some(line) = read_line(reader, stopped)? else {
+      return Ok(None);
+    };
+    if line.is_empty() {
+      let (path, query) = target.split_once('?').unwrap_or((target, ""));
+      let mut body = vec![0; content_length];
+      if !fill(reader, &mut body, Some(stopped))? {
+        return Ok(None);
+      }
+
+      return Ok(Some(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: parse_query(query)?,
+        body,
+        keep_alive,
+      }));
+    }
+
+    let Some((name, value)) = line.split_once(':') else {
+      return Err(invalid("Malformed header"));
+    };
+    let value = value.trim();
+    if name.eq_ignore_ascii_case("content-length") {
+      content_length = value
+        .parse()
+        .ok()
+        .filter(|&len| len <= MAX_FRAME_SIZE)
+        .ok_or_else(|| invalid("Invalid Content-Length"))?;
+    } else if name.eq_ignore_ascii_case("connection") {
+      keep_alive = !value.eq_ignore_ascii_case("close");
+    }
+  }
+  Err(invalid("Too many headers"))
+}
+
+/// Reads a CRLF or LF terminated line without its terminator.
+fn read_line(
+  reader: &mut BufReader<TcpStream>,
+  stopped: &AtomicBool,
+) -> Result<Option<String>, io::Error> {
+  let mut line = Vec::new();
+  loop {
+    match reader
+      .by_ref()
+      .take((MAX_LINE_SIZE - line.len()) as u64)
+      .read_until(b'\n', &mut line)
+    {
+      Ok(0) if line.is_empty() => return Ok(None),
+      Ok(_) => break,
+      Err(e)
+        if matches!(
+          e.kind(),
+          io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ) =>
+      {
+        if line.is_empty() && stopped.load(Ordering::Relaxed) {
+          return Ok(None);
+        }
+      }
+      Err(e) => return Err(e),
+    }
+  }
+
+  if line.pop() != Some(b'\n') {
+    return Err(invalid("Request line or header is too long"));
+  }
+  if line.last() == Some(&b'\r') {
+    line.pop();
+  }
+  String::from_utf8(line)
+    .map_err(|_| invalid("Invalid UTF-8 in request"))
+    .map(Some)
+}
+
+fn parse_query(query: &str) -> Result<Vec<(String, String)>, io::Error> {
+  query
+    .split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      Ok((percent_decode(key, true)?, percent_decode(value, true)?))
+    })
+    .collect()
+}
+
+/// Decodes `%XX` escapes, and `+` as a space in query strings.
+fn percent_decode(input: &str, query: bool) -> Result<String, io::Error> {
+  let mut bytes = Vec::with_capacity(input.len());
+  let mut rest = input.as_bytes();
+  while let Some((&byte, tail)) = rest.split_first() {
+    rest = tail;
+    match byte {
+      b'%' => {
+        let hex = rest
+          .get(..2)
+          .and_then(|hex| std::str::from_utf8(hex).ok())
+          .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+          .ok_or_else(|| invalid("Invalid percent encoding"))?;
+        bytes.push(hex);
+        rest = &rest[2..];
+      }
+      b'+' if query => bytes.push(b' '),
+      _ => bytes.push(byte),
+    }
+  }
+  String::from_utf8(bytes).map_err(|_| invalid("Invalid UTF-8 in percent encoding"))
+}
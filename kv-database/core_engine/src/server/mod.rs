@@ -0,0 +1,419 @@
+//! Network access to a [`LogFile`].
+//!
+//! A [`Server`] speaks the small binary protocol below and [`Client`] is the
+//! matching blocking client. An [`HttpServer`] offers the same operations as
+//! a JSON API for curl and web services. Both share one log between every
+//! connection, each served on its own thread.
+//!
+//! # Wire format
+//!
+//! Both directions exchange frames: a u32 body length followed by the body.
+//! Integers are little endian and strings are a u32 length plus UTF-8 bytes.
+//!
+//! A request body is an opcode byte and its arguments:
+//!
+//! - `GET` (1): key.
+//! - `PUT` (2): key, value. Neither may be empty.
+//! - `DELETE` (3): key.
+//! - `SCAN` (4): prefix, then the maximum number of entries (u32, 0 for no
+//!   limit).
+//! - `METRICS` (5): nothing.
+//! - `WATCH` (6): prefix.
+//!
+//! A response body is a status byte, `OK` (0), `NOT_FOUND` (1) or `ERROR`
+//! (2), and then:
+//!
+//! - for `GET`, the value when found;
+//! - for `SCAN`, the number of entries (u32) and a key and value for each,
+//!   in ascending key order;
+//! - for `METRICS`, the metrics in the Prometheus text format;
+//! - for `ERROR`, a message.
+//!
+//! After the `OK` for `WATCH` the connection only carries changes to keys
+//! starting with the prefix, as [`LogFile::watch`] reports them, until the
+//! client hangs up. Each is a frame of its own: an event byte, `PUT` (1),
+//! `DELETE` (2) or `MERGE` (3), the key, and for `PUT` the value or for
+//! `MERGE` the operand.
+
+mod __test__;
+mod client;
+mod http;
+
+use std::{
+  io::{self, BufReader, BufWriter, Read, Write},
+  net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+use crate::{
+  log_file::LogFile,
+  logging::{error, info},
+  watch::Event,
+};
+pub use client::{Client, Watch};
+pub use http::HttpServer;
+
+const OP_GET: u8 = 1;
+const OP_PUT: u8 = 2;
+const OP_DELETE: u8 = 3;
+const OP_SCAN: u8 = 4;
+const OP_METRICS: u8 = 5;
+const OP_WATCH: u8 = 6;
+
+const STATUS_OK: u8 = 0;
+const STATUS_NOT_FOUND: u8 = 1;
+const STATUS_ERROR: u8 = 2;
+
+const EVENT_PUT: u8 = 1;
+const EVENT_DELETE: u8 = 2;
+const EVENT_MERGE: u8 = 3;
+
+/// Frames larger than this are rejected before anything is allocated.
+const MAX_FRAME_SIZE: usize = 64 * 1024 * 1024;
+/// How often idle threads check whether the server was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct Shared {
+  log: LogFile,
+  stopped: AtomicBool,
+}
+
+/// Serves a log to [`Client`]s connecting on a TCP port until dropped.
+#[derive(Debug)]
+pub struct Server {
+  listener: Listener,
+}
+
+impl Server {
+  /// Starts serving the started `log` on `addr`.
+  pub fn start(log: &LogFile, addr: impl ToSocketAddrs) -> Result<Self, io::Error> {
+    let listener = Listener::start(log, addr, serve)?;
+    info!(
+      "[SERVER] Server is listening.",
+      addr = listener.local_addr.to_string()
+    );
+    Ok(Self { listener })
+  }
+
+  /// The address clients connect to, useful when bound to port 0.
+  pub fn local_addr(&self) -> SocketAddr {
+    self.listener.local_addr
+  }
+
+  /// Blocks for as long as the server runs, which is until the process
+  /// exits.
+  pub fn join(self) {
+    self.listener.join();
+  }
+}
+
+/// A bound socket whose accept loop runs on a background thread until the
+/// listener is dropped.
+#[derive(Debug)]
+struct Listener {
+  local_addr: SocketAddr,
+  shared: Arc<Shared>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Listener {
+  fn start(
+    log: &LogFile,
+    addr: impl ToSocketAddrs,
+    serve: fn(&Shared, TcpStream) -> Result<(), io::Error>,
+  ) -> Result<Self, io::Error> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+
+    let shared = Arc::new(Shared {
+      log: log.clone(),
+      stopped: AtomicBool::new(false),
+    });
+    let thread = {
+      let shared = shared.clone();
+      thread::spawn(move || accept_loop(&shared, listener, serve))
+    };
+
+    Ok(Self {
+      local_addr,
+      shared,
+      thread: Some(thread),
+    })
+  }
+
+  fn join(mut self) {
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Drop for Listener {
+  fn drop(&mut self) {
+    self.shared.stopped.store(true, Ordering::Relaxed);
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+/// Hands every connection to `serve` on its own thread until `stopped` is
+/// set, then waits for the connections to wind down.
+fn accept_loop(
+  shared: &Arc<Shared>,
+  listener: TcpListener,
+  serve: fn(&Shared, TcpStream) -> Result<(), io::Error>,
+) {
+  let mut connections = Vec::new();
+  while !shared.stopped.load(Ordering::Relaxed) {
+    match listener.accept() {
+      Ok((stream, peer)) => {
+        let shared = shared.clone();
+        connections.push(thread::spawn(move || {
+          if let Err(e) = serve(&shared, stream) {
+            error!(
+              "[SERVER] Connection failed.",
+              peer = peer.to_string(),
+              error = e.to_string()
+            );
+          }
+        }));
+      }
+      Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+      Err(e) => {
+        error!("[SERVER] Accept failed.", error = e.to_string());
+        thread::sleep(POLL_INTERVAL);
+      }
+    }
+    connections.retain(|connection| !connection.is_finished());
+  }
+
+  for connection in connections {
+    let _ = connection.join();
+  }
+}
+
+fn serve(shared: &Shared, stream: TcpStream) -> Result<(), io::Error> {
+  stream.set_nonblocking(false)?;
+  stream.set_nodelay(true)?;
+  stream.set_read_timeout(Some(POLL_INTERVAL))?;
+  let mut reader = BufReader::new(stream.try_clone()?);
+  let mut writer = BufWriter::new(stream);
+
+  while let Some(request) = read_frame(&mut reader, Some(&shared.stopped))? {
+    if let [OP_WATCH, prefix @ ..] = &request[..] {
+      let prefix = take_str(&mut &prefix[..])?;
+      return watch(shared, &prefix, reader.get_ref(), &mut writer);
+    }
+
+    let mut response = Vec::new();
+    if let Err(e) = handle(&shared.log, &request, &mut response) {
+      response.clear();
+      response.push(STATUS_ERROR);
+      put_bytes(&mut response, e.to_string().as_bytes());
+    }
+    write_frame(&mut writer, &response)?;
+    writer.flush()?;
+  }
+  Ok(())
+}
+
+/// Runs one request and writes the response body to `response`.
+fn handle(log: &LogFile, request: &[u8], response: &mut Vec<u8>) -> Result<(), io::Error> {
+  let mut request = request;
+  let op = take_u8(&mut request)?;
+  match op {
+    OP_GET => {
+      let key = take_str(&mut request)?;
+      match log.multi_get(&[key])?.pop().flatten() {
+        Some(value) => {
+          response.push(STATUS_OK);
+          put_bytes(response, &value);
+        }
+        None => response.push(STATUS_NOT_FOUND),
+      }
+    }
+    OP_PUT => {
+      let key = take_str(&mut request)?;
+      let value = take_str(&mut request)?;
+      log.append(&key, &value)?;
+      response.push(STATUS_OK);
+    }
+    OP_DELETE => {
+      let key = take_str(&mut request)?;
+      match log.delete(&key) {
+        Ok(_) => response.push(STATUS_OK),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => response.push(STATUS_NOT_FOUND),
+        Err(e) => return Err(e),
+      }
+    }
+    OP_SCAN => {
+      let prefix = take_str(&mut request)?;
+      let limit = match take_u32(&mut request)? {
+        0 => usize::MAX,
+        limit => limit as usize,
+      };
+      let entries = log
+        .scan(&prefix)
+        .take(limit)
+        .collect::<Result<Vec<_>, _>>()?;
+
+      response.push(STATUS_OK);
+      response.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+      for (key, value) in entries {
+        put_bytes(response, key.as_bytes());
+        put_bytes(response, value.as_bytes());
+      }
+    }
+    OP_METRICS => {
+      response.push(STATUS_OK);
+      put_bytes(response, log.render_metrics()?.as_bytes());
+    }
+    _ => return Err(invalid("Unknown opcode")),
+  }
+  Ok(())
+}
+
+/// Streams changes under `prefix` to the client until it hangs up or the
+/// server stops.
+fn watch(
+  shared: &Shared,
+  prefix: &str,
+  stream: &TcpStream,
+  writer: &mut impl Write,
+) -> Result<(), io::Error> {
+  let changes = shared.log.watch(prefix);
+  write_frame(writer, &[STATUS_OK])?;
+  writer.flush()?;
+
+  while !shared.stopped.load(Ordering::Relaxed) {
+    for change in changes.try_iter() {
+      let mut frame = Vec::new();
+      match change {
+        Event::Put { key, value } => {
+          frame.push(EVENT_PUT);
+          put_bytes(&mut frame, key.as_bytes());
+          put_bytes(&mut frame, value.as_bytes());
+        }
+        Event::Delete { key } => {
+          frame.push(EVENT_DELETE);
+          put_bytes(&mut frame, key.as_bytes());
+        }
+        Event::Merge { key, operand } => {
+          frame.push(EVENT_MERGE);
+          put_bytes(&mut frame, key.as_bytes());
+          put_bytes(&mut frame, operand.as_bytes());
+        }
+      }
+      write_frame(writer, &frame)?;
+    }
+    writer.flush()?;
+
+    // Waits out the read timeout, which also tells when the client is gone.
+    match stream.peek(&mut [0]) {
+      Ok(0) => break,
+      Ok(_) => return Err(invalid("Unexpected request while watching")),
+      Err(e)
+        if matches!(
+          e.kind(),
+          io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ) => {}
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(())
+}
+
+/// Reads one frame, or `None` when the peer closed the connection between
+/// frames or `stopped` was set while waiting.
+fn read_frame(
+  reader: &mut impl Read,
+  stopped: Option<&AtomicBool>,
+) -> Result<Option<Vec<u8>>, io::Error> {
+  let mut len = [0; 4];
+  if !fill(reader, &mut len, stopped)? {
+    return Ok(None);
+  }
+
+  let len = u32::from_le_bytes(len) as usize;
+  if len > MAX_FRAME_SIZE {
+    return Err(invalid("Frame is too large"));
+  }
+  let mut body = vec![0; len];
+  if !fill(reader, &mut body, stopped)? {
+    return Ok(None);
+  }
+  Ok(Some(body))
+}
+
+/// `read_exact` that sits out read timeouts, returning false if the stream
+/// ended before the first byte or `stopped` was set.
+fn fill(
+  reader: &mut impl Read,
+  buf: &mut [u8],
+  stopped: Option<&AtomicBool>,
+) -> Result<bool, io::Error> {
+  let mut filled = 0;
+  while filled < buf.len() {
+    match reader.read(&mut buf[filled..]) {
+      Ok(0) if filled == 0 => return Ok(false),
+      Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+      Ok(n) => filled += n,
+      Err(e)
+        if matches!(
+          e.kind(),
+          io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ) =>
+      {
+        if stopped.is_some_and(|stopped| stopped.load(Ordering::Relaxed)) {
+          return Ok(false);
+        }
+      }
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(true)
+}
+
+fn write_frame(writer: &mut impl Write, body: &[u8]) -> Result<(), io::Error> {
+  writer.write_all(&(body.len() as u32).to_le_bytes())?;
+  writer.write_all(body)
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+  buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+  buf.extend_from_slice(bytes);
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], io::Error> {
+  if buf.len() < len {
+    return Err(invalid("Truncated frame"));
+  }
+  let (head, rest) = buf.split_at(len);
+  *buf = rest;
+  Ok(head)
+}
+
+fn take_u8(buf: &mut &[u8]) -> Result<u8, io::Error> {
+  Ok(take(buf, 1)?[0])
+}
+
+fn take_u32(buf: &mut &[u8]) -> Result<u32, io::Error> {
+  Ok(u32::from_le_bytes(take(buf, 4)?.try_into().unwrap()))
+}
+
+fn take_str(buf: &mut &[u8]) -> Result<String, io::Error> {
+  let len = take_u32(buf)? as usize;
+  String::from_utf8(take(buf, len)?.to_vec()).map_err(|_| invalid("Invalid UTF-8 in frame"))
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,63 @@
+//! Point-in-time statistics about a [`LogFile`](crate::log_file::LogFile).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Snapshot returned by [`LogFile::stats`](crate::log_file::LogFile::stats).
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+  /// Keys that currently have a value.
+  pub live_keys: usize,
+  /// Every segment on disk, ordered by file id.
+  pub segments: Vec<SegmentStats>,
+  /// Sum of all segment sizes.
+  pub total_bytes: u64,
+  /// Estimated bytes a compaction would reclaim: overwritten records and
+  /// tombstones.
+  pub dead_bytes: u64,
+  pub last_compaction: Option<DateTime<Utc>>,
+  /// Reads served since the store was opened.
+  pub reads: u64,
+  /// Records written since the store was opened.
+  pub writes: u64,
+  /// Reads that found their segment already open or mapped, since the store
+  /// was opened.
+  pub cache_hits: u64,
+  /// Reads that had to open or map their segment first.
+  pub cache_misses: u64,
+}
+
+impl Stats {
+  pub fn segment_count(&self) -> usize {
+    self.segments.len()
+  }
+
+  /// Share of segment lookups served from the cache, or `None` before the
+  /// first read from disk.
+  pub fn cache_hit_rate(&self) -> Option<f64> {
+    let lookups = self.cache_hits + self.cache_misses;
+    (lookups > 0).then(|| self.cache_hits as f64 / lookups as f64)
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentStats {
+  pub file_id: u64,
+  pub size: u64,
+  pub dead_bytes: u64,
+  /// Whether this is the segment currently being appended to.
+  pub active: bool,
+}
+
+impl SegmentStats {
+  /// Dead bytes per live byte. A segment holding nothing but garbage reports
+  /// infinity.
+  pub fn dead_ratio(&self) -> f64 {
+    let live = self.size.saturating_sub(self.dead_bytes);
+    match (self.dead_bytes, live) {
+      (0, _) => 0.0,
+      (_, 0) => f64::INFINITY,
+      (dead, live) => dead as f64 / live as f64,
+    }
+  }
+}
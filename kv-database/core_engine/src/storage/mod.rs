@@ -0,0 +1,97 @@
+//! The platform-specific parts of file access.
+//!
+//! Everything else the engine does with files goes through `std::fs` and
+//! works the same everywhere. Positional reads, which let threads share a
+//! file handle without a shared cursor, are the exception and live behind
+//! [`ReadAt`]: `pread` on unix, `seek_read` on Windows, and a seek and read
+//! under a global lock elsewhere, such as on `wasm32-wasip1`. Memory maps
+//! are the other: wasm32 has none, so [`map`] reads the whole file there.
+//! Targets without a file system at all run with `Options::in_memory`.
+
+use std::{fs::File, io, path::Path};
+
+/// A sealed segment's contents, as returned by [`map`].
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type Map = memmap2::Mmap;
+#[cfg(target_arch = "wasm32")]
+pub(crate) type Map = Vec<u8>;
+
+/// Maps `file` into memory. The file must never be written to again.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn map(file: &File) -> Result<Map, io::Error> {
+  // SAFETY: the caller only maps sealed segments, which are never written
+  // to again. Compaction unlinks them, which leaves existing mappings intact.
+  unsafe { memmap2::Mmap::map(file) }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn map(mut file: &File) -> Result<Map, io::Error> {
+  use std::io::Read;
+
+  let mut bytes = Vec::new();
+  file.read_to_end(&mut bytes)?;
+  Ok(bytes)
+}
+
+/// Reads at an offset, so concurrent readers of the same handle don't
+/// interfere.
+pub(crate) trait ReadAt {
+  fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<(), io::Error>;
+}
+
+#[cfg(unix)]
+impl ReadAt for File {
+  fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<(), io::Error> {
+    std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)
+  }
+}
+
+#[cfg(windows)]
+impl ReadAt for File {
+  fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> Result<(), io::Error> {
+    use std::os::windows::fs::FileExt;
+
+    // `seek_read` moves the handle's cursor, which no other read relies on.
+    while !buf.is_empty() {
+      match self.seek_read(buf, offset) {
+        Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        Ok(read) => {
+          buf = &mut buf[read..];
+          offset += read as u64;
+        }
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+        Err(e) => return Err(e),
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(not(any(unix, windows)))]
+impl ReadAt for File {
+  fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> Result<(), io::Error> {
+    use std::{
+      io::{Read, Seek, SeekFrom},
+      sync::Mutex,
+    };
+
+    // Handles are shared between threads, so seeking and reading has to
+    // happen as one step.
+    static CURSOR: Mutex<()> = Mutex::new(());
+    let _cursor = CURSOR.lock().unwrap();
+    let mut file = self;
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+  }
+}
+
+/// Makes the entries created, renamed or deleted in `dir` durable.
+///
+/// Windows can't open a directory as a file and commits directory changes
+/// along with the files in them, so this does nothing there.
+pub(crate) fn sync_dir(dir: &Path) -> Result<(), io::Error> {
+  if cfg!(windows) {
+    return Ok(());
+  }
+  File::open(dir)?.sync_all()
+}
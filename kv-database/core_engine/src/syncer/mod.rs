@@ -0,0 +1,48 @@
+//! Periodic fsync for [`SyncPolicy::Interval`](crate::options::SyncPolicy::Interval).
+//!
+//! The thread only holds what its tick closure captures, so it never keeps
+//! the log alive on its own. It exits when the [`Syncer`] is dropped or when
+//! the tick reports that there is nothing left to sync.
+
+use std::{
+  sync::mpsc::{self, RecvTimeoutError, Sender},
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+#[derive(Debug)]
+pub(crate) struct Syncer {
+  stop: Option<Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Syncer {
+  /// Calls `tick` every `interval` until it returns false or the syncer is
+  /// dropped.
+  pub(crate) fn spawn(interval: Duration, mut tick: impl FnMut() -> bool + Send + 'static) -> Self {
+    let (stop, stopped) = mpsc::channel::<()>();
+    let thread = thread::spawn(move || {
+      while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        if !tick() {
+          break;
+        }
+      }
+    });
+
+    Self {
+      stop: Some(stop),
+      thread: Some(thread),
+    }
+  }
+}
+
+impl Drop for Syncer {
+  fn drop(&mut self) {
+    if let Some(stop) = self.stop.take() {
+      let _ = stop.send(());
+    }
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
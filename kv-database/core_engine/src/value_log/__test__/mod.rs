@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod value_log_test {
+  use std::path::PathBuf;
+
+  use crate::value_log::*;
+
+  /// An empty directory, unique to `name`.
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("blob-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  // ---------------------------------------------------------
+  // pointer tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn pointers_round_trip() {
+    let pointer = BlobPointer {
+      file_id: 3,
+      offset: 112,
+      len: 100,
+    };
+    let encoded = pointer.encode();
+    assert_eq!(encoded.len(), BLOB_POINTER_SIZE);
+    assert_eq!(BlobPointer::decode(&encoded).unwrap(), pointer);
+    assert_eq!(pointer.entry_len(), ENTRY_HEADER_SIZE + 100);
+
+    let err = BlobPointer::decode(&encoded[1..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn parses_blob_file_names() {
+    let log = ValueLog::new(PathBuf::from("data"), 1024);
+    let name = log.path(42);
+    assert_eq!(
+      parse_file_name(name.file_name().unwrap().to_str().unwrap()),
+      Some(42)
+    );
+    assert_eq!(parse_file_name("blob-"), None);
+    assert_eq!(parse_file_name("segment-000042.duck"), None);
+  }
+
+  // ---------------------------------------------------------
+  // read and write tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn reads_values_back_through_their_pointers() {
+    let log = ValueLog::new(temp_dir("read"), 1024);
+    assert_eq!(log.active_id(), None);
+
+    let first = log.append(b"first value", true).unwrap();
+    let second = log.append(b"second", false).unwrap();
+    assert_eq!(first.file_id, 1);
+    assert_eq!(first.offset, 0);
+    assert_eq!(second.offset, first.entry_len());
+
+    assert_eq!(log.read(&first).unwrap(), b"first value");
+    assert_eq!(log.read(&second).unwrap(), b"second");
+    assert_eq!(log.active_id(), Some(1));
+    assert_eq!(
+      log.file_size(1).unwrap(),
+      first.entry_len() + second.entry_len()
+    );
+  }
+
+  #[test]
+  fn rotates_once_a_file_is_full() {
+    let dir = temp_dir("rotate");
+    let log = ValueLog::new(dir.clone(), 10);
+
+    let pointers = (0..3)
+      .map(|i| log.append(format!("value-{i}").as_bytes(), false).unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      pointers.iter().map(|p| p.file_id).collect::<Vec<_>>(),
+      [1, 2, 3]
+    );
+    assert_eq!(log.file_ids().unwrap(), [1, 2, 3]);
+
+    // A new value log on the same directory never appends to an old file.
+    let reopened = ValueLog::new(dir, 10);
+    assert_eq!(reopened.append(b"later", false).unwrap().file_id, 4);
+    assert_eq!(reopened.read(&pointers[1]).unwrap(), b"value-1");
+  }
+
+  #[test]
+  fn detects_a_corrupt_value() {
+    let dir = temp_dir("corrupt");
+    let log = ValueLog::new(dir.clone(), 1024);
+    let pointer = log.append(b"some value", true).unwrap();
+
+    let path = log.path(pointer.file_id);
+    let mut bytes = fs::read(&path).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xff;
+    fs::write(&path, bytes).unwrap();
+
+    let err = ValueLog::new(dir, 1024).read(&pointer).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn removes_files() {
+    let log = ValueLog::new(temp_dir("remove"), 1);
+    let pointer = log.append(b"value", false).unwrap();
+    log.append(b"value", false).unwrap();
+
+    log.remove(pointer.file_id).unwrap();
+    log.remove(pointer.file_id).unwrap();
+    assert_eq!(log.file_ids().unwrap(), [2]);
+    assert!(log.read(&pointer).is_err());
+  }
+}
@@ -0,0 +1,222 @@
+//! WiscKey-style storage of large values outside the log.
+//!
+//! With `Options::value_log_threshold` set, a put whose value is at least
+//! that many bytes writes the value to the active blob file and stores only a
+//! [`BlobPointer`] in its record. Compaction then copies 24-byte pointers
+//! instead of megabytes of values.
+//!
+//! Blob files are named `blob-<id>` and hold entries of a CRC32 of the value,
+//! the value's length (u64) and the value. They are only appended to and
+//! rotate at `Options::value_log_file_size`. A blob file's garbage is
+//! collected during compaction, which knows every value still referenced:
+//! files nothing points into anymore are deleted, and the live values of
+//! files that are mostly garbage (see `Options::value_log_gc_ratio`) are
+//! copied to the active blob file first.
+
+use std::{
+  collections::HashMap,
+  fs::{self, File, OpenOptions},
+  io::{self, Write},
+  path::PathBuf,
+  sync::{Arc, Mutex},
+};
+
+use crate::storage::ReadAt;
+
+mod __test__;
+
+/// Size of an encoded [`BlobPointer`].
+pub(crate) const BLOB_POINTER_SIZE: usize = 24;
+/// CRC32 and length in front of every value.
+const ENTRY_HEADER_SIZE: u64 = 4 + 8;
+const FILE_PREFIX: &str = "blob-";
+
+/// Where a value lives in the value log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BlobPointer {
+  pub(crate) file_id: u64,
+  /// Start of the entry, header included.
+  pub(crate) offset: u64,
+  /// Length of the value.
+  pub(crate) len: u64,
+}
+
+impl BlobPointer {
+  pub(crate) fn encode(&self) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(BLOB_POINTER_SIZE);
+    buf.extend_from_slice(&self.file_id.to_le_bytes());
+    buf.extend_from_slice(&self.offset.to_le_bytes());
+    buf.extend_from_slice(&self.len.to_le_bytes());
+    buf
+  }
+
+  pub(crate) fn decode(buf: &[u8]) -> Result<Self, io::Error> {
+    if buf.len() != BLOB_POINTER_SIZE {
+      return Err(invalid("Corrupted blob pointer"));
+    }
+    let field = |i: usize| u64::from_le_bytes(buf[i * 8..(i + 1) * 8].try_into().unwrap());
+    Ok(Self {
+      file_id: field(0),
+      offset: field(1),
+      len: field(2),
+    })
+  }
+
+  /// Bytes the entry takes up in its blob file.
+  pub(crate) fn entry_len(&self) -> u64 {
+    ENTRY_HEADER_SIZE + self.len
+  }
+}
+
+#[derive(Debug)]
+struct Active {
+  file_id: u64,
+  file: File,
+  size: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  /// Created on the first append, so a log without large values never has
+  /// blob files.
+  active: Option<Active>,
+  readers: HashMap<u64, Arc<File>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ValueLog {
+  dir: PathBuf,
+  file_size: u64,
+  state: Mutex<State>,
+}
+
+impl ValueLog {
+  pub(crate) fn new(dir: PathBuf, file_size: u64) -> Self {
+    Self {
+      dir,
+      file_size,
+      state: Mutex::default(),
+    }
+  }
+
+  /// Appends `value` to the active blob file, fsyncing it when `sync` is
+  /// set, and returns where it went.
+  pub(crate) fn append(&self, value: &[u8], sync: bool) -> Result<BlobPointer, io::Error> {
+    let mut state = self.state.lock().unwrap();
+    if state
+      .active
+      .as_ref()
+      .is_none_or(|active| active.size >= self.file_size)
+    {
+      let file_id = self.file_ids()?.last().map_or(1, |last| last + 1);
+      let file = OpenOptions::new()
+        .create_new(true)
+        .append(true)
+        .open(self.path(file_id))?;
+      state.active = Some(Active {
+        file_id,
+        file,
+        size: 0,
+      });
+    }
+    let active = state.active.as_mut().unwrap();
+
+    let mut entry = Vec::with_capacity(ENTRY_HEADER_SIZE as usize + value.len());
+    entry.extend_from_slice(&crc32fast::hash(value).to_le_bytes());
+    entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    entry.extend_from_slice(value);
+    active.file.write_all(&entry)?;
+    if sync {
+      active.file.sync_data()?;
+    }
+
+    let pointer = BlobPointer {
+      file_id: active.file_id,
+      offset: active.size,
+      len: value.len() as u64,
+    };
+    active.size += entry.len() as u64;
+    Ok(pointer)
+  }
+
+  /// Reads the value `pointer` points at and checks its checksum.
+  pub(crate) fn read(&self, pointer: &BlobPointer) -> Result<Vec<u8>, io::Error> {
+    let file = self.reader(pointer.file_id)?;
+    let mut entry = vec![0; pointer.entry_len() as usize];
+    file.read_exact_at(&mut entry, pointer.offset)?;
+
+    let (header, value) = entry.split_at(ENTRY_HEADER_SIZE as usize);
+    let crc = u32::from_le_bytes(header[..4].try_into().unwrap());
+    let len = u64::from_le_bytes(header[4..].try_into().unwrap());
+    if len != pointer.len || crc32fast::hash(value) != crc {
+      return Err(invalid("Corrupted blob: checksum mismatch"));
+    }
+    Ok(value.to_vec())
+  }
+
+  fn reader(&self, file_id: u64) -> Result<Arc<File>, io::Error> {
+    let mut state = self.state.lock().unwrap();
+    if let Some(file) = state.readers.get(&file_id) {
+      return Ok(file.clone());
+    }
+    let file = Arc::new(File::open(self.path(file_id))?);
+    state.readers.insert(file_id, file.clone());
+    Ok(file)
+  }
+
+  /// Makes every append so far durable.
+  pub(crate) fn sync(&self) -> Result<(), io::Error> {
+    match &self.state.lock().unwrap().active {
+      Some(active) => active.file.sync_data(),
+      None => Ok(()),
+    }
+  }
+
+  /// The blob file appends go to, if one has been created yet.
+  pub(crate) fn active_id(&self) -> Option<u64> {
+    let state = self.state.lock().unwrap();
+    state.active.as_ref().map(|active| active.file_id)
+  }
+
+  /// Ids of the blob files on disk, in ascending order.
+  pub(crate) fn file_ids(&self) -> Result<Vec<u64>, io::Error> {
+    let mut ids = match fs::read_dir(&self.dir) {
+      Ok(entries) => entries
+        .filter_map(|entry| {
+          let name = entry.ok()?.file_name().into_string().ok()?;
+          parse_file_name(&name)
+        })
+        .collect::<Vec<_>>(),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+      Err(e) => return Err(e),
+    };
+    ids.sort_unstable();
+    Ok(ids)
+  }
+
+  pub(crate) fn file_size(&self, file_id: u64) -> Result<u64, io::Error> {
+    Ok(fs::metadata(self.path(file_id))?.len())
+  }
+
+  /// Deletes blob file `file_id` if it still exists.
+  pub(crate) fn remove(&self, file_id: u64) -> Result<(), io::Error> {
+    self.state.lock().unwrap().readers.remove(&file_id);
+    match fs::remove_file(self.path(file_id)) {
+      Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+      _ => Ok(()),
+    }
+  }
+
+  pub(crate) fn path(&self, file_id: u64) -> PathBuf {
+    self.dir.join(format!("{FILE_PREFIX}{file_id}"))
+  }
+}
+
+/// The id of the blob file named `name`.
+pub(crate) fn parse_file_name(name: &str) -> Option<u64> {
+  name.strip_prefix(FILE_PREFIX)?.parse().ok()
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,76 @@
+//! Results of [`LogFile::verify`](crate::log_file::LogFile::verify), an
+//! fsck for the data directory.
+//!
+//! Every segment is read front to back and each record's checksum checked.
+//! A record that fails makes the rest of its segment untrustworthy, since
+//! its size fields may be garbage too, so scanning that segment stops there.
+//! Hint files are checked against the records they describe.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+  /// Every segment that was scanned, ordered by file id.
+  pub segments: Vec<SegmentReport>,
+  pub problems: Vec<Problem>,
+}
+
+impl VerifyReport {
+  pub fn is_ok(&self) -> bool {
+    self.problems.is_empty()
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentReport {
+  pub file_id: u64,
+  pub size: u64,
+  /// Records read before the end of the segment or the first problem.
+  pub records: u64,
+  pub has_hint: bool,
+}
+
+/// Something wrong with `file`, at `offset` when it concerns one record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+  pub file: String,
+  pub offset: Option<u64>,
+  pub kind: ProblemKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemKind {
+  /// The record fails its checksum or can't be parsed.
+  CorruptRecord(String),
+  /// The segment ends partway through a record or write batch.
+  TruncatedRecord,
+  /// The record's checksum is fine but its key isn't UTF-8.
+  InvalidKey,
+  /// The hint file fails its own checksums or is cut short.
+  InvalidHint(String),
+  /// The hint file disagrees with its segment.
+  HintMismatch(String),
+  /// A hint file without a segment.
+  OrphanHint,
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.offset {
+      Some(offset) => write!(f, "{} at offset {offset}: {}", self.file, self.kind),
+      None => write!(f, "{}: {}", self.file, self.kind),
+    }
+  }
+}
+
+impl fmt::Display for ProblemKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ProblemKind::CorruptRecord(reason) | ProblemKind::InvalidHint(reason) => f.write_str(reason),
+      ProblemKind::TruncatedRecord => f.write_str("Truncated record"),
+      ProblemKind::InvalidKey => f.write_str("Key is not valid UTF-8"),
+      ProblemKind::HintMismatch(reason) => write!(f, "Hint does not match its segment: {reason}"),
+      ProblemKind::OrphanHint => f.write_str("Hint file without a segment"),
+    }
+  }
+}
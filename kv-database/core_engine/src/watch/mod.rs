@@ -0,0 +1,100 @@
+//! Key change notifications for [`LogFile::watch`](crate::log_file::LogFile::watch).
+//!
+//! Writes are published under the log's writer lock, so events queue up in
+//! sequence order. They are held back until the sync policy has made the
+//! write durable and then fanned out to every subscriber whose prefix
+//! matches, so nobody reacts to a change a crash could still undo.
+
+use std::{
+  collections::VecDeque,
+  sync::{
+    mpsc::{self, Receiver, Sender},
+    Mutex,
+  },
+};
+
+/// A durable change to a watched key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+  Put {
+    key: String,
+    value: String,
+  },
+  Delete {
+    key: String,
+  },
+  /// A merge operand was queued for `key`; read the key for the folded
+  /// value.
+  Merge {
+    key: String,
+    operand: String,
+  },
+}
+
+impl Event {
+  pub fn key(&self) -> &str {
+    match self {
+      Event::Put { key, .. } | Event::Delete { key } | Event::Merge { key, .. } => key,
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Subscriber {
+  prefix: String,
+  sender: Sender<Event>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  subscribers: Vec<Subscriber>,
+  /// Events waiting for their write to become durable, by sequence number.
+  pending: VecDeque<(u64, Event)>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Watchers {
+  state: Mutex<State>,
+}
+
+impl Watchers {
+  pub(crate) fn subscribe(&self, prefix: &str) -> Receiver<Event> {
+    let (sender, receiver) = mpsc::channel();
+    self.state.lock().unwrap().subscribers.push(Subscriber {
+      prefix: prefix.to_string(),
+      sender,
+    });
+    receiver
+  }
+
+  /// Queues the event built by `event` for the write stamped `seq`, if
+  /// anyone watches `key`.
+  pub(crate) fn push(&self, seq: u64, key: &str, event: impl FnOnce() -> Event) {
+    let mut state = self.state.lock().unwrap();
+    if state
+      .subscribers
+      .iter()
+      .any(|subscriber| key.starts_with(&subscriber.prefix))
+    {
+      state.pending.push_back((seq, event()));
+    }
+  }
+
+  /// Delivers every queued event whose write is covered by `durable_seq`.
+  /// Subscribers that dropped their receiver are forgotten.
+  pub(crate) fn release(&self, durable_seq: u64) {
+    let mut state = self.state.lock().unwrap();
+    let State {
+      subscribers,
+      pending,
+    } = &mut *state;
+
+    while pending.front().is_some_and(|(seq, _)| *seq <= durable_seq) {
+      let (_, event) = pending.pop_front().unwrap();
+      subscribers.retain(|subscriber| {
+        !event.key().starts_with(&subscriber.prefix)
+          || subscriber.sender.send(event.clone()).is_ok()
+      });
+    }
+  }
+}
@@ -0,0 +1,71 @@
+//! Buffered multi-key writes that are committed atomically.
+//!
+//! A [`WriteBatch`] collects puts and deletes in memory. Handing it to
+//! [`LogFile::write`](crate::log_file::LogFile::write) appends every record in
+//! one go behind a batch header and syncs once, so after a crash either the
+//! whole batch is visible or none of it is.
+
+#[derive(Debug, Clone)]
+pub(crate) enum BatchOp {
+  Put { key: String, value: String },
+  Delete { key: String },
+}
+
+impl BatchOp {
+  pub(crate) fn key(&self) -> &str {
+    match self {
+      BatchOp::Put { key, .. } | BatchOp::Delete { key } => key,
+    }
+  }
+
+  /// The value written to disk; deletes are stored as empty tombstones.
+  pub(crate) fn value(&self) -> &str {
+    match self {
+      BatchOp::Put { value, .. } => value,
+      BatchOp::Delete { .. } => "",
+    }
+  }
+}
+
+/// An ordered list of puts and deletes applied as a single unit.
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+  pub(crate) ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Queues `key = value`. Later operations on the same key win.
+  pub fn put(&mut self, key: &str, value: &str) -> &mut Self {
+    self.ops.push(BatchOp::Put {
+      key: key.to_string(),
+      value: value.to_string(),
+    });
+    self
+  }
+
+  /// Queues the removal of `key`.
+  pub fn delete(&mut self, key: &str) -> &mut Self {
+    self.ops.push(BatchOp::Delete {
+      key: key.to_string(),
+    });
+    self
+  }
+
+  /// Returns the number of queued operations.
+  pub fn len(&self) -> usize {
+    self.ops.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ops.is_empty()
+  }
+
+  /// Drops every queued operation so the batch can be reused.
+  pub fn clear(&mut self) {
+    self.ops.clear();
+  }
+}
@@ -0,0 +1,113 @@
+//! The embedding API of the duck key-value store.
+//!
+//! [`DuckDb`] is the one type most applications need: open a directory,
+//! then put, get, delete and scan string keys and values. It wraps the
+//! engine in `core_engine`, whose log, index and logging setup stay out of
+//! sight; reach for that crate directly for column families, replication,
+//! backups and the rest.
+//!
+//! ```no_run
+//! use duck::{DuckDb, Options};
+//!
+//! let db = DuckDb::open("data", Options::default())?;
+//! db.put("greeting", "hello")?;
+//! assert_eq!(db.get("greeting")?.as_deref(), Some("hello"));
+//! for entry in db.scan("greet") {
+//!   let (key, value) = entry?;
+//!   println!("{key} = {value}");
+//! }
+//! db.close()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::{io, path::Path};
+
+use core_engine::{cursor::Cursor, log_file::LogFile};
+
+pub use core_engine::{
+  comparator::KeyComparator,
+  error::DbError,
+  merge::MergeOperator,
+  options::{Compression, Options, SyncPolicy},
+};
+
+/// An open database. Clones share it, and the data directory stays locked
+/// until the last one is dropped.
+#[derive(Debug, Clone)]
+pub struct DuckDb {
+  log: LogFile,
+}
+
+impl DuckDb {
+  /// Opens, creating it if needed, the database in the directory `path`.
+  /// `options.dir` is replaced by `path`.
+  pub fn open(path: impl AsRef<Path>, options: Options) -> Result<Self, io::Error> {
+    let log = LogFile::with_options(Options {
+      dir: path.as_ref().to_path_buf(),
+      ..options
+    })?;
+    log.start()?;
+    Ok(Self { log })
+  }
+
+  /// Stores `value` under `key`, durable as configured by
+  /// `Options::sync_policy` once this returns. An empty key or value fails
+  /// with [`DbError::EmptyKey`] or [`DbError::EmptyValue`].
+  pub fn put(&self, key: &str, value: &str) -> Result<(), io::Error> {
+    self.log.append(key, value)?;
+    Ok(())
+  }
+
+  /// The value of `key`, or `None` if there is no such key.
+  pub fn get(&self, key: &str) -> Result<Option<String>, io::Error> {
+    let Some(value) = self.log.multi_get(&[key])?.pop().flatten() else {
+      return Ok(None);
+    };
+    String::from_utf8(value)
+      .map(Some)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+
+  /// Deletes `key` and returns whether there was such a key.
+  pub fn delete(&self, key: &str) -> Result<bool, io::Error> {
+    match self.log.delete(key) {
+      Ok(_) => Ok(true),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Iterates over the keys starting with `prefix` (`""` for every key) and
+  /// their values, in ascending key order, as they were when the scan was
+  /// started. Compaction waits until the scan is dropped.
+  pub fn scan(&self, prefix: &str) -> Scan {
+    Scan {
+      cursor: self.log.cursor(prefix),
+    }
+  }
+
+  /// Writes everything put or deleted so far to disk, whatever the sync
+  /// policy.
+  pub fn flush(&self) -> Result<(), io::Error> {
+    self.log.sync()
+  }
+
+  /// Flushes and closes this handle. Clones stay open.
+  pub fn close(self) -> Result<(), io::Error> {
+    self.flush()
+  }
+}
+
+/// Entries returned by [`DuckDb::scan`].
+#[derive(Debug)]
+pub struct Scan {
+  cursor: Cursor,
+}
+
+impl Iterator for Scan {
+  type Item = Result<(String, String), io::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.cursor.next()
+  }
+}
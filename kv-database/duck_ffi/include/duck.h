@@ -0,0 +1,77 @@
+/*
+ * C interface of the duck key-value store, implemented by the duck_ffi
+ * crate. Link against libduck_ffi.so (or .a / .dylib / .dll).
+ *
+ * Every function returns a DuckStatus. On an error, duck_last_error()
+ * describes it until the next failing call on the same thread. Keys and
+ * values are UTF-8 byte strings passed as pointer and length; buffers
+ * returned through out parameters are freed with duck_free().
+ */
+
+#ifndef DUCK_H
+#define DUCK_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum DuckStatus {
+  DUCK_OK = 0,
+  /* A scan has no more entries. */
+  DUCK_DONE = 1,
+  DUCK_NOT_FOUND = 2,
+  /* A null pointer, a key or value that isn't UTF-8, or an empty value. */
+  DUCK_INVALID_ARGUMENT = 3,
+  /* Another process or handle has the data directory open. */
+  DUCK_LOCKED = 4,
+  /* The key or value is over the configured size limits. */
+  DUCK_TOO_LARGE = 5,
+  DUCK_IO_ERROR = 6,
+  DUCK_PANIC = 7
+} DuckStatus;
+
+typedef struct DuckDb DuckDb;
+typedef struct DuckScan DuckScan;
+
+/* Opens, creating it if needed, the database in the directory `path`. */
+DuckStatus duck_open(const char *path, DuckDb **db);
+
+/* Syncs and closes `db`, releasing the handle even if the sync fails. */
+DuckStatus duck_close(DuckDb *db);
+
+DuckStatus duck_put(const DuckDb *db, const char *key, size_t key_len,
+                    const char *value, size_t value_len);
+
+/* Returns DUCK_NOT_FOUND if there is no such key. */
+DuckStatus duck_get(const DuckDb *db, const char *key, size_t key_len,
+                    char **value, size_t *value_len);
+
+/* Returns DUCK_NOT_FOUND if there is no such key. */
+DuckStatus duck_delete(const DuckDb *db, const char *key, size_t key_len);
+
+/*
+ * Starts a scan over the keys starting with `prefix`, in ascending order,
+ * as of when it was started. Compaction waits until the scan is freed.
+ */
+DuckStatus duck_scan(const DuckDb *db, const char *prefix,
+                     size_t prefix_len, DuckScan **scan);
+
+/* Returns DUCK_DONE once there are no more entries. */
+DuckStatus duck_scan_next(DuckScan *scan, char **key, size_t *key_len,
+                          char **value, size_t *value_len);
+
+void duck_scan_free(DuckScan *scan);
+
+/* Frees a buffer returned by duck_get() or duck_scan_next(). */
+void duck_free(char *ptr, size_t len);
+
+/* The last error on this thread, or NULL. Not to be freed. */
+const char *duck_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DUCK_H */
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod duck_ffi_test {
+  use std::ptr;
+
+  use core_engine::log_file::LogFile;
+
+  use crate::*;
+
+  fn open() -> *mut DuckDb {
+    let log = LogFile::in_memory().unwrap();
+    Box::into_raw(Box::new(DuckDb { log }))
+  }
+
+  unsafe fn get(db: *const DuckDb, key: &str) -> (DuckStatus, Option<String>) {
+    let mut value = ptr::null_mut();
+    let mut value_len = 0;
+    let status = duck_get(
+      db,
+      key.as_ptr().cast(),
+      key.len(),
+      &mut value,
+      &mut value_len,
+    );
+    if value.is_null() {
+      return (status, None);
+    }
+    let text = std::str::from_utf8(slice::from_raw_parts(value.cast::<u8>(), value_len))
+      .unwrap()
+      .to_string();
+    duck_free(value, value_len);
+    (status, Some(text))
+  }
+
+  #[test]
+  fn put_get_delete() {
+    unsafe {
+      let db = open();
+      let status = duck_put(db, "k".as_ptr().cast(), 1, "v".as_ptr().cast(), 1);
+      assert_eq!(status, DuckStatus::Ok);
+      assert_eq!(get(db, "k"), (DuckStatus::Ok, Some("v".to_string())));
+
+      assert_eq!(duck_delete(db, "k".as_ptr().cast(), 1), DuckStatus::Ok);
+      assert_eq!(duck_close(db), DuckStatus::Ok);
+    }
+  }
+
+  #[test]
+  fn missing_keys_are_not_found() {
+    unsafe {
+      let db = open();
+      assert_eq!(get(db, "nope"), (DuckStatus::NotFound, None));
+      assert_eq!(
+        duck_delete(db, "nope".as_ptr().cast(), 4),
+        DuckStatus::NotFound
+      );
+
+      duck_put(db, "k".as_ptr().cast(), 1, "v".as_ptr().cast(), 1);
+      duck_delete(db, "k".as_ptr().cast(), 1);
+      assert_eq!(get(db, "k"), (DuckStatus::NotFound, None));
+      assert_eq!(
+        duck_delete(db, "k".as_ptr().cast(), 1),
+        DuckStatus::NotFound
+      );
+      duck_close(db);
+    }
+  }
+}
@@ -0,0 +1,335 @@
+//! A C ABI over the engine, so it can be embedded from C, Python (ctypes)
+//! and anything else that calls C. The declarations live in
+//! `include/duck.h`.
+//!
+//! Databases and scans are opaque handles, released with [`duck_close`] and
+//! [`duck_scan_free`]. Every function returns a [`DuckStatus`]; when it is
+//! an error, [`duck_last_error`] describes it. Keys and values are passed as
+//! a pointer and a length, need not be NUL-terminated and must be UTF-8.
+//! Buffers handed back to the caller are freed with [`duck_free`].
+//!
+//! Panics are caught at the boundary and reported as `DUCK_PANIC` instead
+//! of unwinding into the caller.
+
+mod __test__;
+
+use std::{
+  cell::RefCell,
+  ffi::{c_char, CStr, CString},
+  io,
+  panic::{self, AssertUnwindSafe},
+  path::PathBuf,
+  ptr, slice,
+};
+
+use core_engine::{cursor::Cursor, error::DbError, log_file::LogFile, options::Options};
+
+/// Outcome of a call. The values are part of the ABI and never change.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuckStatus {
+  Ok = 0,
+  /// A scan has no more entries.
+  Done = 1,
+  NotFound = 2,
+  /// A null pointer, a key or value that isn't UTF-8, or an empty value.
+  InvalidArgument = 3,
+  /// Another process or handle has the data directory open.
+  Locked = 4,
+  /// The key or value is over the configured size limits.
+  TooLarge = 5,
+  IoError = 6,
+  Panic = 7,
+}
+
+/// An open database.
+pub struct DuckDb {
+  log: LogFile,
+}
+
+/// The entries of a scan, read from a snapshot taken when it was started.
+pub struct DuckScan {
+  cursor: Cursor,
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opens, creating it if needed, the database in the directory `path`.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `db` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_open(path: *const c_char, db: *mut *mut DuckDb) -> DuckStatus {
+  guard(|| {
+    if path.is_null() || db.is_null() {
+      return Err(null_argument());
+    }
+    let path = CStr::from_ptr(path)
+      .to_str()
+      .map_err(|_| invalid("The path is not valid UTF-8"))?;
+
+    let log = LogFile::with_options(Options {
+      dir: PathBuf::from(path),
+      ..Options::default()
+    })?;
+    log.start()?;
+    *db = Box::into_raw(Box::new(DuckDb { log }));
+    Ok(DuckStatus::Ok)
+  })
+}
+
+/// Syncs and closes `db`. The handle is released even if the sync fails.
+/// Closing a null handle does nothing.
+///
+/// # Safety
+///
+/// `db` must come from [`duck_open`] and not be used afterwards, including
+/// by scans started on it.
+#[no_mangle]
+pub unsafe extern "C" fn duck_close(db: *mut DuckDb) -> DuckStatus {
+  if db.is_null() {
+    return DuckStatus::Ok;
+  }
+  let db = Box::from_raw(db);
+  guard(|| {
+    db.log.sync()?;
+    Ok(DuckStatus::Ok)
+  })
+}
+
+/// Stores `value` under `key`.
+///
+/// # Safety
+///
+/// `db` must be an open handle, and `key` and `value` valid for reads of
+/// `key_len` and `value_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_put(
+  db: *const DuckDb,
+  key: *const c_char,
+  key_len: usize,
+  value: *const c_char,
+  value_len: usize,
+) -> DuckStatus {
+  guard(|| {
+    let db = handle(db)?;
+    let key = text(key, key_len, "key")?;
+    let value = text(value, value_len, "value")?;
+    db.log.append(key, value)?;
+    Ok(DuckStatus::Ok)
+  })
+}
+
+/// Looks up `key` and stores a copy of its value in `value` and
+/// `value_len`, to be freed with [`duck_free`]. Returns `DUCK_NOT_FOUND`
+/// and leaves them alone if there is no such key.
+///
+/// # Safety
+///
+/// `db` must be an open handle, `key` valid for reads of `key_len` bytes,
+/// and `value` and `value_len` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_get(
+  db: *const DuckDb,
+  key: *const c_char,
+  key_len: usize,
+  value: *mut *mut c_char,
+  value_len: *mut usize,
+) -> DuckStatus {
+  guard(|| {
+    let db = handle(db)?;
+    let key = text(key, key_len, "key")?;
+    if value.is_null() || value_len.is_null() {
+      return Err(null_argument());
+    }
+    // One call, so a concurrent delete can't turn a miss into an error.
+    match db.log.read(key) {
+      Ok(text) => {
+        give(text, value, value_len);
+        Ok(DuckStatus::Ok)
+      }
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DuckStatus::NotFound),
+      Err(e) => Err(e),
+    }
+  })
+}
+
+/// Deletes `key`, returning `DUCK_NOT_FOUND` if there is no such key.
+///
+/// # Safety
+///
+/// `db` must be an open handle and `key` valid for reads of `key_len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_delete(
+  db: *const DuckDb,
+  key: *const c_char,
+  key_len: usize,
+) -> DuckStatus {
+  guard(|| {
+    let db = handle(db)?;
+    let key = text(key, key_len, "key")?;
+    match db.log.delete(key) {
+      Ok(_) => Ok(DuckStatus::Ok),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DuckStatus::NotFound),
+      Err(e) => Err(e),
+    }
+  })
+}
+
+/// Starts a scan over the keys starting with `prefix` in ascending order.
+/// Compaction waits until the scan is freed, so don't keep it around.
+///
+/// # Safety
+///
+/// `db` must be an open handle that outlives the scan, `prefix` valid for
+/// reads of `prefix_len` bytes and `scan` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_scan(
+  db: *const DuckDb,
+  prefix: *const c_char,
+  prefix_len: usize,
+  scan: *mut *mut DuckScan,
+) -> DuckStatus {
+  guard(|| {
+    let db = handle(db)?;
+    let prefix = text(prefix, prefix_len, "prefix")?;
+    if scan.is_null() {
+      return Err(null_argument());
+    }
+    *scan = Box::into_raw(Box::new(DuckScan {
+      cursor: db.log.cursor(prefix),
+    }));
+    Ok(DuckStatus::Ok)
+  })
+}
+
+/// Moves `scan` to its next entry and hands out copies of its key and
+/// value, each to be freed with [`duck_free`]. Returns `DUCK_DONE` once
+/// there are no more.
+///
+/// # Safety
+///
+/// `scan` must come from [`duck_scan`] and the four out pointers be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn duck_scan_next(
+  scan: *mut DuckScan,
+  key: *mut *mut c_char,
+  key_len: *mut usize,
+  value: *mut *mut c_char,
+  value_len: *mut usize,
+) -> DuckStatus {
+  guard(|| {
+    let scan = scan.as_mut().ok_or_else(null_argument)?;
+    if key.is_null() || key_len.is_null() || value.is_null() || value_len.is_null() {
+      return Err(null_argument());
+    }
+    let Some(entry) = scan.cursor.next() else {
+      return Ok(DuckStatus::Done);
+    };
+    let (next_key, next_value) = entry?;
+    give(next_key, key, key_len);
+    give(next_value, value, value_len);
+    Ok(DuckStatus::Ok)
+  })
+}
+
+/// Releases `scan`. Freeing a null scan does nothing.
+///
+/// # Safety
+///
+/// `scan` must come from [`duck_scan`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn duck_scan_free(scan: *mut DuckScan) {
+  if !scan.is_null() {
+    drop(Box::from_raw(scan));
+  }
+}
+
+/// Frees a buffer handed out by [`duck_get`] or [`duck_scan_next`].
+///
+/// # Safety
+///
+/// `ptr` and `len` must be exactly as they were handed out, and the buffer
+/// not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn duck_free(ptr: *mut c_char, len: usize) {
+  if !ptr.is_null() {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+      ptr.cast::<u8>(),
+      len,
+    )));
+  }
+}
+
+/// Describes the last error returned on the calling thread, or returns
+/// null if there was none. The string stays valid until the next call on
+/// the thread that fails.
+#[no_mangle]
+pub extern "C" fn duck_last_error() -> *const c_char {
+  LAST_ERROR.with(|last| {
+    last
+      .borrow()
+      .as_ref()
+      .map_or(ptr::null(), |message| message.as_ptr())
+  })
+}
+
+/// Runs the body of a call, turning its error or panic into a status and
+/// recording the message for [`duck_last_error`].
+fn guard(body: impl FnOnce() -> Result<DuckStatus, io::Error>) -> DuckStatus {
+  let (status, message) = match panic::catch_unwind(AssertUnwindSafe(body)) {
+    Ok(Ok(status)) => return status,
+    Ok(Err(e)) => (status_of(&e), e.to_string()),
+    Err(_) => (DuckStatus::Panic, "The engine panicked".to_string()),
+  };
+  let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+  LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+  status
+}
+
+fn status_of(error: &io::Error) -> DuckStatus {
+  match DbError::from_io(error) {
+    Some(DbError::AlreadyLocked { .. }) => DuckStatus::Locked,
+    Some(DbError::KeyTooLarge { .. } | DbError::ValueTooLarge { .. }) => DuckStatus::TooLarge,
+    _ => match error.kind() {
+      io::ErrorKind::NotFound => DuckStatus::NotFound,
+      io::ErrorKind::InvalidInput => DuckStatus::InvalidArgument,
+      _ => DuckStatus::IoError,
+    },
+  }
+}
+
+unsafe fn handle<'a>(db: *const DuckDb) -> Result<&'a DuckDb, io::Error> {
+  db.as_ref().ok_or_else(null_argument)
+}
+
+/// The `len` bytes at `ptr` as a string. A null `ptr` is only accepted
+/// with a zero `len`.
+unsafe fn text<'a>(ptr: *const c_char, len: usize, what: &str) -> Result<&'a str, io::Error> {
+  let bytes = match ptr.is_null() {
+    true if len == 0 => &[],
+    true => return Err(null_argument()),
+    false => slice::from_raw_parts(ptr.cast::<u8>(), len),
+  };
+  std::str::from_utf8(bytes).map_err(|_| invalid(&format!("The {what} is not valid UTF-8")))
+}
+
+/// Moves `text` into a buffer the caller frees with [`duck_free`].
+unsafe fn give(text: String, ptr: *mut *mut c_char, len: *mut usize) {
+  let bytes = text.into_bytes().into_boxed_slice();
+  *len = bytes.len();
+  *ptr = Box::into_raw(bytes).cast::<c_char>();
+}
+
+fn null_argument() -> io::Error {
+  invalid("A required pointer is null")
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, message)
+}
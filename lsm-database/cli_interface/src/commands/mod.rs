@@ -0,0 +1,366 @@
+//! Command line parsing.
+//!
+//! Every command opens the database in `./tmp`, does one thing and exits,
+//! so the database can be driven from shell scripts. Values and snapshots
+//! go to stdout and errors to stderr with a non-zero exit status.
+
+use std::{
+  fs::{self, File},
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  net::{TcpListener, TcpStream},
+  path::{Path, PathBuf},
+  process,
+  sync::Arc,
+  thread,
+  time::Duration,
+};
+
+use clap::{Parser, Subcommand};
+use core_engine::log_file::{LogFile, PERIODIC_COMPACTION_INTERVAL};
+use serde_json::{json, Value};
+use ttlog::{
+  event::LogLevel,
+  file_listener::FileListener,
+  stdout_listener::StdoutListener,
+  trace::Trace,
+  ttlog_macros::{error, info},
+};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:4900";
+
+#[derive(Debug, Parser)]
+#[command(version, about = "An embedded log-structured key-value store")]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Command,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+  /// Print the value of KEY.
+  Get { key: String },
+  /// Store VALUE under KEY, reading it from stdin if omitted.
+  Put { key: String, value: Option<String> },
+  /// Delete KEY.
+  Delete { key: String },
+  /// Print the keys starting with PREFIX and their values, tab-separated, in
+  /// key order.
+  Scan {
+    #[arg(default_value = "")]
+    prefix: String,
+    #[arg(long)]
+    limit: Option<usize>,
+  },
+  /// Compact the log and print its size before and after.
+  Compact,
+  /// Print the number of keys and a table of the segments.
+  Stats {
+    /// Print the statistics as JSON instead.
+    #[arg(long)]
+    json: bool,
+  },
+  /// Answer `GET`, `PUT`, `DELETE` and `SCAN` lines over TCP, compacting in
+  /// the background, until the process is killed.
+  Serve {
+    #[arg(long, default_value = DEFAULT_ADDR)]
+    addr: String,
+    /// Seconds between compactions.
+    #[arg(long, default_value_t = PERIODIC_COMPACTION_INTERVAL)]
+    compaction_interval: u64,
+  },
+  /// Write every live key to a JSON lines snapshot.
+  Dump {
+    /// Snapshot file, stdout if omitted.
+    #[arg(long)]
+    out: Option<PathBuf>,
+  },
+  /// Load a snapshot written by `dump` into an empty database.
+  Restore {
+    /// Snapshot file, stdin if omitted.
+    #[arg(long = "in")]
+    input: Option<PathBuf>,
+  },
+}
+
+/// Runs `cli` and exits with 1 if it fails.
+pub fn main(cli: Cli) {
+  match run(cli.command) {
+    // The reader of our output went away, as `head` does.
+    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {},
+    Err(e) => {
+      eprintln!("error: {e}");
+      process::exit(1);
+    },
+    Ok(()) => {},
+  }
+}
+
+fn run(command: Command) -> Result<(), io::Error> {
+  // Only the server logs to stdout, where it doesn't get in the way of
+  // values and snapshots.
+  fs::create_dir_all("./tmp")?;
+  let trace = Trace::init(2, 64, "lsm", Some("./tmp"));
+  trace.add_listener(Arc::new(FileListener::new("./tmp/ttlog.log")?));
+  if let Command::Serve { .. } = command {
+    trace.add_listener(Arc::new(StdoutListener::new()));
+    trace.set_level(LogLevel::INFO);
+  } else {
+    trace.set_level(LogLevel::TRACE);
+  }
+
+  let log = LogFile::new()?;
+  log.start()?;
+
+  let mut stdout = io::stdout().lock();
+  match command {
+    Command::Get { key } => writeln!(stdout, "{}", get(&log, &key)?)?,
+    Command::Put { key, value } => {
+      let value = match value {
+        Some(value) => value,
+        None => {
+          let mut value = String::new();
+          io::stdin().read_to_string(&mut value)?;
+          value
+        },
+      };
+      put(&log, &key, &value)?;
+    },
+    Command::Delete { key } => {
+      get(&log, &key)?;
+      log.delete(&key)?;
+    },
+    Command::Scan { prefix, limit } => {
+      for (key, value) in scan(&log, &prefix).take(limit.unwrap_or(usize::MAX)) {
+        writeln!(stdout, "{key}\t{}", value?)?;
+      }
+    },
+    Command::Compact => {
+      let before = size(&log)?;
+      log.compact()?;
+      writeln!(stdout, "Compacted {before} bytes to {}", size(&log)?)?;
+    },
+    Command::Stats { json: false } => {
+      writeln!(stdout, "keys      {}", log.len())?;
+      writeln!(stdout, "segment   size")?;
+      for (file_id, size) in log.segments()? {
+        writeln!(stdout, "{file_id:<9} {size}")?;
+      }
+    },
+    Command::Stats { json: true } => {
+      let segments = log
+        .segments()?
+        .into_iter()
+        .map(|(file_id, size)| json!({ "file_id": file_id, "size": size }))
+        .collect::<Vec<_>>();
+      let stats = json!({ "keys": log.len(), "segments": segments });
+      serde_json::to_writer_pretty(&mut stdout, &stats)?;
+      writeln!(stdout)?;
+    },
+    Command::Serve {
+      addr,
+      compaction_interval,
+    } => {
+      drop(stdout);
+      serve(&log, &addr, compaction_interval)?;
+    },
+    Command::Dump { out } => {
+      let count = match out {
+        Some(out) => dump_to(&log, &out)?,
+        None => dump(&log, &mut stdout)?,
+      };
+      eprintln!("Dumped {count} records");
+    },
+    Command::Restore { input } => {
+      // Restoring on top of existing keys would mix two databases.
+      if !log.is_empty() {
+        return Err(io::Error::new(
+          io::ErrorKind::AlreadyExists,
+          format!(
+            "The database already holds {} keys, restore into an empty one",
+            log.len()
+          ),
+        ));
+      }
+      let reader: Box<dyn BufRead> = match input {
+        Some(input) => Box::new(BufReader::new(File::open(input)?)),
+        None => Box::new(io::stdin().lock()),
+      };
+      eprintln!("Restored {} records", restore(&log, reader)?);
+    },
+  }
+  Ok(())
+}
+
+fn get(log: &LogFile, key: &str) -> Result<String, io::Error> {
+  if !log.contains_key(key) {
+    return Err(io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("No such key: {key}"),
+    ));
+  }
+  log.read(key)
+}
+
+/// Stores `value`, refusing an empty one, which the log would read back as
+/// a delete.
+fn put(log: &LogFile, key: &str, value: &str) -> Result<(), io::Error> {
+  if value.is_empty() {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "The value must not be empty",
+    ));
+  }
+  log.append(key, value)?;
+  Ok(())
+}
+
+/// The keys starting with `prefix` in key order, each with its value.
+fn scan<'a>(
+  log: &'a LogFile,
+  prefix: &'a str,
+) -> impl Iterator<Item = (String, Result<String, io::Error>)> + 'a {
+  log
+    .sorted_keys()
+    .into_iter()
+    .filter(move |key| key.starts_with(prefix))
+    .map(|key| {
+      let value = log.read(&key);
+      (key, value)
+    })
+}
+
+fn size(log: &LogFile) -> Result<u64, io::Error> {
+  Ok(log.segments()?.iter().map(|&(_, size)| size).sum())
+}
+
+/// Writes one `{"key": …, "value": …}` object per line.
+fn dump(log: &LogFile, writer: &mut impl Write) -> Result<usize, io::Error> {
+  let mut count = 0;
+  for (key, value) in scan(log, "") {
+    serde_json::to_writer(&mut *writer, &json!({ "key": key, "value": value? }))?;
+    writeln!(writer)?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// Writes the snapshot to a temporary file next to `out` and renames it into
+/// place once complete, so `out` is never a partial snapshot.
+fn dump_to(log: &LogFile, out: &Path) -> Result<usize, io::Error> {
+  let mut partial = out.as_os_str().to_owned();
+  partial.push(".partial");
+  let partial = PathBuf::from(partial);
+
+  let dumped = File::create(&partial).and_then(|file| {
+    let mut writer = BufWriter::new(file);
+    let count = dump(log, &mut writer)?;
+    writer.into_inner()?.sync_all()?;
+    Ok(count)
+  });
+  match dumped {
+    Ok(count) => {
+      fs::rename(&partial, out)?;
+      Ok(count)
+    },
+    Err(e) => {
+      let _ = fs::remove_file(&partial);
+      Err(e)
+    },
+  }
+}
+
+/// Loads every line of a snapshot, failing on the first malformed one.
+fn restore(log: &LogFile, reader: impl BufRead) -> Result<usize, io::Error> {
+  let mut count = 0;
+  for (number, line) in reader.lines().enumerate() {
+    let line = line?;
+    if line.trim().is_empty() {
+      continue;
+    }
+    let record = serde_json::from_str::<Value>(&line).ok();
+    let entry = record
+      .as_ref()
+      .and_then(|record| Some((record["key"].as_str()?, record["value"].as_str()?)));
+    let Some((key, value)) = entry else {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Line {}: expected {{\"key\": …, \"value\": …}}", number + 1),
+      ));
+    };
+    put(log, key, value)?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// Serves `log` on `addr`, one thread per connection, and compacts it every
+/// `compaction_interval` seconds.
+fn serve(log: &LogFile, addr: &str, compaction_interval: u64) -> Result<(), io::Error> {
+  let listener = TcpListener::bind(addr)?;
+  info!(
+    "[SERVE] Ready.",
+    addr = listener.local_addr()?.to_string(),
+    keys = log.len() as u64
+  );
+
+  let compacting = log.clone();
+  thread::spawn(move || loop {
+    thread::sleep(Duration::from_secs(compaction_interval));
+    if let Err(e) = compacting.compact() {
+      error!("[SERVE] Compaction failed.", error = e.to_string());
+    }
+  });
+
+  for stream in listener.incoming() {
+    let stream = stream?;
+    let log = log.clone();
+    thread::spawn(move || {
+      if let Err(e) = handle(&log, stream) {
+        error!("[SERVE] Connection failed.", error = e.to_string());
+      }
+    });
+  }
+  Ok(())
+}
+
+/// Answers one request per line:
+///
+/// - `GET key`: `OK value`, or `NOT_FOUND`.
+/// - `PUT key value`: `OK`. The value is the rest of the line.
+/// - `DELETE key`: `OK`, or `NOT_FOUND`.
+/// - `SCAN [prefix]`: a `key<TAB>value` line per key, then `END`.
+///
+/// Anything that fails is answered with `ERR message`.
+fn handle(log: &LogFile, stream: TcpStream) -> Result<(), io::Error> {
+  let reader = BufReader::new(stream.try_clone()?);
+  let mut writer = BufWriter::new(stream);
+  for line in reader.lines() {
+    let line = line?;
+    let (command, args) = line.split_once(' ').unwrap_or((&line, ""));
+    let response = match command.to_ascii_uppercase().as_str() {
+      "GET" => get(log, args).map(|value| format!("OK {value}")),
+      "PUT" => {
+        let (key, value) = args.split_once(' ').unwrap_or((args, ""));
+        put(log, key, value).map(|()| "OK".to_string())
+      },
+      "DELETE" => get(log, args)
+        .and_then(|_| log.delete(args))
+        .map(|_| "OK".to_string()),
+      "SCAN" => scan(log, args)
+        .map(|(key, value)| Ok(format!("{key}\t{}\n", value?)))
+        .collect::<Result<String, io::Error>>()
+        .map(|entries| format!("{entries}END")),
+      _ => Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unknown command: {command}"),
+      )),
+    };
+    match response {
+      Ok(response) => writeln!(writer, "{response}")?,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => writeln!(writer, "NOT_FOUND")?,
+      Err(e) => writeln!(writer, "ERR {e}")?,
+    }
+    writer.flush()?;
+  }
+  Ok(())
+}
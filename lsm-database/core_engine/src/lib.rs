@@ -1,5 +1,7 @@
 // mod binary_search;
 // mod binary_tree;
 // mod linear_search;
-// pub mod log_file;
-// pub mod memtable;
+pub mod log_file;
+pub mod memtable;
+pub mod options;
+mod syncer;
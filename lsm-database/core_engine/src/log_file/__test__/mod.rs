@@ -0,0 +1,220 @@
+#[cfg(test)]
+mod log_file_test {
+  use crate::log_file::*;
+
+  /// A file holding `bytes`, unique to `name`.
+  fn temp_file(name: &str, bytes: &[u8]) -> File {
+    let path = std::env::temp_dir().join(format!("lsm-{name}-{}", std::process::id()));
+    fs::write(&path, bytes).unwrap();
+    File::open(path).unwrap()
+  }
+
+  /// A log appending to a fresh segment unique to `name`, instead of the
+  /// `./tmp` directory `start` works in.
+  fn temp_log(name: &str) -> LogFile {
+    temp_log_with(name, Options::default())
+  }
+
+  fn temp_log_with(name: &str, options: Options) -> LogFile {
+    let path = std::env::temp_dir().join(format!("lsm-{name}-{}", std::process::id()));
+    fs::write(&path, b"").unwrap();
+    let path = path.to_str().unwrap().to_string();
+
+    let log = LogFile::with_options(options).unwrap();
+    {
+      let mut inner = log.inner.lock().unwrap();
+      let file_id = inner.current_file_id;
+      log
+        .keydir
+        .write()
+        .unwrap()
+        .file_index
+        .insert(file_id, path.clone());
+      inner.path = path;
+      inner.byte_offset = 0;
+    }
+    log
+  }
+
+  fn record(key: &str, value: &str) -> MetaIndex {
+    MetaIndex {
+      timestamp: 42,
+      key_size: key.len(),
+      key_buf: key.as_bytes().to_vec(),
+      value_size: value.len(),
+      value_buf: value.as_bytes().to_vec(),
+    }
+  }
+
+  // ---------------------------------------------------------
+  // record tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn records_round_trip() {
+    let first = record("123:1", "first");
+    let second = record("123:2", "");
+    let mut bytes = first.to_bytes();
+    assert_eq!(bytes.len() as u64, first.len());
+    bytes.extend(second.to_bytes());
+    let file = temp_file("round-trip", &bytes);
+
+    let log = LogFile::new().unwrap();
+    let mut offset = 0;
+    let read = log.get_index_from_file(&mut offset, &file).unwrap();
+    assert_eq!(offset, first.len());
+    assert_eq!(read.timestamp, 42);
+    assert_eq!(read.key_buf, b"123:1");
+    assert_eq!(read.value_buf, b"first");
+
+    let read = log.get_index_from_file(&mut offset, &file).unwrap();
+    assert_eq!(offset, bytes.len() as u64);
+    assert_eq!(read.key_buf, b"123:2");
+    assert!(read.value_buf.is_empty());
+  }
+
+  #[test]
+  fn rejects_a_size_past_the_file() {
+    let mut bytes = record("key", "value").to_bytes();
+    bytes.truncate(bytes.len() - 1);
+    let file = temp_file("cut-short", &bytes);
+
+    let err = LogFile::new()
+      .unwrap()
+      .get_index_from_file(&mut 0, &file)
+      .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+  }
+
+  #[test]
+  fn update_advances_the_offset_by_the_whole_record() {
+    let log = temp_log("update");
+    log.append("a", "1").unwrap();
+    log.update("a", "22").unwrap();
+    log.append("b", "333").unwrap();
+
+    // The record written after the update is indexed where it starts.
+    assert_eq!(log.read("a").unwrap(), "22");
+    assert_eq!(log.read("b").unwrap(), "333");
+    let inner = log.inner.lock().unwrap();
+    assert_eq!(inner.byte_offset, fs::metadata(&inner.path).unwrap().len());
+  }
+
+  #[test]
+  fn lists_live_keys_and_segments() {
+    let log = temp_log("listing");
+    assert!(log.is_empty());
+    for key in ["b", "c", "a"] {
+      log.append(key, "value").unwrap();
+    }
+    log.delete("c").unwrap();
+
+    assert_eq!(log.sorted_keys(), ["a", "b"]);
+    assert_eq!(log.len(), 2);
+    assert!(log.contains_key("a") && !log.contains_key("c"));
+    let inner = log.inner.lock().unwrap();
+    assert_eq!(log.segments().unwrap(), [(1, inner.byte_offset)]);
+  }
+
+  #[test]
+  fn reads_do_not_wait_for_a_writer() {
+    let log = temp_log("concurrent-read");
+    log.append("a", "1").unwrap();
+
+    // Held the way a writer holds it through a slow fsync.
+    let writer = log.inner.lock().unwrap();
+    let (done, read) = std::sync::mpsc::channel();
+    let reader = log.clone();
+    std::thread::spawn(move || done.send(reader.read("a").unwrap()).unwrap());
+    let value = read.recv_timeout(std::time::Duration::from_secs(5));
+    drop(writer);
+    assert_eq!(value.expect("the read waited for the writer"), "1");
+  }
+
+  // ---------------------------------------------------------
+  // sync tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn only_always_syncs_every_write() {
+    for (sync_policy, unsynced) in [
+      (SyncPolicy::Always, false),
+      (SyncPolicy::Interval(Duration::from_secs(60)), true),
+      (SyncPolicy::Os, false),
+    ] {
+      let log = temp_log_with(&format!("{sync_policy:?}"), Options { sync_policy });
+      log.append("a", "1").unwrap();
+      assert_eq!(log.inner.lock().unwrap().unsynced, unsynced);
+      assert_eq!(log.read("a").unwrap(), "1");
+    }
+  }
+
+  #[test]
+  fn interval_syncs_in_the_background() {
+    let interval = Duration::from_millis(10);
+    let log = temp_log_with(
+      "interval",
+      Options {
+        sync_policy: SyncPolicy::Interval(interval),
+      },
+    );
+    log.append("a", "1").unwrap();
+    let _syncer = log.spawn_syncer(interval);
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while log.inner.lock().unwrap().unsynced {
+      assert!(
+        std::time::Instant::now() < deadline,
+        "the write was never synced"
+      );
+      std::thread::sleep(interval);
+    }
+  }
+
+  // ---------------------------------------------------------
+  // hint tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn hint_entries_round_trip() {
+    let mut data_index = HashMap::new();
+    data_index.insert(
+      "a".to_string(),
+      Index {
+        file_id: 1,
+        offset: 0,
+      },
+    );
+    data_index.insert(
+      "bb".to_string(),
+      Index {
+        file_id: 3,
+        offset: 96,
+      },
+    );
+
+    let mut decoded = HashMap::new();
+    decode_hint(&encode_hint(&data_index), &mut decoded).unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded["a"].file_id, 1);
+    assert_eq!(decoded["a"].offset, 0);
+    assert_eq!(decoded["bb"].file_id, 3);
+    assert_eq!(decoded["bb"].offset, 96);
+  }
+
+  #[test]
+  fn truncated_hint_fails() {
+    let mut data_index = HashMap::new();
+    data_index.insert(
+      "a".to_string(),
+      Index {
+        file_id: 1,
+        offset: 0,
+      },
+    );
+    let hint = encode_hint(&data_index);
+
+    let err = decode_hint(&hint[..hint.len() - 1], &mut HashMap::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+  }
+}
@@ -3,14 +3,23 @@ use std::{
   fs::{self, File, OpenOptions},
   io::{self, Write},
   os::unix::fs::{FileExt, MetadataExt},
-  sync::{Arc, Mutex, MutexGuard},
+  sync::{Arc, Mutex, OnceLock, RwLock},
+  time::Duration,
 };
 
 use chrono::Utc;
-use serde;
 use ttlog::ttlog_macros::{error, info, trace};
+use utils::codec::{RecordReader, RecordWriter};
+
+use crate::{
+  options::{Options, SyncPolicy},
+  syncer::Syncer,
+};
+
+mod __test__;
 
 const FILE_THRESHOLD: u64 = 1024; // 1KB
+const HEADER_SIZE: u64 = 8 * 3; // timestamp, key size, value size
 pub const PERIODIC_COMPACTION_INTERVAL: u64 = 60 * 10; // 10 minutes
 
 #[derive(Debug)]
@@ -22,100 +31,101 @@ struct MetaIndex {
   value_buf: Vec<u8>,
 }
 
+impl MetaIndex {
+  /// Size of the record on disk.
+  fn len(&self) -> u64 {
+    HEADER_SIZE + (self.key_size + self.value_size) as u64
+  }
+
+  /// Encodes the record: ts, key_size, value_size, key, value.
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut record = RecordWriter::with_capacity(self.len() as usize);
+    record.put_i64(self.timestamp);
+    record.put_u64(self.key_size as u64);
+    record.put_u64(self.value_size as u64);
+    record.put_bytes(&self.key_buf);
+    record.put_bytes(&self.value_buf);
+    record.into_bytes()
+  }
+}
+
 #[derive(Debug)]
 struct Index {
   file_id: u64,
   offset: u64,
 }
 
+/// Writers take `inner` for a whole append, fsync included, and only take
+/// `keydir` exclusively to publish a record once it is on disk. Readers
+/// only ever share `keydir`, so they don't wait on each other or on a
+/// write in progress. Locks are taken in that order.
 #[derive(Debug, Clone)]
 pub struct LogFile {
   inner: Arc<Mutex<Inner>>,
+  keydir: Arc<RwLock<KeyDir>>,
+  options: Options,
+  /// Runs while the log does under `SyncPolicy::Interval`.
+  syncer: Arc<OnceLock<Syncer>>,
 }
 
+/// State owned by writers: where the next record goes.
 #[derive(Debug)]
 struct Inner {
   byte_offset: u64,
   current_file_id: u64,
   path: String,
+  /// Whether the active segment has writes that weren't fsynced yet.
+  unsynced: bool,
+}
+
+/// Where the latest record of every key is, and the segments they are in.
+#[derive(Debug, Default)]
+struct KeyDir {
   data_index: HashMap<String, Index>,
   file_index: HashMap<u64, String>,
 }
 
 impl LogFile {
   pub fn new() -> Result<Self, std::io::Error> {
+    Self::with_options(Options::default())
+  }
+
+  pub fn with_options(options: Options) -> Result<Self, std::io::Error> {
     Ok(Self {
       inner: Arc::new(Mutex::new(Inner {
         path: "".to_string(),
         byte_offset: 0x1,
         current_file_id: 0x1,
-        data_index: HashMap::new(),
-        file_index: HashMap::new(),
+        unsynced: false,
       })),
+      keydir: Arc::new(RwLock::new(KeyDir::default())),
+      options,
+      syncer: Arc::new(OnceLock::new()),
     })
   }
 
-  fn read_hint_file(&self, inner: &mut MutexGuard<'_, Inner>) -> Result<(), std::io::Error> {
-    let path = format!("./tmp/hint-{}", inner.current_file_id);
+  fn read_hint_file(&self, file_id: u64, keydir: &mut KeyDir) -> Result<(), std::io::Error> {
+    let path = format!("./tmp/hint-{file_id}");
     if !fs::exists(&path)? {
       return Ok(());
     }
 
-    let hint_file = OpenOptions::new().read(true).open(&path)?;
-    let mut offset = 0;
-
-    loop {
-      if fs::metadata(&path)?.size() <= offset {
-        break;
-      }
-
-      let mut key_size_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut key_size_buf, offset)?;
-      let key_size = u64::from_le_bytes(key_size_buf);
-      offset += 8;
-
-      let mut key_buf = vec![0u8; key_size as usize];
-      hint_file.read_exact_at(&mut key_buf, offset)?;
-      let key_value = String::from_utf8(key_buf.clone()).unwrap();
-      offset += key_size;
-
-      // adding because here we read the timestamp
-      offset += 8;
-
-      let mut file_id_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut file_id_buf, offset)?;
-      let file_id = u64::from_le_bytes(file_id_buf);
-      offset += 8;
-
-      let mut offset_buf = [0u8; 8];
-      hint_file.read_exact_at(&mut offset_buf, offset)?;
-      let offset_value = u64::from_le_bytes(offset_buf);
-      offset += 8;
-
-      inner.data_index.insert(
-        key_value,
-        Index {
-          offset: offset_value,
-          file_id,
-        },
-      );
-    }
-
-    Ok(())
+    decode_hint(&fs::read(&path)?, &mut keydir.data_index)
   }
 
   pub fn start(&self) -> Result<(), std::io::Error> {
     fs::create_dir_all("tmp")?;
+    let mut inner = self.inner.lock().unwrap();
 
     // rebuild index from hint
     {
-      let mut inner = self.inner.lock().unwrap();
-      self.read_hint_file(&mut inner)?;
+      let mut keydir = self.keydir.write().unwrap();
+      self.read_hint_file(inner.current_file_id, &mut keydir)?;
     }
 
     // rebuild from log files
     {
-      let mut inner = self.inner.lock().unwrap();
+      let mut keydir = self.keydir.write().unwrap();
 
       let mut files = fs::read_dir("./tmp")?
         .filter_map(|entry| entry.ok())
@@ -156,7 +166,7 @@ impl LogFile {
           .unwrap();
         let metadata = fs::metadata(file_path)?;
 
-        inner
+        keydir
           .file_index
           .insert(file_id, file_path.to_str().unwrap().to_string());
 
@@ -176,9 +186,9 @@ impl LogFile {
 
           let key = String::from_utf8(meta.key_buf.clone()).unwrap();
           if meta.value_buf.is_empty() {
-            inner.data_index.remove(&key);
+            keydir.data_index.remove(&key);
           } else {
-            inner.data_index.insert(key, index);
+            keydir.data_index.insert(key, index);
           }
         }
       }
@@ -201,21 +211,53 @@ impl LogFile {
       inner.current_file_id = id + 1;
     }
 
-    // we drop the lock BEFORE calling create()
-    self.create()?;
+    self.create(&mut inner)?;
 
+    if let SyncPolicy::Interval(interval) = self.options.sync_policy {
+      self.syncer.get_or_init(|| self.spawn_syncer(interval));
+    }
     Ok(())
   }
 
-  fn create(&self) -> Result<(), std::io::Error> {
-    let mut inner = self.inner.lock().unwrap();
+  /// Starts the `SyncPolicy::Interval` thread. It only holds a weak handle
+  /// on the writer state and stops once the log is gone.
+  fn spawn_syncer(&self, interval: Duration) -> Syncer {
+    let inner = Arc::downgrade(&self.inner);
+    Syncer::spawn(interval, move || {
+      let Some(inner) = inner.upgrade() else {
+        return false;
+      };
+      let mut inner = inner.lock().unwrap();
+      if let Err(e) = Self::sync_active(&mut inner) {
+        error!(
+          "[SYNC] Syncing the active segment failed.",
+          error = e.to_string()
+        );
+      }
+      true
+    })
+  }
+
+  /// Fsyncs the active segment if it has writes that aren't yet.
+  fn sync_active(inner: &mut Inner) -> Result<(), io::Error> {
+    if inner.unsynced {
+      OpenOptions::new()
+        .append(true)
+        .open(&inner.path)?
+        .sync_all()?;
+      inner.unsynced = false;
+    }
+    Ok(())
+  }
+
+  fn create(&self, inner: &mut Inner) -> Result<(), std::io::Error> {
     let path = format!("./tmp/log-file-{}", inner.current_file_id);
 
     OpenOptions::new().create(true).append(true).open(&path)?;
     inner.path = path;
     let path = inner.path.clone();
     let id = inner.current_file_id;
-    inner.file_index.insert(id, path);
+    self.keydir.write().unwrap().file_index.insert(id, path);
     inner.byte_offset = 0;
 
     trace!(
@@ -232,15 +274,6 @@ impl LogFile {
       return Err(io::Error::other(""));
     }
 
-    let data_size = (value.len() + key.len() + 8 * 3) as u64;
-    let index_value = Index {
-      offset: inner.byte_offset,
-      file_id: inner.current_file_id,
-    };
-
-    inner.data_index.insert(key.to_string(), index_value);
-    inner.byte_offset += data_size;
-
     let timestamp = Utc::now().timestamp_nanos_opt().unwrap();
 
     self.insert_index_value(
@@ -259,10 +292,6 @@ impl LogFile {
   }
 
   pub fn read(&self, id: &str) -> Result<String, io::Error> {
-    if !self.inner.lock().unwrap().data_index.contains_key(id) {
-      return Err(io::Error::other("This key does not exist in the index"));
-    }
-
     let index = self.get_index_value(id)?;
 
     // let timestamp = Utc.timestamp_opt(index.timestamp, 0);
@@ -273,6 +302,45 @@ impl LogFile {
     Ok(value)
   }
 
+  pub fn contains_key(&self, key: &str) -> bool {
+    self.keydir.read().unwrap().data_index.contains_key(key)
+  }
+
+  /// Number of live keys.
+  pub fn len(&self) -> usize {
+    self.keydir.read().unwrap().data_index.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Every live key, in ascending order.
+  pub fn sorted_keys(&self) -> Vec<String> {
+    let mut keys = self
+      .keydir
+      .read()
+      .unwrap()
+      .data_index
+      .keys()
+      .cloned()
+      .collect::<Vec<_>>();
+    keys.sort();
+    keys
+  }
+
+  /// The id and size in bytes of every segment, oldest first.
+  pub fn segments(&self) -> Result<Vec<(u64, u64)>, io::Error> {
+    let keydir = self.keydir.read().unwrap();
+    let mut segments = keydir
+      .file_index
+      .iter()
+      .map(|(&file_id, path)| Ok((file_id, fs::metadata(path)?.len())))
+      .collect::<Result<Vec<_>, io::Error>>()?;
+    segments.sort();
+    Ok(segments)
+  }
+
   pub fn update(&self, key: &str, value: &str) -> Result<String, io::Error> {
     let mut inner = self.inner.lock().unwrap();
     if key.is_empty() {
@@ -280,20 +348,12 @@ impl LogFile {
       return Err(io::Error::other(""));
     }
 
-    if !inner.data_index.contains_key(key) {
+    // Writers are serialized by `inner`, so the key can't go away before
+    // the update is written.
+    if !self.keydir.read().unwrap().data_index.contains_key(key) {
       return Err(io::Error::other("This key does not exist in the index"));
     }
 
-    let index_value = Index {
-      offset: inner.byte_offset,
-      file_id: inner.current_file_id,
-    };
-
-    let data_size = (value.len() + key.len() + 8 * 2) as u64;
-
-    inner.data_index.insert(key.to_string(), index_value);
-    inner.byte_offset += data_size;
-
     let timestamp = Utc::now().timestamp();
 
     // drop(inner);
@@ -322,26 +382,25 @@ impl LogFile {
     index.value_size = 0;
     index.value_buf.clear();
     self.insert_index_value(index, &mut inner)?;
-    inner.data_index.remove(id);
 
     info!("[DELETE]", key = id.to_string(), value = value);
     Ok(value.to_string())
   }
 
+  /// Rewrites the live records into a single segment. Writers wait until it
+  /// is done; readers keep reading the old segments until the new one
+  /// replaces them.
   pub fn compact(&self) -> Result<(), io::Error> {
-    let new_hash = std::mem::take(&mut self.inner.lock().unwrap().file_index);
+    let mut inner = self.inner.lock().unwrap();
+    let file_index = self.keydir.read().unwrap().file_index.clone();
     let mut end_file = HashMap::<String, MetaIndex>::new();
-    let mut sorted_file_ids = new_hash.keys().collect::<Vec<_>>();
+    let mut sorted_file_ids = file_index.keys().collect::<Vec<_>>();
     sorted_file_ids.sort();
 
-    for &file_id in sorted_file_ids {
-      let file_idx = new_hash.get(&file_id).unwrap();
-      self.compact_file(&mut end_file, file_idx)?;
+    for file_id in sorted_file_ids {
+      self.compact_file(&mut end_file, &file_index[file_id])?;
     }
 
-    let mut inner = self.inner.lock().unwrap();
-    let _ = core::mem::replace(&mut inner.file_index, new_hash);
-
     let temp_file_path = format!(
       "./tmp/temp-log-file-{}",
       Utc::now().timestamp_nanos_opt().unwrap()
@@ -351,19 +410,14 @@ impl LogFile {
     let mut offset = 0;
     let mut final_data_index = HashMap::<String, Index>::new();
 
-    // Keep record layout identical to append: ts, key_size, value_size, key, value.
     for (key, value) in end_file.into_iter() {
       final_data_index.insert(key, Index { offset, file_id: 1 });
 
-      temp_file.write_all(&value.timestamp.to_le_bytes())?;
-      temp_file.write_all(&value.key_size.to_le_bytes())?;
-      temp_file.write_all(&value.value_size.to_le_bytes())?;
-      temp_file.write_all(&value.key_buf)?;
-      temp_file.write_all(&value.value_buf)?;
+      temp_file.write_all(&value.to_bytes())?;
 
       // CRASH SAFETY HERE
       temp_file.sync_all()?; // durability guarantee
-      offset += (value.key_size + value.value_size) as u64 + 8 * 3;
+      offset += value.len();
     }
 
     temp_file.flush()?;
@@ -371,39 +425,37 @@ impl LogFile {
     inner.current_file_id = 1;
     let path = format!("./tmp/log-file-{}", inner.current_file_id);
 
+    // Readers open a segment while they share the keydir, so none is left
+    // pointing at a file once the old ones are gone.
+    let mut keydir = self.keydir.write().unwrap();
+
     // Clear the index file and remove the old files
-    for (_, path) in inner.file_index.iter() {
+    for (_, path) in keydir.file_index.iter() {
       fs::remove_file(path)?;
     }
-    inner.file_index.clear();
+    keydir.file_index.clear();
 
     drop(temp_file);
     fs::rename(&temp_file_path, &path)?;
 
     let current_file_id = inner.current_file_id;
     inner.path = path.clone();
-    inner.file_index.insert(current_file_id, path);
-    inner.data_index = final_data_index;
+    inner.byte_offset = offset;
+    // Everything unsynced was copied into the synced output.
+    inner.unsynced = false;
+    keydir.file_index.insert(current_file_id, path);
+    keydir.data_index = final_data_index;
+    drop(keydir);
     info!("[COMPACT] Compaction has been completed successfully.");
 
-    drop(inner);
-    self.write_hint_file()?;
+    self.write_hint_file(&inner)?;
     Ok(())
   }
 
-  fn write_hint_file(&self) -> Result<(), io::Error> {
-    let inner = self.inner.lock().unwrap();
+  fn write_hint_file(&self, inner: &Inner) -> Result<(), io::Error> {
     let path = format!("./tmp/hint-{}", inner.current_file_id);
     let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
-
-    for (key, value) in inner.data_index.iter() {
-      let timestamp = Utc::now().timestamp();
-      file.write_all(&key.len().to_le_bytes())?;
-      file.write_all(key.as_bytes())?;
-      file.write_all(&timestamp.to_le_bytes())?;
-      file.write_all(&value.file_id.to_le_bytes())?;
-      file.write_all(&value.offset.to_le_bytes())?;
-    }
+    file.write_all(&encode_hint(&self.keydir.read().unwrap().data_index))?;
 
     info!("[HINT] Hint file has been written successfully.");
 
@@ -438,21 +490,34 @@ impl LogFile {
     Ok(())
   }
 
-  fn insert_index_value(
-    &self,
-    meta: MetaIndex,
-    inner: &mut MutexGuard<'_, Inner>,
-  ) -> Result<(), io::Error> {
+  /// Appends `meta` to the active segment, syncing it as
+  /// `Options::sync_policy` asks, then points the keydir at it, or drops the
+  /// key for a record without a value.
+  fn insert_index_value(&self, meta: MetaIndex, inner: &mut Inner) -> Result<(), io::Error> {
+    let index = Index {
+      offset: inner.byte_offset,
+      file_id: inner.current_file_id,
+    };
     let mut file = OpenOptions::new().append(true).open(&inner.path)?;
 
-    file.write_all(&meta.timestamp.to_le_bytes())?;
-    file.write_all(&meta.key_size.to_le_bytes())?;
-    file.write_all(&meta.value_size.to_le_bytes())?;
-    file.write_all(&meta.key_buf)?;
-    file.write_all(&meta.value_buf)?;
+    file.write_all(&meta.to_bytes())?;
 
     // CRASH SAFETY HERE
-    file.sync_all()?; // durability guarantee
+    match self.options.sync_policy {
+      SyncPolicy::Always => file.sync_all()?, // durability guarantee
+      SyncPolicy::Interval(_) => inner.unsynced = true,
+      SyncPolicy::Os => {},
+    }
+    inner.byte_offset += meta.len();
+
+    let key = String::from_utf8(meta.key_buf).unwrap();
+    let mut keydir = self.keydir.write().unwrap();
+    if meta.value_buf.is_empty() {
+      keydir.data_index.remove(&key);
+    } else {
+      keydir.data_index.insert(key, index);
+    }
+    drop(keydir);
 
     // FILE SEGMENTATION HERE
     self.split(inner)?;
@@ -461,34 +526,27 @@ impl LogFile {
   }
 
   fn get_index_value(&self, id: &str) -> Result<MetaIndex, io::Error> {
-    let inner = self.inner.lock().unwrap();
-    if !inner.data_index.contains_key(id) {
-      return Err(io::Error::other(""));
-    }
+    let keydir = self.keydir.read().unwrap();
+    let Some(index) = keydir.data_index.get(id) else {
+      return Err(io::Error::other("This key does not exist in the index"));
+    };
 
-    let index = inner.data_index.get(id).unwrap();
-    let file = File::open(inner.file_index.get(&index.file_id).unwrap())?;
+    // Opened while the keydir is shared so compaction can't remove it first.
+    let file = File::open(&keydir.file_index[&index.file_id])?;
     let mut offset = index.offset;
 
-    drop(inner);
+    drop(keydir);
     self.get_index_from_file(&mut offset, &file)
   }
 
   fn get_index_from_file(&self, offset: &mut u64, file: &File) -> Result<MetaIndex, io::Error> {
-    let mut ts_buff = [0u8; 8];
-    file.read_exact_at(&mut ts_buff, *offset)?;
-    let timestamp = i64::from_le_bytes(ts_buff);
-    *offset += 8;
-
-    let mut key_size_buf = [0u8; 8];
-    file.read_exact_at(&mut key_size_buf, *offset)?;
-    let key_size = u64::from_le_bytes(key_size_buf) as usize;
-    *offset += 8;
-
-    let mut value_size_buf = [0u8; 8];
-    file.read_exact_at(&mut value_size_buf, *offset)?;
-    let value_size = u64::from_le_bytes(value_size_buf) as usize;
-    *offset += 8;
+    let mut header = [0u8; HEADER_SIZE as usize];
+    file.read_exact_at(&mut header, *offset)?;
+    let mut fields = RecordReader::new(&header);
+    let timestamp = fields.get_i64()?;
+    let key_size = fields.get_u64()? as usize;
+    let value_size = fields.get_u64()? as usize;
+    *offset += HEADER_SIZE;
 
     let file_size = file.metadata()?.size();
     if *offset + key_size as u64 + value_size as u64 > file_size {
@@ -515,7 +573,7 @@ impl LogFile {
     })
   }
 
-  fn split(&self, inner: &mut MutexGuard<'_, Inner>) -> Result<(), io::Error> {
+  fn split(&self, inner: &mut Inner) -> Result<(), io::Error> {
     let metadata = fs::metadata(&inner.path)?;
 
     if metadata.size() > FILE_THRESHOLD {
@@ -525,9 +583,48 @@ impl LogFile {
         file_size = metadata.size()
       );
 
+      // The syncer only knows the active segment, so this one is synced
+      // before it stops being that.
+      Self::sync_active(inner)?;
       inner.current_file_id += 1;
-      self.create()?;
+      self.create(inner)?;
     }
     Ok(())
   }
 }
+
+/// Encodes a hint entry per key: key_size, key, ts, file_id, offset.
+fn encode_hint(data_index: &HashMap<String, Index>) -> Vec<u8> {
+  let mut hint = RecordWriter::new();
+  let timestamp = Utc::now().timestamp();
+  for (key, value) in data_index.iter() {
+    hint.put_prefixed(key.as_bytes());
+    hint.put_i64(timestamp);
+    hint.put_u64(value.file_id);
+    hint.put_u64(value.offset);
+  }
+  hint.into_bytes()
+}
+
+/// Adds the entries [`encode_hint`] wrote to `data_index`; the ts isn't
+/// needed.
+fn decode_hint(buf: &[u8], data_index: &mut HashMap<String, Index>) -> Result<(), io::Error> {
+  let mut reader = RecordReader::new(buf);
+  while !reader.is_empty() {
+    let key_value = String::from_utf8(reader.get_prefixed(usize::MAX)?.to_vec())
+      .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Corrupted hint key"))?;
+    reader.get_i64()?;
+    let file_id = reader.get_u64()?;
+    let offset_value = reader.get_u64()?;
+
+    data_index.insert(
+      key_value,
+      Index {
+        offset: offset_value,
+        file_id,
+      },
+    );
+  }
+
+  Ok(())
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod memtable_test {
+  use crate::memtable::{node::Color, *};
+
+  /// Checks the red-black and search tree properties, returning the black
+  /// height of the subtree at `node`.
+  fn check_subtree(tree: &RBTree<u32, u32>, node: NodeId<u32, u32>) -> usize {
+    if tree.is_sentinel(node) {
+      assert_eq!(tree.color(node), Color::Black);
+      return 1;
+    }
+
+    let (left, right) = (tree.nodes[node].left, tree.nodes[node].right);
+    if tree.color(node) == Color::Red {
+      assert_eq!(tree.color(left), Color::Black, "red node with a red child");
+      assert_eq!(tree.color(right), Color::Black, "red node with a red child");
+    }
+    for child in [left, right] {
+      if !tree.is_sentinel(child) {
+        assert_eq!(tree.nodes[child].parent, node);
+      }
+    }
+    if !tree.is_sentinel(left) {
+      assert!(tree.nodes[left].key < tree.nodes[node].key);
+    }
+    if !tree.is_sentinel(right) {
+      assert!(tree.nodes[right].key > tree.nodes[node].key);
+    }
+
+    let height = check_subtree(tree, left);
+    assert_eq!(height, check_subtree(tree, right), "unequal black heights");
+    height + usize::from(tree.color(node) == Color::Black)
+  }
+
+  fn check(tree: &RBTree<u32, u32>) -> usize {
+    assert_eq!(tree.color(tree.root), Color::Black);
+    assert!(tree.is_sentinel(tree.nodes[tree.root].parent));
+    check_subtree(tree, tree.root)
+  }
+
+  fn tree_of(keys: impl IntoIterator<Item = u32>) -> RBTree<u32, u32> {
+    let mut tree = RBTree::new();
+    for key in keys {
+      tree.insert(key, key * 10);
+      check(&tree);
+    }
+    tree
+  }
+
+  // ---------------------------------------------------------
+  // insertion tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn empty_tree() {
+    let tree = RBTree::<u32, u32>::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.get(&1), None);
+    assert_eq!(tree.iter().count(), 0);
+    check(&tree);
+  }
+
+  #[test]
+  fn ascending_keys_stay_balanced() {
+    let tree = tree_of(0..1000);
+    assert_eq!(tree.size(), 1000);
+    // A red-black tree is at most twice as tall as a perfect one.
+    assert!(check(&tree) <= 11);
+  }
+
+  #[test]
+  fn descending_keys_stay_balanced() {
+    let tree = tree_of((0..1000).rev());
+    assert_eq!(tree.size(), 1000);
+    assert!(check(&tree) <= 11);
+  }
+
+  #[test]
+  fn scattered_keys_stay_balanced() {
+    let keys = (0..1000u32).map(|i| i.wrapping_mul(2_654_435_761) % 10_007);
+    let tree = tree_of(keys.clone());
+    for key in keys {
+      assert_eq!(tree.get(&key), Some(&(key * 10)));
+    }
+    assert_eq!(tree.get(&10_008), None);
+  }
+
+  #[test]
+  fn insert_replaces_an_existing_value() {
+    let mut tree = tree_of([5, 3, 8]);
+    assert_eq!(tree.insert(3, 99), Some(30));
+    assert_eq!(tree.get(&3), Some(&99));
+    assert_eq!(tree.size(), 3);
+    check(&tree);
+  }
+
+  // ---------------------------------------------------------
+  // comparator tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn comparator_orders_lookups_and_iteration() {
+    // Little-endian numbers, which sort wrongly byte by byte.
+    let mut tree = RBTree::with_comparator(|a: &Vec<u8>, b: &Vec<u8>| {
+      let number = |key: &[u8]| u32::from_le_bytes(key.try_into().unwrap());
+      number(a).cmp(&number(b))
+    });
+    for n in [256u32, 1, 65_536, 2, 255] {
+      tree.insert(n.to_le_bytes().to_vec(), n);
+    }
+    assert_eq!(tree.insert(2u32.to_le_bytes().to_vec(), 20), Some(2));
+
+    assert_eq!(tree.get(&65_536u32.to_le_bytes().to_vec()), Some(&65_536));
+    assert_eq!(tree.get(&3u32.to_le_bytes().to_vec()), None);
+    let values = tree.iter().map(|(_, &v)| v).collect::<Vec<_>>();
+    assert_eq!(values, [1, 20, 255, 256, 65_536]);
+  }
+
+  #[test]
+  fn reversed_comparator_sorts_descending() {
+    let mut tree = RBTree::with_comparator(|a: &u32, b: &u32| b.cmp(a));
+    for key in 0..1000 {
+      tree.insert(key, key);
+    }
+    let keys = tree.iter().map(|(&k, _)| k).collect::<Vec<_>>();
+    assert_eq!(keys, (0..1000).rev().collect::<Vec<_>>());
+    for key in 0..1000 {
+      assert_eq!(tree.get(&key), Some(&key));
+    }
+  }
+
+  // ---------------------------------------------------------
+  // iteration and clear tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn iter_is_in_key_order() {
+    let tree = tree_of([7, 1, 9, 3, 5, 2, 8]);
+    let entries = tree.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+    assert_eq!(entries, [1, 2, 3, 5, 7, 8, 9].map(|k| (k, k * 10)).to_vec());
+  }
+
+  #[test]
+  fn clear_empties_the_tree_for_reuse() {
+    let mut tree = tree_of(0..100);
+    tree.clear();
+    assert!(tree.is_empty());
+    assert_eq!(tree.get(&5), None);
+    check(&tree);
+
+    tree.insert(42, 1);
+    assert_eq!(tree.get(&42), Some(&1));
+    assert_eq!(tree.size(), 1);
+    check(&tree);
+  }
+}
@@ -1,137 +1,261 @@
-use std::thread::current;
+use std::cmp::Ordering;
 
-use crate::memtable::node::{Color, Node};
+use utils::arena::Arena;
 
+use crate::memtable::node::{Color, Node, NodeId};
+
+mod __test__;
 mod node;
 
-// Decide how you represent null leaves. Usually a single shared sentinel node that is always black.
-pub(crate) struct RBTree<K, V> {
-  pub root: *mut Node<K, V>,
-  pub size: usize,
-  pub sentinel: *mut Node<K, V>,
+type CompareFn<K> = dyn Fn(&K, &K) -> Ordering + Send + Sync;
+
+// Null leaves are a single shared sentinel node that is always black. Nodes
+// live in an arena, so a flush frees the whole tree at once.
+pub struct RBTree<K, V> {
+  nodes: Arena<Node<K, V>>,
+  root: NodeId<K, V>,
+  size: usize,
+  sentinel: NodeId<K, V>,
+  /// Orders the keys for lookups, inserts and iteration.
+  compare: Box<CompareFn<K>>,
+}
+
+impl<K, V> Default for RBTree<K, V>
+where
+  K: Default + Ord + 'static,
+  V: Default,
+{
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl<K, V> RBTree<K, V>
 where
-  K: Default + Ord,
+  K: Default + Ord + 'static,
   V: Default,
 {
-  pub(crate) fn new() -> Self {
-    let mut s = Box::new(Node::<K, V>::sentinel());
-    let s_ptr: *mut _ = &mut *s;
-
-    unsafe {
-      (*s_ptr).parent = s_ptr;
-      (*s_ptr).left = s_ptr;
-      (*s_ptr).right = s_ptr;
-    }
+  /// A tree ordered by `K`'s `Ord`.
+  pub fn new() -> Self {
+    Self::with_comparator(K::cmp)
+  }
+}
 
-    // Leak the sentinel so the pointer stays alive
-    std::mem::forget(s);
+impl<K, V> RBTree<K, V>
+where
+  K: Default,
+  V: Default,
+{
+  /// A tree ordered by `compare` instead, e.g. to sort big-endian numbers
+  /// or a timestamp suffix newest first. It must be a total order.
+  pub fn with_comparator(compare: impl Fn(&K, &K) -> Ordering + Send + Sync + 'static) -> Self {
+    let mut nodes = Arena::new();
+    let s = nodes.next_id();
+    nodes.alloc(Node::sentinel(s));
 
     Self {
-      root: s_ptr,
-      sentinel: s_ptr,
+      nodes,
+      root: s,
+      sentinel: s,
       size: 0,
+      compare: Box::new(compare),
     }
   }
 
-  fn insert(&mut self, key: K, value: V) {
-    unsafe {
-      let mut node = Box::new(Node::new(key, value, Color::Red));
-      let node_ptr: *mut _ = node.as_mut();
+  pub fn size(&self) -> usize {
+    self.size
+  }
 
-      (*node_ptr).left = self.sentinel;
-      (*node_ptr).right = self.sentinel;
+  pub fn is_empty(&self) -> bool {
+    self.size == 0
+  }
 
-      let s = self.sentinel;
-      let mut parent = s;
-      let mut current = self.root;
+  /// Drops every node, keeping the arena's memory for the next memtable.
+  pub fn clear(&mut self) {
+    self.nodes.clear();
+    let s = self.nodes.next_id();
+    self.nodes.alloc(Node::sentinel(s));
+    self.root = s;
+    self.sentinel = s;
+    self.size = 0;
+  }
 
-      while !self.is_sentinel(current) {
-        parent = current;
-
-        if (*node_ptr).key < (*current).key {
-          current = (*current).left;
-        } else if (*node_ptr).key > (*current).key {
-          current = (*current).right;
-        } else {
-          (*current).value = std::mem::take(&mut (*node_ptr).value);
-          drop(node);
-          return;
-        }
+  pub fn get(&self, key: &K) -> Option<&V> {
+    let mut current = self.root;
+
+    while !self.is_sentinel(current) {
+      let node = &self.nodes[current];
+      match (self.compare)(key, &node.key) {
+        Ordering::Less => current = node.left,
+        Ordering::Greater => current = node.right,
+        Ordering::Equal => return Some(&node.value),
       }
+    }
+    None
+  }
 
-      (*node_ptr).parent = parent;
+  /// Inserts `key`, returning the value it replaced if it was already there.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let s = self.sentinel;
+    let mut parent = s;
+    let mut current = self.root;
 
-      if self.is_sentinel(parent) {
-        self.root = node_ptr;
-      } else if (*node_ptr).key < (*parent).key {
-        (*parent).left = node_ptr;
-      } else {
-        (*parent).right = node_ptr;
+    let mut is_left = false;
+    while !self.is_sentinel(current) {
+      parent = current;
+
+      let ordering = (self.compare)(&key, &self.nodes[current].key);
+      is_left = ordering == Ordering::Less;
+      match ordering {
+        Ordering::Less => current = self.nodes[current].left,
+        Ordering::Greater => current = self.nodes[current].right,
+        Ordering::Equal => {
+          return Some(std::mem::replace(&mut self.nodes[current].value, value));
+        },
       }
+    }
 
-      (*self.root).parent = s;
-      drop(node);
+    let mut node = Node::new(key, value, Color::Red, s);
+    node.parent = parent;
+    let node_id = self.nodes.alloc(node);
+    self.size += 1;
 
-      self.fix_insert(node_ptr);
+    if self.is_sentinel(parent) {
+      self.root = node_id;
+    } else if is_left {
+      self.nodes[parent].left = node_id;
+    } else {
+      self.nodes[parent].right = node_id;
     }
+
+    let root = self.root;
+    self.nodes[root].parent = s;
+
+    self.fix_insert(node_id);
+    None
   }
 
-  fn is_sentinel(&self, s: *mut Node<K, V>) -> bool {
+  /// Entries in key order, which is the order a flush writes them in.
+  pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+    let mut stack = Vec::new();
+    let mut current = self.root;
+
+    std::iter::from_fn(move || {
+      while !self.is_sentinel(current) {
+        stack.push(current);
+        current = self.nodes[current].left;
+      }
+      let node = &self.nodes[stack.pop()?];
+      current = node.right;
+      Some((&node.key, &node.value))
+    })
+  }
+
+  fn is_sentinel(&self, s: NodeId<K, V>) -> bool {
     self.sentinel == s
   }
 
-  fn fix_insert(&mut self, mut node: *mut Node<K, V>) {
-    unsafe {}
+  fn color(&self, node: NodeId<K, V>) -> Color {
+    self.nodes[node].color
   }
 
-  fn rotation_left(&mut self, x: *mut Node<K, V>) {
-    unsafe {
-      let y = (*x).right;
-      (*x).right = (*y).left;
+  // Restores the red-black properties after `node` was inserted red. Only a
+  // red parent breaks them: a red uncle is fixed by recoloring and moves the
+  // problem up to the grandparent, a black one by at most two rotations.
+  fn fix_insert(&mut self, mut node: NodeId<K, V>) {
+    while self.color(self.nodes[node].parent) == Color::Red {
+      let parent = self.nodes[node].parent;
+      let grandparent = self.nodes[parent].parent;
 
-      if !self.is_sentinel((*y).left) {
-        (*(*y).left).parent = x;
-      }
-
-      (*y).parent = (*x).parent;
+      if parent == self.nodes[grandparent].left {
+        let uncle = self.nodes[grandparent].right;
+        if self.color(uncle) == Color::Red {
+          self.nodes[parent].color = Color::Black;
+          self.nodes[uncle].color = Color::Black;
+          self.nodes[grandparent].color = Color::Red;
+          node = grandparent;
+          continue;
+        }
 
-      if self.is_sentinel((*x).parent) {
-        self.root = y;
-      } else if (*(*x).parent).left == x {
-        (*(*x).parent).left = y;
+        if node == self.nodes[parent].right {
+          node = parent;
+          self.rotation_left(node);
+        }
+        let parent = self.nodes[node].parent;
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[parent].color = Color::Black;
+        self.nodes[grandparent].color = Color::Red;
+        self.rotation_right(grandparent);
       } else {
-        (*(*x).parent).right = y;
-      }
+        let uncle = self.nodes[grandparent].left;
+        if self.color(uncle) == Color::Red {
+          self.nodes[parent].color = Color::Black;
+          self.nodes[uncle].color = Color::Black;
+          self.nodes[grandparent].color = Color::Red;
+          node = grandparent;
+          continue;
+        }
 
-      (*y).left = x;
-      (*x).parent = y;
+        if node == self.nodes[parent].left {
+          node = parent;
+          self.rotation_right(node);
+        }
+        let parent = self.nodes[node].parent;
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[parent].color = Color::Black;
+        self.nodes[grandparent].color = Color::Red;
+        self.rotation_left(grandparent);
+      }
     }
+
+    let root = self.root;
+    self.nodes[root].color = Color::Black;
   }
+  fn rotation_left(&mut self, x: NodeId<K, V>) {
+    let y = self.nodes[x].right;
+    self.nodes[x].right = self.nodes[y].left;
 
-  fn rotation_right(&mut self, x: *mut Node<K, V>) {
-    unsafe {
-      let y = (*x).left;
-      (*x).left = (*y).right;
+    let y_left = self.nodes[y].left;
+    if !self.is_sentinel(y_left) {
+      self.nodes[y_left].parent = x;
+    }
 
-      if !self.is_sentinel((*y).right) {
-        (*(*y).right).parent = x;
-      }
+    let x_parent = self.nodes[x].parent;
+    self.nodes[y].parent = x_parent;
 
-      (*y).parent = (*x).parent;
+    if self.is_sentinel(x_parent) {
+      self.root = y;
+    } else if self.nodes[x_parent].left == x {
+      self.nodes[x_parent].left = y;
+    } else {
+      self.nodes[x_parent].right = y;
+    }
 
-      if self.is_sentinel((*x).parent) {
-        self.root = y;
-      } else if x == (*(*x).parent).right {
-        (*(*x).parent).right = y;
-      } else {
-        (*(*x).parent).left = y;
-      }
+    self.nodes[y].left = x;
+    self.nodes[x].parent = y;
+  }
+
+  fn rotation_right(&mut self, x: NodeId<K, V>) {
+    let y = self.nodes[x].left;
+    self.nodes[x].left = self.nodes[y].right;
+
+    let y_right = self.nodes[y].right;
+    if !self.is_sentinel(y_right) {
+      self.nodes[y_right].parent = x;
+    }
+
+    let x_parent = self.nodes[x].parent;
+    self.nodes[y].parent = x_parent;
 
-      (*y).right = x;
-      (*x).parent = y;
+    if self.is_sentinel(x_parent) {
+      self.root = y;
+    } else if x == self.nodes[x_parent].right {
+      self.nodes[x_parent].right = y;
+    } else {
+      self.nodes[x_parent].left = y;
     }
+
+    self.nodes[y].right = x;
+    self.nodes[x].parent = y;
   }
 }
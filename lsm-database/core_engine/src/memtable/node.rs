@@ -1,41 +1,41 @@
+use utils::arena::Id;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
   Red,
   Black,
 }
 
+pub type NodeId<K, V> = Id<Node<K, V>>;
+
 pub struct Node<K, V> {
   pub key: K,
   pub value: V,
   pub color: Color,
-  pub left: *mut Node<K, V>,
-  pub right: *mut Node<K, V>,
-  pub parent: *mut Node<K, V>,
+  pub left: NodeId<K, V>,
+  pub right: NodeId<K, V>,
+  pub parent: NodeId<K, V>,
 }
 
 impl<K, V> Node<K, V>
 where
-  K: Default + Ord,
+  K: Default,
   V: Default,
 {
-  pub fn new(key: K, value: V, color: Color) -> Self {
+  /// A node whose links all point at the tree's sentinel `nil`.
+  pub fn new(key: K, value: V, color: Color, nil: NodeId<K, V>) -> Self {
     Self {
       key,
       value,
       color,
-      parent: std::ptr::null_mut(),
-      right: std::ptr::null_mut(),
-      left: std::ptr::null_mut(),
+      parent: nil,
+      right: nil,
+      left: nil,
     }
   }
 
-  pub fn sentinel() -> Self {
-    Self {
-      key: K::default(),
-      value: V::default(),
-      color: Color::Black,
-      parent: std::ptr::null_mut(),
-      right: std::ptr::null_mut(),
-      left: std::ptr::null_mut(),
-    }
+  /// The sentinel, linked to itself; `nil` is the id it is allocated at.
+  pub fn sentinel(nil: NodeId<K, V>) -> Self {
+    Self::new(K::default(), V::default(), Color::Black, nil)
   }
 }
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// Settings for a [`LogFile`](crate::log_file::LogFile).
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+  /// When writes are fsynced. See [`SyncPolicy`].
+  pub sync_policy: SyncPolicy,
+}
+
+/// How much durability a write gets before `append`, `update` and `delete`
+/// return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncPolicy {
+  /// Every write waits for an fsync. Nothing acknowledged is lost on a
+  /// crash.
+  #[default]
+  Always,
+  /// Writes return immediately and a background thread fsyncs the active
+  /// segment on this interval, bounding what a crash can lose.
+  Interval(Duration),
+  /// Writes are never fsynced explicitly; the OS flushes them whenever it
+  /// likes.
+  Os,
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod arena_test {
+  use std::{cell::Cell, rc::Rc};
+
+  use crate::arena::Arena;
+
+  #[test]
+  fn ids_stay_valid_as_it_grows() {
+    let mut arena = Arena::with_capacity(1);
+    assert!(arena.is_empty());
+
+    let ids = (0..100).map(|i| arena.alloc(i * 10)).collect::<Vec<_>>();
+    assert_eq!(arena.size(), 100);
+    for (i, &id) in ids.iter().enumerate() {
+      assert_eq!(id.index(), i);
+      assert_eq!(arena[id], i * 10);
+    }
+
+    arena[ids[3]] += 1;
+    *arena.get_mut(ids[4]).unwrap() += 2;
+    assert_eq!(arena.get(ids[3]), Some(&31));
+    assert_eq!(arena.get(ids[4]), Some(&42));
+  }
+
+  #[test]
+  fn next_id_is_the_next_allocation() {
+    let mut arena = Arena::new();
+    arena.alloc("a");
+    let expected = arena.next_id();
+    assert_eq!(arena.alloc("b"), expected);
+    assert_ne!(arena.next_id(), expected);
+  }
+
+  #[test]
+  fn clear_drops_everything_and_keeps_capacity() {
+    struct Counted(Rc<Cell<usize>>);
+    impl Drop for Counted {
+      fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+      }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let mut arena = Arena::new();
+    let id = arena.alloc(Counted(drops.clone()));
+    for _ in 0..9 {
+      arena.alloc(Counted(drops.clone()));
+    }
+    let capacity = arena.capacity();
+
+    arena.clear();
+    assert_eq!(drops.get(), 10);
+    assert!(arena.is_empty());
+    assert!(arena.get(id).is_none());
+    assert_eq!(arena.capacity(), capacity);
+  }
+
+  #[test]
+  fn iterates_in_allocation_order() {
+    let mut arena = Arena::new();
+    let a = arena.alloc('a');
+    let b = arena.alloc('b');
+
+    assert_eq!(arena.iter().collect::<Vec<_>>(), vec![(a, &'a'), (b, &'b')]);
+    assert_eq!(arena.into_vec(), vec!['a', 'b']);
+  }
+
+  #[test]
+  #[should_panic]
+  fn indexing_a_cleared_id_panics() {
+    let mut arena = Arena::new();
+    let id = arena.alloc(1);
+    arena.clear();
+    let _ = arena[id];
+  }
+}
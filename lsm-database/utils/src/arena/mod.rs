@@ -0,0 +1,188 @@
+//! A typed bump allocator that hands out stable indices.
+//!
+//! Values are pushed into one growing `Vec` and addressed by an [`Id`], a
+//! typed index that stays valid as the arena grows and is `Copy`, so linked
+//! structures such as a tree's parent pointers can refer to each other
+//! without `Rc`, lifetimes or `unsafe`. Nothing is freed on its own:
+//! [`clear`](Arena::clear) frees everything at once and keeps the memory for
+//! the next round, which is how a memtable is emptied after a flush.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::arena::{Arena, Id};
+//!
+//! struct Node {
+//!   key: u32,
+//!   next: Option<Id<Node>>,
+//! }
+//!
+//! let mut nodes = Arena::new();
+//! let tail = nodes.alloc(Node { key: 2, next: None });
+//! let head = nodes.alloc(Node { key: 1, next: Some(tail) });
+//!
+//! let next = nodes[head].next.unwrap();
+//! assert_eq!(nodes[next].key, 2);
+//!
+//! nodes.clear();
+//! assert!(nodes.get(head).is_none());
+//! ```
+
+mod __test__;
+
+use std::{
+  fmt,
+  hash::{Hash, Hasher},
+  marker::PhantomData,
+  ops,
+};
+
+/// The index of a value in an [`Arena`] of `T`s.
+///
+/// An `Id` is only meaningful for the arena that returned it, and only until
+/// that arena is cleared.
+pub struct Id<T> {
+  index: usize,
+  _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+  /// Returns the position of the value in allocation order.
+  pub fn index(self) -> usize {
+    self.index
+  }
+}
+
+// Implemented by hand so `Id<T>` is `Copy` and comparable whatever `T` is.
+impl<T> Clone for Id<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.index == other.index
+  }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> Hash for Id<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.index.hash(state);
+  }
+}
+
+impl<T> fmt::Debug for Id<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Id({})", self.index)
+  }
+}
+
+/// Owns values of one type and hands out an [`Id`] for each.
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+  items: Vec<T>,
+}
+
+impl<T> Default for Arena<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> Arena<T> {
+  /// Creates an empty arena.
+  pub fn new() -> Self {
+    Self { items: Vec::new() }
+  }
+
+  /// Creates an empty arena with room for `capacity` values.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      items: Vec::with_capacity(capacity),
+    }
+  }
+
+  /// Returns `true` if nothing was allocated.
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// Returns the number of allocated values.
+  pub fn size(&self) -> usize {
+    self.items.len()
+  }
+
+  /// Returns how many values fit before the arena grows.
+  pub fn capacity(&self) -> usize {
+    self.items.capacity()
+  }
+
+  /// Moves `value` into the arena and returns its id.
+  pub fn alloc(&mut self, value: T) -> Id<T> {
+    let id = self.next_id();
+    self.items.push(value);
+    id
+  }
+
+  /// Returns the id the next [`alloc`](Self::alloc) will return, so a value
+  /// can refer to itself, e.g. a sentinel node that is its own parent.
+  pub fn next_id(&self) -> Id<T> {
+    Id {
+      index: self.items.len(),
+      _marker: PhantomData,
+    }
+  }
+
+  /// Returns the value of `id`, or `None` if it isn't allocated.
+  pub fn get(&self, id: Id<T>) -> Option<&T> {
+    self.items.get(id.index)
+  }
+
+  /// Returns the value of `id` mutably, or `None` if it isn't allocated.
+  pub fn get_mut(&mut self, id: Id<T>) -> Option<&mut T> {
+    self.items.get_mut(id.index)
+  }
+
+  /// Drops every value at once, invalidating all ids, and keeps the memory
+  /// for reuse. Apart from running the values' destructors, this is O(1).
+  pub fn clear(&mut self) {
+    self.items.clear();
+  }
+
+  /// Returns an iterator over the values and their ids in allocation order.
+  pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> + '_ {
+    self.items.iter().enumerate().map(|(index, value)| {
+      let id = Id {
+        index,
+        _marker: PhantomData,
+      };
+      (id, value)
+    })
+  }
+
+  /// Returns the values in allocation order.
+  pub fn into_vec(self) -> Vec<T> {
+    self.items
+  }
+}
+
+/// Panics if `id` isn't allocated.
+impl<T> ops::Index<Id<T>> for Arena<T> {
+  type Output = T;
+
+  fn index(&self, id: Id<T>) -> &T {
+    &self.items[id.index]
+  }
+}
+
+/// Panics if `id` isn't allocated.
+impl<T> ops::IndexMut<Id<T>> for Arena<T> {
+  fn index_mut(&mut self, id: Id<T>) -> &mut T {
+    &mut self.items[id.index]
+  }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod avl_test {
+  use std::collections::BTreeMap;
+
+  use crate::avl::{AvlTree, Link};
+
+  /// Checks key order, cached heights and balance, and returns the height.
+  fn check_link<K: Ord, V>(link: &Link<K, V>, low: Option<&K>, high: Option<&K>) -> usize {
+    let Some(node) = link else {
+      return 0;
+    };
+    assert!(low.is_none_or(|low| node.key > *low));
+    assert!(high.is_none_or(|high| node.key < *high));
+
+    let left = check_link(&node.left, low, Some(&node.key));
+    let right = check_link(&node.right, Some(&node.key), high);
+    assert!(left.abs_diff(right) <= 1, "unbalanced node");
+    assert_eq!(node.height, 1 + left.max(right));
+    node.height
+  }
+
+  fn check<K: Ord, V>(tree: &AvlTree<K, V>) {
+    assert_eq!(check_link(&tree.root, None, None), tree.height());
+    assert_eq!(tree.iter().count(), tree.size());
+  }
+
+  #[test]
+  fn new_tree_is_empty() {
+    let tree: AvlTree<u32, u32> = AvlTree::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.size(), 0);
+    assert_eq!(tree.height(), 0);
+    assert!(tree.get(&1).is_none());
+    assert!(tree.first().is_none());
+    assert_eq!(tree.iter().count(), 0);
+  }
+
+  #[test]
+  fn insert_replaces_existing_value() {
+    let mut tree = AvlTree::new();
+    assert_eq!(tree.insert("a".to_string(), 1), None);
+    assert_eq!(tree.insert("a".to_string(), 2), Some(1));
+    *tree.get_mut("a").unwrap() += 1;
+    assert_eq!(tree.get("a"), Some(&3));
+    assert_eq!(tree.size(), 1);
+  }
+
+  #[test]
+  fn sorted_inserts_stay_balanced() {
+    let ascending: AvlTree<u32, ()> = (0..1_023).map(|key| (key, ())).collect();
+    let descending: AvlTree<u32, ()> = (0..1_023).rev().map(|key| (key, ())).collect();
+
+    check(&ascending);
+    check(&descending);
+    assert_eq!(ascending.height(), 10);
+    assert_eq!(descending.height(), 10);
+  }
+
+  #[test]
+  fn iterates_in_key_order() {
+    let tree: AvlTree<u32, u32> = [50, 10, 40, 20, 30]
+      .into_iter()
+      .map(|k| (k, k * 2))
+      .collect();
+
+    let entries: Vec<_> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(
+      entries,
+      vec![(10, 20), (20, 40), (30, 60), (40, 80), (50, 100)]
+    );
+    assert_eq!(tree.iter().len(), 5);
+    assert_eq!(tree.first(), Some((&10, &20)));
+    assert_eq!(tree.last(), Some((&50, &100)));
+  }
+
+  #[test]
+  fn remove_rebalances() {
+    let mut tree: AvlTree<u32, u32> = (0..500).map(|key| (key, key)).collect();
+
+    for key in (0..500).step_by(3) {
+      assert_eq!(tree.remove(&key), Some(key));
+      check(&tree);
+    }
+    assert_eq!(tree.remove(&0), None);
+    assert_eq!(tree.size(), 333);
+  }
+
+  #[test]
+  fn matches_btree_map() {
+    let mut tree = AvlTree::new();
+    let mut model = BTreeMap::new();
+    let mut seed = 7u64;
+
+    for i in 0..20_000u64 {
+      seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+      let key = (seed >> 33) % 500;
+      if seed & 3 == 0 {
+        assert_eq!(tree.remove(&key), model.remove(&key));
+      } else {
+        assert_eq!(tree.insert(key, i), model.insert(key, i));
+      }
+      if i % 1_000 == 0 {
+        check(&tree);
+      }
+    }
+
+    check(&tree);
+    assert_eq!(tree.size(), model.len());
+    assert!(tree.iter().eq(model.iter()));
+  }
+}
@@ -0,0 +1,382 @@
+//! An ordered map built as an AVL tree.
+//!
+//! After every insert and remove the nodes on the changed path are
+//! rebalanced with rotations so that the heights of the two subtrees of
+//! any node differ by at most one. That keeps the tree within about 1.44
+//! times the optimal height, so lookups take `O(log n)` in the worst case.
+//!
+//! Nodes are plain `Box`es owned by their parent, so unlike the raw-pointer
+//! red-black tree of the memtable this needs no `unsafe`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::avl::AvlTree;
+//!
+//! let mut tree = AvlTree::new();
+//! for key in 1..=7 {
+//!   tree.insert(key, key * 10);
+//! }
+//!
+//! // Sorted inserts would make a plain binary search tree a list.
+//! assert_eq!(tree.height(), 3);
+//! assert_eq!(tree.remove(&4), Some(40));
+//!
+//! let keys: Vec<_> = tree.iter().map(|(key, _)| *key).collect();
+//! assert_eq!(keys, vec![1, 2, 3, 5, 6, 7]);
+//! ```
+
+mod __test__;
+
+use std::{borrow::Borrow, cmp::Ordering};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+/// An entry and the subtrees of smaller and larger keys.
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+  key: K,
+  value: V,
+  /// The number of levels in the subtree rooted here, 1 for a leaf.
+  height: usize,
+  left: Link<K, V>,
+  right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+  fn new(key: K, value: V) -> Box<Self> {
+    Box::new(Self {
+      key,
+      value,
+      height: 1,
+      left: None,
+      right: None,
+    })
+  }
+
+  fn update_height(&mut self) {
+    self.height = 1 + height(&self.left).max(height(&self.right));
+  }
+
+  /// How much taller the left subtree is than the right one.
+  fn balance(&self) -> isize {
+    height(&self.left) as isize - height(&self.right) as isize
+  }
+}
+
+/// A self-balancing binary search tree mapping `K` to `V`.
+#[derive(Debug, Clone)]
+pub struct AvlTree<K, V> {
+  root: Link<K, V>,
+  len: usize,
+}
+
+impl<K: Ord, V> Default for AvlTree<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Ord, V> AvlTree<K, V> {
+  /// Creates a new empty tree.
+  pub fn new() -> Self {
+    Self { root: None, len: 0 }
+  }
+
+  /// Returns `true` if tree is empty.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns number of entries in tree.
+  pub fn size(&self) -> usize {
+    self.len
+  }
+
+  /// Returns the number of levels in the tree, 0 for an empty one.
+  pub fn height(&self) -> usize {
+    height(&self.root)
+  }
+
+  /// Returns `true` if the tree has an entry for `key`.
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get(key).is_some()
+  }
+
+  /// Returns the value of `key`.
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut link = &self.root;
+    while let Some(node) = link {
+      link = match key.cmp(node.key.borrow()) {
+        Ordering::Less => &node.left,
+        Ordering::Greater => &node.right,
+        Ordering::Equal => return Some(&node.value),
+      };
+    }
+    None
+  }
+
+  /// Returns the value of `key` for changing it in place.
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut link = &mut self.root;
+    while let Some(node) = link {
+      link = match key.cmp(node.key.borrow()) {
+        Ordering::Less => &mut node.left,
+        Ordering::Greater => &mut node.right,
+        Ordering::Equal => return Some(&mut node.value),
+      };
+    }
+    None
+  }
+
+  /// Returns the entry with the smallest key.
+  pub fn first(&self) -> Option<(&K, &V)> {
+    let mut node = self.root.as_ref()?;
+    while let Some(left) = &node.left {
+      node = left;
+    }
+    Some((&node.key, &node.value))
+  }
+
+  /// Returns the entry with the largest key.
+  pub fn last(&self) -> Option<(&K, &V)> {
+    let mut node = self.root.as_ref()?;
+    while let Some(right) = &node.right {
+      node = right;
+    }
+    Some((&node.key, &node.value))
+  }
+
+  /// Stores `value` under `key`, rebalancing on the way back up, and
+  /// returns the value it replaced.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let (root, old) = insert(self.root.take(), key, value);
+    self.root = Some(root);
+    if old.is_none() {
+      self.len += 1;
+    }
+    old
+  }
+
+  /// Deletes `key`, rebalancing on the way back up, and returns its value.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let (root, value) = remove(self.root.take(), key);
+    self.root = root;
+    if value.is_some() {
+      self.len -= 1;
+    }
+    value
+  }
+
+  /// Removes every entry.
+  pub fn clear(&mut self) {
+    self.root = None;
+    self.len = 0;
+  }
+
+  /// Returns an in-order iterator over the entries, in ascending key order.
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    let mut iter = Iter {
+      stack: Vec::new(),
+      remaining: self.len,
+    };
+    iter.descend(&self.root);
+    iter
+  }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> usize {
+  link.as_ref().map_or(0, |node| node.height)
+}
+
+fn rotate_left<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+  let mut right = node
+    .right
+    .take()
+    .expect("rotating left needs a right child");
+  node.right = right.left.take();
+  node.update_height();
+  right.left = Some(node);
+  right.update_height();
+  right
+}
+
+fn rotate_right<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+  let mut left = node.left.take().expect("rotating right needs a left child");
+  node.left = left.right.take();
+  node.update_height();
+  left.right = Some(node);
+  left.update_height();
+  left
+}
+
+/// Restores the AVL property at `node`, whose subtrees are balanced and
+/// differ in height by at most two.
+fn rebalance<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+  node.update_height();
+  match node.balance() {
+    2.. => {
+      // Left-right case: turn it into a left-left case first.
+      if node.left.as_ref().is_some_and(|left| left.balance() < 0) {
+        node.left = node.left.take().map(rotate_left);
+      }
+      rotate_right(node)
+    },
+    ..=-2 => {
+      if node.right.as_ref().is_some_and(|right| right.balance() > 0) {
+        node.right = node.right.take().map(rotate_right);
+      }
+      rotate_left(node)
+    },
+    _ => node,
+  }
+}
+
+fn insert<K: Ord, V>(link: Link<K, V>, key: K, value: V) -> (Box<Node<K, V>>, Option<V>) {
+  let Some(mut node) = link else {
+    return (Node::new(key, value), None);
+  };
+
+  match key.cmp(&node.key) {
+    Ordering::Less => {
+      let (left, old) = insert(node.left.take(), key, value);
+      node.left = Some(left);
+      (rebalance(node), old)
+    },
+    Ordering::Greater => {
+      let (right, old) = insert(node.right.take(), key, value);
+      node.right = Some(right);
+      (rebalance(node), old)
+    },
+    Ordering::Equal => {
+      let old = std::mem::replace(&mut node.value, value);
+      (node, Some(old))
+    },
+  }
+}
+
+fn remove<K, V, Q>(link: Link<K, V>, key: &Q) -> (Link<K, V>, Option<V>)
+where
+  K: Borrow<Q>,
+  Q: Ord + ?Sized,
+{
+  let Some(mut node) = link else {
+    return (None, None);
+  };
+
+  match key.cmp(node.key.borrow()) {
+    Ordering::Less => {
+      let (left, value) = remove(node.left.take(), key);
+      node.left = left;
+      (Some(rebalance(node)), value)
+    },
+    Ordering::Greater => {
+      let (right, value) = remove(node.right.take(), key);
+      node.right = right;
+      (Some(rebalance(node)), value)
+    },
+    Ordering::Equal => {
+      let Node {
+        value, left, right, ..
+      } = *node;
+      match (left, right) {
+        (None, child) | (child, None) => (child, Some(value)),
+        (Some(left), Some(right)) => {
+          // The successor takes the place of the removed node.
+          let (right, mut successor) = remove_first(right);
+          successor.left = Some(left);
+          successor.right = right;
+          (Some(rebalance(successor)), Some(value))
+        },
+      }
+    },
+  }
+}
+
+/// Detaches the node with the smallest key from the subtree, returning what
+/// is left of the subtree and that node.
+fn remove_first<K, V>(mut node: Box<Node<K, V>>) -> (Link<K, V>, Box<Node<K, V>>) {
+  match node.left.take() {
+    None => (node.right.take(), node),
+    Some(left) => {
+      let (left, first) = remove_first(left);
+      node.left = left;
+      (Some(rebalance(node)), first)
+    },
+  }
+}
+
+/// In-order iterator over the entries of an [`AvlTree`].
+pub struct Iter<'a, K, V> {
+  /// The nodes whose entry and right subtree are still to come, innermost
+  /// last.
+  stack: Vec<&'a Node<K, V>>,
+  remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+  fn descend(&mut self, mut link: &'a Link<K, V>) {
+    while let Some(node) = link {
+      self.stack.push(node);
+      link = &node.left;
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let node = self.stack.pop()?;
+    self.descend(&node.right);
+    self.remaining -= 1;
+    Some((&node.key, &node.value))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K: Ord, V> IntoIterator for &'a AvlTree<K, V> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for AvlTree<K, V> {
+  fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+    for (key, value) in iter {
+      self.insert(key, value);
+    }
+  }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for AvlTree<K, V> {
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut tree = Self::new();
+    tree.extend(iter);
+    tree
+  }
+}
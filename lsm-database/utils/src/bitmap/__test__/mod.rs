@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod bitmap_test {
+  use std::collections::BTreeSet;
+
+  use crate::bitmap::{Bitmap, Container};
+
+  fn kinds(bitmap: &Bitmap) -> Vec<&'static str> {
+    bitmap
+      .containers
+      .iter()
+      .map(|(_, container)| match container {
+        Container::Array(_) => "array",
+        Container::Bitset { .. } => "bitset",
+        Container::Runs(_) => "runs",
+      })
+      .collect()
+  }
+
+  #[test]
+  fn new_bitmap_is_empty() {
+    let bitmap = Bitmap::new();
+    assert!(bitmap.is_empty());
+    assert_eq!(bitmap.size(), 0);
+    assert!(!bitmap.contains(0));
+    assert_eq!(bitmap.rank(u32::MAX), 0);
+    assert_eq!(bitmap.select(0), None);
+    assert_eq!(bitmap.min(), None);
+    assert_eq!(bitmap.max(), None);
+  }
+
+  #[test]
+  fn insert_and_remove() {
+    let mut bitmap = Bitmap::new();
+    assert!(bitmap.insert(7));
+    assert!(!bitmap.insert(7));
+    assert!(bitmap.insert(u32::MAX));
+    assert!(bitmap.contains(7));
+    assert!(bitmap.contains(u32::MAX));
+
+    assert!(bitmap.remove(7));
+    assert!(!bitmap.remove(7));
+    assert!(!bitmap.remove(8));
+    assert_eq!(bitmap.iter().collect::<Vec<_>>(), vec![u32::MAX]);
+    assert_eq!(bitmap.containers.len(), 1);
+  }
+
+  #[test]
+  fn arrays_become_bitsets_and_back() {
+    let mut bitmap: Bitmap = (0..10_000).map(|i| i * 3).collect();
+    assert_eq!(kinds(&bitmap), vec!["bitset"]);
+    assert_eq!(bitmap.size(), 10_000);
+
+    for i in 0..6_000 {
+      bitmap.remove(i * 3);
+    }
+    assert_eq!(kinds(&bitmap), vec!["array"]);
+    assert_eq!(bitmap.min(), Some(18_000));
+    assert_eq!(bitmap.max(), Some(29_997));
+  }
+
+  #[test]
+  fn optimize_picks_runs_for_dense_ranges() {
+    let mut bitmap: Bitmap = (0..60_000).chain(100_000..100_010).collect();
+    let unoptimized = bitmap.clone();
+    let before = bitmap.size_in_bytes();
+    bitmap.optimize();
+    assert_eq!(bitmap, unoptimized);
+
+    assert_eq!(kinds(&bitmap), vec!["runs", "runs"]);
+    assert!(bitmap.size_in_bytes() < before / 50);
+
+    assert!(bitmap.remove(30_000));
+    assert!(bitmap.insert(60_000));
+    assert!(bitmap.insert(30_000));
+    assert!(!bitmap.contains(60_001));
+    assert_eq!(bitmap.size(), 60_011);
+    assert_eq!(bitmap.rank(100_005), 60_007);
+    assert_eq!(bitmap.select(60_001), Some(100_000));
+  }
+
+  #[test]
+  fn scattered_runs_fall_back_to_a_bitset() {
+    let mut bitmap: Bitmap = (0..65_536).collect();
+    bitmap.optimize();
+    assert_eq!(kinds(&bitmap), vec!["runs"]);
+
+    for value in (0..65_536).step_by(2) {
+      bitmap.remove(value);
+    }
+    assert_eq!(kinds(&bitmap), vec!["bitset"]);
+    assert_eq!(bitmap.size(), 32_768);
+  }
+
+  #[test]
+  fn rank_and_select_are_inverse() {
+    let values = [3, 64, 65, 4_000, 70_000, 70_001, 1 << 31];
+    let mut bitmap: Bitmap = values.into_iter().collect();
+
+    for optimize in [false, true] {
+      if optimize {
+        bitmap.optimize();
+      }
+      for (n, &value) in values.iter().enumerate() {
+        assert_eq!(bitmap.select(n as u64), Some(value));
+        assert_eq!(bitmap.rank(value), n as u64 + 1);
+      }
+      assert_eq!(bitmap.rank(2), 0);
+      assert_eq!(bitmap.rank(69_999), 4);
+      assert_eq!(bitmap.select(values.len() as u64), None);
+    }
+  }
+
+  #[test]
+  fn matches_btree_set() {
+    let mut bitmap = Bitmap::new();
+    let mut model = BTreeSet::new();
+    let mut seed = 7u64;
+
+    for i in 0..50_000u32 {
+      seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+      // Mostly dense values around a few high halves, so every container
+      // form gets used.
+      let value = (((seed >> 40) as u32 % 3) << 16) | ((seed >> 20) as u32 % 12_000);
+      if seed & 3 == 0 {
+        assert_eq!(bitmap.remove(value), model.remove(&value));
+      } else {
+        assert_eq!(bitmap.insert(value), model.insert(value));
+      }
+      if i % 10_000 == 0 {
+        bitmap.optimize();
+      }
+    }
+
+    assert_eq!(bitmap.size(), model.len() as u64);
+    assert!(bitmap.iter().eq(model.iter().copied()));
+    for (n, &value) in model.iter().enumerate().step_by(97) {
+      assert_eq!(bitmap.select(n as u64), Some(value));
+      assert_eq!(bitmap.rank(value), n as u64 + 1);
+    }
+  }
+}
@@ -0,0 +1,457 @@
+//! A compressed bitmap of `u32` values in the style of Roaring bitmaps.
+//!
+//! Values are split into their high and low 16 bits. Each distinct high
+//! half gets a container for the low halves, stored as whichever of three
+//! forms fits its contents:
+//!
+//! - an array: a sorted `Vec<u16>`, for up to 4096 values;
+//! - a bitset: 1024 `u64` words, 8 KiB whatever the number of values;
+//! - runs: sorted `(start, length - 1)` pairs, for long stretches of
+//!   consecutive values, chosen by [`Bitmap::optimize`].
+//!
+//! Arrays turn into bitsets and back as they cross 4096 values, so a
+//! segment's live record offsets take a few bytes each at most, far less
+//! than the same offsets in a `HashSet<u64>`. `rank` and `select` answer
+//! "how many live records come before this one" and "where is the n-th
+//! one" without expanding anything.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::bitmap::Bitmap;
+//!
+//! let mut live: Bitmap = [0, 128, 4096, 70_000].into_iter().collect();
+//! live.remove(128);
+//!
+//! assert!(live.contains(4096));
+//! assert_eq!(live.size(), 3);
+//! assert_eq!(live.rank(4096), 2);
+//! assert_eq!(live.select(2), Some(70_000));
+//! ```
+
+mod __test__;
+
+/// The most values an array container holds before becoming a bitset.
+const ARRAY_MAX: usize = 4096;
+
+/// The number of `u64` words in a bitset container.
+const BITSET_WORDS: usize = 1024;
+
+/// The low halves of the values that share one high half.
+#[derive(Debug, Clone)]
+enum Container {
+  Array(Vec<u16>),
+  Bitset {
+    words: Box<[u64; BITSET_WORDS]>,
+    len: usize,
+  },
+  /// `(start, length - 1)` pairs, sorted, neither overlapping nor touching.
+  Runs(Vec<(u16, u16)>),
+}
+
+impl Container {
+  fn len(&self) -> usize {
+    match self {
+      Self::Array(values) => values.len(),
+      Self::Bitset { len, .. } => *len,
+      Self::Runs(runs) => runs.iter().map(|&(_, extra)| extra as usize + 1).sum(),
+    }
+  }
+
+  fn contains(&self, value: u16) -> bool {
+    match self {
+      Self::Array(values) => values.binary_search(&value).is_ok(),
+      Self::Bitset { words, .. } => words[value as usize / 64] & (1 << (value % 64)) != 0,
+      Self::Runs(runs) => {
+        let i = runs.partition_point(|&(start, _)| start <= value);
+        i > 0 && value <= run_end(runs[i - 1])
+      },
+    }
+  }
+
+  fn insert(&mut self, value: u16) -> bool {
+    match self {
+      Self::Array(values) => {
+        let Err(i) = values.binary_search(&value) else {
+          return false;
+        };
+        values.insert(i, value);
+        if values.len() > ARRAY_MAX {
+          *self = Self::bitset_of(self.iter());
+        }
+        true
+      },
+      Self::Bitset { words, len } => {
+        let (word, bit) = (value as usize / 64, 1 << (value % 64));
+        if words[word] & bit != 0 {
+          return false;
+        }
+        words[word] |= bit;
+        *len += 1;
+        true
+      },
+      Self::Runs(runs) => {
+        let i = runs.partition_point(|&(start, _)| start <= value);
+        if i > 0 && value <= run_end(runs[i - 1]) {
+          return false;
+        }
+        let joins_prev = i > 0 && run_end(runs[i - 1]) as u32 + 1 == value as u32;
+        let joins_next = i < runs.len() && value as u32 + 1 == runs[i].0 as u32;
+        match (joins_prev, joins_next) {
+          (true, true) => {
+            let (_, next_extra) = runs.remove(i);
+            runs[i - 1].1 += next_extra + 2;
+          },
+          (true, false) => runs[i - 1].1 += 1,
+          (false, true) => {
+            runs[i].0 -= 1;
+            runs[i].1 += 1;
+          },
+          (false, false) => runs.insert(i, (value, 0)),
+        }
+        self.shed_runs();
+        true
+      },
+    }
+  }
+
+  fn remove(&mut self, value: u16) -> bool {
+    match self {
+      Self::Array(values) => match values.binary_search(&value) {
+        Ok(i) => {
+          values.remove(i);
+          true
+        },
+        Err(_) => false,
+      },
+      Self::Bitset { words, len } => {
+        let (word, bit) = (value as usize / 64, 1 << (value % 64));
+        if words[word] & bit == 0 {
+          return false;
+        }
+        words[word] &= !bit;
+        *len -= 1;
+        if *len <= ARRAY_MAX {
+          *self = Self::Array(self.iter().collect());
+        }
+        true
+      },
+      Self::Runs(runs) => {
+        let i = runs.partition_point(|&(start, _)| start <= value);
+        if i == 0 || value > run_end(runs[i - 1]) {
+          return false;
+        }
+        let (start, extra) = runs[i - 1];
+        let end = run_end(runs[i - 1]);
+        if extra == 0 {
+          runs.remove(i - 1);
+        } else if value == start {
+          runs[i - 1] = (start + 1, extra - 1);
+        } else if value == end {
+          runs[i - 1].1 -= 1;
+        } else {
+          runs[i - 1].1 = value - start - 1;
+          runs.insert(i, (value + 1, end - value - 1));
+        }
+        self.shed_runs();
+        true
+      },
+    }
+  }
+
+  /// The number of values less than or equal to `value`.
+  fn rank(&self, value: u16) -> usize {
+    match self {
+      Self::Array(values) => values.partition_point(|&v| v <= value),
+      Self::Bitset { words, .. } => {
+        let word = value as usize / 64;
+        let full: u32 = words[..word].iter().map(|w| w.count_ones()).sum();
+        let mask = u64::MAX >> (63 - value % 64);
+        (full + (words[word] & mask).count_ones()) as usize
+      },
+      Self::Runs(runs) => runs
+        .iter()
+        .take_while(|&&(start, _)| start <= value)
+        .map(|&run| (run_end(run).min(value) - run.0) as usize + 1)
+        .sum(),
+    }
+  }
+
+  /// The `n`-th smallest value, counting from 0.
+  fn select(&self, mut n: usize) -> Option<u16> {
+    match self {
+      Self::Array(values) => values.get(n).copied(),
+      Self::Bitset { words, .. } => {
+        for (i, &word) in words.iter().enumerate() {
+          let ones = word.count_ones() as usize;
+          if n < ones {
+            let mut word = word;
+            for _ in 0..n {
+              word &= word - 1;
+            }
+            return Some((i * 64) as u16 + word.trailing_zeros() as u16);
+          }
+          n -= ones;
+        }
+        None
+      },
+      Self::Runs(runs) => {
+        for &(start, extra) in runs {
+          if n <= extra as usize {
+            return Some(start + n as u16);
+          }
+          n -= extra as usize + 1;
+        }
+        None
+      },
+    }
+  }
+
+  fn iter(&self) -> Box<dyn Iterator<Item = u16> + '_> {
+    match self {
+      Self::Array(values) => Box::new(values.iter().copied()),
+      Self::Bitset { words, .. } => Box::new(words.iter().enumerate().flat_map(|(i, &word)| {
+        let mut word = word;
+        std::iter::from_fn(move || {
+          if word == 0 {
+            return None;
+          }
+          let bit = word.trailing_zeros() as u16;
+          word &= word - 1;
+          Some((i * 64) as u16 + bit)
+        })
+      })),
+      Self::Runs(runs) => Box::new(runs.iter().flat_map(|&run| run.0..=run_end(run))),
+    }
+  }
+
+  fn bitset_of(values: impl Iterator<Item = u16>) -> Self {
+    let mut words = Box::new([0u64; BITSET_WORDS]);
+    let mut len = 0;
+    for value in values {
+      words[value as usize / 64] |= 1 << (value % 64);
+      len += 1;
+    }
+    Self::Bitset { words, len }
+  }
+
+  fn runs_of(values: impl Iterator<Item = u16>) -> Vec<(u16, u16)> {
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+    for value in values {
+      match runs.last_mut() {
+        Some(run) if run_end(*run) as u32 + 1 == value as u32 => run.1 += 1,
+        _ => runs.push((value, 0)),
+      }
+    }
+    runs
+  }
+
+  /// Switches to whichever form takes the fewest bytes.
+  fn optimize(&mut self) {
+    let len = self.len();
+    let runs = Self::runs_of(self.iter());
+    let array_bytes = len * 2;
+    let bitset_bytes = BITSET_WORDS * 8;
+    let runs_bytes = runs.len() * 4;
+
+    *self = if runs_bytes < array_bytes.min(bitset_bytes) {
+      Self::Runs(runs)
+    } else if len <= ARRAY_MAX {
+      Self::Array(self.iter().collect())
+    } else {
+      Self::bitset_of(self.iter())
+    };
+  }
+
+  /// Leaves the run form once scattered values make it bigger than a
+  /// bitset.
+  fn shed_runs(&mut self) {
+    if matches!(self, Self::Runs(runs) if runs.len() * 4 > BITSET_WORDS * 8) {
+      self.optimize();
+    }
+  }
+
+  /// The approximate heap size of the container.
+  fn size_in_bytes(&self) -> usize {
+    match self {
+      Self::Array(values) => values.len() * 2,
+      Self::Bitset { .. } => BITSET_WORDS * 8,
+      Self::Runs(runs) => runs.len() * 4,
+    }
+  }
+}
+
+fn run_end((start, extra): (u16, u16)) -> u16 {
+  start + extra
+}
+
+/// A set of `u32` values stored in compressed containers.
+#[derive(Debug, Clone, Default)]
+pub struct Bitmap {
+  /// Containers by the high half of their values, sorted and never empty.
+  containers: Vec<(u16, Container)>,
+}
+
+impl Bitmap {
+  /// Creates a new empty bitmap.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns `true` if bitmap is empty.
+  pub fn is_empty(&self) -> bool {
+    self.containers.is_empty()
+  }
+
+  /// Returns number of values in bitmap.
+  pub fn size(&self) -> u64 {
+    self.containers.iter().map(|(_, c)| c.len() as u64).sum()
+  }
+
+  /// Returns `true` if `value` is in the bitmap.
+  pub fn contains(&self, value: u32) -> bool {
+    let (high, low) = split(value);
+    self.container(high).is_some_and(|c| c.contains(low))
+  }
+
+  /// Adds `value` and returns whether it was new.
+  pub fn insert(&mut self, value: u32) -> bool {
+    let (high, low) = split(value);
+    let i = match self.containers.binary_search_by_key(&high, |(key, _)| *key) {
+      Ok(i) => i,
+      Err(i) => {
+        self
+          .containers
+          .insert(i, (high, Container::Array(Vec::new())));
+        i
+      },
+    };
+    self.containers[i].1.insert(low)
+  }
+
+  /// Removes `value` and returns whether it was there.
+  pub fn remove(&mut self, value: u32) -> bool {
+    let (high, low) = split(value);
+    let Ok(i) = self.containers.binary_search_by_key(&high, |(key, _)| *key) else {
+      return false;
+    };
+    let removed = self.containers[i].1.remove(low);
+    if self.containers[i].1.len() == 0 {
+      self.containers.remove(i);
+    }
+    removed
+  }
+
+  /// Returns how many values are less than or equal to `value`.
+  pub fn rank(&self, value: u32) -> u64 {
+    let (high, low) = split(value);
+    let mut rank = 0;
+    for (key, container) in &self.containers {
+      if *key > high {
+        break;
+      }
+      rank += if *key == high {
+        container.rank(low)
+      } else {
+        container.len()
+      } as u64;
+    }
+    rank
+  }
+
+  /// Returns the `n`-th smallest value, counting from 0, so that
+  /// `rank(select(n)) == n + 1`.
+  pub fn select(&self, n: u64) -> Option<u32> {
+    let mut n = n;
+    for (key, container) in &self.containers {
+      let len = container.len() as u64;
+      if n < len {
+        let low = container.select(n as usize)?;
+        return Some(((*key as u32) << 16) | low as u32);
+      }
+      n -= len;
+    }
+    None
+  }
+
+  /// Returns the smallest value.
+  pub fn min(&self) -> Option<u32> {
+    self.select(0)
+  }
+
+  /// Returns the largest value.
+  pub fn max(&self) -> Option<u32> {
+    let (key, container) = self.containers.last()?;
+    let low = container.select(container.len() - 1)?;
+    Some(((*key as u32) << 16) | low as u32)
+  }
+
+  /// Removes every value.
+  pub fn clear(&mut self) {
+    self.containers.clear();
+  }
+
+  /// Stores every container in its most compact form, turning stretches of
+  /// consecutive values into runs. Worth calling once a bitmap is built
+  /// and before it is kept around.
+  pub fn optimize(&mut self) {
+    for (_, container) in &mut self.containers {
+      container.optimize();
+    }
+  }
+
+  /// Returns the approximate number of bytes the bitmap takes on the heap.
+  pub fn size_in_bytes(&self) -> usize {
+    self
+      .containers
+      .iter()
+      .map(|(_, c)| std::mem::size_of::<(u16, Container)>() + c.size_in_bytes())
+      .sum()
+  }
+
+  /// Returns an iterator over the values in ascending order.
+  pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+    self.containers.iter().flat_map(|(key, container)| {
+      let high = (*key as u32) << 16;
+      container.iter().map(move |low| high | low as u32)
+    })
+  }
+
+  fn container(&self, high: u16) -> Option<&Container> {
+    let i = self
+      .containers
+      .binary_search_by_key(&high, |(key, _)| *key)
+      .ok()?;
+    Some(&self.containers[i].1)
+  }
+}
+
+fn split(value: u32) -> (u16, u16) {
+  ((value >> 16) as u16, value as u16)
+}
+
+/// Bitmaps are equal when they hold the same values, whatever form their
+/// containers are in.
+impl PartialEq for Bitmap {
+  fn eq(&self, other: &Self) -> bool {
+    self.iter().eq(other.iter())
+  }
+}
+
+impl Eq for Bitmap {}
+
+impl Extend<u32> for Bitmap {
+  fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+    for value in iter {
+      self.insert(value);
+    }
+  }
+}
+
+impl FromIterator<u32> for Bitmap {
+  fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+    let mut bitmap = Self::new();
+    bitmap.extend(iter);
+    bitmap
+  }
+}
@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod block_test {
+  use std::io;
+
+  use crate::block::{Block, BlockBuilder};
+
+  fn keys(count: usize) -> Vec<Vec<u8>> {
+    (1..=count)
+      .map(|i| format!("123:{i:04}").into_bytes())
+      .collect()
+  }
+
+  fn build(keys: &[Vec<u8>], restart_interval: usize) -> Vec<u8> {
+    let mut builder = BlockBuilder::with_restart_interval(restart_interval);
+    for key in keys {
+      builder.add(key, &[key.as_slice(), b"-value"].concat());
+    }
+    assert_eq!(builder.size(), keys.len());
+    let estimate = builder.size_estimate();
+    let bytes = builder.finish();
+    assert_eq!(bytes.len(), estimate);
+    bytes
+  }
+
+  fn entries<'a>(
+    iter: impl Iterator<Item = Result<(Vec<u8>, &'a [u8]), io::Error>>,
+  ) -> Vec<Vec<u8>> {
+    iter.map(|entry| entry.unwrap().0).collect()
+  }
+
+  // ---------------------------------------------------------
+  // round trip tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn empty_block() {
+    let bytes = BlockBuilder::new().finish();
+    assert_eq!(bytes, 0u32.to_le_bytes());
+
+    let block = Block::new(&bytes).unwrap();
+    assert!(block.is_empty());
+    assert_eq!(block.iter().count(), 0);
+    assert_eq!(block.get(b"any").unwrap(), None);
+    assert_eq!(block.seek(b"any").count(), 0);
+  }
+
+  #[test]
+  fn iter_returns_every_entry_in_order() {
+    let keys = keys(100);
+    for restart_interval in [1, 2, 16, 1000] {
+      let bytes = build(&keys, restart_interval);
+      let block = Block::new(&bytes).unwrap();
+      let decoded = block.iter().map(Result::unwrap).collect::<Vec<_>>();
+      assert_eq!(decoded.len(), keys.len());
+      for ((key, value), expected) in decoded.iter().zip(&keys) {
+        assert_eq!(key, expected);
+        assert_eq!(*value, [expected.as_slice(), b"-value"].concat());
+      }
+    }
+  }
+
+  #[test]
+  fn shared_prefixes_save_space() {
+    let keys = keys(400);
+    let whole = build(&keys, 1).len();
+    let compressed = build(&keys, 16).len();
+    // Each key is 8 bytes and shares 6 or 7 of them with the previous one.
+    assert!(compressed + 400 * 4 < whole, "{compressed} vs {whole}");
+  }
+
+  #[test]
+  fn keys_without_a_shared_prefix() {
+    let keys = [
+      b"a".to_vec(),
+      b"b".to_vec(),
+      b"c".to_vec(),
+      vec![],
+      vec![0xff; 3],
+    ];
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+    let bytes = build(&sorted, 2);
+    let block = Block::new(&bytes).unwrap();
+    assert_eq!(entries(block.iter()), sorted);
+  }
+
+  #[test]
+  #[should_panic(expected = "strictly increasing")]
+  fn out_of_order_keys_panic() {
+    let mut builder = BlockBuilder::new();
+    builder.add(b"b", b"");
+    builder.add(b"a", b"");
+  }
+
+  #[test]
+  #[should_panic(expected = "strictly increasing")]
+  fn duplicate_keys_panic() {
+    let mut builder = BlockBuilder::new();
+    builder.add(b"a", b"1");
+    builder.add(b"a", b"2");
+  }
+
+  // ---------------------------------------------------------
+  // lookup tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn get_finds_every_key() {
+    let keys = keys(300);
+    for restart_interval in [1, 3, 16] {
+      let bytes = build(&keys, restart_interval);
+      let block = Block::new(&bytes).unwrap();
+      for key in &keys {
+        let value = block.get(key).unwrap().unwrap();
+        assert_eq!(value, [key.as_slice(), b"-value"].concat());
+      }
+      assert_eq!(block.get(b"123:").unwrap(), None);
+      assert_eq!(block.get(b"123:0150x").unwrap(), None);
+      assert_eq!(block.get(b"124").unwrap(), None);
+      assert_eq!(block.get(b"").unwrap(), None);
+    }
+  }
+
+  #[test]
+  fn seek_starts_at_the_first_key_not_before() {
+    let keys = keys(100);
+    let bytes = build(&keys, 16);
+    let block = Block::new(&bytes).unwrap();
+
+    assert_eq!(entries(block.seek(b"")), keys);
+    assert_eq!(entries(block.seek(b"123:0050")), keys[49..]);
+    // Between two keys, and right after a restart point.
+    assert_eq!(entries(block.seek(b"123:0050a")), keys[50..]);
+    assert_eq!(entries(block.seek(b"123:0017")), keys[16..]);
+    assert_eq!(entries(block.seek(b"123:0100")), keys[99..]);
+    assert_eq!(block.seek(b"123:0100a").count(), 0);
+  }
+
+  // ---------------------------------------------------------
+  // corruption tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn rejects_a_bad_trailer() {
+    let bytes = build(&keys(40), 16);
+    assert!(Block::new(&bytes[..3]).is_err());
+
+    // More restart points than fit.
+    let mut corrupt = bytes.clone();
+    let count_at = corrupt.len() - 4;
+    corrupt[count_at..].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+      Block::new(&corrupt).unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+
+    // A restart point past the entries.
+    let mut corrupt = bytes.clone();
+    let last_restart = corrupt.len() - 8;
+    corrupt[last_restart..last_restart + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+      Block::new(&corrupt).unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+
+    // Entries without any restart point.
+    let mut corrupt = bytes[..bytes.len() - 3 * 4 - 4].to_vec();
+    corrupt.extend_from_slice(&0u32.to_le_bytes());
+    assert_eq!(
+      Block::new(&corrupt).unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+  }
+
+  #[test]
+  fn corrupt_entries_end_iteration_with_an_error() {
+    let bytes = build(&keys(40), 16);
+    // The second entry claims to share more than the first key has.
+    let mut corrupt = bytes.clone();
+    let second = 3 + 8 + 14;
+    corrupt[second] = 100;
+    let block = Block::new(&corrupt).unwrap();
+
+    let mut iter = block.iter();
+    assert!(iter.next().unwrap().is_ok());
+    assert_eq!(
+      iter.next().unwrap().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+    assert!(iter.next().is_none());
+    assert!(block.get(b"123:0002").is_err());
+  }
+}
@@ -0,0 +1,354 @@
+//! The data block of the planned SSTables, with prefix compressed keys.
+//!
+//! Keys in a block are sorted, so neighbours tend to share long prefixes
+//! (`123:1`, `123:2`, ... `123:400`). Each entry stores only how many bytes
+//! its key shares with the previous one and the bytes that differ:
+//!
+//! ```text
+//! entry   := shared (varint) | unshared (varint) | value_len (varint)
+//!            | key[shared..] | value
+//! trailer := restart offset (u32 LE) * count | count (u32 LE)
+//! ```
+//!
+//! Every `restart_interval` entries the key is stored whole, with `shared`
+//! set to 0. The trailer lists the offsets of these restart points, so
+//! [`Block::get`] and [`Block::seek`] binary search them and only decode the
+//! run of entries after one, instead of the whole block.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::block::{Block, BlockBuilder};
+//!
+//! let mut builder = BlockBuilder::new();
+//! for i in 1..=400 {
+//!   builder.add(format!("123:{i:03}").as_bytes(), b"value");
+//! }
+//! let bytes = builder.finish();
+//!
+//! let block = Block::new(&bytes)?;
+//! assert_eq!(block.get(b"123:042")?, Some(&b"value"[..]));
+//! assert_eq!(block.get(b"124:001")?, None);
+//!
+//! let (key, _) = block.seek(b"123:399").next().unwrap()?;
+//! assert_eq!(key, b"123:399");
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+mod __test__;
+
+use std::io;
+
+use crate::codec::{RecordReader, RecordWriter};
+
+/// Entries between restart points unless configured otherwise.
+pub const DEFAULT_RESTART_INTERVAL: usize = 16;
+
+/// Size of one restart offset, and of the count that ends the block.
+const OFFSET_SIZE: usize = 4;
+
+/// Builds a block out of entries added in strictly increasing key order.
+#[derive(Debug, Clone)]
+pub struct BlockBuilder {
+  buf: RecordWriter,
+  restarts: Vec<u32>,
+  restart_interval: usize,
+  /// Entries since the last restart point.
+  run: usize,
+  last_key: Vec<u8>,
+  entries: usize,
+}
+
+impl Default for BlockBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl BlockBuilder {
+  /// A builder with a restart point every [`DEFAULT_RESTART_INTERVAL`]
+  /// entries.
+  pub fn new() -> Self {
+    Self::with_restart_interval(DEFAULT_RESTART_INTERVAL)
+  }
+
+  /// A builder with a restart point every `restart_interval` entries. An
+  /// interval of 1 stores every key whole; 0 is treated as 1.
+  pub fn with_restart_interval(restart_interval: usize) -> Self {
+    Self {
+      buf: RecordWriter::new(),
+      restarts: Vec::new(),
+      restart_interval: restart_interval.max(1),
+      run: 0,
+      last_key: Vec::new(),
+      entries: 0,
+    }
+  }
+
+  /// Returns `true` if no entry was added yet.
+  pub fn is_empty(&self) -> bool {
+    self.entries == 0
+  }
+
+  /// Returns the number of entries added.
+  pub fn size(&self) -> usize {
+    self.entries
+  }
+
+  /// Returns the size in bytes [`finish`](Self::finish) would produce, to
+  /// decide when a block is full.
+  pub fn size_estimate(&self) -> usize {
+    self.buf.len() + (self.restarts.len() + 1) * OFFSET_SIZE
+  }
+
+  /// Appends an entry.
+  ///
+  /// # Panics
+  ///
+  /// If `key` is not greater than the previously added key.
+  pub fn add(&mut self, key: &[u8], value: &[u8]) {
+    assert!(
+      self.is_empty() || key > self.last_key.as_slice(),
+      "block keys must be added in strictly increasing order"
+    );
+
+    let shared = if self.run == self.restart_interval || self.is_empty() {
+      self.restarts.push(self.buf.len() as u32);
+      self.run = 0;
+      0
+    } else {
+      shared_prefix_len(&self.last_key, key)
+    };
+
+    self.buf.put_varint(shared as u64);
+    self.buf.put_varint((key.len() - shared) as u64);
+    self.buf.put_varint(value.len() as u64);
+    self.buf.put_bytes(&key[shared..]);
+    self.buf.put_bytes(value);
+
+    self.last_key.truncate(shared);
+    self.last_key.extend_from_slice(&key[shared..]);
+    self.run += 1;
+    self.entries += 1;
+  }
+
+  /// Appends the restart points and returns the encoded block.
+  pub fn finish(mut self) -> Vec<u8> {
+    for &restart in &self.restarts {
+      self.buf.put_u32(restart);
+    }
+    self.buf.put_u32(self.restarts.len() as u32);
+    self.buf.into_bytes()
+  }
+}
+
+/// A block parsed from bytes written by [`BlockBuilder::finish`].
+///
+/// Entries are decoded on demand. Corrupted entries surface as `InvalidData`
+/// or `UnexpectedEof` errors when they are reached.
+#[derive(Debug, Clone, Copy)]
+pub struct Block<'a> {
+  /// The entries, without the trailer.
+  data: &'a [u8],
+  restarts: &'a [u8],
+}
+
+impl<'a> Block<'a> {
+  /// Checks the trailer of `bytes`. Fails with `InvalidData` if it doesn't
+  /// describe restart points inside the block.
+  pub fn new(bytes: &'a [u8]) -> Result<Self, io::Error> {
+    let count_at = bytes.len().checked_sub(OFFSET_SIZE).ok_or_else(corrupted)?;
+    let count = u32::from_le_bytes(bytes[count_at..].try_into().unwrap()) as usize;
+    let restarts_at = count
+      .checked_mul(OFFSET_SIZE)
+      .and_then(|len| count_at.checked_sub(len))
+      .ok_or_else(corrupted)?;
+
+    let block = Self {
+      data: &bytes[..restarts_at],
+      restarts: &bytes[restarts_at..count_at],
+    };
+    if count == 0 && !block.data.is_empty() {
+      return Err(corrupted());
+    }
+    let mut previous = None;
+    for i in 0..count {
+      let restart = block.restart(i);
+      let in_order = match previous {
+        None => restart == 0,
+        Some(previous) => restart > previous,
+      };
+      if !in_order || restart >= block.data.len() {
+        return Err(corrupted());
+      }
+      previous = Some(restart);
+    }
+    Ok(block)
+  }
+
+  /// Returns `true` if the block holds no entries.
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Returns the value stored under `key`, if any.
+  pub fn get(&self, key: &[u8]) -> Result<Option<&'a [u8]>, io::Error> {
+    match self.seek(key).next().transpose()? {
+      Some((found, value)) if found == key => Ok(Some(value)),
+      _ => Ok(None),
+    }
+  }
+
+  /// Iterates over every entry in key order.
+  pub fn iter(&self) -> Iter<'a> {
+    Iter::new(self.data, 0)
+  }
+
+  /// Iterates in key order from the first entry whose key is at least
+  /// `key`.
+  pub fn seek(&self, key: &[u8]) -> Iter<'a> {
+    // The last restart point whose key is not past `key`.
+    let (mut low, mut high) = (0, self.restart_count());
+    while high - low > 1 {
+      let mid = low + (high - low) / 2;
+      match self.restart_key(mid) {
+        Ok(restart_key) if restart_key > key => high = mid,
+        Ok(_) => low = mid,
+        Err(e) => return Iter::failed(e),
+      }
+    }
+
+    let start = if self.restart_count() == 0 {
+      0
+    } else {
+      self.restart(low)
+    };
+    let mut iter = Iter::new(self.data, start);
+    loop {
+      let before = iter.clone();
+      match iter.next() {
+        Some(Ok((found, _))) if found.as_slice() < key => {},
+        Some(Ok(_)) => return before,
+        Some(Err(e)) => return Iter::failed(e),
+        None => return iter,
+      }
+    }
+  }
+
+  fn restart_count(&self) -> usize {
+    self.restarts.len() / OFFSET_SIZE
+  }
+
+  fn restart(&self, i: usize) -> usize {
+    let bytes = &self.restarts[i * OFFSET_SIZE..(i + 1) * OFFSET_SIZE];
+    u32::from_le_bytes(bytes.try_into().unwrap()) as usize
+  }
+
+  /// The whole key stored at restart point `i`.
+  fn restart_key(&self, i: usize) -> Result<&'a [u8], io::Error> {
+    let entry = decode_entry(&self.data[self.restart(i)..])?;
+    if entry.shared != 0 {
+      return Err(corrupted());
+    }
+    Ok(entry.suffix)
+  }
+}
+
+/// Entries of a [`Block`] in key order, as `(key, value)` pairs.
+///
+/// Keys are rebuilt from the shared prefixes, so each one is returned as an
+/// owned copy. After an error the iterator is exhausted.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+  data: &'a [u8],
+  offset: usize,
+  key: Vec<u8>,
+  error: Option<io::ErrorKind>,
+}
+
+impl<'a> Iter<'a> {
+  fn new(data: &'a [u8], offset: usize) -> Self {
+    Self {
+      data,
+      offset,
+      key: Vec::new(),
+      error: None,
+    }
+  }
+
+  fn failed(error: io::Error) -> Self {
+    Self {
+      data: &[],
+      offset: 0,
+      key: Vec::new(),
+      error: Some(error.kind()),
+    }
+  }
+}
+
+impl<'a> Iterator for Iter<'a> {
+  type Item = Result<(Vec<u8>, &'a [u8]), io::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(kind) = self.error.take() {
+      return Some(Err(io::Error::new(kind, "Corrupted block")));
+    }
+    if self.offset >= self.data.len() {
+      return None;
+    }
+
+    let entry = match decode_entry(&self.data[self.offset..]) {
+      Ok(entry) if entry.shared <= self.key.len() => entry,
+      Ok(_) => return Some(Err(self.fail(corrupted()))),
+      Err(e) => return Some(Err(self.fail(e))),
+    };
+    self.key.truncate(entry.shared);
+    self.key.extend_from_slice(entry.suffix);
+    self.offset += entry.len;
+    Some(Ok((self.key.clone(), entry.value)))
+  }
+}
+
+impl Iter<'_> {
+  /// Ends the iteration after `error`.
+  fn fail(&mut self, error: io::Error) -> io::Error {
+    self.offset = self.data.len();
+    error
+  }
+}
+
+/// One entry as stored, before its key is rebuilt.
+struct Entry<'a> {
+  shared: usize,
+  suffix: &'a [u8],
+  value: &'a [u8],
+  /// Encoded size of the entry.
+  len: usize,
+}
+
+fn decode_entry(bytes: &[u8]) -> Result<Entry<'_>, io::Error> {
+  let mut reader = RecordReader::new(bytes);
+  let shared = reader.get_varint()?;
+  let unshared = reader.get_varint()?;
+  let value_len = reader.get_varint()?;
+  let len = |value: u64| usize::try_from(value).map_err(|_| corrupted());
+
+  let shared = len(shared)?;
+  let suffix = reader.get_bytes(len(unshared)?)?;
+  let value = reader.get_bytes(len(value_len)?)?;
+  Ok(Entry {
+    shared,
+    suffix,
+    value,
+    len: reader.position(),
+  })
+}
+
+/// How many leading bytes `a` and `b` have in common.
+fn shared_prefix_len(a: &[u8], b: &[u8]) -> usize {
+  a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn corrupted() -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, "Corrupted block")
+}
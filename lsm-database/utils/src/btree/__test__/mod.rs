@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod btree_test {
+  use std::collections::BTreeMap as StdBTreeMap;
+
+  use crate::btree::{BTreeMap, Node};
+
+  /// Checks key order, node sizes and that all leaves are at one depth, and
+  /// returns that depth.
+  fn check_node<K: Ord, V>(node: &Node<K, V>, t: usize, is_root: bool) -> usize {
+    assert!(node.keys.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(node.keys.len(), node.values.len());
+    assert!(node.keys.len() < 2 * t);
+    if !is_root {
+      assert!(node.keys.len() >= t - 1);
+    }
+    if node.is_leaf() {
+      return 1;
+    }
+
+    assert_eq!(node.children.len(), node.keys.len() + 1);
+    for (i, child) in node.children.iter().enumerate() {
+      if i > 0 {
+        assert!(child.keys.iter().all(|key| *key > node.keys[i - 1]));
+      }
+      if i < node.keys.len() {
+        assert!(child.keys.iter().all(|key| *key < node.keys[i]));
+      }
+    }
+    let depths: Vec<_> = node
+      .children
+      .iter()
+      .map(|child| check_node(child, t, false))
+      .collect();
+    assert!(depths.windows(2).all(|pair| pair[0] == pair[1]));
+    depths[0] + 1
+  }
+
+  fn check<K: Ord, V>(map: &BTreeMap<K, V>) {
+    assert!(map.root.is_leaf() || !map.root.keys.is_empty());
+    assert_eq!(check_node(&map.root, map.min_degree, true), map.height());
+    assert_eq!(map.iter().count(), map.size());
+  }
+
+  #[test]
+  fn new_map_is_empty() {
+    let map: BTreeMap<u32, u32> = BTreeMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.size(), 0);
+    assert_eq!(map.height(), 1);
+    assert_eq!(map.fanout(), 12);
+    assert!(map.get(&1).is_none());
+    assert!(map.first().is_none());
+    assert!(map.last().is_none());
+    assert_eq!(map.iter().count(), 0);
+  }
+
+  #[test]
+  #[should_panic(expected = "fanout must be an even number")]
+  fn rejects_odd_fanout() {
+    BTreeMap::<u32, u32>::with_fanout(5);
+  }
+
+  #[test]
+  fn insert_replaces_existing_value() {
+    let mut map = BTreeMap::with_fanout(4);
+    assert_eq!(map.insert("a", 1), None);
+    assert_eq!(map.insert("a", 2), Some(1));
+    assert_eq!(map.get("a"), Some(&2));
+    assert_eq!(map.size(), 1);
+  }
+
+  #[test]
+  fn fanout_controls_height() {
+    let mut narrow = BTreeMap::with_fanout(4);
+    narrow.extend((0..1_000u32).map(|key| (key, ())));
+    let wide: BTreeMap<u32, ()> = (0..1_000).map(|key| (key, ())).collect();
+
+    check(&narrow);
+    check(&wide);
+    assert!(narrow.height() > wide.height());
+    assert_eq!(narrow, wide);
+  }
+
+  #[test]
+  fn iterates_in_key_order() {
+    let map: BTreeMap<u32, u32> = [50, 10, 40, 20, 30]
+      .into_iter()
+      .map(|k| (k, k * 2))
+      .collect();
+
+    let entries: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(
+      entries,
+      vec![(10, 20), (20, 40), (30, 60), (40, 80), (50, 100)]
+    );
+    assert_eq!(map.iter().len(), 5);
+    assert_eq!(map.first(), Some((&10, &20)));
+    assert_eq!(map.last(), Some((&50, &100)));
+  }
+
+  #[test]
+  fn remove_shrinks_back_to_a_leaf() {
+    let mut map = BTreeMap::with_fanout(4);
+    map.extend((0..200u32).map(|key| (key, key)));
+
+    for key in (0..200).rev() {
+      assert_eq!(map.remove(&key), Some(key));
+      check(&map);
+    }
+    assert!(map.is_empty());
+    assert_eq!(map.height(), 1);
+    assert_eq!(map.remove(&0), None);
+  }
+
+  #[test]
+  fn removing_missing_keys_keeps_the_tree_balanced() {
+    let keys = [
+      15, 32, 24, 25, 50, 17, 10, 52, 37, 8, 4, 22, 35, 20, 33, 42, 14, 29, 23, 19, 53,
+    ];
+    let mut map = BTreeMap::with_fanout(4);
+    map.extend(keys.iter().map(|&key| (key, key)));
+    let height = map.height();
+
+    // Each miss still merges nodes on the way down.
+    for missing in [6, 45, 49] {
+      assert_eq!(map.remove(&missing), None);
+      check(&map);
+    }
+    assert!(map.height() <= height);
+    assert_eq!(map.size(), keys.len());
+    for key in keys {
+      assert_eq!(map.remove(&key), Some(key));
+      check(&map);
+    }
+    assert!(map.is_empty());
+  }
+
+  #[test]
+  fn matches_std_btree_map() {
+    for fanout in [4, 6, 12] {
+      let mut map = BTreeMap::with_fanout(fanout);
+      let mut model = StdBTreeMap::new();
+      let mut seed = 7u64;
+
+      for i in 0..20_000u64 {
+        seed = seed
+          .wrapping_mul(6364136223846793005)
+          .wrapping_add(1442695040888963407);
+        let key = (seed >> 33) % 500;
+        if seed & 3 == 0 {
+          assert_eq!(map.remove(&key), model.remove(&key));
+        } else {
+          assert_eq!(map.insert(key, i), model.insert(key, i));
+        }
+        if i % 1_000 == 0 {
+          check(&map);
+        }
+      }
+
+      check(&map);
+      assert_eq!(map.size(), model.len());
+      assert!(map.iter().eq(model.iter()));
+    }
+  }
+}
@@ -0,0 +1,482 @@
+//! An ordered map built as a B-tree with a configurable fanout.
+//!
+//! Every node holds a sorted run of keys and, unless it is a leaf, one more
+//! child than keys. Nodes other than the root are kept at least half full
+//! by splitting full nodes on the way down during inserts and by borrowing
+//! from or merging with a sibling on the way down during removes, so all
+//! leaves stay at the same depth and every operation takes `O(log n)`.
+//!
+//! The implementation follows the textbook (CLRS) algorithms closely and
+//! uses only owned nodes, no `unsafe`, so it is easy to read next to the
+//! red-black tree of the memtable.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::btree::BTreeMap;
+//!
+//! let mut map = BTreeMap::with_fanout(4);
+//! for key in [30, 10, 50, 20, 40] {
+//!   map.insert(key, key * 10);
+//! }
+//!
+//! assert_eq!(map.get(&20), Some(&200));
+//! assert_eq!(map.remove(&30), Some(300));
+//!
+//! let keys: Vec<_> = map.iter().map(|(key, _)| *key).collect();
+//! assert_eq!(keys, vec![10, 20, 40, 50]);
+//! ```
+
+mod __test__;
+
+use std::borrow::Borrow;
+
+/// The fanout [`BTreeMap::new`] uses, the same as the standard library's.
+pub const DEFAULT_FANOUT: usize = 12;
+
+/// A node with up to `fanout - 1` entries.
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+  keys: Vec<K>,
+  values: Vec<V>,
+  /// Empty for a leaf, `keys.len() + 1` children otherwise.
+  children: Vec<Node<K, V>>,
+}
+
+impl<K, V> Node<K, V> {
+  fn leaf() -> Self {
+    Self {
+      keys: Vec::new(),
+      values: Vec::new(),
+      children: Vec::new(),
+    }
+  }
+
+  fn is_leaf(&self) -> bool {
+    self.children.is_empty()
+  }
+
+  /// Where `key` is among the keys of this node.
+  fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.keys.binary_search_by(|k| k.borrow().cmp(key))
+  }
+}
+
+/// An ordered map from `K` to `V`.
+#[derive(Debug, Clone)]
+pub struct BTreeMap<K, V> {
+  root: Node<K, V>,
+  /// The minimum degree: nodes other than the root hold between
+  /// `min_degree - 1` and `2 * min_degree - 1` keys.
+  min_degree: usize,
+  len: usize,
+}
+
+impl<K: Ord, V> Default for BTreeMap<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Ord, V> BTreeMap<K, V> {
+  /// Creates a new empty map with [`DEFAULT_FANOUT`].
+  pub fn new() -> Self {
+    Self::with_fanout(DEFAULT_FANOUT)
+  }
+
+  /// Creates a new empty map whose nodes have at most `fanout` children.
+  ///
+  /// A small fanout makes a deep tree that is handy for following the
+  /// algorithms by hand; a large one makes a shallow tree with long runs of
+  /// keys per node.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `fanout` is odd or less than 4.
+  pub fn with_fanout(fanout: usize) -> Self {
+    assert!(
+      fanout >= 4 && fanout.is_multiple_of(2),
+      "fanout must be an even number of at least 4, got {fanout}"
+    );
+    Self {
+      root: Node::leaf(),
+      min_degree: fanout / 2,
+      len: 0,
+    }
+  }
+
+  /// Returns the most children a node can have.
+  pub fn fanout(&self) -> usize {
+    2 * self.min_degree
+  }
+
+  /// Returns `true` if map is empty.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns number of entries in map.
+  pub fn size(&self) -> usize {
+    self.len
+  }
+
+  /// Returns the number of levels of nodes, 1 for a map that fits in the
+  /// root.
+  pub fn height(&self) -> usize {
+    let mut height = 1;
+    let mut node = &self.root;
+    while let Some(child) = node.children.first() {
+      height += 1;
+      node = child;
+    }
+    height
+  }
+
+  /// Returns `true` if the map has an entry for `key`.
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.get(key).is_some()
+  }
+
+  /// Returns the value of `key`.
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut node = &self.root;
+    loop {
+      match node.search(key) {
+        Ok(i) => return Some(&node.values[i]),
+        Err(_) if node.is_leaf() => return None,
+        Err(i) => node = &node.children[i],
+      }
+    }
+  }
+
+  /// Returns the value of `key` for changing it in place.
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let mut node = &mut self.root;
+    loop {
+      match node.search(key) {
+        Ok(i) => return Some(&mut node.values[i]),
+        Err(_) if node.is_leaf() => return None,
+        Err(i) => node = &mut node.children[i],
+      }
+    }
+  }
+
+  /// Returns the entry with the smallest key.
+  pub fn first(&self) -> Option<(&K, &V)> {
+    let mut node = &self.root;
+    while let Some(child) = node.children.first() {
+      node = child;
+    }
+    Some((node.keys.first()?, node.values.first()?))
+  }
+
+  /// Returns the entry with the largest key.
+  pub fn last(&self) -> Option<(&K, &V)> {
+    let mut node = &self.root;
+    while let Some(child) = node.children.last() {
+      node = child;
+    }
+    Some((node.keys.last()?, node.values.last()?))
+  }
+
+  /// Stores `value` under `key` and returns the value it replaced.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    if let Some(old) = self.get_mut(&key) {
+      return Some(std::mem::replace(old, value));
+    }
+
+    let t = self.min_degree;
+    if self.root.keys.len() == 2 * t - 1 {
+      let old_root = std::mem::replace(&mut self.root, Node::leaf());
+      self.root.children.push(old_root);
+      split_child(&mut self.root, 0, t);
+    }
+
+    let mut node = &mut self.root;
+    loop {
+      let mut i = node.search(&key).unwrap_err();
+      if node.is_leaf() {
+        node.keys.insert(i, key);
+        node.values.insert(i, value);
+        break;
+      }
+      if node.children[i].keys.len() == 2 * t - 1 {
+        split_child(node, i, t);
+        if key > node.keys[i] {
+          i += 1;
+        }
+      }
+      node = &mut node.children[i];
+    }
+
+    self.len += 1;
+    None
+  }
+
+  /// Removes `key` and returns its value.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let removed = remove_from(&mut self.root, key, self.min_degree);
+    // A merge on the way down can leave the root without keys, even when
+    // the key turns out to be missing; its only child takes over.
+    if self.root.keys.is_empty() {
+      if let Some(child) = self.root.children.pop() {
+        self.root = child;
+      }
+    }
+    let (_, value) = removed?;
+    self.len -= 1;
+    Some(value)
+  }
+
+  /// Removes every entry.
+  pub fn clear(&mut self) {
+    self.root = Node::leaf();
+    self.len = 0;
+  }
+
+  /// Returns an iterator over the entries in ascending key order.
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    let mut iter = Iter {
+      stack: Vec::new(),
+      remaining: self.len,
+    };
+    iter.descend(&self.root);
+    iter
+  }
+}
+
+/// Splits the full child `i` of `parent` around its middle key, which moves
+/// up into `parent`.
+fn split_child<K, V>(parent: &mut Node<K, V>, i: usize, t: usize) {
+  let child = &mut parent.children[i];
+  let right = Node {
+    keys: child.keys.split_off(t),
+    values: child.values.split_off(t),
+    children: if child.is_leaf() {
+      Vec::new()
+    } else {
+      child.children.split_off(t)
+    },
+  };
+  let key = child.keys.pop().expect("a full node has a middle key");
+  let value = child.values.pop().expect("a full node has a middle value");
+
+  parent.keys.insert(i, key);
+  parent.values.insert(i, value);
+  parent.children.insert(i + 1, right);
+}
+
+/// Removes `key` from the subtree of `node`, which has at least `t` keys
+/// unless it is the root.
+fn remove_from<K, V, Q>(node: &mut Node<K, V>, key: &Q, t: usize) -> Option<(K, V)>
+where
+  K: Borrow<Q>,
+  Q: Ord + ?Sized,
+{
+  match node.search(key) {
+    Ok(i) if node.is_leaf() => Some((node.keys.remove(i), node.values.remove(i))),
+    Ok(i) => {
+      // Replace the entry with its predecessor or successor from a child
+      // that can spare one, or merge the two children around it.
+      if node.children[i].keys.len() >= t {
+        let (k, v) = remove_last(&mut node.children[i], t);
+        Some(replace_entry(node, i, k, v))
+      } else if node.children[i + 1].keys.len() >= t {
+        let (k, v) = remove_first(&mut node.children[i + 1], t);
+        Some(replace_entry(node, i, k, v))
+      } else {
+        merge_children(node, i);
+        remove_from(&mut node.children[i], key, t)
+      }
+    },
+    Err(_) if node.is_leaf() => None,
+    Err(i) => {
+      let i = ensure_spare(node, i, t);
+      remove_from(&mut node.children[i], key, t)
+    },
+  }
+}
+
+fn remove_first<K, V>(node: &mut Node<K, V>, t: usize) -> (K, V) {
+  if node.is_leaf() {
+    return (node.keys.remove(0), node.values.remove(0));
+  }
+  let i = ensure_spare(node, 0, t);
+  remove_first(&mut node.children[i], t)
+}
+
+fn remove_last<K, V>(node: &mut Node<K, V>, t: usize) -> (K, V) {
+  if node.is_leaf() {
+    let key = node
+      .keys
+      .pop()
+      .expect("a child being removed from has keys");
+    let value = node
+      .values
+      .pop()
+      .expect("a child being removed from has values");
+    return (key, value);
+  }
+  let i = ensure_spare(node, node.children.len() - 1, t);
+  remove_last(&mut node.children[i], t)
+}
+
+fn replace_entry<K, V>(node: &mut Node<K, V>, i: usize, key: K, value: V) -> (K, V) {
+  (
+    std::mem::replace(&mut node.keys[i], key),
+    std::mem::replace(&mut node.values[i], value),
+  )
+}
+
+/// Makes sure child `i` has at least `t` keys before descending into it,
+/// by rotating a key from a sibling through the parent or by merging with a
+/// sibling. Returns the index of the child that now covers the same keys.
+fn ensure_spare<K, V>(node: &mut Node<K, V>, i: usize, t: usize) -> usize {
+  if node.children[i].keys.len() >= t {
+    return i;
+  }
+
+  if i > 0 && node.children[i - 1].keys.len() >= t {
+    let (left, right) = node.children.split_at_mut(i);
+    let (left, child) = (&mut left[i - 1], &mut right[0]);
+    let key = std::mem::replace(&mut node.keys[i - 1], left.keys.pop().unwrap());
+    let value = std::mem::replace(&mut node.values[i - 1], left.values.pop().unwrap());
+    child.keys.insert(0, key);
+    child.values.insert(0, value);
+    if let Some(grandchild) = left.children.pop() {
+      child.children.insert(0, grandchild);
+    }
+    return i;
+  }
+
+  if i + 1 < node.children.len() && node.children[i + 1].keys.len() >= t {
+    let (left, right) = node.children.split_at_mut(i + 1);
+    let (child, right) = (&mut left[i], &mut right[0]);
+    let key = std::mem::replace(&mut node.keys[i], right.keys.remove(0));
+    let value = std::mem::replace(&mut node.values[i], right.values.remove(0));
+    child.keys.push(key);
+    child.values.push(value);
+    if !right.is_leaf() {
+      child.children.push(right.children.remove(0));
+    }
+    return i;
+  }
+
+  if i + 1 < node.children.len() {
+    merge_children(node, i);
+    i
+  } else {
+    merge_children(node, i - 1);
+    i - 1
+  }
+}
+
+/// Merges child `i + 1` and the key between them into child `i`.
+fn merge_children<K, V>(node: &mut Node<K, V>, i: usize) {
+  let right = node.children.remove(i + 1);
+  let key = node.keys.remove(i);
+  let value = node.values.remove(i);
+
+  let left = &mut node.children[i];
+  left.keys.push(key);
+  left.values.push(value);
+  left.keys.extend(right.keys);
+  left.values.extend(right.values);
+  left.children.extend(right.children);
+}
+
+/// Iterator over the entries of a [`BTreeMap`] in ascending key order.
+pub struct Iter<'a, K, V> {
+  /// The nodes on the path to the next entry, with the index of the next
+  /// key to return from each.
+  stack: Vec<(&'a Node<K, V>, usize)>,
+  remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+  fn descend(&mut self, mut node: &'a Node<K, V>) {
+    loop {
+      self.stack.push((node, 0));
+      match node.children.first() {
+        Some(child) => node = child,
+        None => break,
+      }
+    }
+  }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let (node, i) = self.stack.pop()?;
+      if i < node.keys.len() {
+        self.stack.push((node, i + 1));
+        if let Some(child) = node.children.get(i + 1) {
+          self.descend(child);
+        }
+        self.remaining -= 1;
+        return Some((&node.keys[i], &node.values[i]));
+      }
+    }
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BTreeMap<K, V> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
+  fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+    for (key, value) in iter {
+      self.insert(key, value);
+    }
+  }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut map = Self::new();
+    map.extend(iter);
+    map
+  }
+}
+
+/// Maps are equal when they hold the same entries, whatever their fanout.
+impl<K: Ord, V: PartialEq> PartialEq for BTreeMap<K, V> {
+  fn eq(&self, other: &Self) -> bool {
+    self.len == other.len && self.iter().eq(other.iter())
+  }
+}
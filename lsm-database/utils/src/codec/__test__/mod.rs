@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod codec_test {
+  use std::io;
+
+  use crate::codec::{RecordReader, RecordWriter};
+
+  #[test]
+  fn fields_round_trip() {
+    let mut writer = RecordWriter::with_capacity(64);
+    writer.put_u8(7);
+    writer.put_u32(0xdead_beef);
+    writer.put_u64(u64::MAX);
+    writer.put_i64(-5);
+    writer.put_varint(300);
+    writer.put_prefixed(b"key");
+    writer.put_bytes(b"value");
+    assert_eq!(writer.len(), 1 + 4 + 8 + 8 + 2 + 8 + 3 + 5);
+
+    let bytes = writer.into_bytes();
+    let mut reader = RecordReader::new(&bytes);
+    assert_eq!(reader.get_u8().unwrap(), 7);
+    assert_eq!(reader.get_u32().unwrap(), 0xdead_beef);
+    assert_eq!(reader.get_u64().unwrap(), u64::MAX);
+    assert_eq!(reader.get_i64().unwrap(), -5);
+    assert_eq!(reader.get_varint().unwrap(), 300);
+    assert_eq!(reader.get_prefixed(16).unwrap(), b"key");
+    assert_eq!(reader.get_bytes(5).unwrap(), b"value");
+    assert!(reader.is_empty());
+  }
+
+  #[test]
+  fn layout_is_little_endian() {
+    let mut writer = RecordWriter::new();
+    writer.put_u32(1);
+    writer.put_i64(-1);
+    assert_eq!(
+      writer.as_bytes(),
+      [1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+  }
+
+  #[test]
+  fn short_input_is_unexpected_eof() {
+    let mut reader = RecordReader::new(&[1, 2, 3]);
+    let err = reader.get_u64().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    // A failed read consumes nothing.
+    assert_eq!(reader.position(), 0);
+    assert_eq!(
+      reader.get_bytes(usize::MAX).unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+
+    let mut reader = RecordReader::new(&[0x80, 0x80]);
+    assert_eq!(
+      reader.get_varint().unwrap_err().kind(),
+      io::ErrorKind::UnexpectedEof
+    );
+  }
+
+  #[test]
+  fn oversized_lengths_are_invalid_data() {
+    let mut writer = RecordWriter::new();
+    writer.put_prefixed(&[0; 10]);
+    let bytes = writer.into_bytes();
+
+    let mut reader = RecordReader::new(&bytes);
+    assert_eq!(
+      reader.get_prefixed(9).unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+
+    let mut reader = RecordReader::new(&[0xff; 11]);
+    assert_eq!(
+      reader.get_varint().unwrap_err().kind(),
+      io::ErrorKind::InvalidData
+    );
+  }
+
+  #[test]
+  fn read_since_covers_parsed_fields() {
+    let mut writer = RecordWriter::new();
+    writer.put_u32(0);
+    writer.put_prefixed(b"body");
+    let bytes = writer.into_bytes();
+
+    let mut reader = RecordReader::new(&bytes);
+    reader.get_u32().unwrap();
+    let start = reader.position();
+    reader.get_prefixed(64).unwrap();
+    assert_eq!(reader.read_since(start), &bytes[4..]);
+    assert_eq!(reader.remaining(), 0);
+  }
+}
@@ -0,0 +1,210 @@
+//! Little-endian field encoding for on-disk records.
+//!
+//! Record headers, hint entries and network frames are all runs of
+//! fixed-width little-endian integers and length-prefixed byte strings.
+//! [`RecordWriter`] appends such fields to a buffer and [`RecordReader`]
+//! takes them back off a slice in the same order, so a format is written
+//! down once as a sequence of `put_*` calls and once as the matching `get_*`
+//! calls instead of as offset arithmetic spread over every reader.
+//!
+//! Reading past the end of the slice fails with `UnexpectedEof`, which is
+//! how the engines already tell a torn write from corruption.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::codec::{RecordReader, RecordWriter};
+//!
+//! let mut writer = RecordWriter::new();
+//! writer.put_i64(1_700_000_000);
+//! writer.put_prefixed(b"user:1");
+//! writer.put_u64(42);
+//! let bytes = writer.into_bytes();
+//!
+//! let mut reader = RecordReader::new(&bytes);
+//! assert_eq!(reader.get_i64()?, 1_700_000_000);
+//! assert_eq!(reader.get_prefixed(usize::MAX)?, b"user:1");
+//! assert_eq!(reader.get_u64()?, 42);
+//! assert!(reader.is_empty());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+mod __test__;
+
+use std::io;
+
+use crate::varint;
+
+/// Appends fields to a byte buffer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordWriter {
+  buf: Vec<u8>,
+}
+
+impl RecordWriter {
+  /// Creates a writer with an empty buffer.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a writer with room for `capacity` bytes.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      buf: Vec::with_capacity(capacity),
+    }
+  }
+
+  /// Returns the number of bytes written.
+  pub fn len(&self) -> usize {
+    self.buf.len()
+  }
+
+  /// Returns `true` if nothing was written.
+  pub fn is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
+
+  /// Returns the bytes written so far.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf
+  }
+
+  /// Returns the buffer.
+  pub fn into_bytes(self) -> Vec<u8> {
+    self.buf
+  }
+
+  pub fn put_u8(&mut self, value: u8) {
+    self.buf.push(value);
+  }
+
+  pub fn put_u32(&mut self, value: u32) {
+    self.buf.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn put_u64(&mut self, value: u64) {
+    self.buf.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn put_i64(&mut self, value: i64) {
+    self.buf.extend_from_slice(&value.to_le_bytes());
+  }
+
+  /// Writes `value` as a [`varint`].
+  pub fn put_varint(&mut self, value: u64) {
+    varint::encode_u64(value, &mut self.buf);
+  }
+
+  /// Writes `bytes` as they are, without their length.
+  pub fn put_bytes(&mut self, bytes: &[u8]) {
+    self.buf.extend_from_slice(bytes);
+  }
+
+  /// Writes the length of `bytes` as a `u64` followed by `bytes`.
+  pub fn put_prefixed(&mut self, bytes: &[u8]) {
+    self.put_u64(bytes.len() as u64);
+    self.put_bytes(bytes);
+  }
+}
+
+/// Takes fields off the front of a byte slice.
+#[derive(Debug, Clone)]
+pub struct RecordReader<'a> {
+  buf: &'a [u8],
+  position: usize,
+}
+
+impl<'a> RecordReader<'a> {
+  /// Creates a reader at the start of `buf`.
+  pub fn new(buf: &'a [u8]) -> Self {
+    Self { buf, position: 0 }
+  }
+
+  /// Returns how many bytes were read.
+  pub fn position(&self) -> usize {
+    self.position
+  }
+
+  /// Returns how many bytes are left.
+  pub fn remaining(&self) -> usize {
+    self.buf.len() - self.position
+  }
+
+  /// Returns `true` if every byte was read.
+  pub fn is_empty(&self) -> bool {
+    self.remaining() == 0
+  }
+
+  /// Returns the bytes read since `start`, a previous
+  /// [`position`](Self::position), e.g. to checksum a record once parsed.
+  pub fn read_since(&self, start: usize) -> &'a [u8] {
+    &self.buf[start..self.position]
+  }
+
+  pub fn get_u8(&mut self) -> Result<u8, io::Error> {
+    Ok(self.get_array::<1>()?[0])
+  }
+
+  pub fn get_u32(&mut self) -> Result<u32, io::Error> {
+    self.get_array().map(u32::from_le_bytes)
+  }
+
+  pub fn get_u64(&mut self) -> Result<u64, io::Error> {
+    self.get_array().map(u64::from_le_bytes)
+  }
+
+  pub fn get_i64(&mut self) -> Result<i64, io::Error> {
+    self.get_array().map(i64::from_le_bytes)
+  }
+
+  /// Reads a [`varint`]. An overlong one is `InvalidData`.
+  pub fn get_varint(&mut self) -> Result<u64, io::Error> {
+    let rest = &self.buf[self.position..];
+    match varint::decode_u64(rest) {
+      Some((value, len)) => {
+        self.position += len;
+        Ok(value)
+      },
+      None if rest.len() < varint::MAX_LEN_U64 && rest.iter().all(|byte| byte & 0x80 != 0) => {
+        Err(truncated())
+      },
+      None => Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Malformed varint",
+      )),
+    }
+  }
+
+  /// Reads the next `N` bytes.
+  pub fn get_array<const N: usize>(&mut self) -> Result<[u8; N], io::Error> {
+    Ok(self.get_bytes(N)?.try_into().unwrap())
+  }
+
+  /// Reads the next `len` bytes.
+  pub fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], io::Error> {
+    let end = self
+      .position
+      .checked_add(len)
+      .filter(|&end| end <= self.buf.len())
+      .ok_or_else(truncated)?;
+    let bytes = &self.buf[self.position..end];
+    self.position = end;
+    Ok(bytes)
+  }
+
+  /// Reads bytes written by [`RecordWriter::put_prefixed`]. A length over
+  /// `max_len` is `InvalidData`, so a corrupted length can't pass for a
+  /// truncated record.
+  pub fn get_prefixed(&mut self, max_len: usize) -> Result<&'a [u8], io::Error> {
+    let len = self.get_u64()?;
+    let len = usize::try_from(len)
+      .ok()
+      .filter(|&len| len <= max_len)
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Field length out of range"))?;
+    self.get_bytes(len)
+  }
+}
+
+fn truncated() -> io::Error {
+  io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated record")
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod crc_test {
+  use crate::crc::{crc32c, crc64, Crc32c, Crc64};
+
+  #[test]
+  fn crc32c_known_answers() {
+    assert_eq!(crc32c(b""), 0);
+    assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    // RFC 3720, appendix B.4.
+    assert_eq!(crc32c(&[0x00; 32]), 0x8a91_36aa);
+    assert_eq!(crc32c(&[0xff; 32]), 0x62a8_ab43);
+    let ascending: Vec<u8> = (0..32).collect();
+    assert_eq!(crc32c(&ascending), 0x46dd_794e);
+  }
+
+  #[test]
+  fn crc64_known_answers() {
+    assert_eq!(crc64(b""), 0);
+    assert_eq!(crc64(b"123456789"), 0x995d_c9bb_df19_39fa);
+  }
+
+  #[test]
+  fn incremental_matches_one_shot() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(3_000).collect();
+
+    for split in [1, 7, 64, 1_000] {
+      let mut crc32 = Crc32c::new();
+      let mut crc64_hasher = Crc64::default();
+      for chunk in data.chunks(split) {
+        crc32.update(chunk);
+        crc64_hasher.update(chunk);
+      }
+      assert_eq!(crc32.finalize(), crc32c(&data));
+      assert_eq!(crc64_hasher.finalize(), crc64(&data));
+    }
+  }
+
+  #[test]
+  fn finalize_does_not_end_the_checksum() {
+    let mut crc = Crc32c::new();
+    crc.update(b"1234");
+    assert_eq!(crc.finalize(), crc32c(b"1234"));
+    crc.update(b"56789");
+    assert_eq!(crc.finalize(), crc32c(b"123456789"));
+
+    crc.reset();
+    assert_eq!(crc, Crc32c::new());
+  }
+
+  #[test]
+  fn detects_single_bit_flips() {
+    let data = b"key=user:1 value=hello".to_vec();
+    let (crc32, crc64_sum) = (crc32c(&data), crc64(&data));
+
+    for bit in 0..data.len() * 8 {
+      let mut corrupted = data.clone();
+      corrupted[bit / 8] ^= 1 << (bit % 8);
+      assert_ne!(crc32c(&corrupted), crc32);
+      assert_ne!(crc64(&corrupted), crc64_sum);
+    }
+  }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod cuckoo_test {
+  use crate::cuckoo::CuckooFilter;
+
+  #[test]
+  fn new_filter_is_empty() {
+    let filter = CuckooFilter::with_capacity(100);
+    assert!(filter.is_empty());
+    assert_eq!(filter.size(), 0);
+    assert!(filter.capacity() >= 100);
+    assert!(!filter.contains("missing"));
+  }
+
+  #[test]
+  fn inserted_keys_are_always_found() {
+    let mut filter = CuckooFilter::with_capacity(10_000);
+    for i in 0..10_000 {
+      assert!(filter.insert(format!("key:{i}")));
+    }
+
+    assert_eq!(filter.size(), 10_000);
+    assert!((0..10_000).all(|i| filter.contains(format!("key:{i}"))));
+  }
+
+  #[test]
+  fn false_positive_rate_is_low() {
+    let mut filter = CuckooFilter::with_capacity(10_000);
+    for i in 0..10_000 {
+      filter.insert(format!("key:{i}"));
+    }
+
+    let false_positives = (0..100_000)
+      .filter(|i| filter.contains(format!("other:{i}")))
+      .count();
+    assert!(false_positives < 100, "{false_positives} false positives");
+  }
+
+  #[test]
+  fn remove_forgets_keys() {
+    let mut filter = CuckooFilter::with_capacity(1_000);
+    for i in 0..1_000 {
+      filter.insert(format!("key:{i}"));
+    }
+    for i in (0..1_000).step_by(2) {
+      assert!(filter.remove(format!("key:{i}")));
+    }
+
+    assert_eq!(filter.size(), 500);
+    assert!((1..1_000)
+      .step_by(2)
+      .all(|i| filter.contains(format!("key:{i}"))));
+    let remaining = (0..1_000)
+      .step_by(2)
+      .filter(|i| filter.contains(format!("key:{i}")))
+      .count();
+    assert!(remaining < 5);
+    assert!(!filter.remove("never inserted"));
+  }
+
+  #[test]
+  fn duplicates_need_as_many_removes() {
+    let mut filter = CuckooFilter::with_capacity(8);
+    filter.insert("dup");
+    filter.insert("dup");
+
+    assert!(filter.remove("dup"));
+    assert!(filter.contains("dup"));
+    assert!(filter.remove("dup"));
+    assert!(!filter.contains("dup"));
+  }
+
+  #[test]
+  fn full_filter_rejects_inserts_without_losing_keys() {
+    let mut filter = CuckooFilter::with_capacity(16);
+    let inserted: Vec<_> = (0..1_000)
+      .take_while(|i| filter.insert(format!("key:{i}")))
+      .collect();
+
+    assert!(inserted.len() >= 16);
+    assert!(!filter.insert("one more"));
+    assert_eq!(filter.size(), inserted.len());
+    assert!(inserted.iter().all(|i| filter.contains(format!("key:{i}"))));
+
+    assert!(filter.remove(format!("key:{}", inserted[0])));
+    assert!(inserted[1..]
+      .iter()
+      .all(|i| filter.contains(format!("key:{i}"))));
+  }
+
+  #[test]
+  fn round_trips_through_bytes() {
+    let mut filter = CuckooFilter::with_capacity(500);
+    for i in 0..400 {
+      filter.insert(format!("key:{i}"));
+    }
+
+    let restored = CuckooFilter::from_bytes(&filter.to_bytes()).unwrap();
+    assert_eq!(restored, filter);
+    assert!((0..400).all(|i| restored.contains(format!("key:{i}"))));
+  }
+
+  #[test]
+  fn from_bytes_rejects_garbage() {
+    let bytes = CuckooFilter::with_capacity(8).to_bytes();
+
+    assert!(CuckooFilter::from_bytes(&[]).is_none());
+    assert!(CuckooFilter::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(CuckooFilter::from_bytes(&wrong_magic).is_none());
+
+    let mut wrong_len = bytes.clone();
+    wrong_len[13] = 1;
+    assert!(CuckooFilter::from_bytes(&wrong_len).is_none());
+  }
+}
@@ -0,0 +1,306 @@
+//! A cuckoo filter: a set membership filter like a bloom filter that can
+//! also forget keys.
+//!
+//! Each key is reduced to a 16-bit fingerprint that lives in one of two
+//! four-slot buckets. `contains` never misses a key that was inserted and
+//! wrongly reports a key that wasn't with a probability of about 0.01%.
+//! Because fingerprints are stored rather than bits, a key can be removed
+//! again, so a filter can shrink along with the memtable it describes.
+//!
+//! Keys are hashed with a fixed function, FNV-1a, so a filter written with
+//! [`CuckooFilter::to_bytes`] answers the same after
+//! [`CuckooFilter::from_bytes`] in any process or build.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::cuckoo::CuckooFilter;
+//!
+//! let mut filter = CuckooFilter::with_capacity(1_000);
+//! assert!(filter.insert("user:1"));
+//! assert!(filter.contains("user:1"));
+//!
+//! assert!(filter.remove("user:1"));
+//! assert!(!filter.contains("user:1"));
+//!
+//! let bytes = filter.to_bytes();
+//! assert_eq!(CuckooFilter::from_bytes(&bytes), Some(filter));
+//! ```
+
+mod __test__;
+
+use crate::hash::{fmix64, fnv1a_64};
+
+/// Slots per bucket.
+const BUCKET_SIZE: usize = 4;
+
+/// How many fingerprints an insert may move around before giving up.
+const MAX_KICKS: usize = 500;
+
+/// Marks an empty slot; no fingerprint is ever 0.
+const EMPTY: u16 = 0;
+
+/// The first bytes of every serialized filter.
+const MAGIC: &[u8; 4] = b"DCKF";
+
+/// The version of the serialized layout.
+const VERSION: u8 = 1;
+
+/// Magic, version, bucket count, length, victim flag, victim bucket and
+/// victim fingerprint.
+const HEADER_LEN: usize = 4 + 1 + 8 + 8 + 1 + 8 + 2;
+
+/// A cuckoo filter over byte strings.
+#[derive(Debug, Clone)]
+pub struct CuckooFilter {
+  /// `bucket_count * BUCKET_SIZE` fingerprints.
+  slots: Vec<u16>,
+  bucket_count: usize,
+  len: usize,
+  /// A fingerprint that was kicked out and found no free slot. While it is
+  /// set the filter is full.
+  victim: Option<(usize, u16)>,
+  /// The state of the generator picking which slot to kick.
+  rng: u64,
+}
+
+impl PartialEq for CuckooFilter {
+  fn eq(&self, other: &Self) -> bool {
+    self.slots == other.slots && self.len == other.len && self.victim == other.victim
+  }
+}
+
+impl Eq for CuckooFilter {}
+
+impl CuckooFilter {
+  /// Creates a new empty filter with room for about `capacity` keys.
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut bucket_count = capacity.div_ceil(BUCKET_SIZE).max(1).next_power_of_two();
+    // Inserts start to fail at around 95% occupancy.
+    if capacity * 100 > bucket_count * BUCKET_SIZE * 95 {
+      bucket_count *= 2;
+    }
+    Self::with_buckets(bucket_count)
+  }
+
+  fn with_buckets(bucket_count: usize) -> Self {
+    Self {
+      slots: vec![EMPTY; bucket_count * BUCKET_SIZE],
+      bucket_count,
+      len: 0,
+      victim: None,
+      rng: 0x9E37_79B9_7F4A_7C15,
+    }
+  }
+
+  /// Returns the number of fingerprints the filter has slots for.
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  /// Returns `true` if filter is empty.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns number of keys in filter.
+  pub fn size(&self) -> usize {
+    self.len
+  }
+
+  /// Adds `key` to the filter.
+  ///
+  /// Returns `false` when the filter is too full to take it, in which case
+  /// the filter is unchanged. Inserting a key twice stores it twice, and it
+  /// then takes two removes to forget it.
+  pub fn insert(&mut self, key: impl AsRef<[u8]>) -> bool {
+    if self.victim.is_some() {
+      return false;
+    }
+
+    let (first, fingerprint) = self.locate(key.as_ref());
+    let second = self.alternate(first, fingerprint);
+    if self.put(first, fingerprint) || self.put(second, fingerprint) {
+      self.len += 1;
+      return true;
+    }
+
+    let mut bucket = if self.next_random() & 1 == 0 {
+      first
+    } else {
+      second
+    };
+    let mut fingerprint = fingerprint;
+    for _ in 0..MAX_KICKS {
+      let slot = bucket * BUCKET_SIZE + (self.next_random() as usize) % BUCKET_SIZE;
+      fingerprint = std::mem::replace(&mut self.slots[slot], fingerprint);
+      bucket = self.alternate(bucket, fingerprint);
+      if self.put(bucket, fingerprint) {
+        self.len += 1;
+        return true;
+      }
+    }
+
+    // The new key is in by now; keep whatever fell out on the side so no
+    // earlier key is forgotten.
+    self.victim = Some((bucket, fingerprint));
+    self.len += 1;
+    true
+  }
+
+  /// Returns `true` if `key` may be in the filter, `false` if it is not.
+  pub fn contains(&self, key: impl AsRef<[u8]>) -> bool {
+    let (first, fingerprint) = self.locate(key.as_ref());
+    let second = self.alternate(first, fingerprint);
+    self.find(first, fingerprint).is_some()
+      || self.find(second, fingerprint).is_some()
+      || self.victim == Some((first, fingerprint))
+      || self.victim == Some((second, fingerprint))
+  }
+
+  /// Removes `key` and returns whether it was there.
+  ///
+  /// Only remove keys that were inserted: removing another key that happens
+  /// to share a fingerprint and bucket would make the filter miss that key.
+  pub fn remove(&mut self, key: impl AsRef<[u8]>) -> bool {
+    let (first, fingerprint) = self.locate(key.as_ref());
+    let second = self.alternate(first, fingerprint);
+
+    if self.victim == Some((first, fingerprint)) || self.victim == Some((second, fingerprint)) {
+      self.victim = None;
+      self.len -= 1;
+      return true;
+    }
+
+    let Some(slot) = self
+      .find(first, fingerprint)
+      .or_else(|| self.find(second, fingerprint))
+    else {
+      return false;
+    };
+    self.slots[slot] = EMPTY;
+    self.len -= 1;
+
+    // A slot just opened up, so the victim may fit again.
+    if let Some((bucket, fingerprint)) = self.victim {
+      let other = self.alternate(bucket, fingerprint);
+      if self.put(bucket, fingerprint) || self.put(other, fingerprint) {
+        self.victim = None;
+      }
+    }
+    true
+  }
+
+  /// Removes every key.
+  pub fn clear(&mut self) {
+    self.slots.fill(EMPTY);
+    self.len = 0;
+    self.victim = None;
+  }
+
+  /// Serializes the filter.
+  ///
+  /// The layout is the magic `DCKF`, a version byte, the bucket count and
+  /// key count as little-endian `u64`s, the victim as a flag byte, a `u64`
+  /// bucket and a `u16` fingerprint, then every fingerprint as a
+  /// little-endian `u16`.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + self.slots.len() * 2);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(self.bucket_count as u64).to_le_bytes());
+    bytes.extend_from_slice(&(self.len as u64).to_le_bytes());
+    let (bucket, fingerprint) = self.victim.unwrap_or((0, EMPTY));
+    bytes.push(self.victim.is_some() as u8);
+    bytes.extend_from_slice(&(bucket as u64).to_le_bytes());
+    bytes.extend_from_slice(&fingerprint.to_le_bytes());
+    for slot in &self.slots {
+      bytes.extend_from_slice(&slot.to_le_bytes());
+    }
+    bytes
+  }
+
+  /// Reads a filter written by [`to_bytes`](Self::to_bytes), or returns
+  /// `None` if `bytes` aren't one.
+  pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    let (header, body) = bytes.split_at_checked(HEADER_LEN)?;
+    if &header[..4] != MAGIC || header[4] != VERSION {
+      return None;
+    }
+
+    let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+    let bucket_count = usize::try_from(u64_at(5)).ok()?;
+    let len = usize::try_from(u64_at(13)).ok()?;
+    let has_victim = header[21];
+    let victim_bucket = usize::try_from(u64_at(22)).ok()?;
+    let victim_fingerprint = u16::from_le_bytes([header[30], header[31]]);
+
+    if !bucket_count.is_power_of_two() || body.len() != bucket_count.checked_mul(BUCKET_SIZE * 2)? {
+      return None;
+    }
+    let victim = match has_victim {
+      0 => None,
+      1 if victim_bucket < bucket_count && victim_fingerprint != EMPTY => {
+        Some((victim_bucket, victim_fingerprint))
+      },
+      _ => return None,
+    };
+
+    let mut filter = Self::with_buckets(bucket_count);
+    for (slot, chunk) in filter.slots.iter_mut().zip(body.chunks_exact(2)) {
+      *slot = u16::from_le_bytes([chunk[0], chunk[1]]);
+    }
+    let stored = filter.slots.iter().filter(|&&slot| slot != EMPTY).count();
+    if len != stored + victim.is_some() as usize {
+      return None;
+    }
+    filter.len = len;
+    filter.victim = victim;
+    Some(filter)
+  }
+
+  /// The first bucket and the fingerprint of `key`.
+  fn locate(&self, key: &[u8]) -> (usize, u16) {
+    let hash = hash(key);
+    let fingerprint = match (hash >> 48) as u16 {
+      EMPTY => 1,
+      fingerprint => fingerprint,
+    };
+    (hash as usize & (self.bucket_count - 1), fingerprint)
+  }
+
+  /// The other bucket `fingerprint` may live in. Applying it twice gives
+  /// back `bucket`.
+  fn alternate(&self, bucket: usize, fingerprint: u16) -> usize {
+    (bucket ^ hash(&fingerprint.to_le_bytes()) as usize) & (self.bucket_count - 1)
+  }
+
+  fn find(&self, bucket: usize, fingerprint: u16) -> Option<usize> {
+    let start = bucket * BUCKET_SIZE;
+    (start..start + BUCKET_SIZE).find(|&slot| self.slots[slot] == fingerprint)
+  }
+
+  fn put(&mut self, bucket: usize, fingerprint: u16) -> bool {
+    match self.find(bucket, EMPTY) {
+      Some(slot) => {
+        self.slots[slot] = fingerprint;
+        true
+      },
+      None => false,
+    }
+  }
+
+  fn next_random(&mut self) -> u64 {
+    // xorshift64
+    self.rng ^= self.rng << 13;
+    self.rng ^= self.rng >> 7;
+    self.rng ^= self.rng << 17;
+    self.rng
+  }
+}
+
+/// FNV-1a, finished with [`fmix64`] so that keys which only differ in their
+/// last bytes still differ in the high bits the fingerprint comes from.
+fn hash(bytes: &[u8]) -> u64 {
+  fmix64(fnv1a_64(bytes))
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod deque_test {
+  use crate::deque::Deque;
+
+  #[test]
+  fn new_deque_is_empty() {
+    let mut deque: Deque<i32> = Deque::new();
+    assert_eq!(deque.size(), 0);
+    assert!(deque.is_empty());
+    assert!(deque.peek_front().is_none());
+    assert!(deque.peek_back().is_none());
+    assert!(deque.pop_front().is_none());
+    assert!(deque.pop_back().is_none());
+  }
+
+  #[test]
+  fn works_as_a_queue_and_a_stack() {
+    let mut deque = Deque::new();
+    for i in 1..=3 {
+      deque.push_back(i);
+    }
+    assert_eq!(deque.pop_front(), Some(1));
+    assert_eq!(deque.pop_back(), Some(3));
+    assert_eq!(deque.pop_back(), Some(2));
+    assert!(deque.is_empty());
+  }
+
+  #[test]
+  fn push_at_both_ends() {
+    let mut deque = Deque::new();
+    deque.push_front(2);
+    deque.push_back(3);
+    deque.push_front(1);
+
+    assert_eq!(deque.peek_front(), Some(1));
+    assert_eq!(deque.peek_back(), Some(3));
+    assert_eq!(deque.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(format!("{deque:?}"), "[1, 2, 3]");
+    assert_eq!(deque.into_vec(), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn owns_non_copy_values() {
+    let mut deque = Deque::new();
+    deque.push_back("b".to_string());
+    deque.push_front("a".to_string());
+
+    assert_eq!(deque.pop_back().as_deref(), Some("b"));
+    assert_eq!(deque.pop_back().as_deref(), Some("a"));
+
+    deque.push_back("c".to_string());
+    deque.clear();
+    assert!(deque.is_empty());
+  }
+
+  #[test]
+  fn pops_once_an_iterator_is_dropped() {
+    let mut deque = Deque::new();
+    for i in 1..=3 {
+      deque.push_back(i);
+    }
+
+    // A half-used iterator still holds on to both ends until dropped.
+    let mut iter = deque.iter();
+    assert_eq!(iter.next(), Some(1));
+    assert_eq!(iter.next_back(), Some(3));
+    drop(iter);
+
+    assert_eq!(deque.pop_front(), Some(1));
+    assert_eq!(deque.pop_back(), Some(3));
+    assert_eq!(deque.into_vec(), vec![2]);
+  }
+}
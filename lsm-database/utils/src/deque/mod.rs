@@ -0,0 +1,150 @@
+//! A double-ended queue built on [`LinkedList`].
+//!
+//! Pushing and popping at either end is `O(1)`. Rather than keeping a third
+//! copy of the node code of the stack and queue, the deque hands its values
+//! to the crate's doubly linked list and only unwraps the nodes it pops.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::deque::Deque;
+//!
+//! let mut deque = Deque::new();
+//! deque.push_back(2);
+//! deque.push_front(1);
+//! deque.push_back(3);
+//!
+//! assert_eq!(deque.peek_front(), Some(1));
+//! assert_eq!(deque.peek_back(), Some(3));
+//! assert_eq!(deque.pop_back(), Some(3));
+//! assert_eq!(deque.pop_front(), Some(1));
+//! assert_eq!(deque.size(), 1);
+//! ```
+
+mod __test__;
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::linked_list::{LinkedList, LinkedListIter, Node};
+
+/// A double-ended queue implemented as a doubly linked list.
+pub struct Deque<T>
+where
+  T: PartialEq,
+{
+  list: LinkedList<T>,
+}
+
+impl<T> Default for Deque<T>
+where
+  T: PartialEq,
+{
+  fn default() -> Self {
+    Self {
+      list: LinkedList::new(),
+    }
+  }
+}
+
+impl<T> Deque<T>
+where
+  T: PartialEq,
+{
+  /// Creates a new empty deque.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns `true` if deque is empty.
+  pub fn is_empty(&self) -> bool {
+    self.list.size() == 0
+  }
+
+  /// Returns number of items in deque.
+  pub fn size(&self) -> usize {
+    self.list.size()
+  }
+
+  /// Pushes a value to the front of the deque.
+  pub fn push_front(&mut self, value: T) {
+    self.list.insert_start(value);
+  }
+
+  /// Pushes a value to the back of the deque.
+  pub fn push_back(&mut self, value: T) {
+    self.list.insert_end(value);
+  }
+
+  /// Pops a value from the front of the deque.
+  pub fn pop_front(&mut self) -> Option<T> {
+    self.list.pop_start().map(into_value)
+  }
+
+  /// Pops a value from the back of the deque.
+  pub fn pop_back(&mut self) -> Option<T> {
+    self.list.pop_end().map(into_value)
+  }
+
+  /// Returns the value at the front of the deque.
+  pub fn peek_front(&self) -> Option<T>
+  where
+    T: Clone,
+  {
+    self.list.head().map(|node| node.borrow().value.clone())
+  }
+
+  /// Returns the value at the back of the deque.
+  pub fn peek_back(&self) -> Option<T>
+  where
+    T: Clone,
+  {
+    self.list.tail().map(|node| node.borrow().value.clone())
+  }
+
+  /// Removes every value.
+  pub fn clear(&mut self) {
+    while self.list.pop_start().is_some() {}
+  }
+
+  /// Returns an iterator over deque items (front → back).
+  ///
+  /// The iterator borrows the deque, so it can't be popped from meanwhile:
+  ///
+  /// ```rust,compile_fail,E0502
+  /// use utils::deque::Deque;
+  ///
+  /// let mut deque = Deque::new();
+  /// deque.push_back(1);
+  ///
+  /// let mut iter = deque.iter();
+  /// deque.pop_front();
+  /// iter.next();
+  /// ```
+  pub fn iter(&self) -> LinkedListIter<'_, T> {
+    self.list.iter()
+  }
+
+  /// Converts the deque into a vec (front to back).
+  pub fn into_vec(mut self) -> Vec<T> {
+    std::iter::from_fn(|| self.pop_front()).collect()
+  }
+}
+
+/// Takes the value out of a node that was just popped off the list. The list
+/// is private and its iterators borrow the deque, so nothing else can still
+/// refer to the node.
+fn into_value<T: PartialEq>(node: Rc<RefCell<Node<T>>>) -> T {
+  match Rc::try_unwrap(node) {
+    Ok(node) => node.into_inner().value,
+    Err(_) => unreachable!("a popped node has no other owners"),
+  }
+}
+
+impl<T> std::fmt::Debug for Deque<T>
+where
+  T: PartialEq + std::fmt::Debug + Clone,
+{
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
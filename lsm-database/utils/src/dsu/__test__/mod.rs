@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod dsu_test {
+  use crate::dsu::DisjointSet;
+
+  #[test]
+  fn starts_with_singletons() {
+    let mut sets = DisjointSet::new(3);
+    assert_eq!(sets.size(), 3);
+    assert_eq!(sets.set_count(), 3);
+    assert!(!sets.connected(0, 1));
+    assert_eq!(sets.set_size(2), 1);
+    assert!(DisjointSet::new(0).is_empty());
+  }
+
+  #[test]
+  fn union_merges_sets() {
+    let mut sets = DisjointSet::new(5);
+    assert!(sets.union(0, 1));
+    assert!(sets.union(3, 4));
+    assert!(sets.union(1, 4));
+    assert!(!sets.union(0, 3));
+
+    assert!(sets.connected(0, 4));
+    assert!(!sets.connected(2, 0));
+    assert_eq!(sets.set_count(), 2);
+    assert_eq!(sets.set_size(3), 4);
+    assert_eq!(sets.groups(), vec![vec![0, 1, 3, 4], vec![2]]);
+  }
+
+  #[test]
+  fn add_grows_the_partition() {
+    let mut sets = DisjointSet::new(2);
+    let c = sets.add();
+    assert_eq!(c, 2);
+    assert_eq!(sets.set_count(), 3);
+    sets.union(0, c);
+    assert!(sets.connected(2, 0));
+  }
+
+  #[test]
+  fn long_chains_are_compressed() {
+    let n = 10_000;
+    let mut sets = DisjointSet::new(n);
+    for i in 1..n {
+      sets.union(i - 1, i);
+    }
+
+    let root = sets.find(0);
+    assert!((0..n).all(|i| sets.find(i) == root));
+    assert!((0..n).all(|i| sets.parent[i] == root));
+    assert_eq!(sets.set_count(), 1);
+    assert_eq!(sets.set_size(n - 1), n);
+  }
+
+  #[test]
+  #[should_panic]
+  fn find_out_of_range_panics() {
+    DisjointSet::new(2).find(2);
+  }
+}
@@ -0,0 +1,141 @@
+//! A disjoint-set forest (union-find) over the elements `0..n`.
+//!
+//! Every set is a tree whose root names the set. `find` points every node
+//! it passes straight at the root (path compression) and `union` hangs the
+//! shallower tree under the deeper one (union by rank), which together make
+//! both run in effectively constant amortized time.
+//!
+//! # Example
+//!
+//! Grouping segments whose key ranges overlap into merge clusters:
+//!
+//! ```rust
+//! use utils::dsu::DisjointSet;
+//!
+//! let ranges = [("a", "f"), ("p", "t"), ("e", "k"), ("x", "z")];
+//! let mut clusters = DisjointSet::new(ranges.len());
+//! for (i, a) in ranges.iter().enumerate() {
+//!   for (j, b) in ranges.iter().enumerate().skip(i + 1) {
+//!     if a.0 <= b.1 && b.0 <= a.1 {
+//!       clusters.union(i, j);
+//!     }
+//!   }
+//! }
+//!
+//! assert_eq!(clusters.groups(), vec![vec![0, 2], vec![1], vec![3]]);
+//! ```
+
+mod __test__;
+
+/// A partition of the elements `0..size()` into disjoint sets.
+#[derive(Debug, Clone, Default)]
+pub struct DisjointSet {
+  parent: Vec<usize>,
+  /// An upper bound on the height of the tree below each root.
+  rank: Vec<u8>,
+  /// The number of elements in the set of each root.
+  set_size: Vec<usize>,
+  sets: usize,
+}
+
+impl DisjointSet {
+  /// Creates `n` sets holding one element each.
+  pub fn new(n: usize) -> Self {
+    Self {
+      parent: (0..n).collect(),
+      rank: vec![0; n],
+      set_size: vec![1; n],
+      sets: n,
+    }
+  }
+
+  /// Returns `true` if there are no elements.
+  pub fn is_empty(&self) -> bool {
+    self.parent.is_empty()
+  }
+
+  /// Returns number of elements.
+  pub fn size(&self) -> usize {
+    self.parent.len()
+  }
+
+  /// Returns the number of disjoint sets.
+  pub fn set_count(&self) -> usize {
+    self.sets
+  }
+
+  /// Adds a new element in a set of its own and returns it.
+  pub fn add(&mut self) -> usize {
+    let element = self.parent.len();
+    self.parent.push(element);
+    self.rank.push(0);
+    self.set_size.push(1);
+    self.sets += 1;
+    element
+  }
+
+  /// Returns the representative of the set holding `element`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `element` is not less than [`size`](Self::size).
+  pub fn find(&mut self, element: usize) -> usize {
+    let mut root = element;
+    while self.parent[root] != root {
+      root = self.parent[root];
+    }
+
+    let mut current = element;
+    while self.parent[current] != root {
+      current = std::mem::replace(&mut self.parent[current], root);
+    }
+    root
+  }
+
+  /// Merges the sets holding `a` and `b`. Returns `false` if they already
+  /// were in the same set.
+  pub fn union(&mut self, a: usize, b: usize) -> bool {
+    let (mut a, mut b) = (self.find(a), self.find(b));
+    if a == b {
+      return false;
+    }
+
+    if self.rank[a] < self.rank[b] {
+      std::mem::swap(&mut a, &mut b);
+    }
+    self.parent[b] = a;
+    self.set_size[a] += self.set_size[b];
+    if self.rank[a] == self.rank[b] {
+      self.rank[a] += 1;
+    }
+    self.sets -= 1;
+    true
+  }
+
+  /// Returns `true` if `a` and `b` are in the same set.
+  pub fn connected(&mut self, a: usize, b: usize) -> bool {
+    self.find(a) == self.find(b)
+  }
+
+  /// Returns the number of elements in the set holding `element`.
+  pub fn set_size(&mut self, element: usize) -> usize {
+    let root = self.find(element);
+    self.set_size[root]
+  }
+
+  /// Returns the elements of every set, each set in ascending order and the
+  /// sets ordered by their smallest element.
+  pub fn groups(&mut self) -> Vec<Vec<usize>> {
+    let mut group_of_root = vec![usize::MAX; self.parent.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::with_capacity(self.sets);
+    for element in 0..self.parent.len() {
+      let root = self.find(element);
+      if group_of_root[root] == usize::MAX {
+        group_of_root[root] = groups.len();
+        groups.push(Vec::with_capacity(self.set_size[root]));
+      }
+      groups[group_of_root[root]].push(element);
+    }
+    groups
+  }
+}
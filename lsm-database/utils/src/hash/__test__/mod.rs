@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod hash_test {
+  use std::{
+    collections::HashSet,
+    hash::{BuildHasher, Hasher},
+  };
+
+  use crate::hash::{
+    fmix64, fnv1a_64, xxh64, BuildFnvHasher, BuildXxHasher, FnvHasher, XxHasher64,
+  };
+
+  /// Known answers from the reference implementation.
+  const XXH64_VECTORS: [(&[u8], u64, u64); 5] = [
+    (b"", 0, 0xef46_db37_51d8_e999),
+    (b"a", 0, 0xd24e_c4f1_a98c_6e5b),
+    (b"abc", 0, 0x44bc_2cf5_ad77_0999),
+    (
+      b"Nobody inspects the spammish repetition",
+      0,
+      0xfbce_a83c_8a37_8bf1,
+    ),
+    (b"", 1, 0xd5af_ba13_36a3_be4b),
+  ];
+
+  #[test]
+  fn xxh64_known_answers() {
+    for (input, seed, expected) in XXH64_VECTORS {
+      assert_eq!(
+        xxh64(input, seed),
+        expected,
+        "{:?}",
+        String::from_utf8_lossy(input)
+      );
+    }
+  }
+
+  #[test]
+  fn xxh64_streaming_matches_one_shot() {
+    let input: Vec<u8> = (0..=255u8).cycle().take(1_000).collect();
+
+    for len in [0, 1, 3, 4, 7, 8, 31, 32, 33, 63, 64, 100, 1_000] {
+      let expected = xxh64(&input[..len], 42);
+      for split in [1, 5, 13, 32, 40] {
+        let mut hasher = XxHasher64::with_seed(42);
+        for chunk in input[..len].chunks(split) {
+          hasher.write(chunk);
+        }
+        assert_eq!(hasher.finish(), expected, "len {len}, chunks of {split}");
+      }
+    }
+  }
+
+  #[test]
+  fn fnv1a_known_answers() {
+    assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+
+    let mut hasher = FnvHasher::default();
+    hasher.write(b"foo");
+    hasher.write(b"bar");
+    assert_eq!(hasher.finish(), fnv1a_64(b"foobar"));
+  }
+
+  #[test]
+  fn fmix64_spreads_bits() {
+    assert_eq!(fmix64(0), 0);
+    let flipped = (0..64).map(|bit| (fmix64(1 << bit) ^ fmix64(0)).count_ones());
+    assert!(flipped.into_iter().all(|ones| (16..=48).contains(&ones)));
+  }
+
+  #[test]
+  fn no_collisions_on_similar_keys() {
+    let keys: Vec<String> = (0..100_000).map(|i| format!("key:{i}")).collect();
+
+    let xxh: HashSet<u64> = keys.iter().map(|key| xxh64(key.as_bytes(), 0)).collect();
+    assert_eq!(xxh.len(), keys.len());
+    let fnv: HashSet<u64> = keys.iter().map(|key| fnv1a_64(key.as_bytes())).collect();
+    assert_eq!(fnv.len(), keys.len());
+  }
+
+  #[test]
+  fn build_hashers_are_deterministic() {
+    assert_eq!(
+      BuildXxHasher::default().hash_one("key"),
+      BuildXxHasher::default().hash_one("key")
+    );
+    assert_eq!(
+      BuildFnvHasher::default().hash_one(7u64),
+      BuildFnvHasher::default().hash_one(7u64)
+    );
+
+    let mut set: HashSet<&str, BuildXxHasher> = HashSet::default();
+    set.insert("a");
+    assert!(set.contains("a"));
+  }
+}
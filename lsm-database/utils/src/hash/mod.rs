@@ -0,0 +1,221 @@
+//! Fast non-cryptographic 64-bit hash functions.
+//!
+//! - [`xxh64`]: xxHash64, the default choice. Fast on long keys, with good
+//!   distribution, and byte-for-byte compatible with the reference
+//!   implementation, so hashes can be stored on disk or sent to other
+//!   processes.
+//! - [`fnv1a_64`]: FNV-1a, tiny and fast on short keys, but with weak high
+//!   bits; pass it through [`fmix64`] if those matter.
+//! - [`fmix64`]: the MurmurHash3 finalizer, which spreads every input bit
+//!   over the whole output.
+//!
+//! [`XxHasher64`] and [`FnvHasher`] implement [`Hasher`], and
+//! [`BuildXxHasher`] and [`BuildFnvHasher`] plug them into `HashMap`. None
+//! of these resist hash flooding: don't key a map on untrusted input with
+//! them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use utils::hash::{xxh64, BuildXxHasher};
+//!
+//! assert_eq!(xxh64(b"", 0), 0xef46_db37_51d8_e999);
+//!
+//! let mut shards: HashMap<&str, usize, BuildXxHasher> = HashMap::default();
+//! shards.insert("user:1", xxh64(b"user:1", 0) as usize % 16);
+//! ```
+
+mod __test__;
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes `bytes` with xxHash64 and the given seed.
+pub fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+  let mut hasher = XxHasher64::with_seed(seed);
+  hasher.write(bytes);
+  hasher.finish()
+}
+
+/// Hashes `bytes` with 64-bit FNV-1a.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+    (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+  })
+}
+
+/// The 64-bit finalizer of MurmurHash3: a bijection that lets every input
+/// bit flip about half of the output bits.
+pub fn fmix64(mut hash: u64) -> u64 {
+  hash ^= hash >> 33;
+  hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+  hash ^= hash >> 33;
+  hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+  hash ^ (hash >> 33)
+}
+
+/// A streaming xxHash64 hasher. Feeding the same bytes in any number of
+/// `write` calls gives the same hash as [`xxh64`].
+#[derive(Debug, Clone)]
+pub struct XxHasher64 {
+  seed: u64,
+  lanes: [u64; 4],
+  /// Input not yet folded into `lanes`, less than one 32-byte stripe.
+  buffer: [u8; 32],
+  buffered: usize,
+  total_len: u64,
+}
+
+impl Default for XxHasher64 {
+  fn default() -> Self {
+    Self::with_seed(0)
+  }
+}
+
+impl XxHasher64 {
+  /// Creates a hasher with the given seed.
+  pub fn with_seed(seed: u64) -> Self {
+    Self {
+      seed,
+      lanes: [
+        seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+        seed.wrapping_add(PRIME64_2),
+        seed,
+        seed.wrapping_sub(PRIME64_1),
+      ],
+      buffer: [0; 32],
+      buffered: 0,
+      total_len: 0,
+    }
+  }
+
+  fn consume_stripe(&mut self, stripe: &[u8]) {
+    for (lane, chunk) in self.lanes.iter_mut().zip(stripe.chunks_exact(8)) {
+      *lane = xxh_round(*lane, read_u64(chunk));
+    }
+  }
+}
+
+impl Hasher for XxHasher64 {
+  fn write(&mut self, mut bytes: &[u8]) {
+    self.total_len += bytes.len() as u64;
+
+    if self.buffered > 0 {
+      let take = bytes.len().min(32 - self.buffered);
+      self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+      self.buffered += take;
+      bytes = &bytes[take..];
+      if self.buffered < 32 {
+        return;
+      }
+      let stripe = self.buffer;
+      self.consume_stripe(&stripe);
+      self.buffered = 0;
+    }
+
+    let mut stripes = bytes.chunks_exact(32);
+    for stripe in &mut stripes {
+      self.consume_stripe(stripe);
+    }
+    let rest = stripes.remainder();
+    self.buffer[..rest.len()].copy_from_slice(rest);
+    self.buffered = rest.len();
+  }
+
+  fn finish(&self) -> u64 {
+    let mut hash = if self.total_len >= 32 {
+      let [v1, v2, v3, v4] = self.lanes;
+      let mut hash = v1
+        .rotate_left(1)
+        .wrapping_add(v2.rotate_left(7))
+        .wrapping_add(v3.rotate_left(12))
+        .wrapping_add(v4.rotate_left(18));
+      for lane in self.lanes {
+        hash = (hash ^ xxh_round(0, lane))
+          .wrapping_mul(PRIME64_1)
+          .wrapping_add(PRIME64_4);
+      }
+      hash
+    } else {
+      self.seed.wrapping_add(PRIME64_5)
+    };
+    hash = hash.wrapping_add(self.total_len);
+
+    let mut rest = &self.buffer[..self.buffered];
+    while rest.len() >= 8 {
+      hash ^= xxh_round(0, read_u64(rest));
+      hash = hash
+        .rotate_left(27)
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(PRIME64_4);
+      rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+      hash ^= (u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64).wrapping_mul(PRIME64_1);
+      hash = hash
+        .rotate_left(23)
+        .wrapping_mul(PRIME64_2)
+        .wrapping_add(PRIME64_3);
+      rest = &rest[4..];
+    }
+    for &byte in rest {
+      hash ^= (byte as u64).wrapping_mul(PRIME64_5);
+      hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME64_3);
+    hash ^ (hash >> 32)
+  }
+}
+
+fn xxh_round(acc: u64, input: u64) -> u64 {
+  acc
+    .wrapping_add(input.wrapping_mul(PRIME64_2))
+    .rotate_left(31)
+    .wrapping_mul(PRIME64_1)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+  u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// A streaming 64-bit FNV-1a hasher.
+#[derive(Debug, Clone)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+  fn default() -> Self {
+    Self(FNV_OFFSET_BASIS)
+  }
+}
+
+impl Hasher for FnvHasher {
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.0 = (self.0 ^ byte as u64).wrapping_mul(FNV_PRIME);
+    }
+  }
+
+  fn finish(&self) -> u64 {
+    self.0
+  }
+}
+
+/// Builds [`XxHasher64`]s with seed 0, for `HashMap` and `HashSet`.
+pub type BuildXxHasher = BuildHasherDefault<XxHasher64>;
+
+/// Builds [`FnvHasher`]s, for `HashMap` and `HashSet`.
+pub type BuildFnvHasher = BuildHasherDefault<FnvHasher>;
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod heap_test {
+  use crate::heap::{BinaryHeap, HeapOrder};
+
+  #[test]
+  fn new_heap_is_empty() {
+    let mut heap: BinaryHeap<i32> = BinaryHeap::new();
+    assert_eq!(heap.size(), 0);
+    assert!(heap.is_empty());
+    assert!(heap.peek().is_none());
+    assert!(heap.pop().is_none());
+    assert_eq!(heap.order(), HeapOrder::Max);
+  }
+
+  #[test]
+  fn max_heap_pops_largest_first() {
+    let mut heap = BinaryHeap::new_max();
+    heap.extend([3, 9, 1, 9, 4]);
+
+    assert_eq!(heap.peek(), Some(&9));
+    let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+    assert_eq!(popped, vec![9, 9, 4, 3, 1]);
+  }
+
+  #[test]
+  fn min_heap_pops_smallest_first() {
+    let mut heap = BinaryHeap::new_min();
+    heap.extend([3, 9, 1, 9, 4]);
+
+    assert_eq!(heap.peek(), Some(&1));
+    let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+    assert_eq!(popped, vec![1, 3, 4, 9, 9]);
+  }
+
+  #[test]
+  fn from_vec_builds_a_valid_heap() {
+    let values: Vec<u32> = (0..1_000).map(|i| (i * 7919) % 1_000).collect();
+
+    for order in [HeapOrder::Max, HeapOrder::Min] {
+      let heap = BinaryHeap::from_vec(values.clone(), order);
+      assert_eq!(heap.size(), values.len());
+      assert_eq!(heap.into_sorted_vec(), (0..1_000).collect::<Vec<u32>>());
+    }
+  }
+
+  #[test]
+  fn interleaved_push_and_pop() {
+    let mut heap = BinaryHeap::new_min();
+    heap.push(5);
+    heap.push(2);
+    assert_eq!(heap.pop(), Some(2));
+    heap.push(1);
+    heap.push(7);
+    assert_eq!(heap.pop(), Some(1));
+    assert_eq!(heap.pop(), Some(5));
+    heap.clear();
+    assert!(heap.is_empty());
+  }
+
+  #[test]
+  fn k_way_merge_of_sorted_runs() {
+    let runs = [vec![1, 4, 7], vec![2, 5, 8], vec![0, 3, 6, 9]];
+    let mut heads = BinaryHeap::new_min();
+    for (run, values) in runs.iter().enumerate() {
+      heads.push((values[0], run, 0));
+    }
+
+    let mut merged = Vec::new();
+    while let Some((value, run, pos)) = heads.pop() {
+      merged.push(value);
+      if let Some(&next) = runs[run].get(pos + 1) {
+        heads.push((next, run, pos + 1));
+      }
+    }
+
+    assert_eq!(merged, (0..10).collect::<Vec<_>>());
+  }
+}
@@ -0,0 +1,221 @@
+//! A `Vec` backed binary heap that works as either a max-heap or a min-heap.
+//!
+//! `push` and `pop` stay at `O(log n)` and `peek` at `O(1)`, which is what a
+//! k-way merge needs: keep the head of every sorted run in the heap and
+//! repeatedly pop the smallest one. Unlike `std::collections::BinaryHeap`
+//! the direction is picked at construction, so a min-heap doesn't need
+//! `Reverse` wrappers around every element.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::heap::BinaryHeap;
+//!
+//! let mut heap = BinaryHeap::new_min();
+//! heap.push(30);
+//! heap.push(10);
+//! heap.push(20);
+//!
+//! assert_eq!(heap.peek(), Some(&10));
+//! assert_eq!(heap.pop(), Some(10));
+//! assert_eq!(heap.pop(), Some(20));
+//! assert_eq!(heap.size(), 1);
+//! ```
+
+mod __test__;
+
+/// Which element a [`BinaryHeap`] keeps at the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapOrder {
+  /// The largest element is popped first.
+  Max,
+  /// The smallest element is popped first.
+  Min,
+}
+
+/// A priority queue stored as an implicit binary tree in a `Vec`.
+///
+/// The children of the element at index `i` live at `2i + 1` and `2i + 2`,
+/// and no element is ordered after its parent.
+#[derive(Debug, Clone)]
+pub struct BinaryHeap<T> {
+  data: Vec<T>,
+  order: HeapOrder,
+}
+
+impl<T: Ord> Default for BinaryHeap<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Ord> BinaryHeap<T> {
+  /// Creates a new empty max-heap.
+  pub fn new() -> Self {
+    Self::with_order(HeapOrder::Max)
+  }
+
+  /// Creates a new empty max-heap.
+  pub fn new_max() -> Self {
+    Self::with_order(HeapOrder::Max)
+  }
+
+  /// Creates a new empty min-heap.
+  pub fn new_min() -> Self {
+    Self::with_order(HeapOrder::Min)
+  }
+
+  /// Creates a new empty heap that pops in the given order.
+  pub fn with_order(order: HeapOrder) -> Self {
+    Self {
+      data: Vec::new(),
+      order,
+    }
+  }
+
+  /// Builds a heap out of `data` in `O(n)`.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::heap::{BinaryHeap, HeapOrder};
+  ///
+  /// let heap = BinaryHeap::from_vec(vec![4, 1, 3], HeapOrder::Max);
+  /// assert_eq!(heap.peek(), Some(&4));
+  /// ```
+  ///
+  pub fn from_vec(data: Vec<T>, order: HeapOrder) -> Self {
+    let mut heap = Self { data, order };
+    for i in (0..heap.data.len() / 2).rev() {
+      heap.sift_down(i);
+    }
+    heap
+  }
+
+  /// Returns the order this heap pops in.
+  pub fn order(&self) -> HeapOrder {
+    self.order
+  }
+
+  /// Returns `true` if heap is empty.
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Returns number of items in heap.
+  pub fn size(&self) -> usize {
+    self.data.len()
+  }
+
+  /// Returns the element [`pop`](Self::pop) would return, without removing
+  /// it.
+  pub fn peek(&self) -> Option<&T> {
+    self.data.first()
+  }
+
+  /// Adds a value to the heap.
+  pub fn push(&mut self, value: T) {
+    self.data.push(value);
+    self.sift_up(self.data.len() - 1);
+  }
+
+  /// Removes the largest value of a max-heap or the smallest value of a
+  /// min-heap.
+  pub fn pop(&mut self) -> Option<T> {
+    let last = self.data.len().checked_sub(1)?;
+    self.data.swap(0, last);
+    let value = self.data.pop();
+    self.sift_down(0);
+    value
+  }
+
+  /// Removes every value.
+  pub fn clear(&mut self) {
+    self.data.clear();
+  }
+
+  /// Returns an iterator over the values in no particular order.
+  pub fn iter(&self) -> std::slice::Iter<'_, T> {
+    self.data.iter()
+  }
+
+  /// Consumes the heap and returns its values in ascending order, whatever
+  /// the heap's order.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::heap::BinaryHeap;
+  ///
+  /// let mut heap = BinaryHeap::new_max();
+  /// for value in [5, 2, 8, 1] {
+  ///   heap.push(value);
+  /// }
+  ///
+  /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 5, 8]);
+  /// ```
+  ///
+  pub fn into_sorted_vec(mut self) -> Vec<T> {
+    let mut sorted = Vec::with_capacity(self.data.len());
+    while let Some(value) = self.pop() {
+      sorted.push(value);
+    }
+    if self.order == HeapOrder::Max {
+      sorted.reverse();
+    }
+    sorted
+  }
+
+  /// Consumes the heap and returns its values in no particular order.
+  pub fn into_vec(self) -> Vec<T> {
+    self.data
+  }
+
+  /// Whether `a` belongs above `b`.
+  fn above(&self, a: &T, b: &T) -> bool {
+    match self.order {
+      HeapOrder::Max => a > b,
+      HeapOrder::Min => a < b,
+    }
+  }
+
+  fn sift_up(&mut self, mut child: usize) {
+    while child > 0 {
+      let parent = (child - 1) / 2;
+      if !self.above(&self.data[child], &self.data[parent]) {
+        break;
+      }
+      self.data.swap(child, parent);
+      child = parent;
+    }
+  }
+
+  fn sift_down(&mut self, mut parent: usize) {
+    let len = self.data.len();
+    loop {
+      let left = 2 * parent + 1;
+      if left >= len {
+        break;
+      }
+
+      let right = left + 1;
+      let child = if right < len && self.above(&self.data[right], &self.data[left]) {
+        right
+      } else {
+        left
+      };
+
+      if !self.above(&self.data[child], &self.data[parent]) {
+        break;
+      }
+      self.data.swap(child, parent);
+      parent = child;
+    }
+  }
+}
+
+impl<T: Ord> Extend<T> for BinaryHeap<T> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    for value in iter {
+      self.push(value);
+    }
+  }
+}
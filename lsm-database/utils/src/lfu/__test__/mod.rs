@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod lfu_test {
+  use crate::lfu::LfuCache;
+
+  #[test]
+  fn new_cache_is_empty() {
+    let mut cache: LfuCache<u32, u32> = LfuCache::new(4);
+    assert_eq!(cache.size(), 0);
+    assert_eq!(cache.capacity(), 4);
+    assert!(cache.is_empty());
+    assert!(cache.get(&1).is_none());
+    assert!(cache.pop_lfu().is_none());
+  }
+
+  #[test]
+  fn evicts_least_frequently_used() {
+    let mut cache = LfuCache::new(3);
+    cache.put(1, "one");
+    cache.put(2, "two");
+    cache.put(3, "three");
+    cache.get(&1);
+    cache.get(&1);
+    cache.get(&3);
+
+    cache.put(4, "four");
+    assert!(!cache.contains(&2));
+    assert_eq!(cache.frequency(&1), Some(3));
+    assert_eq!(cache.frequency(&3), Some(2));
+    assert_eq!(cache.frequency(&4), Some(1));
+  }
+
+  #[test]
+  fn ties_evict_least_recently_used() {
+    let mut cache = LfuCache::new(2);
+    cache.put("a", 1);
+    cache.put("b", 2);
+    cache.get(&"b");
+    cache.get(&"a");
+
+    assert_eq!(cache.pop_lfu(), Some(("b", 2)));
+    assert_eq!(cache.pop_lfu(), Some(("a", 1)));
+    assert!(cache.is_empty());
+  }
+
+  #[test]
+  fn put_replaces_and_counts_as_use() {
+    let mut cache = LfuCache::new(2);
+    assert_eq!(cache.put("a", 1), None);
+    assert_eq!(cache.put("a", 10), Some(1));
+    assert_eq!(cache.frequency(&"a"), Some(2));
+    assert_eq!(cache.peek(&"a"), Some(&10));
+    assert_eq!(cache.frequency(&"a"), Some(2));
+    assert_eq!(cache.size(), 1);
+  }
+
+  #[test]
+  fn remove_keeps_frequency_order() {
+    let mut cache = LfuCache::new(4);
+    for key in 1..=4 {
+      cache.put(key, key * 10);
+      for _ in 1..key {
+        cache.get(&key);
+      }
+    }
+
+    assert_eq!(cache.remove(&1), Some(10));
+    assert_eq!(cache.remove(&1), None);
+    assert_eq!(cache.remove(&3), Some(30));
+
+    assert_eq!(cache.pop_lfu(), Some((2, 20)));
+    assert_eq!(cache.pop_lfu(), Some((4, 40)));
+    assert!(cache.pop_lfu().is_none());
+  }
+
+  #[test]
+  fn hot_keys_survive_a_scan() {
+    let mut cache = LfuCache::new(8);
+    for hot in 0..4 {
+      cache.put(format!("hot:{hot}"), hot);
+      cache.get(format!("hot:{hot}").as_str());
+    }
+    for cold in 0..100 {
+      cache.put(format!("cold:{cold}"), cold);
+    }
+
+    for hot in 0..4 {
+      assert_eq!(cache.get(format!("hot:{hot}").as_str()), Some(&hot));
+    }
+    assert_eq!(cache.size(), 8);
+  }
+
+  #[test]
+  fn zero_capacity_stores_nothing() {
+    let mut cache = LfuCache::new(0);
+    assert_eq!(cache.put(1, 1), None);
+    assert!(cache.is_empty());
+  }
+}
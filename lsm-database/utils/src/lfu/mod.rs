@@ -0,0 +1,316 @@
+//! A least-frequently-used cache with `O(1)` lookups, inserts and evictions.
+//!
+//! Every entry counts how often it was read or written. When the cache is
+//! full the entry with the lowest count goes, and among entries with the same
+//! count the one used longest ago. That keeps a small set of hot keys cached
+//! even when a scan touches many cold keys once, which is where an LRU cache
+//! falls over.
+//!
+//! Entries live in a slab and are chained into one list per frequency, and
+//! the frequencies are chained in ascending order, so no operation ever has
+//! to search.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::lfu::LfuCache;
+//!
+//! let mut cache = LfuCache::new(2);
+//! cache.put("a", 1);
+//! cache.put("b", 2);
+//! cache.get(&"a");
+//!
+//! // "b" was used once and "a" twice, so "b" makes room for "c".
+//! cache.put("c", 3);
+//! assert_eq!(cache.get(&"b"), None);
+//! assert_eq!(cache.get(&"a"), Some(&1));
+//! assert_eq!(cache.size(), 2);
+//! ```
+
+mod __test__;
+
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
+
+/// A cached value with its links in the list of its frequency.
+#[derive(Debug)]
+struct Entry<K, V> {
+  key: K,
+  value: V,
+  freq: u64,
+  /// The more recently used neighbor.
+  prev: Option<usize>,
+  /// The less recently used neighbor.
+  next: Option<usize>,
+}
+
+/// The entries used exactly some number of times, most recent first.
+#[derive(Debug)]
+struct Bucket {
+  head: usize,
+  tail: usize,
+  /// The next lower frequency that has entries.
+  lower: Option<u64>,
+  /// The next higher frequency that has entries.
+  higher: Option<u64>,
+}
+
+/// A fixed-capacity cache that evicts its least frequently used entry.
+#[derive(Debug)]
+pub struct LfuCache<K, V> {
+  capacity: usize,
+  slots: Vec<Option<Entry<K, V>>>,
+  free: Vec<usize>,
+  index: HashMap<K, usize>,
+  buckets: HashMap<u64, Bucket>,
+  lowest: Option<u64>,
+}
+
+impl<K, V> LfuCache<K, V>
+where
+  K: Hash + Eq + Clone,
+{
+  /// Creates a new empty cache holding at most `capacity` entries. A cache
+  /// with no capacity stores nothing.
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      slots: Vec::with_capacity(capacity),
+      free: Vec::new(),
+      index: HashMap::with_capacity(capacity),
+      buckets: HashMap::new(),
+      lowest: None,
+    }
+  }
+
+  /// Returns the maximum number of entries.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Returns `true` if cache is empty.
+  pub fn is_empty(&self) -> bool {
+    self.index.is_empty()
+  }
+
+  /// Returns number of entries in cache.
+  pub fn size(&self) -> usize {
+    self.index.len()
+  }
+
+  /// Returns `true` if `key` is cached, without counting as a use.
+  pub fn contains<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.index.contains_key(key)
+  }
+
+  /// Returns how many times `key` was used since it was inserted.
+  pub fn frequency<Q>(&self, key: &Q) -> Option<u64>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.index.get(key).map(|&idx| self.entry(idx).freq)
+  }
+
+  /// Returns the value of `key` and counts it as a use.
+  pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let idx = *self.index.get(key)?;
+    self.touch(idx);
+    Some(&self.entry(idx).value)
+  }
+
+  /// Returns the value of `key` without counting it as a use.
+  pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.index.get(key).map(|&idx| &self.entry(idx).value)
+  }
+
+  /// Caches `value` under `key` and returns the value it replaced.
+  ///
+  /// Replacing a value counts as a use of the key. Inserting a new key into
+  /// a full cache first evicts the least frequently used entry.
+  pub fn put(&mut self, key: K, value: V) -> Option<V> {
+    if self.capacity == 0 {
+      return None;
+    }
+
+    if let Some(&idx) = self.index.get(&key) {
+      self.touch(idx);
+      return Some(std::mem::replace(&mut self.entry_mut(idx).value, value));
+    }
+
+    if self.index.len() == self.capacity {
+      self.pop_lfu();
+    }
+
+    let entry = Entry {
+      key: key.clone(),
+      value,
+      freq: 1,
+      prev: None,
+      next: None,
+    };
+    let idx = match self.free.pop() {
+      Some(idx) => {
+        self.slots[idx] = Some(entry);
+        idx
+      },
+      None => {
+        self.slots.push(Some(entry));
+        self.slots.len() - 1
+      },
+    };
+    self.index.insert(key, idx);
+    // 1 is the lowest possible frequency, so its bucket always comes first.
+    self.attach(idx, None);
+    None
+  }
+
+  /// Removes `key` and returns its value.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let idx = self.index.remove(key)?;
+    self.detach(idx);
+    Some(self.release(idx).value)
+  }
+
+  /// Evicts and returns the least frequently used entry, the least recently
+  /// used one on a tie.
+  pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+    let idx = self.buckets[&self.lowest?].tail;
+    self.detach(idx);
+    let entry = self.release(idx);
+    self.index.remove(&entry.key);
+    Some((entry.key, entry.value))
+  }
+
+  /// Removes every entry.
+  pub fn clear(&mut self) {
+    self.slots.clear();
+    self.free.clear();
+    self.index.clear();
+    self.buckets.clear();
+    self.lowest = None;
+  }
+
+  fn entry(&self, idx: usize) -> &Entry<K, V> {
+    self.slots[idx].as_ref().expect("indexed slot is occupied")
+  }
+
+  fn entry_mut(&mut self, idx: usize) -> &mut Entry<K, V> {
+    self.slots[idx].as_mut().expect("indexed slot is occupied")
+  }
+
+  fn release(&mut self, idx: usize) -> Entry<K, V> {
+    self.free.push(idx);
+    self.slots[idx].take().expect("indexed slot is occupied")
+  }
+
+  /// Moves the entry to the front of the next frequency's list.
+  fn touch(&mut self, idx: usize) {
+    let after = self.detach(idx);
+    self.entry_mut(idx).freq += 1;
+    self.attach(idx, after);
+  }
+
+  /// Unlinks the entry from its frequency list, dropping the list if it
+  /// became empty. Returns the frequency a list for a higher frequency
+  /// would now follow.
+  fn detach(&mut self, idx: usize) -> Option<u64> {
+    let (freq, prev, next) = {
+      let entry = self.entry(idx);
+      (entry.freq, entry.prev, entry.next)
+    };
+
+    match prev {
+      Some(prev) => self.entry_mut(prev).next = next,
+      None => {
+        if let Some(bucket) = self.buckets.get_mut(&freq) {
+          if let Some(next) = next {
+            bucket.head = next;
+          }
+        }
+      },
+    }
+    match next {
+      Some(next) => self.entry_mut(next).prev = prev,
+      None => {
+        if let Some(bucket) = self.buckets.get_mut(&freq) {
+          if let Some(prev) = prev {
+            bucket.tail = prev;
+          }
+        }
+      },
+    }
+
+    if prev.is_some() || next.is_some() {
+      return Some(freq);
+    }
+
+    let bucket = self.buckets.remove(&freq).expect("entry has a bucket");
+    match bucket.lower {
+      Some(lower) => self.bucket_mut(lower).higher = bucket.higher,
+      None => self.lowest = bucket.higher,
+    }
+    if let Some(higher) = bucket.higher {
+      self.bucket_mut(higher).lower = bucket.lower;
+    }
+    bucket.lower
+  }
+
+  /// Links the entry in at the front of its frequency's list, creating the
+  /// list right after the `after` frequency, or first, if it doesn't exist.
+  fn attach(&mut self, idx: usize, after: Option<u64>) {
+    let freq = self.entry(idx).freq;
+
+    if let Some(bucket) = self.buckets.get_mut(&freq) {
+      let head = bucket.head;
+      bucket.head = idx;
+      self.entry_mut(head).prev = Some(idx);
+      let entry = self.entry_mut(idx);
+      entry.prev = None;
+      entry.next = Some(head);
+      return;
+    }
+
+    let higher = match after {
+      Some(lower) => self.bucket_mut(lower).higher.replace(freq),
+      None => self.lowest.replace(freq),
+    };
+    if let Some(higher) = higher {
+      self.bucket_mut(higher).lower = Some(freq);
+    }
+    self.buckets.insert(
+      freq,
+      Bucket {
+        head: idx,
+        tail: idx,
+        lower: after,
+        higher,
+      },
+    );
+    let entry = self.entry_mut(idx);
+    entry.prev = None;
+    entry.next = None;
+  }
+
+  fn bucket_mut(&mut self, freq: u64) -> &mut Bucket {
+    self
+      .buckets
+      .get_mut(&freq)
+      .expect("linked frequency has a bucket")
+  }
+}
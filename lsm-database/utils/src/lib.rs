@@ -3,22 +3,72 @@
 //! The crate is intentionally small and focuses on keeping frequently used
 //! utilities in one place so the higher-level crates (CLI, core engine, etc.)
 //! can import them without duplicating logic. The currently exposed helpers are:
-//! - [`searcher`]: linear and binary search routines that work
-//!   on ordered, copyable data.
-//! - [`sorter`]: a reference selection-sort implementation that
-//!   keeps the input immutable and returns a newly allocated vector.
+//! - [`searcher`]: linear and binary search routines over slices of
+//!   ordered data, returning references or indices.
+//! - [`sorter`]: selection, quick and merge sort implementations that
+//!   either sort a vector they take ownership of or a slice in place.
+//! - [`arena`]: a typed bump allocator whose values are addressed by
+//!   stable, `Copy` ids and freed all at once.
+//! - [`avl`]: an ordered map built as an AVL tree out of `Box`ed nodes,
+//!   with no `unsafe`.
+//! - [`bitmap`]: a Roaring-style compressed bitmap of `u32` values with
+//!   `rank` and `select`.
+//! - [`block`]: the data block format of the planned SSTables, storing
+//!   each key as the length of the prefix it shares with the previous one
+//!   plus the rest, with restart points to binary search.
+//! - [`btree`]: an ordered map built as a B-tree with a configurable
+//!   fanout.
+//! - [`codec`]: `RecordWriter` and `RecordReader`, which put and get the
+//!   little-endian fields of the engines' record and hint formats.
+//! - [`crc`]: table-driven CRC-32C and CRC-64 checksums, one-shot or
+//!   incremental.
+//! - [`cuckoo`]: a cuckoo filter, a membership filter that supports
+//!   removing keys and round-trips through bytes.
+//! - [`deque`]: a double-ended queue on top of [`linked_list`], pushing and
+//!   popping at both ends.
+//! - [`dsu`]: a union-find over `0..n` with path compression and union by
+//!   rank, for grouping related items into clusters.
+//! - [`hash`]: xxHash64, FNV-1a and the MurmurHash3 finalizer, with
+//!   `Hasher` implementations.
+//! - [`heap`]: a binary heap that pops either its largest or its smallest
+//!   element first, for priority queues such as a k-way merge.
+//! - [`lfu`]: a fixed-capacity cache that evicts its least frequently used
+//!   entry, in constant time.
 //! - [`linked_list`]: a flexible `Rc<RefCell<_>>` powered doubly linked list
 //!   used internally and by the other collections in this crate.
 //! - [`queue`]: a FIFO queue built on top of the same node representation,
 //!   offering `enqueue`, `dequeue`, and iteration helpers.
+//! - [`ring`]: a fixed-capacity FIFO buffer that overwrites its oldest
+//!   value when full, for bounded histories and samples.
+//! - [`skiplist`]: an ordered map built as a skip list, with range
+//!   iteration, the planned alternative memtable.
 //! - [`stack`]: a LIFO stack that exposes the classic `push`, `pop`, and
 //!   peek-style helpers while still allowing iteration when needed.
+//! - [`varint`]: LEB128 varint and zigzag encoding, to store small integers
+//!   in fewer bytes.
 //!
 //! Additional utilities should follow the same pattern: small, well-documented,
 //! and dependency-free, making them easy to audit and test.
 pub mod searcher;
 pub mod sorter;
 
+pub mod arena;
+pub mod avl;
+pub mod bitmap;
+pub mod block;
+pub mod btree;
+pub mod codec;
+pub mod crc;
+pub mod cuckoo;
+pub mod deque;
+pub mod dsu;
+pub mod hash;
+pub mod heap;
+pub mod lfu;
+
 pub mod linked_list;
 pub mod queue;
+pub mod ring;
+pub mod skiplist;
 pub mod stack;
+pub mod varint;
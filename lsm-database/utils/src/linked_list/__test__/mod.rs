@@ -4,6 +4,17 @@ mod linked_list_test {
 
   type TestList = LinkedList<&'static str>;
 
+  /// Weak handles to every node, to check that they are freed.
+  fn weak_nodes(list: &TestList) -> Vec<Weak<RefCell<Node<&'static str>>>> {
+    let mut nodes = Vec::new();
+    let mut cursor = list.head.clone();
+    while let Some(node) = cursor {
+      nodes.push(Rc::downgrade(&node));
+      cursor = node.borrow().next.clone();
+    }
+    nodes
+  }
+
   fn make_list() -> TestList {
     let mut list = TestList::new();
     list.insert_end("a");
@@ -97,6 +108,20 @@ mod linked_list_test {
     assert!(list.find("z").is_none());
   }
 
+  #[test]
+  fn contains_and_index_of() {
+    let mut list = make_list();
+    list.insert_end("b"); // a b c d b
+
+    assert!(list.contains(&"d"));
+    assert!(!list.contains(&"x"));
+    assert_eq!(list.index_of(&"a"), Some(0));
+    assert_eq!(list.index_of(&"b"), Some(1));
+    assert_eq!(list.index_of(&"d"), Some(3));
+    assert_eq!(list.index_of(&"x"), None);
+    assert!(!TestList::new().contains(&"a"));
+  }
+
   // ---------------------------------------------------------
   // update tests
   // ---------------------------------------------------------
@@ -181,6 +206,80 @@ mod linked_list_test {
     assert!(list.pop_at(10).is_none());
   }
 
+  #[test]
+  fn remove_first_match() {
+    let mut list = make_list();
+    list.insert_end("b"); // a b c d b
+
+    assert_eq!(list.remove(&"b"), Some("b"));
+    assert_eq!(list.size(), 4);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "c", "d", "b"]);
+
+    let c = list.node_at(1).unwrap();
+    assert_eq!(c.borrow().prev().unwrap().borrow().value, "a");
+    assert_eq!(c.borrow().next.as_ref().unwrap().borrow().value, "d");
+  }
+
+  #[test]
+  fn remove_without_clone() {
+    #[derive(Debug, PartialEq)]
+    struct Token(u32);
+
+    let mut list = LinkedList::new();
+    list.insert_end(Token(1));
+    list.insert_end(Token(2));
+
+    assert_eq!(list.remove(&Token(1)), Some(Token(1)));
+    assert_eq!(list.size(), 1);
+  }
+
+  #[test]
+  fn remove_ends_and_missing() {
+    let mut list = make_list();
+
+    assert_eq!(list.remove(&"a"), Some("a"));
+    assert_eq!(list.remove(&"d"), Some("d"));
+    assert_eq!(list.remove(&"x"), None);
+    assert_eq!(list.size(), 2);
+    assert_eq!(list.head.as_ref().unwrap().borrow().value, "b");
+    assert!(list.head.as_ref().unwrap().borrow().prev.is_none());
+    assert_eq!(list.tail.as_ref().unwrap().borrow().value, "c");
+    assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+
+    list.remove(&"b");
+    list.remove(&"c");
+    assert_eq!(list.size(), 0);
+    assert!(list.head.is_none());
+    assert!(list.tail.is_none());
+  }
+
+  #[test]
+  fn remove_after_a_handle_is_dropped() {
+    let mut list = make_list();
+    let handle = list.find("c").unwrap();
+    drop(handle);
+
+    assert_eq!(list.remove(&"c"), Some("c"));
+    assert_eq!(list.size(), 3);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b", "d"]);
+  }
+
+  #[test]
+  fn remove_while_a_handle_is_held() {
+    for value in ["a", "c", "d"] {
+      let mut list = make_list();
+      let handle = list.find(value).unwrap();
+
+      assert_eq!(list.remove(&value), None);
+      assert_eq!(list.size(), 4);
+      assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b", "c", "d"]);
+
+      drop(handle);
+      assert_eq!(list.remove(&value), Some(value));
+      assert_eq!(list.size(), 3);
+    }
+  }
+
   // ---------------------------------------------------------
   // iteration tests
   // ---------------------------------------------------------
@@ -199,6 +298,31 @@ mod linked_list_test {
     assert!(collected.is_empty());
   }
 
+  #[test]
+  fn iter_rev_walks_prev_pointers() {
+    let list = make_list();
+    assert_eq!(
+      list.iter_rev().collect::<Vec<_>>(),
+      vec!["d", "c", "b", "a"]
+    );
+    assert_eq!(TestList::new().iter_rev().next(), None);
+  }
+
+  #[test]
+  fn iter_from_both_ends_meets_in_the_middle() {
+    let list = make_list();
+    let mut iter = list.iter();
+
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some("a"));
+    assert_eq!(iter.next_back(), Some("d"));
+    assert_eq!(iter.next_back(), Some("c"));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next(), Some("b"));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+  }
+
   // ---------------------------------------------------------
   // structural integrity tests
   // ---------------------------------------------------------
@@ -210,7 +334,7 @@ mod linked_list_test {
     let c = list.node_at(2).unwrap();
 
     assert_eq!(b.borrow().next.as_ref().unwrap().borrow().value, "c");
-    assert_eq!(c.borrow().prev.as_ref().unwrap().borrow().value, "b");
+    assert_eq!(c.borrow().prev().unwrap().borrow().value, "b");
   }
 
   #[test]
@@ -222,7 +346,7 @@ mod linked_list_test {
     let c = list.node_at(1).unwrap();
 
     assert_eq!(a.borrow().next.as_ref().unwrap().borrow().value, "c");
-    assert_eq!(c.borrow().prev.as_ref().unwrap().borrow().value, "a");
+    assert_eq!(c.borrow().prev().unwrap().borrow().value, "a");
   }
 
   #[test]
@@ -235,6 +359,235 @@ mod linked_list_test {
     assert!(list.head.is_none());
   }
 
+  #[test]
+  fn append_links_the_lists() {
+    let mut list = make_list();
+    let mut other = TestList::new();
+    other.insert_end("e");
+    other.insert_end("f");
+
+    list.append(other);
+
+    assert_eq!(list.size(), 6);
+    assert_eq!(
+      list.iter().collect::<Vec<_>>(),
+      vec!["a", "b", "c", "d", "e", "f"]
+    );
+    let e = list.node_at(4).unwrap();
+    assert_eq!(e.borrow().prev().unwrap().borrow().value, "d");
+    assert_eq!(list.tail.as_ref().unwrap().borrow().value, "f");
+
+    list.append(TestList::new());
+    assert_eq!(list.size(), 6);
+
+    let mut empty = TestList::new();
+    empty.append(list);
+    assert_eq!(empty.size(), 6);
+    assert_eq!(empty.head.as_ref().unwrap().borrow().value, "a");
+    assert_eq!(empty.tail.as_ref().unwrap().borrow().value, "f");
+  }
+
+  #[test]
+  fn split_at_middle() {
+    let mut list = make_list();
+
+    let suffix = list.split_at(3);
+
+    assert_eq!(list.size(), 3);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+    assert_eq!(suffix.size(), 1);
+    assert_eq!(suffix.iter().collect::<Vec<_>>(), vec!["d"]);
+    assert!(suffix.head.as_ref().unwrap().borrow().prev.is_none());
+    assert!(Rc::ptr_eq(
+      suffix.head.as_ref().unwrap(),
+      suffix.tail.as_ref().unwrap()
+    ));
+
+    list.append(suffix);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b", "c", "d"]);
+  }
+
+  #[test]
+  fn split_at_ends() {
+    let mut list = make_list();
+
+    let empty = list.split_at(4);
+    assert_eq!(empty.size(), 0);
+    assert_eq!(list.size(), 4);
+
+    let all = list.split_at(0);
+    assert_eq!(all.size(), 4);
+    assert_eq!(list.size(), 0);
+    assert!(list.head.is_none() && list.tail.is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "out of bounds")]
+  fn split_at_past_the_end_panics() {
+    make_list().split_at(5);
+  }
+
+  #[test]
+  fn reverse_swaps_links_and_ends() {
+    let mut list = make_list();
+    let b = list.node_at(1).unwrap();
+
+    list.reverse();
+
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["d", "c", "b", "a"]);
+    assert_eq!(list.size(), 4);
+    assert!(list.head.as_ref().unwrap().borrow().prev.is_none());
+    assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+    assert_eq!(list.tail.as_ref().unwrap().borrow().value, "a");
+
+    // the handle still points into the list, with its links swapped
+    assert_eq!(b.borrow().prev().unwrap().borrow().value, "c");
+    assert_eq!(b.borrow().next.as_ref().unwrap().borrow().value, "a");
+
+    list.insert_end("z");
+    list.reverse();
+    assert_eq!(
+      list.iter().collect::<Vec<_>>(),
+      vec!["z", "a", "b", "c", "d"]
+    );
+  }
+
+  #[test]
+  fn reverse_empty_and_single() {
+    let mut list = TestList::new();
+    list.reverse();
+    assert!(list.head.is_none());
+
+    list.insert_end("a");
+    list.reverse();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a"]);
+    assert!(Rc::ptr_eq(
+      list.head.as_ref().unwrap(),
+      list.tail.as_ref().unwrap()
+    ));
+  }
+
+  #[test]
+  fn sort_relinks_nodes() {
+    let mut list = TestList::new();
+    for value in ["d", "b", "e", "a", "c"] {
+      list.insert_end(value);
+    }
+    let a = list.find("a").unwrap();
+
+    list.sort();
+
+    assert_eq!(
+      list.iter().collect::<Vec<_>>(),
+      vec!["a", "b", "c", "d", "e"]
+    );
+    assert_eq!(list.size(), 5);
+    assert!(Rc::ptr_eq(list.head.as_ref().unwrap(), &a));
+    assert!(a.borrow().prev.is_none());
+    assert_eq!(list.tail.as_ref().unwrap().borrow().value, "e");
+    assert!(list.tail.as_ref().unwrap().borrow().next.is_none());
+
+    // walking back from the tail visits every node
+    let mut backwards = Vec::new();
+    let mut cursor = list.tail.clone();
+    while let Some(node) = cursor {
+      backwards.push(node.borrow().value);
+      cursor = node.borrow().prev();
+    }
+    assert_eq!(backwards, vec!["e", "d", "c", "b", "a"]);
+  }
+
+  #[test]
+  fn sort_by_is_stable() {
+    let mut list = LinkedList::new();
+    let pairs = (0..37).map(|i| ((i * 7) % 5, i)).collect::<Vec<_>>();
+    for &pair in &pairs {
+      list.insert_end(pair);
+    }
+
+    list.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut expected = pairs.clone();
+    expected.sort_by_key(|pair| pair.0);
+    assert_eq!(list.iter().collect::<Vec<_>>(), expected);
+  }
+
+  #[test]
+  fn sort_short_lists() {
+    let mut list = TestList::new();
+    list.sort();
+    assert_eq!(list.size(), 0);
+
+    list.insert_end("b");
+    list.sort();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["b"]);
+
+    list.insert_end("a");
+    list.sort();
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(list.tail.as_ref().unwrap().borrow().value, "b");
+  }
+
+  // ---------------------------------------------------------
+  // leak tests
+  // ---------------------------------------------------------
+
+  #[test]
+  fn drop_frees_every_node() {
+    let list = make_list();
+    let nodes = weak_nodes(&list);
+    assert_eq!(nodes.len(), 4);
+
+    drop(list);
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+  }
+
+  #[test]
+  fn relinked_nodes_are_freed() {
+    let mut list = make_list();
+    list.insert_at("x", 2);
+    list.reverse();
+    list.sort();
+    let mut suffix = list.split_at(2);
+    suffix.reverse();
+    list.append(suffix);
+    list.remove(&"c");
+    list.pop_at(1);
+    let nodes = weak_nodes(&list);
+
+    drop(list);
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+  }
+
+  #[test]
+  fn popped_node_is_freed_with_its_handle() {
+    let mut list = make_list();
+    let popped = list.pop_at(1).unwrap();
+    let weak = Rc::downgrade(&popped);
+
+    drop(list);
+    assert!(weak.upgrade().is_some());
+    drop(popped);
+    assert!(weak.upgrade().is_none());
+  }
+
+  #[test]
+  fn held_handle_keeps_the_rest_alive() {
+    let list = make_list();
+    let c = list.node_at(2).unwrap();
+    let nodes = weak_nodes(&list);
+
+    drop(list);
+    assert!(nodes[0].upgrade().is_none());
+    assert!(nodes[1].upgrade().is_none());
+    assert_eq!(c.borrow().next.as_ref().unwrap().borrow().value, "d");
+    assert!(c.borrow().prev().is_none());
+
+    drop(c);
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+  }
+
   // ---------------------------------------------------------
   // stress tests
   // ---------------------------------------------------------
@@ -261,4 +614,13 @@ mod linked_list_test {
 
     assert_eq!(list.size(), 250);
   }
+
+  #[test]
+  fn dropping_a_long_list_does_not_overflow_the_stack() {
+    let mut list = LinkedList::new();
+    for i in 0..200_000 {
+      list.insert_end(i);
+    }
+    drop(list);
+  }
 }
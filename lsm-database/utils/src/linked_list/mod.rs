@@ -1,13 +1,20 @@
 //! A simple doubly linked list implemented with `Rc<RefCell<Node<T>>>`.
 //!
+//! `next` pointers own the following node and `prev` pointers are `Weak`, so
+//! the nodes don't form reference cycles and are freed with the list.
+//!
 //! This list keeps both `head` and `tail` pointers and supports:
 //!
 //! * inserting at the start, end, or at an index
 //! * updating at the start, end, or at an index
 //! * removing (popping) from the start, end, or at an index
-//! * searching by value
+//! * removing the first node holding a value
+//! * searching by value, or for its index
 //! * accessing a node by index
-//! * iterating over all values in order
+//! * appending another list and splitting off a suffix
+//! * reversing the order in place
+//! * sorting by relinking the nodes
+//! * iterating over all values in order, from either end
 //!
 //! # Example
 //!
@@ -28,22 +35,27 @@
 
 mod __test__;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+  cell::RefCell,
+  marker::PhantomData,
+  rc::{Rc, Weak},
+};
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
 
 /// A single node in the linked list.
 ///
 /// Each node stores:
 ///
-/// * a previous pointer `prev`
+/// * a previous pointer `prev`, which doesn't keep that node alive
 /// * a value `value`
 /// * a next pointer `next`
 pub struct Node<T>
 where
   T: PartialEq,
 {
-  pub prev: Link<T>,
+  pub prev: WeakLink<T>,
   pub value: T,
   pub next: Link<T>,
 }
@@ -56,13 +68,18 @@ where
     Self {
       prev: None,
       next: None,
-      value: value,
+      value,
     }
   }
 
   fn wrap(self) -> Rc<RefCell<Node<T>>> {
     Rc::new(RefCell::new(self))
   }
+
+  /// Returns the previous node, or `None` for the head.
+  pub fn prev(&self) -> Link<T> {
+    self.prev.as_ref().and_then(Weak::upgrade)
+  }
 }
 
 impl<T> std::fmt::Debug for Node<T>
@@ -122,6 +139,16 @@ where
     self.len
   }
 
+  /// Returns the first node, or `None` if the list is empty.
+  pub fn head(&self) -> Link<T> {
+    self.head.clone()
+  }
+
+  /// Returns the last node, or `None` if the list is empty.
+  pub fn tail(&self) -> Link<T> {
+    self.tail.clone()
+  }
+
   /// Finds the first node whose value equals `value`.
   ///
   /// Returns `Some(node)` if found, or `None` if not present.
@@ -139,6 +166,55 @@ where
     None
   }
 
+  /// Returns `true` if a node holds `value`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(1);
+  ///
+  /// assert!(list.contains(&1));
+  /// assert!(!list.contains(&2));
+  /// ```
+  pub fn contains(&self, value: &T) -> bool {
+    self.index_of(value).is_some()
+  }
+
+  /// Returns the index of the first node whose value equals `value`, or
+  /// `None` if not present.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end("a");
+  /// list.insert_end("b");
+  /// list.insert_end("b");
+  ///
+  /// assert_eq!(list.index_of(&"b"), Some(1));
+  /// assert_eq!(list.index_of(&"c"), None);
+  /// ```
+  pub fn index_of(&self, value: &T) -> Option<usize> {
+    let mut cursor = self.head.clone();
+    let mut index = 0;
+
+    while let Some(node_rc) = cursor {
+      let node = node_rc.borrow();
+      if node.value == *value {
+        return Some(index);
+      }
+      cursor = node.next.clone();
+      index += 1;
+    }
+
+    None
+  }
+
   /// Returns the node at the given index, if it exists.
   ///
   /// Indexes are zero based, from `0` to `len - 1`.
@@ -185,7 +261,7 @@ where
 
     match self.head.take() {
       Some(old_head) => {
-        old_head.borrow_mut().prev = Some(new.clone());
+        old_head.borrow_mut().prev = Some(Rc::downgrade(&new));
         new.borrow_mut().next = Some(old_head);
         self.head = Some(new.clone());
       },
@@ -221,8 +297,8 @@ where
 
     match self.tail.take() {
       Some(old_tail) => {
+        new.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
         old_tail.borrow_mut().next = Some(new.clone());
-        new.borrow_mut().prev = Some(old_tail);
         self.tail = Some(new.clone());
         if self.head.is_none() {
           self.head = self.tail.clone();
@@ -273,7 +349,7 @@ where
 
     // index is between 1 and len - 1
     let current = self.node_at(index).unwrap();
-    let prev = current.borrow().prev.clone().unwrap();
+    let prev = current.borrow().prev().unwrap();
 
     let new = Node::new(value).wrap();
 
@@ -284,13 +360,13 @@ where
 
     {
       let mut new_ref = new.borrow_mut();
-      new_ref.prev = Some(prev.clone());
+      new_ref.prev = Some(Rc::downgrade(&prev));
       new_ref.next = Some(current.clone());
     }
 
     {
       let mut cur_ref = current.borrow_mut();
-      cur_ref.prev = Some(new.clone());
+      cur_ref.prev = Some(Rc::downgrade(&new));
     }
 
     self.len += 1;
@@ -371,7 +447,7 @@ where
   pub fn pop_end(&mut self) -> Link<T> {
     let old_tail = self.tail.clone()?;
 
-    let prev = old_tail.borrow_mut().prev.take();
+    let prev = old_tail.borrow_mut().prev.take().and_then(|p| p.upgrade());
     match prev {
       Some(ref new_tail) => {
         new_tail.borrow_mut().next = None;
@@ -425,7 +501,7 @@ where
 
     let current = self.node_at(index).unwrap();
 
-    let prev = current.borrow().prev.clone();
+    let prev = current.borrow().prev();
     let next = current.borrow().next.clone();
 
     if let Some(ref p) = prev {
@@ -433,13 +509,324 @@ where
     }
 
     if let Some(ref n) = next {
-      n.borrow_mut().prev = prev.clone();
+      n.borrow_mut().prev = prev.as_ref().map(Rc::downgrade);
     }
 
     self.len = self.len.saturating_sub(1);
     Some(current)
   }
 
+  /// Unlinks the first node whose value equals `value` and returns its
+  /// value.
+  ///
+  /// Returns `None` if no node holds `value`, or if another handle to the
+  /// first one, e.g. from [`find`](Self::find), is still alive. The list is
+  /// left unchanged in both cases.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end("a");
+  /// list.insert_end("b");
+  /// list.insert_end("a");
+  ///
+  /// assert_eq!(list.remove(&"a"), Some("a"));
+  /// assert_eq!(list.remove(&"x"), None);
+  ///
+  /// let values: Vec<_> = list.iter().collect();
+  /// assert_eq!(values, vec!["b", "a"]);
+  /// ```
+  pub fn remove(&mut self, value: &T) -> Option<T> {
+    let mut cursor = self.head.clone();
+
+    while let Some(node_rc) = cursor {
+      if node_rc.borrow().value == *value {
+        // The list owns the node through its predecessor (or `head`) and,
+        // at the end, `tail`; anything past that and `cursor` is a handle.
+        let is_tail = self
+          .tail
+          .as_ref()
+          .is_some_and(|tail| Rc::ptr_eq(tail, &node_rc));
+        if Rc::strong_count(&node_rc) > 2 + usize::from(is_tail) {
+          return None;
+        }
+
+        self.unlink(&node_rc);
+        return Rc::try_unwrap(node_rc)
+          .ok()
+          .map(|node| node.into_inner().value);
+      }
+      cursor = node_rc.borrow().next.clone();
+    }
+
+    None
+  }
+
+  /// Moves every node of `other` onto the end of this list, in O(1).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(1);
+  /// let mut other = LinkedList::new();
+  /// other.insert_end(2);
+  /// other.insert_end(3);
+  ///
+  /// list.append(other);
+  ///
+  /// assert_eq!(list.size(), 3);
+  /// let values: Vec<_> = list.iter().collect();
+  /// assert_eq!(values, vec![1, 2, 3]);
+  /// ```
+  pub fn append(&mut self, mut other: LinkedList<T>) {
+    let Some(other_head) = other.head.take() else {
+      return;
+    };
+
+    match self.tail.take() {
+      Some(old_tail) => {
+        other_head.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
+        old_tail.borrow_mut().next = Some(other_head);
+      },
+      None => {
+        // List was empty, it becomes `other`
+        self.head = Some(other_head);
+      },
+    }
+
+    self.tail = other.tail.take();
+    self.len += std::mem::take(&mut other.len);
+  }
+
+  /// Splits the list in two at `index`: this list keeps the nodes before
+  /// it and the nodes from `index` on are returned as a new list.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end("a");
+  /// list.insert_end("b");
+  /// list.insert_end("c");
+  ///
+  /// let suffix = list.split_at(1);
+  ///
+  /// assert_eq!(list.iter().collect::<Vec<_>>(), vec!["a"]);
+  /// assert_eq!(suffix.iter().collect::<Vec<_>>(), vec!["b", "c"]);
+  /// ```
+  pub fn split_at(&mut self, index: usize) -> LinkedList<T> {
+    assert!(
+      index <= self.len,
+      "index {index} is out of bounds for a list of length {}",
+      self.len
+    );
+
+    if index == 0 {
+      return std::mem::take(self);
+    } else if index == self.len {
+      return Self::new();
+    }
+
+    // index is between 1 and len - 1
+    let first = self.node_at(index).unwrap();
+    let last = first
+      .borrow_mut()
+      .prev
+      .take()
+      .and_then(|p| p.upgrade())
+      .unwrap();
+    last.borrow_mut().next = None;
+
+    let suffix = LinkedList {
+      head: Some(first),
+      tail: self.tail.replace(last),
+      len: self.len - index,
+    };
+    self.len = index;
+    suffix
+  }
+
+  /// Reverses the order of the list in place.
+  ///
+  /// Every node's `prev` and `next` pointers are swapped, and so are `head`
+  /// and `tail`; no node is reallocated, so handles to nodes stay valid.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(1);
+  /// list.insert_end(2);
+  /// list.insert_end(3);
+  ///
+  /// list.reverse();
+  ///
+  /// let values: Vec<_> = list.iter().collect();
+  /// assert_eq!(values, vec![3, 2, 1]);
+  /// ```
+  pub fn reverse(&mut self) {
+    let mut cursor = self.head.clone();
+    // The node reversed last, which owns the ones before it.
+    let mut previous: Link<T> = None;
+
+    while let Some(node_rc) = cursor {
+      let next = node_rc.borrow_mut().next.take();
+      {
+        let mut node = node_rc.borrow_mut();
+        node.next = previous.take();
+        node.prev = next.as_ref().map(Rc::downgrade);
+      }
+      previous = Some(node_rc);
+      cursor = next;
+    }
+
+    std::mem::swap(&mut self.head, &mut self.tail);
+  }
+
+  /// Sorts the list in ascending order.
+  ///
+  /// See [`sort_by`](Self::sort_by).
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(3);
+  /// list.insert_end(1);
+  /// list.insert_end(2);
+  ///
+  /// list.sort();
+  ///
+  /// let values: Vec<_> = list.iter().collect();
+  /// assert_eq!(values, vec![1, 2, 3]);
+  /// ```
+  pub fn sort(&mut self)
+  where
+    T: Ord,
+  {
+    self.sort_by(T::cmp);
+  }
+
+  /// Sorts the list with a comparator function.
+  ///
+  /// This is a bottom-up merge sort: it merges runs of 1, 2, 4, ... nodes
+  /// by relinking them, so values are never moved or cloned and handles to
+  /// nodes stay valid. The sort is stable, so equal nodes keep their order.
+  ///
+  /// Time complexity: O(n log n), with O(1) extra space.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(1.5);
+  /// list.insert_end(-2.0);
+  /// list.insert_end(0.25);
+  ///
+  /// list.sort_by(|a: &f64, b| b.total_cmp(a));
+  ///
+  /// let values: Vec<_> = list.iter().collect();
+  /// assert_eq!(values, vec![1.5, 0.25, -2.0]);
+  /// ```
+  pub fn sort_by<F>(&mut self, mut compare: F)
+  where
+    F: FnMut(&T, &T) -> std::cmp::Ordering,
+  {
+    if self.len < 2 {
+      return;
+    }
+
+    // Only `next` pointers, which own the nodes, are kept up to date while
+    // merging.
+    let mut head = self.head.take();
+    let mut width = 1;
+
+    loop {
+      let mut rest = head.take();
+      let mut tail: Link<T> = None;
+      let mut merges = 0;
+
+      while rest.is_some() {
+        merges += 1;
+        let mut left = rest;
+        let mut right = split_run(&mut left, width);
+        rest = split_run(&mut right, width);
+
+        // Take from the left run on ties, to keep the sort stable.
+        loop {
+          let from_left = match (&left, &right) {
+            (Some(l), Some(r)) => compare(&l.borrow().value, &r.borrow().value).is_le(),
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+          };
+          let run = if from_left { &mut left } else { &mut right };
+          let node = run.take().unwrap();
+          *run = node.borrow_mut().next.take();
+
+          match tail.take() {
+            Some(t) => t.borrow_mut().next = Some(node.clone()),
+            None => head = Some(node.clone()),
+          }
+          tail = Some(node);
+        }
+      }
+
+      if merges <= 1 {
+        break;
+      }
+      width *= 2;
+    }
+
+    // Restore the `prev` pointers and the tail.
+    let mut prev: Link<T> = None;
+    let mut cursor = head.clone();
+    while let Some(node_rc) = cursor {
+      node_rc.borrow_mut().prev = prev.as_ref().map(Rc::downgrade);
+      cursor = node_rc.borrow().next.clone();
+      prev = Some(node_rc);
+    }
+
+    self.head = head;
+    self.tail = prev;
+  }
+
+  /// Detaches `node` from its neighbors, or from the ends of the list, and
+  /// clears its own links.
+  fn unlink(&mut self, node: &Rc<RefCell<Node<T>>>) {
+    let prev = node.borrow_mut().prev.take().and_then(|p| p.upgrade());
+    let next = node.borrow_mut().next.take();
+
+    match prev {
+      Some(ref p) => p.borrow_mut().next = next.clone(),
+      None => self.head = next.clone(),
+    }
+    match next {
+      Some(ref n) => n.borrow_mut().prev = prev.as_ref().map(Rc::downgrade),
+      None => self.tail = prev,
+    }
+
+    self.len -= 1;
+  }
+
   /// Returns an iterator over the values in the list, from head to tail.
   ///
   /// The iterator yields owned `T` values, so `T` must implement `Clone`.
@@ -457,43 +844,133 @@ where
   /// let values: Vec<_> = list.iter().collect();
   /// assert_eq!(values, vec![1, 2, 3]);
   /// ```
-  pub fn iter(&self) -> LinkedListIter<T> {
+  pub fn iter(&self) -> LinkedListIter<'_, T> {
     LinkedListIter {
-      current: self.head.clone(),
+      front: self.head.clone(),
+      back: self.tail.clone(),
+      remaining: self.len,
+      list: PhantomData,
+    }
+  }
+
+  /// Returns an iterator over the values in the list, from tail to head.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use utils::linked_list::LinkedList;
+  ///
+  /// let mut list = LinkedList::new();
+  /// list.insert_end(1);
+  /// list.insert_end(2);
+  /// list.insert_end(3);
+  ///
+  /// let values: Vec<_> = list.iter_rev().collect();
+  /// assert_eq!(values, vec![3, 2, 1]);
+  /// ```
+  pub fn iter_rev(&self) -> std::iter::Rev<LinkedListIter<'_, T>>
+  where
+    T: Clone,
+  {
+    self.iter().rev()
+  }
+}
+
+/// Frees the nodes one at a time. Dropping the head alone would free the
+/// whole chain recursively, one stack frame per node.
+impl<T> Drop for LinkedList<T>
+where
+  T: PartialEq,
+{
+  fn drop(&mut self) {
+    self.tail = None;
+    let mut cursor = self.head.take();
+
+    while let Some(node_rc) = cursor {
+      // A node still held elsewhere keeps the rest of the chain alive.
+      cursor = match Rc::try_unwrap(node_rc) {
+        Ok(node) => node.into_inner().next,
+        Err(_) => None,
+      };
     }
   }
 }
 
-/// Iterator over `LinkedList`, walking from head to tail.
-pub struct LinkedListIter<T>
+/// Cuts the chain starting at `run` after `len` nodes, following only the
+/// `next` pointers, and returns the rest of it.
+fn split_run<T: PartialEq>(run: &mut Link<T>, len: usize) -> Link<T> {
+  let mut cursor = run.clone();
+
+  for _ in 1..len {
+    let next = match &cursor {
+      Some(node_rc) => node_rc.borrow().next.clone(),
+      None => return None,
+    };
+    cursor = next;
+  }
+
+  cursor.and_then(|node_rc| node_rc.borrow_mut().next.take())
+}
+
+/// Iterator over `LinkedList`, walking from head to tail, or from tail to
+/// head through the `prev` pointers when used from the back.
+///
+/// It holds on to the nodes at both ends, so it borrows the list to keep it
+/// from being changed underneath.
+pub struct LinkedListIter<'a, T>
 where
   T: PartialEq,
 {
-  current: Link<T>,
+  front: Link<T>,
+  back: Link<T>,
+  /// Nodes between `front` and `back`, both included, so the two ends
+  /// stop when they meet.
+  remaining: usize,
+  list: PhantomData<&'a LinkedList<T>>,
 }
 
-impl<T> Iterator for LinkedListIter<T>
+impl<T> Iterator for LinkedListIter<'_, T>
 where
   T: Clone + PartialEq,
 {
   type Item = T;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let current = self.current.clone()?;
-    let value;
-    let next;
+    if self.remaining == 0 {
+      return None;
+    }
 
-    {
-      let node = current.borrow();
-      value = node.value.clone();
-      next = node.next.clone();
+    let current = self.front.clone()?;
+    let node = current.borrow();
+    self.front = node.next.clone();
+    self.remaining -= 1;
+    Some(node.value.clone())
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<T> DoubleEndedIterator for LinkedListIter<'_, T>
+where
+  T: Clone + PartialEq,
+{
+  fn next_back(&mut self) -> Option<Self::Item> {
+    if self.remaining == 0 {
+      return None;
     }
 
-    self.current = next;
-    Some(value)
+    let current = self.back.clone()?;
+    let node = current.borrow();
+    self.back = node.prev();
+    self.remaining -= 1;
+    Some(node.value.clone())
   }
 }
 
+impl<T> ExactSizeIterator for LinkedListIter<'_, T> where T: Clone + PartialEq {}
+
 impl<T> std::fmt::Debug for LinkedList<T>
 where
   T: PartialEq + std::fmt::Debug + Clone,
@@ -519,7 +996,7 @@ where
 
       let node = rc_node.borrow();
 
-      let prev = node.prev.as_ref().map(|p| p.borrow().value.clone());
+      let prev = node.prev().map(|p| p.borrow().value.clone());
       let next = node.next.as_ref().map(|n| n.borrow().value.clone());
 
       writeln!(
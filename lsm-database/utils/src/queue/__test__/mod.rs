@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod queue_test {
+  use std::rc::Rc;
+
   use crate::queue::Queue;
 
   #[test]
@@ -51,4 +53,35 @@ mod queue_test {
     let snapshot = queue.into_vec();
     assert_eq!(snapshot, vec![1, 2, 3, 4]);
   }
+
+  #[test]
+  fn nodes_are_freed() {
+    let mut queue = Queue::new();
+    for i in 0..4 {
+      queue.enqueue(i);
+    }
+    let mut nodes = Vec::new();
+    let mut cursor = queue.head.clone();
+    while let Some(node) = cursor {
+      nodes.push(Rc::downgrade(&node));
+      cursor = node.borrow().next.clone();
+    }
+    let tail = queue.tail.clone().unwrap();
+    assert_eq!(tail.borrow().prev().unwrap().borrow().value, 2);
+    drop(tail);
+
+    queue.dequeue();
+    assert!(nodes[0].upgrade().is_none());
+    drop(queue);
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+  }
+
+  #[test]
+  fn dropping_a_long_queue_does_not_overflow_the_stack() {
+    let mut queue = Queue::new();
+    for i in 0..200_000 {
+      queue.enqueue(i);
+    }
+    drop(queue);
+  }
 }
@@ -2,7 +2,8 @@
 //!
 //! The queue mirrors the linked-list implementation powering the stack module,
 //! which means `enqueue` and `dequeue` stay at `O(1)` while still allowing
-//! iteration when desired.
+//! iteration when desired. `prev` pointers are `Weak`, so the nodes are
+//! freed with the queue.
 //!
 //! # Example
 //!
@@ -21,16 +22,20 @@
 
 mod __test__;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+  cell::RefCell,
+  rc::{Rc, Weak},
+};
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
 
 /// A single node in the queue.
 pub struct Node<T>
 where
   T: PartialEq,
 {
-  pub prev: Link<T>,
+  pub prev: WeakLink<T>,
   pub value: T,
   pub next: Link<T>,
 }
@@ -50,6 +55,11 @@ where
   fn wrap(self) -> Rc<RefCell<Node<T>>> {
     Rc::new(RefCell::new(self))
   }
+
+  /// Returns the node ahead of this one, or `None` at the front.
+  pub fn prev(&self) -> Link<T> {
+    self.prev.as_ref().and_then(Weak::upgrade)
+  }
 }
 
 impl<T: std::fmt::Debug + PartialEq> std::fmt::Debug for Node<T> {
@@ -111,8 +121,8 @@ where
 
     match self.tail.take() {
       Some(old_tail) => {
+        new.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
         old_tail.borrow_mut().next = Some(new.clone());
-        new.borrow_mut().prev = Some(old_tail);
         self.tail = Some(new);
       },
       None => {
@@ -201,6 +211,26 @@ where
   }
 }
 
+/// Frees the nodes one at a time. Dropping the head alone would free the
+/// whole chain recursively, one stack frame per node.
+impl<T> Drop for Queue<T>
+where
+  T: PartialEq,
+{
+  fn drop(&mut self) {
+    self.tail = None;
+    let mut cursor = self.head.take();
+
+    while let Some(node_rc) = cursor {
+      // A node still held elsewhere keeps the rest of the chain alive.
+      cursor = match Rc::try_unwrap(node_rc) {
+        Ok(node) => node.into_inner().next,
+        Err(_) => None,
+      };
+    }
+  }
+}
+
 impl<T> std::fmt::Debug for Queue<T>
 where
   T: PartialEq + std::fmt::Debug + Clone,
@@ -226,7 +256,7 @@ where
       visited.push(raw);
 
       let node = rc_node.borrow();
-      let prev = node.prev.as_ref().map(|p| p.borrow().value.clone());
+      let prev = node.prev().map(|p| p.borrow().value.clone());
       let next = node.next.as_ref().map(|n| n.borrow().value.clone());
 
       writeln!(
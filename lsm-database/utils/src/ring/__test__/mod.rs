@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod ring_test {
+  use crate::ring::RingBuffer;
+
+  #[test]
+  fn new_buffer_is_empty() {
+    let buffer: RingBuffer<i32> = RingBuffer::new(4);
+    assert_eq!(buffer.capacity(), 4);
+    assert_eq!(buffer.size(), 0);
+    assert!(buffer.is_empty());
+    assert!(!buffer.is_full());
+    assert!(buffer.peek().is_none());
+    assert!(buffer.peek_back().is_none());
+  }
+
+  #[test]
+  #[should_panic(expected = "at least one value")]
+  fn zero_capacity_panics() {
+    RingBuffer::<i32>::new(0);
+  }
+
+  #[test]
+  fn push_rejects_when_full() {
+    let mut buffer = RingBuffer::new(2);
+    assert_eq!(buffer.push(1), Ok(()));
+    assert_eq!(buffer.push(2), Ok(()));
+    assert!(buffer.is_full());
+    assert_eq!(buffer.push(3), Err(3));
+    assert_eq!(buffer.into_vec(), vec![1, 2]);
+  }
+
+  #[test]
+  fn push_overwrite_drops_oldest() {
+    let mut buffer = RingBuffer::new(3);
+    let dropped: Vec<_> = (1..=7)
+      .filter_map(|value| buffer.push_overwrite(value))
+      .collect();
+
+    assert_eq!(dropped, vec![1, 2, 3, 4]);
+    assert_eq!(buffer.size(), 3);
+    assert_eq!(buffer.peek(), Some(&5));
+    assert_eq!(buffer.peek_back(), Some(&7));
+    assert_eq!(buffer.get(1), Some(&6));
+    assert_eq!(buffer.get(3), None);
+  }
+
+  #[test]
+  fn pop_returns_fifo_order_across_wraparound() {
+    let mut buffer = RingBuffer::new(3);
+    buffer.push_overwrite(1);
+    buffer.push_overwrite(2);
+    assert_eq!(buffer.pop(), Some(1));
+    buffer.push_overwrite(3);
+    buffer.push_overwrite(4);
+    buffer.push_overwrite(5);
+
+    assert_eq!(buffer.pop(), Some(3));
+    assert_eq!(buffer.pop(), Some(4));
+    assert_eq!(buffer.pop(), Some(5));
+    assert_eq!(buffer.pop(), None);
+  }
+
+  #[test]
+  fn iter_both_ends() {
+    let mut buffer = RingBuffer::new(4);
+    for value in 0..6 {
+      buffer.push_overwrite(value);
+    }
+
+    assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+    assert_eq!(
+      buffer.iter().rev().copied().collect::<Vec<_>>(),
+      vec![5, 4, 3, 2]
+    );
+    assert_eq!(buffer.iter().len(), 4);
+
+    buffer.clear();
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.iter().count(), 0);
+  }
+}
@@ -0,0 +1,196 @@
+//! A fixed-capacity FIFO buffer that never grows.
+//!
+//! Once the buffer is full, [`RingBuffer::push_overwrite`] drops the oldest
+//! value to make room, so it can hold the last N operations or latency
+//! samples of a long-running process in constant memory. Values live in a
+//! `Vec` allocated once; the start of the buffer wraps around it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::ring::RingBuffer;
+//!
+//! let mut latencies = RingBuffer::new(3);
+//! for micros in [120, 95, 310, 88] {
+//!   latencies.push_overwrite(micros);
+//! }
+//!
+//! // The first sample was overwritten.
+//! assert_eq!(latencies.iter().copied().collect::<Vec<_>>(), vec![95, 310, 88]);
+//! assert_eq!(latencies.pop(), Some(95));
+//! ```
+
+mod __test__;
+
+/// A FIFO buffer holding at most `capacity` values.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+  slots: Vec<Option<T>>,
+  /// The slot of the oldest value.
+  head: usize,
+  len: usize,
+}
+
+impl<T> RingBuffer<T> {
+  /// Creates a new empty buffer holding at most `capacity` values.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `capacity` is 0.
+  pub fn new(capacity: usize) -> Self {
+    assert!(
+      capacity > 0,
+      "a ring buffer needs room for at least one value"
+    );
+    Self {
+      slots: (0..capacity).map(|_| None).collect(),
+      head: 0,
+      len: 0,
+    }
+  }
+
+  /// Returns the most values the buffer holds.
+  pub fn capacity(&self) -> usize {
+    self.slots.len()
+  }
+
+  /// Returns `true` if buffer is empty.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns `true` if the next push would overwrite a value.
+  pub fn is_full(&self) -> bool {
+    self.len == self.slots.len()
+  }
+
+  /// Returns number of values in buffer.
+  pub fn size(&self) -> usize {
+    self.len
+  }
+
+  /// Adds a value at the back, or hands it back if the buffer is full.
+  pub fn push(&mut self, value: T) -> Result<(), T> {
+    if self.is_full() {
+      return Err(value);
+    }
+    let slot = self.slot(self.len);
+    self.slots[slot] = Some(value);
+    self.len += 1;
+    Ok(())
+  }
+
+  /// Adds a value at the back, dropping the oldest value if the buffer is
+  /// full. Returns the dropped value.
+  pub fn push_overwrite(&mut self, value: T) -> Option<T> {
+    if !self.is_full() {
+      let slot = self.slot(self.len);
+      self.slots[slot] = Some(value);
+      self.len += 1;
+      return None;
+    }
+    let oldest = self.slots[self.head].replace(value);
+    self.head = self.slot(1);
+    oldest
+  }
+
+  /// Removes the oldest value.
+  pub fn pop(&mut self) -> Option<T> {
+    if self.is_empty() {
+      return None;
+    }
+    let value = self.slots[self.head].take();
+    self.head = self.slot(1);
+    self.len -= 1;
+    value
+  }
+
+  /// Returns the oldest value.
+  pub fn peek(&self) -> Option<&T> {
+    self.get(0)
+  }
+
+  /// Returns the newest value.
+  pub fn peek_back(&self) -> Option<&T> {
+    self.get(self.len.checked_sub(1)?)
+  }
+
+  /// Returns the value `index` places after the oldest one.
+  pub fn get(&self, index: usize) -> Option<&T> {
+    if index >= self.len {
+      return None;
+    }
+    self.slots[self.slot(index)].as_ref()
+  }
+
+  /// Removes every value.
+  pub fn clear(&mut self) {
+    self.slots.iter_mut().for_each(|slot| *slot = None);
+    self.head = 0;
+    self.len = 0;
+  }
+
+  /// Returns an iterator over the values from oldest to newest.
+  pub fn iter(&self) -> RingBufferIterator<'_, T> {
+    RingBufferIterator {
+      buffer: self,
+      front: 0,
+      back: self.len,
+    }
+  }
+
+  /// Converts the buffer into a vec (oldest first).
+  pub fn into_vec(mut self) -> Vec<T> {
+    std::iter::from_fn(|| self.pop()).collect()
+  }
+
+  /// The slot `offset` places after the oldest value.
+  fn slot(&self, offset: usize) -> usize {
+    (self.head + offset) % self.slots.len()
+  }
+}
+
+/// Iterator that walks the buffer from oldest to newest.
+pub struct RingBufferIterator<'a, T> {
+  buffer: &'a RingBuffer<T>,
+  front: usize,
+  back: usize,
+}
+
+impl<'a, T> Iterator for RingBufferIterator<'a, T> {
+  type Item = &'a T;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.front == self.back {
+      return None;
+    }
+    self.front += 1;
+    self.buffer.get(self.front - 1)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let remaining = self.back - self.front;
+    (remaining, Some(remaining))
+  }
+}
+
+impl<T> DoubleEndedIterator for RingBufferIterator<'_, T> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    if self.front == self.back {
+      return None;
+    }
+    self.back -= 1;
+    self.buffer.get(self.back)
+  }
+}
+
+impl<T> ExactSizeIterator for RingBufferIterator<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+  type Item = &'a T;
+  type IntoIter = RingBufferIterator<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
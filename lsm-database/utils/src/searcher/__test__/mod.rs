@@ -7,23 +7,23 @@ mod searcher_test {
   fn test_linear_search() {
     let vec = vec![7, 3, 5, 2];
 
-    let result = Searcher::<u32>::linear_search(&vec, 3);
-    assert_eq!(result, Some(3));
+    let result = Searcher::<u32>::linear_search(&vec, &3);
+    assert_eq!(result, Some(&3));
   }
 
   #[test]
   fn test_binary_search() {
     let vec = vec![2, 3, 5, 7, 11, 13];
 
-    let result = Searcher::<u32>::binary_search(&vec, 3);
-    assert_eq!(result, Some(3));
+    let result = Searcher::<u32>::binary_search(&vec, &3);
+    assert_eq!(result, Some(&3));
   }
 
   #[test]
   fn test_linear_search_missing_value() {
     let vec = vec![7, 3, 5, 2];
 
-    let result = Searcher::<u32>::linear_search(&vec, 4);
+    let result = Searcher::<u32>::linear_search(&vec, &4);
     assert_eq!(result, None);
   }
 
@@ -31,15 +31,187 @@ mod searcher_test {
   fn test_binary_search_left_branch() {
     let vec = vec![2, 3, 5, 7, 11, 13];
 
-    let result = Searcher::<u32>::binary_search(&vec, 2);
-    assert_eq!(result, Some(2));
+    let result = Searcher::<u32>::binary_search(&vec, &2);
+    assert_eq!(result, Some(&2));
   }
 
   #[test]
   fn test_binary_search_missing_left_branch() {
     let vec = vec![2, 3, 5, 7, 11, 13];
 
-    let result = Searcher::<u32>::binary_search(&vec, 1);
+    let result = Searcher::<u32>::binary_search(&vec, &1);
     assert_eq!(result, None);
   }
+
+  #[test]
+  fn test_binary_search_idx() {
+    let vec = vec![2, 3, 5, 7, 11, 13];
+
+    assert_eq!(Searcher::<u32>::binary_search_idx(&vec, &2), Ok(0));
+    assert_eq!(Searcher::<u32>::binary_search_idx(&vec, &13), Ok(5));
+  }
+
+  #[test]
+  fn test_binary_search_idx_insertion_point() {
+    let vec = vec![2, 3, 5, 7, 11, 13];
+
+    assert_eq!(Searcher::<u32>::binary_search_idx(&vec, &1), Err(0));
+    assert_eq!(Searcher::<u32>::binary_search_idx(&vec, &6), Err(3));
+    assert_eq!(Searcher::<u32>::binary_search_idx(&vec, &14), Err(6));
+    assert_eq!(Searcher::<u32>::binary_search_idx(&[], &1), Err(0));
+  }
+
+  #[test]
+  fn test_binary_search_by_descending() {
+    let vec = vec![13, 11, 7, 5, 3, 2];
+
+    assert_eq!(Searcher::binary_search_by(&vec, |item| 7.cmp(item)), Ok(2));
+    assert_eq!(Searcher::binary_search_by(&vec, |item| 6.cmp(item)), Err(3));
+  }
+
+  #[test]
+  fn test_binary_search_by_key() {
+    let vec = vec![("a", 10), ("c", 20), ("e", 30)];
+
+    assert_eq!(
+      Searcher::binary_search_by_key(&vec, &"c", |&(key, _)| key),
+      Ok(1)
+    );
+    assert_eq!(
+      Searcher::binary_search_by_key(&vec, &"d", |&(key, _)| key),
+      Err(2)
+    );
+  }
+
+  #[test]
+  fn test_binary_search_missing_right_branch() {
+    let vec = vec![2, 3, 5, 7, 11, 13];
+
+    let result = Searcher::<u32>::binary_search(&vec, &14);
+    assert_eq!(result, None);
+  }
+
+  #[test]
+  fn test_search_strings() {
+    let vec = vec![
+      "apple".to_string(),
+      "mango".to_string(),
+      "peach".to_string(),
+    ];
+    let needle = "mango".to_string();
+
+    assert_eq!(Searcher::linear_search(&vec, &needle), Some(&vec[1]));
+    assert_eq!(Searcher::binary_search(&vec, &needle), Some(&vec[1]));
+  }
+
+  #[test]
+  fn test_interpolation_search_uniform() {
+    let vec: Vec<u64> = (0..1000).map(|i| i * 64).collect();
+
+    assert_eq!(Searcher::interpolation_search(&vec, &0), Ok(0));
+    assert_eq!(Searcher::interpolation_search(&vec, &(700 * 64)), Ok(700));
+    assert_eq!(Searcher::interpolation_search(&vec, &(999 * 64)), Ok(999));
+  }
+
+  #[test]
+  fn test_interpolation_search_insertion_point() {
+    let vec: Vec<i64> = vec![-50, -10, 0, 3, 3, 3, 90, 1000];
+
+    assert_eq!(Searcher::interpolation_search(&vec, &-51), Err(0));
+    assert_eq!(Searcher::interpolation_search(&vec, &1), Err(3));
+    assert_eq!(Searcher::interpolation_search(&vec, &89), Err(6));
+    assert_eq!(Searcher::interpolation_search(&vec, &1001), Err(8));
+    assert!(matches!(
+      Searcher::interpolation_search(&vec, &3),
+      Ok(3..=5)
+    ));
+    assert_eq!(Searcher::<u32>::interpolation_search(&[], &1), Err(0));
+  }
+
+  #[test]
+  fn test_interpolation_search_matches_binary_search() {
+    let vec: Vec<u32> = (0..200u32).map(|i| i * i).collect();
+
+    for value in 0..200 * 200 {
+      assert_eq!(
+        Searcher::interpolation_search(&vec, &value),
+        Searcher::binary_search_idx(&vec, &value)
+      );
+    }
+  }
+
+  #[test]
+  fn test_lower_and_upper_bound() {
+    let vec = vec![2, 3, 3, 3, 7, 11];
+
+    assert_eq!(Searcher::<u32>::lower_bound(&vec, &3), 1);
+    assert_eq!(Searcher::<u32>::upper_bound(&vec, &3), 4);
+    assert_eq!(Searcher::<u32>::lower_bound(&vec, &1), 0);
+    assert_eq!(Searcher::<u32>::upper_bound(&vec, &11), 6);
+    assert_eq!(Searcher::<u32>::lower_bound(&[], &1), 0);
+  }
+
+  #[test]
+  fn test_partition_point_sparse_index() {
+    let first_keys = vec!["apple", "grape", "mango", "peach"];
+
+    // The block that may hold "kiwi" is the one before the first block
+    // starting after it.
+    let next = Searcher::partition_point(&first_keys, |key| *key <= "kiwi");
+    assert_eq!(next, 2);
+    assert_eq!(Searcher::partition_point(&first_keys, |_| true), 4);
+    assert_eq!(Searcher::partition_point(&first_keys, |_| false), 0);
+  }
+
+  #[test]
+  fn test_linear_search_all() {
+    let vec = vec![7, 3, 5, 3, 2, 3];
+
+    assert_eq!(Searcher::<u32>::linear_search_all(&vec, &3), vec![1, 3, 5]);
+    assert_eq!(
+      Searcher::<u32>::linear_search_all(&vec, &4),
+      Vec::<usize>::new()
+    );
+  }
+
+  #[test]
+  fn test_linear_search_iter_is_lazy() {
+    let vec = vec![7, 3, 5, 3, 2, 3];
+
+    let first = Searcher::<u32>::linear_search_iter(&vec, &3).next();
+    assert_eq!(first, Some(1));
+    assert_eq!(Searcher::<u32>::linear_search_iter(&vec, &3).count(), 3);
+  }
+
+  #[test]
+  fn test_fuzzy_search_ranks_by_distance() {
+    let vec = vec!["kitten", "sitting", "mitten", "kitchen", "smitten"];
+
+    let matches = Searcher::fuzzy_search(&vec, "kitten", 2);
+    assert_eq!(
+      matches,
+      vec![
+        (&"kitten", 0),
+        (&"mitten", 1),
+        (&"kitchen", 2),
+        (&"smitten", 2)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_fuzzy_search_no_match() {
+    let vec = vec!["alpha".to_string(), "beta".to_string()];
+
+    assert!(Searcher::fuzzy_search(&vec, "gamma", 1).is_empty());
+    assert_eq!(Searcher::fuzzy_search(&vec, "", 4), vec![(&vec[1], 4)]);
+  }
+
+  #[test]
+  fn test_fuzzy_search_counts_characters() {
+    let vec = vec!["café", "cafe"];
+
+    assert_eq!(Searcher::fuzzy_search(&vec, "cafe", 0), vec![(&"cafe", 0)]);
+    assert_eq!(Searcher::fuzzy_search(&vec, "cafe", 1).len(), 2);
+  }
 }
@@ -1,4 +1,5 @@
-//! Search utilities that expose reusable linear and binary search helpers.
+//! Search utilities that expose reusable linear, binary, interpolation and
+//! fuzzy search helpers.
 //!
 //! The module intentionally keeps the API surface small so it can be embedded
 //! anywhere in the workspace that needs simple search behavior without pulling
@@ -8,9 +9,10 @@ mod __test__;
 /// A generic search helper that provides linear and binary search functions.
 ///
 /// The type parameter `T` defaults to `u32`.  
-/// It must implement:
-/// - `Ord` so values can be compared for ordering
-/// - `Copy` so results can be returned by value
+/// The plain searches need it to implement `Ord` so values can be compared
+/// for ordering; the comparator variants don't need anything.
+/// Needles are taken by reference and results are references into the
+/// slice or indices, so `T` can be anything from `u32` to `String`.
 ///
 /// This struct does not store any data.  
 /// It only carries the type information needed for the search functions.
@@ -18,9 +20,9 @@ pub struct Searcher<T = u32>(std::marker::PhantomData<T>);
 
 impl<T> Searcher<T>
 where
-  T: Copy + Ord,
+  T: Ord,
 {
-  /// Performs a simple linear search on the vector.
+  /// Performs a simple linear search on the slice.
   ///
   /// This function checks each element from left to right until it finds
   /// the same value.  
@@ -31,7 +33,7 @@ where
   /// - Worst case: O(n)
   ///
   /// Returns:
-  /// - Some(value) if the value exists in the vector
+  /// - Some(value) referencing the element if the value exists in the slice
   /// - None if the value does not appear
   ///
   /// Example:
@@ -40,69 +42,475 @@ where
   ///
   /// let data = vec![1, 2, 3, 4, 5];
   ///
-  /// assert_eq!(Searcher::<u32>::linear_search(&data, 1), Some(1));
-  /// assert_eq!(Searcher::<u32>::linear_search(&data, 6), None);
+  /// assert_eq!(Searcher::<u32>::linear_search(&data, &1), Some(&1));
+  /// assert_eq!(Searcher::<u32>::linear_search(&data, &6), None);
   /// ```
   ///
-  pub fn linear_search(data: &Vec<T>, value: T) -> Option<T> {
-    let mut result = None;
+  pub fn linear_search<'a>(data: &'a [T], value: &T) -> Option<&'a T> {
+    data.iter().rev().find(|item| *item == value)
+  }
 
-    for item in data {
-      if *item == value {
-        result = Some(*item);
-      }
-    }
+  /// Performs a linear search on the slice and returns every position of the value.
+  ///
+  /// Unlike [`linear_search`](Self::linear_search), which only reports the
+  /// last match, this collects the index of each element equal to the value,
+  /// in ascending order.
+  ///
+  /// Time complexity: O(n)
+  ///
+  /// Returns:
+  /// - the indices of the matching elements, empty if the value does not appear
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![4, 1, 4, 2, 4];
+  ///
+  /// assert_eq!(Searcher::<u32>::linear_search_all(&data, &4), vec![0, 2, 4]);
+  /// assert!(Searcher::<u32>::linear_search_all(&data, &3).is_empty());
+  /// ```
+  ///
+  pub fn linear_search_all(data: &[T], value: &T) -> Vec<usize> {
+    Self::linear_search_iter(data, value).collect()
+  }
 
-    result
+  /// Lazily yields every position of the value in the slice.
+  ///
+  /// This is the iterator behind [`linear_search_all`](Self::linear_search_all),
+  /// for callers that only need the first few matches or want to avoid the
+  /// allocation.
+  ///
+  /// Time complexity: O(n) to exhaust the iterator
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![4, 1, 4, 2, 4];
+  ///
+  /// let mut matches = Searcher::<u32>::linear_search_iter(&data, &4);
+  /// assert_eq!(matches.next(), Some(0));
+  /// assert_eq!(matches.next(), Some(2));
+  /// ```
+  ///
+  pub fn linear_search_iter<'a>(data: &'a [T], value: &'a T) -> impl Iterator<Item = usize> + 'a {
+    data
+      .iter()
+      .enumerate()
+      .filter(move |(_, item)| *item == value)
+      .map(|(index, _)| index)
   }
 
-  /// Performs a binary search on the vector.
+  /// Performs a binary search on the slice.
   ///
   /// Important:
-  /// Binary search works only when the input vector is sorted in ascending order.
+  /// Binary search works only when the input slice is sorted in ascending order.
   ///
-  /// This function chooses the middle element then selects the left or right half
+  /// This function chooses the middle element then keeps the left or right half
   /// based on comparing the target value with the middle value.  
-  /// It does this recursively until the value is found or the search space is empty.
+  /// It does this in a loop over indices until the value is found or the
+  /// search space is empty, so it never allocates.
   ///
   /// Time complexity:
   /// - Best case: O(1)
   /// - Worst case: O(log n)
   ///
   /// Returns:
-  /// - Some(value) if the target exists
+  /// - Some(value) referencing the element if the target exists
   /// - None if the target is not found
   ///
-  /// Note:
-  /// This implementation allocates new vectors in each recursive step
-  /// because it slices and converts slices to Vec.  
-  /// This is simple but not the most efficient way to implement binary search.
-  ///
   /// Example:
   /// ```rust
   /// use utils::searcher::Searcher;
   ///
   /// let data = vec![1, 2, 3, 4, 5];
   ///
-  /// assert_eq!(Searcher::<u32>::binary_search(&data, 1), Some(1));
-  /// assert_eq!(Searcher::<u32>::binary_search(&data, 0), None);
+  /// assert_eq!(Searcher::<u32>::binary_search(&data, &1), Some(&1));
+  /// assert_eq!(Searcher::<u32>::binary_search(&data, &0), None);
+  ///
+  /// let words = vec!["apple".to_string(), "mango".to_string()];
+  /// assert!(Searcher::binary_search(&words, &"mango".to_string()).is_some());
+  /// ```
+  ///
+  pub fn binary_search<'a>(data: &'a [T], value: &T) -> Option<&'a T> {
+    Self::binary_search_idx(data, value)
+      .ok()
+      .map(|index| &data[index])
+  }
+
+  /// Performs a binary search on the slice and returns the position of the value.
+  ///
+  /// Important:
+  /// Like [`binary_search`](Self::binary_search), this only works when the
+  /// slice is sorted in ascending order.
+  ///
+  /// This mirrors [`slice::binary_search`].
+  ///
+  /// Time complexity:
+  /// - Best case: O(1)
+  /// - Worst case: O(log n)
+  ///
+  /// Returns:
+  /// - Ok(index) of a matching element; if the value appears multiple times,
+  ///   any of the matches may be returned
+  /// - Err(index) where the value could be inserted to keep the slice sorted
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![1, 2, 4, 5];
+  ///
+  /// assert_eq!(Searcher::<u32>::binary_search_idx(&data, &4), Ok(2));
+  /// assert_eq!(Searcher::<u32>::binary_search_idx(&data, &3), Err(2));
+  /// ```
+  ///
+  pub fn binary_search_idx(data: &[T], value: &T) -> Result<usize, usize> {
+    Self::binary_search_by(data, |item| item.cmp(value))
+  }
+
+  /// Finds the first position whose element is not less than the value.
+  ///
+  /// Important:
+  /// The slice must be sorted in ascending order.
+  ///
+  /// This is where a range starting at the value begins, e.g. the first
+  /// block of a sparse index whose first key is at least the target.
+  ///
+  /// Time complexity: O(log n)
+  ///
+  /// Returns:
+  /// - the index of the first element `>= value`, or `data.len()` if there
+  ///   is none
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![1, 2, 2, 2, 5];
+  ///
+  /// assert_eq!(Searcher::<u32>::lower_bound(&data, &2), 1);
+  /// assert_eq!(Searcher::<u32>::lower_bound(&data, &3), 4);
+  /// assert_eq!(Searcher::<u32>::lower_bound(&data, &6), 5);
+  /// ```
+  ///
+  pub fn lower_bound(data: &[T], value: &T) -> usize {
+    Self::partition_point(data, |item| item < value)
+  }
+
+  /// Finds the first position whose element is greater than the value.
+  ///
+  /// Important:
+  /// The slice must be sorted in ascending order.
+  ///
+  /// Together with [`lower_bound`](Self::lower_bound) this gives the range
+  /// of elements equal to the value: `lower_bound..upper_bound`.
+  ///
+  /// Time complexity: O(log n)
+  ///
+  /// Returns:
+  /// - the index of the first element `> value`, or `data.len()` if there
+  ///   is none
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![1, 2, 2, 2, 5];
+  ///
+  /// assert_eq!(Searcher::<u32>::upper_bound(&data, &2), 4);
+  /// assert_eq!(Searcher::<u32>::upper_bound(&data, &0), 0);
+  /// ```
+  ///
+  pub fn upper_bound(data: &[T], value: &T) -> usize {
+    Self::partition_point(data, |item| item <= value)
+  }
+}
+
+impl<T> Searcher<T> {
+  /// Performs a binary search on the slice with a comparator function.
+  ///
+  /// Important:
+  /// The slice must be sorted in the order the comparator describes.
+  ///
+  /// The comparator is called with an element of the slice and returns
+  /// whether that element is `Less`, `Equal` or `Greater` than the target,
+  /// so `T` doesn't need to be comparable itself.
+  ///
+  /// Time complexity:
+  /// - Best case: O(1)
+  /// - Worst case: O(log n)
+  ///
+  /// Returns:
+  /// - Ok(index) of an element the comparator considers equal
+  /// - Err(index) where such an element could be inserted to keep the order
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![1, 2, 4, 5];
+  ///
+  /// assert_eq!(Searcher::binary_search_by(&data, |item| item.cmp(&4)), Ok(2));
+  /// assert_eq!(Searcher::binary_search_by(&data, |item| item.cmp(&3)), Err(2));
+  /// ```
+  ///
+  pub fn binary_search_by<'a, F>(data: &'a [T], mut compare: F) -> Result<usize, usize>
+  where
+    F: FnMut(&'a T) -> std::cmp::Ordering,
+  {
+    let mut low = 0;
+    let mut high = data.len();
+
+    while low < high {
+      let mid = low + (high - low) / 2;
+
+      match compare(&data[mid]) {
+        std::cmp::Ordering::Equal => return Ok(mid),
+        // search in right half
+        std::cmp::Ordering::Less => low = mid + 1,
+        // search in left half
+        std::cmp::Ordering::Greater => high = mid,
+      }
+    }
+
+    Err(low)
+  }
+
+  /// Performs a binary search on the slice by a key extracted from each element.
+  ///
+  /// Important:
+  /// The slice must be sorted by that key.
+  ///
+  /// This searches a slice of structs by one of their fields, without
+  /// mapping the whole slice to the keys first.
+  ///
+  /// Time complexity:
+  /// - Best case: O(1)
+  /// - Worst case: O(log n)
+  ///
+  /// Returns:
+  /// - Ok(index) of an element whose key equals `key`
+  /// - Err(index) where such an element could be inserted to keep the order
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// struct IndexEntry {
+  ///   first_key: &'static str,
+  ///   offset: u64,
+  /// }
+  ///
+  /// let index = vec![
+  ///   IndexEntry { first_key: "apple", offset: 0 },
+  ///   IndexEntry { first_key: "mango", offset: 4096 },
+  ///   IndexEntry { first_key: "peach", offset: 8192 },
+  /// ];
+  ///
+  /// let found = Searcher::binary_search_by_key(&index, &"mango", |entry| entry.first_key);
+  /// assert_eq!(found.map(|i| index[i].offset), Ok(4096));
+  /// assert_eq!(Searcher::binary_search_by_key(&index, &"kiwi", |entry| entry.first_key), Err(1));
+  /// ```
+  ///
+  pub fn binary_search_by_key<'a, B, F>(
+    data: &'a [T],
+    key: &B,
+    mut extract: F,
+  ) -> Result<usize, usize>
+  where
+    F: FnMut(&'a T) -> B,
+    B: Ord,
+  {
+    Self::binary_search_by(data, |item| extract(item).cmp(key))
+  }
+
+  /// Finds the position where a predicate stops holding.
+  ///
+  /// Important:
+  /// The slice must be partitioned by the predicate: every element it holds
+  /// for comes before every element it doesn't hold for.
+  ///
+  /// This is the primitive the other bounds are built on; it mirrors
+  /// [`slice::partition_point`].
+  ///
+  /// Time complexity: O(log n)
+  ///
+  /// Returns:
+  /// - the index of the first element the predicate is false for, or
+  ///   `data.len()` if it holds for all of them
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let data = vec![1, 2, 3, 10, 20];
+  ///
+  /// assert_eq!(Searcher::partition_point(&data, |&item| item < 10), 3);
   /// ```
   ///
-  pub fn binary_search(data: &[T], value: T) -> Option<T> {
+  pub fn partition_point<P>(data: &[T], mut pred: P) -> usize
+  where
+    P: FnMut(&T) -> bool,
+  {
+    let mut low = 0;
+    let mut high = data.len();
+
+    while low < high {
+      let mid = low + (high - low) / 2;
+
+      if pred(&data[mid]) {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+
+    low
+  }
+}
+
+impl<T> Searcher<T>
+where
+  T: Copy + Ord + Into<i128>,
+{
+  /// Performs an interpolation search on a slice of integers.
+  ///
+  /// Important:
+  /// Like binary search, this only works when the slice is sorted in
+  /// ascending order.
+  ///
+  /// Instead of always probing the middle, this function guesses where the
+  /// value should be from where it lies between the smallest and largest
+  /// remaining values, the way one opens a phone book near the end for a
+  /// name starting with "W". On uniformly distributed data, such as
+  /// monotonically increasing record offsets, the guess lands close enough
+  /// to finish in far fewer probes than binary search.
+  ///
+  /// Time complexity:
+  /// - Best case: O(1)
+  /// - Average case on uniform data: O(log log n)
+  /// - Worst case: O(n), when the values are heavily skewed
+  ///
+  /// Returns:
+  /// - Ok(index) of a matching element; if the value appears multiple times,
+  ///   any of the matches may be returned
+  /// - Err(index) where the value could be inserted to keep the slice sorted
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let offsets: Vec<u64> = (0..1000).map(|i| i * 4096).collect();
+  ///
+  /// assert_eq!(Searcher::interpolation_search(&offsets, &(512 * 4096)), Ok(512));
+  /// assert_eq!(Searcher::interpolation_search(&offsets, &100), Err(1));
+  /// ```
+  ///
+  pub fn interpolation_search(data: &[T], value: &T) -> Result<usize, usize> {
     if data.is_empty() {
-      return None;
+      return Err(0);
     }
 
-    let mid = data.len() / 2;
+    let target: i128 = (*value).into();
+    let mut low = 0;
+    let mut high = data.len() - 1;
 
-    if data[mid] == value {
-      Some(data[mid])
-    } else if data[mid] > value {
-      // search in left half
-      Self::binary_search(&data[..mid], value)
-    } else {
-      // search in right half
-      Self::binary_search(&data[mid..], value)
+    loop {
+      let low_value: i128 = data[low].into();
+      let high_value: i128 = data[high].into();
+
+      if target < low_value {
+        return Err(low);
+      }
+      if target > high_value {
+        return Err(high + 1);
+      }
+      if low_value == high_value {
+        return Ok(low);
+      }
+
+      // Estimate the position in floating point, so wide value ranges
+      // can't overflow.
+      let ratio = (target - low_value) as f64 / (high_value - low_value) as f64;
+      let pos = (low + (ratio * (high - low) as f64) as usize).min(high);
+
+      match data[pos].cmp(value) {
+        std::cmp::Ordering::Equal => return Ok(pos),
+        // search to the right of the guess
+        std::cmp::Ordering::Less => low = pos + 1,
+        // search to the left of the guess
+        std::cmp::Ordering::Greater => high = pos - 1,
+      }
     }
   }
 }
+
+impl<T> Searcher<T>
+where
+  T: AsRef<str>,
+{
+  /// Finds the strings within an edit distance of the needle.
+  ///
+  /// The distance is the Levenshtein distance: how many single-character
+  /// insertions, deletions or substitutions turn one string into the other.
+  /// This suits looking up a key whose exact spelling isn't remembered.
+  /// The slice doesn't need to be sorted.
+  ///
+  /// Time complexity: O(n * m * k) for n strings of length m and a needle
+  /// of length k. Strings whose length alone puts them too far away are
+  /// skipped without computing the distance.
+  ///
+  /// Returns:
+  /// - the matching elements with their distance, closest first; matches at
+  ///   the same distance keep their order in the slice
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::searcher::Searcher;
+  ///
+  /// let keys = vec!["user:1", "user:12", "users", "order:1"];
+  ///
+  /// let matches = Searcher::fuzzy_search(&keys, "user:2", 1);
+  /// assert_eq!(matches, vec![(&"user:1", 1), (&"user:12", 1)]);
+  /// ```
+  ///
+  pub fn fuzzy_search<'a>(data: &'a [T], needle: &str, max_distance: usize) -> Vec<(&'a T, usize)> {
+    let needle = needle.chars().collect::<Vec<_>>();
+    let mut matches = Vec::new();
+
+    for item in data {
+      let candidate = item.as_ref().chars().collect::<Vec<_>>();
+      if candidate.len().abs_diff(needle.len()) > max_distance {
+        continue;
+      }
+
+      let distance = levenshtein(&candidate, &needle);
+      if distance <= max_distance {
+        matches.push((item, distance));
+      }
+    }
+
+    // stable, so equally close matches stay in slice order
+    matches.sort_by_key(|&(_, distance)| distance);
+    matches
+  }
+}
+
+/// Edit distance between two strings, keeping only two rows of the table.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+  let mut previous = (0..=b.len()).collect::<Vec<_>>();
+  let mut current = vec![0; b.len() + 1];
+
+  for (i, a_char) in a.iter().enumerate() {
+    current[0] = i + 1;
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous[j] + usize::from(a_char != b_char);
+      current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+    }
+    std::mem::swap(&mut previous, &mut current);
+  }
+
+  previous[b.len()]
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod skiplist_test {
+  use std::{collections::BTreeMap, ops::Bound};
+
+  use crate::skiplist::SkipList;
+
+  #[test]
+  fn new_list_is_empty() {
+    let list: SkipList<u32, u32> = SkipList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.size(), 0);
+    assert!(list.get(&1).is_none());
+    assert!(list.first().is_none());
+    assert_eq!(list.iter().count(), 0);
+  }
+
+  #[test]
+  fn insert_replaces_existing_value() {
+    let mut list = SkipList::new();
+    assert_eq!(list.insert(1, "one"), None);
+    assert_eq!(list.insert(1, "uno"), Some("one"));
+    assert_eq!(list.get(&1), Some(&"uno"));
+    assert_eq!(list.size(), 1);
+  }
+
+  #[test]
+  fn iterates_in_key_order() {
+    let mut list = SkipList::new();
+    for key in [50u32, 10, 40, 20, 30] {
+      list.insert(key, key * 2);
+    }
+
+    let entries: Vec<_> = list.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(
+      entries,
+      vec![(10, 20), (20, 40), (30, 60), (40, 80), (50, 100)]
+    );
+    assert_eq!(list.first(), Some((&10, &20)));
+  }
+
+  #[test]
+  fn get_mut_and_remove() {
+    let mut list = SkipList::new();
+    list.insert("a".to_string(), 1);
+    list.insert("b".to_string(), 2);
+
+    *list.get_mut("a").unwrap() += 10;
+    assert_eq!(list.get("a"), Some(&11));
+    assert_eq!(list.remove("b"), Some(2));
+    assert_eq!(list.remove("b"), None);
+    assert!(!list.contains_key("b"));
+    assert_eq!(list.size(), 1);
+  }
+
+  #[test]
+  fn range_bounds() {
+    let mut list = SkipList::new();
+    for key in 0..10u32 {
+      list.insert(key, ());
+    }
+    let keys = |range: (Bound<u32>, Bound<u32>)| -> Vec<u32> {
+      list.range(range).map(|(k, _)| *k).collect()
+    };
+
+    assert_eq!(
+      keys((Bound::Included(3), Bound::Excluded(6))),
+      vec![3, 4, 5]
+    );
+    assert_eq!(
+      keys((Bound::Excluded(3), Bound::Included(6))),
+      vec![4, 5, 6]
+    );
+    assert_eq!(keys((Bound::Unbounded, Bound::Excluded(2))), vec![0, 1]);
+    assert_eq!(keys((Bound::Included(8), Bound::Unbounded)), vec![8, 9]);
+    assert_eq!(
+      keys((Bound::Included(20), Bound::Unbounded)),
+      Vec::<u32>::new()
+    );
+    assert_eq!(
+      keys((Bound::Included(6), Bound::Excluded(3))),
+      Vec::<u32>::new()
+    );
+    assert_eq!(
+      keys((Bound::Included(4), Bound::Excluded(4))),
+      Vec::<u32>::new()
+    );
+    assert_eq!(list.range(..).count(), 10);
+  }
+
+  #[test]
+  fn matches_btree_map() {
+    let mut list = SkipList::new();
+    let mut model = BTreeMap::new();
+    let mut seed = 7u64;
+
+    for i in 0..20_000u64 {
+      seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+      let key = (seed >> 33) % 500;
+      if seed & 3 == 0 {
+        assert_eq!(list.remove(&key), model.remove(&key));
+      } else {
+        assert_eq!(list.insert(key, i), model.insert(key, i));
+      }
+    }
+
+    assert_eq!(list.size(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    assert!(list.range(100..200).eq(model.range(100..200)));
+  }
+}
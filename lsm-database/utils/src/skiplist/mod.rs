@@ -0,0 +1,352 @@
+//! An ordered map built as a skip list.
+//!
+//! Entries sit in a sorted linked list, and each one is also linked into a
+//! random number of express lanes above it, a quarter as many per lane.
+//! Searches start in the top lane and drop down, so inserts, lookups and
+//! removes take `O(log n)` on average, and iterating in key order is just
+//! following the bottom lane.
+//!
+//! Nodes are stored in a `Vec` and linked by index, so the list needs no
+//! `unsafe` and no reference counting.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::skiplist::SkipList;
+//!
+//! let mut list = SkipList::new();
+//! list.insert("b", 2);
+//! list.insert("a", 1);
+//! list.insert("c", 3);
+//!
+//! assert_eq!(list.get("b"), Some(&2));
+//! assert_eq!(list.remove("b"), Some(2));
+//!
+//! let keys: Vec<_> = list.iter().map(|(key, _)| *key).collect();
+//! assert_eq!(keys, vec!["a", "c"]);
+//! ```
+
+mod __test__;
+
+use std::{
+  borrow::Borrow,
+  ops::{Bound, RangeBounds},
+};
+
+/// The most lanes a list uses; plenty for 4^16 entries.
+const MAX_LEVEL: usize = 16;
+
+/// An entry and its successor in each lane it is part of.
+#[derive(Debug)]
+struct Node<K, V> {
+  key: K,
+  value: V,
+  next: Vec<Option<usize>>,
+}
+
+/// An ordered map from `K` to `V`.
+#[derive(Debug)]
+pub struct SkipList<K, V> {
+  nodes: Vec<Option<Node<K, V>>>,
+  free: Vec<usize>,
+  /// The first node of each lane.
+  head: [Option<usize>; MAX_LEVEL],
+  /// The number of lanes with any nodes.
+  level: usize,
+  len: usize,
+  /// The state of the generator picking node heights.
+  rng: u64,
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+  /// Creates a new empty list.
+  pub fn new() -> Self {
+    Self {
+      nodes: Vec::new(),
+      free: Vec::new(),
+      head: [None; MAX_LEVEL],
+      level: 0,
+      len: 0,
+      rng: 0x9E37_79B9_7F4A_7C15,
+    }
+  }
+
+  /// Returns `true` if list is empty.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns number of entries in list.
+  pub fn size(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the list has an entry for `key`.
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.find(key).is_some()
+  }
+
+  /// Returns the value of `key`.
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self.find(key).map(|idx| &self.node(idx).value)
+  }
+
+  /// Returns the value of `key` for changing it in place.
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let idx = self.find(key)?;
+    Some(&mut self.node_mut(idx).value)
+  }
+
+  /// Returns the entry with the smallest key.
+  pub fn first(&self) -> Option<(&K, &V)> {
+    self.head[0].map(|idx| self.entry(idx))
+  }
+
+  /// Stores `value` under `key` and returns the value it replaced.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    let mut preds = self.predecessors(|k| *k < key);
+    if let Some(idx) = self.next_of(preds[0], 0) {
+      if self.node(idx).key == key {
+        return Some(std::mem::replace(&mut self.node_mut(idx).value, value));
+      }
+    }
+
+    let height = self.random_height();
+    if height > self.level {
+      // Lanes that were empty start at the head.
+      preds[self.level..height].fill(None);
+      self.level = height;
+    }
+
+    let next = (0..height)
+      .map(|lane| self.next_of(preds[lane], lane))
+      .collect();
+    let node = Node { key, value, next };
+    let idx = match self.free.pop() {
+      Some(idx) => {
+        self.nodes[idx] = Some(node);
+        idx
+      },
+      None => {
+        self.nodes.push(Some(node));
+        self.nodes.len() - 1
+      },
+    };
+    for (lane, &pred) in preds.iter().enumerate().take(height) {
+      self.set_next(pred, lane, Some(idx));
+    }
+
+    self.len += 1;
+    None
+  }
+
+  /// Removes `key` and returns its value.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    let preds = self.predecessors(|k| k.borrow() < key);
+    let idx = self.next_of(preds[0], 0)?;
+    if self.node(idx).key.borrow() != key {
+      return None;
+    }
+
+    let node = self.nodes[idx].take().expect("linked node is occupied");
+    for (lane, &next) in node.next.iter().enumerate() {
+      self.set_next(preds[lane], lane, next);
+    }
+    while self.level > 0 && self.head[self.level - 1].is_none() {
+      self.level -= 1;
+    }
+
+    self.free.push(idx);
+    self.len -= 1;
+    Some(node.value)
+  }
+
+  /// Removes every entry.
+  pub fn clear(&mut self) {
+    self.nodes.clear();
+    self.free.clear();
+    self.head = [None; MAX_LEVEL];
+    self.level = 0;
+    self.len = 0;
+  }
+
+  /// Returns an iterator over the entries in ascending key order.
+  pub fn iter(&self) -> Range<'_, K, V> {
+    Range {
+      list: self,
+      current: self.head[0],
+      end: None,
+    }
+  }
+
+  /// Returns an iterator over the entries with keys in `range`, in
+  /// ascending key order.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::skiplist::SkipList;
+  ///
+  /// let mut list = SkipList::new();
+  /// for (offset, size) in [(0, 120), (120, 48), (168, 4096), (4264, 64)] {
+  ///   list.insert(offset, size);
+  /// }
+  ///
+  /// let window: Vec<_> = list.range(100..4264).map(|(offset, _)| *offset).collect();
+  /// assert_eq!(window, vec![120, 168]);
+  /// ```
+  ///
+  pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    R: RangeBounds<Q>,
+  {
+    let start = match range.start_bound() {
+      Bound::Included(start) => self.first_not(|k| k.borrow() < start),
+      Bound::Excluded(start) => self.first_not(|k| k.borrow() <= start),
+      Bound::Unbounded => self.head[0],
+    };
+    let end = match range.end_bound() {
+      Bound::Included(end) => self.first_not(|k| k.borrow() <= end),
+      Bound::Excluded(end) => self.first_not(|k| k.borrow() < end),
+      Bound::Unbounded => None,
+    };
+
+    // A range that ends before it starts is empty.
+    let current = match (start, end) {
+      (Some(start), Some(end)) if self.node(start).key > self.node(end).key => None,
+      _ => start,
+    };
+    Range {
+      list: self,
+      current,
+      end,
+    }
+  }
+
+  fn find<Q>(&self, key: &Q) -> Option<usize>
+  where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+  {
+    self
+      .first_not(|k| k.borrow() < key)
+      .filter(|&idx| self.node(idx).key.borrow() == key)
+  }
+
+  /// The first node whose key isn't `before` the position searched for.
+  fn first_not(&self, before: impl Fn(&K) -> bool) -> Option<usize> {
+    self.next_of(self.predecessors(before)[0], 0)
+  }
+
+  /// The last node in each lane whose key is `before` the position searched
+  /// for, or `None` for the head.
+  fn predecessors(&self, before: impl Fn(&K) -> bool) -> [Option<usize>; MAX_LEVEL] {
+    let mut preds = [None; MAX_LEVEL];
+    let mut pred = None;
+    for lane in (0..self.level).rev() {
+      while let Some(next) = self.next_of(pred, lane) {
+        if !before(&self.node(next).key) {
+          break;
+        }
+        pred = Some(next);
+      }
+      preds[lane] = pred;
+    }
+    preds
+  }
+
+  fn next_of(&self, pred: Option<usize>, lane: usize) -> Option<usize> {
+    match pred {
+      Some(idx) => self.node(idx).next[lane],
+      None => self.head[lane],
+    }
+  }
+
+  fn set_next(&mut self, pred: Option<usize>, lane: usize, next: Option<usize>) {
+    match pred {
+      Some(idx) => self.node_mut(idx).next[lane] = next,
+      None => self.head[lane] = next,
+    }
+  }
+
+  fn random_height(&mut self) -> usize {
+    let mut height = 1;
+    while height < MAX_LEVEL && self.next_random() & 3 == 0 {
+      height += 1;
+    }
+    height
+  }
+
+  fn next_random(&mut self) -> u64 {
+    // xorshift64
+    self.rng ^= self.rng << 13;
+    self.rng ^= self.rng >> 7;
+    self.rng ^= self.rng << 17;
+    self.rng
+  }
+}
+
+impl<K, V> SkipList<K, V> {
+  fn node(&self, idx: usize) -> &Node<K, V> {
+    self.nodes[idx].as_ref().expect("linked node is occupied")
+  }
+
+  fn node_mut(&mut self, idx: usize) -> &mut Node<K, V> {
+    self.nodes[idx].as_mut().expect("linked node is occupied")
+  }
+
+  fn entry(&self, idx: usize) -> (&K, &V) {
+    let node = self.node(idx);
+    (&node.key, &node.value)
+  }
+}
+
+/// Iterator over the entries of a [`SkipList`] in ascending key order.
+pub struct Range<'a, K, V> {
+  list: &'a SkipList<K, V>,
+  current: Option<usize>,
+  /// The first node not to return.
+  end: Option<usize>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let idx = self.current.filter(|&idx| Some(idx) != self.end)?;
+    self.current = self.list.node(idx).next[0];
+    Some(self.list.entry(idx))
+  }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Range<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
@@ -209,4 +209,387 @@ mod sorter_test {
     let sorted = Sorter::merge_sort(v.clone());
     assert_eq!(sorted.len(), v.len());
   }
+
+  // ----------------
+  // In-place tests
+  // ----------------
+
+  #[test]
+  fn selection_sort_in_place_sub_slice() {
+    let mut v = [9, 4, 3, 1, 0];
+    Sorter::<u32>::selection_sort_in_place(&mut v[1..4]);
+    assert_eq!(v, [9, 1, 3, 4, 0]);
+  }
+
+  #[test]
+  fn quick_sort_in_place_sorted_and_reversed() {
+    let mut sorted: Vec<u32> = (0..10_000).collect();
+    let mut reversed: Vec<u32> = (0..10_000).rev().collect();
+    let expected = sorted.clone();
+
+    Sorter::quick_sort_in_place(&mut sorted);
+    Sorter::quick_sort_in_place(&mut reversed);
+    assert_eq!(sorted, expected);
+    assert_eq!(reversed, expected);
+  }
+
+  #[test]
+  fn quick_sort_in_place_with_duplicates() {
+    let mut v = vec![3, 1, 2, 3, 3, 0, 1];
+    Sorter::quick_sort_in_place(&mut v);
+    assert_eq!(v, vec![0, 1, 1, 2, 3, 3, 3]);
+  }
+
+  #[test]
+  fn merge_sort_in_place_strings() {
+    let mut v = vec!["pear".to_string(), "apple".to_string(), "fig".to_string()];
+    Sorter::merge_sort_in_place(&mut v);
+    assert_eq!(v, vec!["apple", "fig", "pear"]);
+  }
+
+  #[test]
+  fn merge_sort_in_place_is_stable() {
+    let mut v = vec![
+      Item { key: 2, id: 1 },
+      Item { key: 1, id: 1 },
+      Item { key: 2, id: 2 },
+      Item { key: 1, id: 2 },
+    ];
+    Sorter::merge_sort_in_place(&mut v);
+
+    let ids: Vec<(i32, i32)> = v.iter().map(|x| (x.key, x.id)).collect();
+    assert_eq!(ids, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+  }
+
+  // ----------------
+  // Introsort tests
+  // ----------------
+
+  #[test]
+  fn intro_sort_small_and_empty() {
+    assert_eq!(Sorter::<u32>::intro_sort(vec![]), Vec::<u32>::new());
+    assert_eq!(Sorter::intro_sort(vec![3, 1, 2]), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn intro_sort_in_place_large_inputs() {
+    let expected: Vec<u64> = (0..5_000).collect();
+    let mut sorted = expected.clone();
+    let mut reversed: Vec<u64> = expected.iter().rev().copied().collect();
+    let mut scrambled: Vec<u64> = expected.iter().map(|i| (i * 7919) % 5_000).collect();
+
+    Sorter::intro_sort_in_place(&mut sorted);
+    Sorter::intro_sort_in_place(&mut reversed);
+    Sorter::intro_sort_in_place(&mut scrambled);
+    assert_eq!(sorted, expected);
+    assert_eq!(reversed, expected);
+    assert_eq!(scrambled, expected);
+  }
+
+  #[test]
+  fn intro_sort_in_place_all_equal_falls_back_to_heap_sort() {
+    // Every partition of equal elements is maximally unbalanced, so the
+    // depth limit is hit.
+    let mut v = vec![7; 10_000];
+    v.push(1);
+    Sorter::intro_sort_in_place(&mut v);
+    assert_eq!(v[0], 1);
+    assert!(v[1..].iter().all(|&x| x == 7));
+  }
+
+  // ----------------
+  // Radix sort tests
+  // ----------------
+
+  #[test]
+  fn radix_sort_u32() {
+    let v: Vec<u32> = vec![0xdead_beef, 1, 0, u32::MAX, 256, 255, 1];
+    let sorted = Sorter::radix_sort(v);
+    assert_eq!(sorted, vec![0, 1, 1, 255, 256, 0xdead_beef, u32::MAX]);
+  }
+
+  #[test]
+  fn radix_sort_in_place_u64_matches_std() {
+    let mut v: Vec<u64> = (0..10_000u64)
+      .map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+      .collect();
+    let mut expected = v.clone();
+    expected.sort();
+
+    Sorter::radix_sort_in_place(&mut v);
+    assert_eq!(v, expected);
+  }
+
+  #[test]
+  fn radix_sort_in_place_skips_shared_bytes() {
+    // only the lowest byte differs, so a single pass leaves the result in
+    // the scratch buffer and it has to be copied back
+    let mut v: Vec<u64> = vec![5, 3, 9, 1];
+    Sorter::radix_sort_in_place(&mut v);
+    assert_eq!(v, vec![1, 3, 5, 9]);
+
+    let mut empty: Vec<u32> = vec![];
+    Sorter::radix_sort_in_place(&mut empty);
+    assert!(empty.is_empty());
+  }
+
+  // -------------------
+  // Counting sort tests
+  // -------------------
+
+  #[test]
+  fn counting_sort_histogram() {
+    let v: Vec<u64> = vec![4, 0, 4, 2, 4, 1];
+    let (sorted, histogram) = Sorter::counting_sort(v, 5);
+
+    assert_eq!(sorted, vec![0, 1, 2, 4, 4, 4]);
+    assert_eq!(histogram, vec![1, 1, 1, 0, 3, 0]);
+  }
+
+  #[test]
+  fn counting_sort_in_place_empty() {
+    let mut v: Vec<u32> = vec![];
+    let histogram = Sorter::counting_sort_in_place(&mut v, 0);
+
+    assert!(v.is_empty());
+    assert_eq!(histogram, vec![0]);
+  }
+
+  #[test]
+  #[should_panic(expected = "0..=3")]
+  fn counting_sort_rejects_values_over_max() {
+    Sorter::<u32>::counting_sort(vec![1, 4], 3);
+  }
+
+  #[test]
+  #[should_panic]
+  fn counting_sort_rejects_negative_values() {
+    Sorter::<i32>::counting_sort(vec![1, -1], 3);
+  }
+
+  // ------------------------
+  // Parallel merge sort tests
+  // ------------------------
+
+  #[test]
+  fn par_merge_sort_small_input_single_threaded() {
+    let v = vec![3, 1, 2];
+    assert_eq!(Sorter::par_merge_sort(v, 8), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn par_merge_sort_uneven_chunks() {
+    // 3 threads over 10_001 elements leave a short last chunk
+    let v: Vec<u64> = (0..10_001).map(|i| (i * 7919) % 10_001).collect();
+    let sorted = Sorter::par_merge_sort(v, 3);
+    assert_eq!(sorted, (0..10_001).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn par_merge_sort_in_place_is_stable() {
+    let mut v: Vec<Item> = (0..20_000).map(|i| Item { key: i % 7, id: i }).collect();
+    Sorter::par_merge_sort_in_place(&mut v, 5);
+
+    assert!(v
+      .windows(2)
+      .all(|pair| (pair[0].key, pair[0].id) < (pair[1].key, pair[1].id)));
+  }
+
+  // ---------------------------
+  // Three-way quick sort tests
+  // ---------------------------
+
+  #[test]
+  fn three_way_quick_sort_basic() {
+    assert_eq!(
+      Sorter::<u32>::three_way_quick_sort(vec![]),
+      Vec::<u32>::new()
+    );
+    assert_eq!(
+      Sorter::three_way_quick_sort(vec![3, 1, 2, 3, 3, 0, 1]),
+      vec![0, 1, 1, 2, 3, 3, 3]
+    );
+  }
+
+  #[test]
+  fn three_way_quick_sort_in_place_many_duplicates() {
+    let mut v: Vec<u32> = (0..100_000).map(|i| i % 3).collect();
+    Sorter::three_way_quick_sort_in_place(&mut v);
+
+    assert!(v[..33_334].iter().all(|&x| x == 0));
+    assert!(v[33_334..66_667].iter().all(|&x| x == 1));
+    assert!(v[66_667..].iter().all(|&x| x == 2));
+  }
+
+  #[test]
+  fn three_way_quick_sort_in_place_distinct() {
+    let mut v: Vec<i64> = (0..5_000).map(|i| (i * 7919) % 5_000 - 2_500).collect();
+    Sorter::three_way_quick_sort_in_place(&mut v);
+    assert_eq!(v, (-2_500..2_500).collect::<Vec<_>>());
+  }
+
+  // ---------------------------
+  // Comparator and key tests
+  // ---------------------------
+
+  #[derive(Debug, Clone, PartialEq)]
+  struct Segment {
+    id: u64,
+    smallest_key: String,
+    size: f64,
+  }
+
+  fn segments() -> Vec<Segment> {
+    [("m", 3.5), ("a", 1.0), ("z", 0.5), ("c", 2.0)]
+      .iter()
+      .enumerate()
+      .map(|(id, (key, size))| Segment {
+        id: id as u64,
+        smallest_key: key.to_string(),
+        size: *size,
+      })
+      .collect()
+  }
+
+  fn ids(segments: &[Segment]) -> Vec<u64> {
+    segments.iter().map(|segment| segment.id).collect()
+  }
+
+  #[test]
+  fn every_algorithm_sorts_by_key() {
+    let sorts: Vec<fn(&mut [Segment])> = vec![
+      |v| Sorter::sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::selection_sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::quick_sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::three_way_quick_sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::intro_sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::merge_sort_by_key(v, |s| s.smallest_key.clone()),
+      |v| Sorter::par_merge_sort_by_key(v, 2, |s| s.smallest_key.clone()),
+    ];
+
+    for sort in sorts {
+      let mut v = segments();
+      sort(&mut v);
+      assert_eq!(ids(&v), vec![1, 3, 0, 2]);
+    }
+  }
+
+  #[test]
+  fn every_algorithm_sorts_by_comparator() {
+    // f64 isn't Ord, so this can only be sorted through a comparator
+    let by_size = |a: &Segment, b: &Segment| a.size.total_cmp(&b.size);
+    let sorts: Vec<fn(&mut [Segment])> = vec![
+      |v| Sorter::sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::selection_sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::quick_sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::three_way_quick_sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::intro_sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::merge_sort_by(v, |a, b| a.size.total_cmp(&b.size)),
+      |v| Sorter::par_merge_sort_by(v, 2, |a, b| a.size.total_cmp(&b.size)),
+    ];
+
+    for sort in sorts {
+      let mut v = segments();
+      sort(&mut v);
+      assert!(v.windows(2).all(|pair| by_size(&pair[0], &pair[1]).is_le()));
+      assert_eq!(ids(&v), vec![2, 1, 3, 0]);
+    }
+  }
+
+  #[test]
+  fn par_merge_sort_by_key_large_input() {
+    let mut v: Vec<(u32, u32)> = (0..10_000).map(|i| ((i * 7919) % 100, i)).collect();
+    Sorter::par_merge_sort_by_key(&mut v, 4, |&(key, _)| key);
+
+    // stable, so ties stay in input order
+    assert!(v.windows(2).all(|pair| pair[0] < pair[1]));
+  }
+
+  // ------------------------------
+  // Descending order and is_sorted
+  // ------------------------------
+
+  #[test]
+  fn sort_desc_is_stable() {
+    let mut v = vec![
+      Item { key: 1, id: 1 },
+      Item { key: 2, id: 1 },
+      Item { key: 1, id: 2 },
+      Item { key: 2, id: 2 },
+    ];
+    Sorter::sort_desc(&mut v);
+
+    let pairs: Vec<(i32, i32)> = v.iter().map(|x| (x.key, x.id)).collect();
+    assert_eq!(pairs, vec![(2, 1), (2, 2), (1, 1), (1, 2)]);
+    assert!(Sorter::is_sorted_desc(&v));
+  }
+
+  #[test]
+  fn is_sorted_edge_cases() {
+    assert!(Sorter::<u32>::is_sorted(&[]));
+    assert!(Sorter::<u32>::is_sorted(&[5]));
+    assert!(Sorter::<u32>::is_sorted(&[5, 5, 5]));
+    assert!(!Sorter::<u32>::is_sorted(&[1, 2, 3, 0]));
+    assert!(!Sorter::<u32>::is_sorted_desc(&[1, 2]));
+  }
+
+  #[test]
+  fn is_sorted_after_every_sort() {
+    let v: Vec<u32> = (0..2_000).map(|i| (i * 7919) % 2_000).collect();
+    assert!(!Sorter::is_sorted(&v));
+
+    assert!(Sorter::is_sorted(&Sorter::quick_sort(v.clone())));
+    assert!(Sorter::is_sorted(&Sorter::intro_sort(v.clone())));
+    assert!(Sorter::is_sorted(&Sorter::radix_sort(v.clone())));
+    assert!(Sorter::is_sorted_by_key(&Sorter::merge_sort(v), |&x| x));
+  }
+
+  // --------------------------
+  // Selection and top-k tests
+  // --------------------------
+
+  #[test]
+  fn select_nth_partitions_around_position() {
+    let mut v: Vec<u32> = (0..1_000).map(|i| (i * 7919) % 1_000).collect();
+
+    for n in [0, 1, 499, 998, 999] {
+      assert_eq!(*Sorter::select_nth(&mut v, n), n as u32);
+      assert!(v[..n].iter().all(|&x| x < n as u32));
+      assert!(v[n + 1..].iter().all(|&x| x > n as u32));
+    }
+  }
+
+  #[test]
+  fn select_nth_with_duplicates() {
+    let mut v = vec![5, 1, 5, 5, 0, 5, 9];
+    assert_eq!(*Sorter::<u32>::select_nth(&mut v, 3), 5);
+    assert_eq!(*Sorter::<u32>::select_nth(&mut v, 6), 9);
+  }
+
+  #[test]
+  #[should_panic(expected = "out of bounds")]
+  fn select_nth_out_of_bounds() {
+    Sorter::<u32>::select_nth(&mut [1, 2], 2);
+  }
+
+  #[test]
+  fn top_k_bounds() {
+    let v = vec![4, 8, 1, 8, 3];
+
+    assert_eq!(Sorter::<u32>::top_k(&v, 0), Vec::<u32>::new());
+    assert_eq!(Sorter::<u32>::top_k(&v, 2), vec![8, 8]);
+    assert_eq!(Sorter::<u32>::top_k(&v, 10), vec![8, 8, 4, 3, 1]);
+    assert_eq!(v, vec![4, 8, 1, 8, 3]);
+  }
+
+  #[test]
+  fn top_k_by_key_hottest() {
+    let reads: Vec<(String, u64)> = (0..500u64)
+      .map(|i| (format!("key:{i}"), (i * 7919) % 500))
+      .collect();
+
+    let hottest = Sorter::top_k_by_key(&reads, 3, |(_, count)| *count);
+    let counts: Vec<u64> = hottest.iter().map(|(_, count)| *count).collect();
+    assert_eq!(counts, vec![499, 498, 497]);
+  }
 }
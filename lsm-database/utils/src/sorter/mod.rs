@@ -2,8 +2,14 @@
 //! educational and reuse purposes across the workspace.
 mod __test__;
 
-/// A simple generic sorting helper that provides a selection sort
-/// implementation for any type that implements `Ord` and `Copy`.
+use std::cmp::Ordering;
+
+/// A simple generic sorting helper that provides selection, quick, merge and
+/// intro sort implementations for any type that implements `Ord` and `Clone`.
+///
+/// Every algorithm comes in two flavors: one that takes and returns a
+/// `Vec<T>`, and an `_in_place` one that sorts a `&mut [T]` without
+/// reallocating.
 ///
 /// The type parameter `T` defaults to `u32`.  
 /// This struct does not hold any data; it only serves as a namespace
@@ -17,25 +23,26 @@ where
   /// Sorts the input vector using the selection sort algorithm.
   ///
   /// Selection sort works by repeatedly finding the smallest value in the
-  /// remaining unsorted portion of the vector, removing it, and placing it
-  /// into a new result vector.  
+  /// remaining unsorted portion of the vector and swapping it to the end of
+  /// the sorted portion.  
   ///
   /// Steps:
-  /// 1. Clone the input so the original data is never modified.
-  /// 2. While the input still has elements:
-  ///    - Scan through all elements to find the smallest one.
-  ///    - Push that smallest value into the output.
-  ///    - Remove the smallest element from the input vector.
+  /// 1. Take ownership of the input; callers that need the original keep a
+  ///    clone, or use [`selection_sort_in_place`](Self::selection_sort_in_place)
+  ///    on a slice instead.
+  /// 2. For every position from left to right:
+  ///    - Scan the rest of the vector to find the smallest element.
+  ///    - Swap it into the current position.
   ///
   /// Time complexity:
   /// - Always O(n squared), because for each element it scans the entire
   ///   remaining list.
   ///
   /// Space complexity:
-  /// - O(n), because it constructs a new result vector.
+  /// - O(1), the vector is reused.
   ///
   /// Returns:
-  /// - A new vector containing the sorted elements.
+  /// - The same vector with its elements sorted.
   ///
   /// Example:
   /// ```rust
@@ -49,77 +56,1218 @@ where
   /// ```
   ///
   pub fn selection_sort(mut data: Vec<T>) -> Vec<T> {
-    let n = data.len();
+    Self::selection_sort_in_place(&mut data);
+    data
+  }
+
+  /// Sorts the slice in place using the selection sort algorithm.
+  ///
+  /// Same algorithm as [`selection_sort`](Self::selection_sort), but the
+  /// smallest remaining value is swapped to the front of the unsorted part
+  /// instead of being moved into a new vector, so nothing is allocated or
+  /// cloned.
+  ///
+  /// Time complexity:
+  /// - Always O(n squared).
+  ///
+  /// Space complexity:
+  /// - O(1).
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = [7, 3, 5, 2];
+  ///
+  /// Sorter::<u32>::selection_sort_in_place(&mut data);
+  /// assert_eq!(data, [2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn selection_sort_in_place(data: &mut [T]) {
+    selection_sort_by(data, &mut T::cmp);
+  }
+
+  /// Sorts the input vector using the quick sort algorithm.
+  ///
+  /// The vector is sorted in place by [`quick_sort_in_place`](Self::quick_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let result = Sorter::<u32>::quick_sort(vec![7, 3, 5, 2]);
+  /// assert_eq!(result, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn quick_sort(mut data: Vec<T>) -> Vec<T> {
+    Self::quick_sort_in_place(&mut data);
+    data
+  }
+
+  /// Sorts the slice in place using the quick sort algorithm.
+  ///
+  /// Steps:
+  /// 1. Pick the median of the first, middle and last elements as the pivot,
+  ///    so already sorted input doesn't degrade to the worst case.
+  /// 2. Partition the slice so smaller elements come before the pivot and
+  ///    the rest after it.
+  /// 3. Recurse into the smaller side and loop on the larger one, which
+  ///    keeps the recursion depth at O(log n).
+  ///
+  /// The sort is not stable.
+  ///
+  /// Time complexity:
+  /// - Average case: O(n log n)
+  /// - Worst case: O(n squared), e.g. when every element is equal
+  ///
+  /// Space complexity:
+  /// - O(log n) for the recursion.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![7, 3, 5, 2];
+  ///
+  /// Sorter::<u32>::quick_sort_in_place(&mut data);
+  /// assert_eq!(data, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn quick_sort_in_place(data: &mut [T]) {
+    quick_sort_by(data, &mut T::cmp);
+  }
+
+  /// Sorts the input vector using the merge sort algorithm.
+  ///
+  /// The vector is sorted in place by [`merge_sort_in_place`](Self::merge_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let result = Sorter::<u32>::merge_sort(vec![7, 3, 5, 2]);
+  /// assert_eq!(result, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn merge_sort(mut data: Vec<T>) -> Vec<T> {
+    Self::merge_sort_in_place(&mut data);
+    data
+  }
+
+  /// Sorts the slice in place using the merge sort algorithm.
+  ///
+  /// Steps:
+  /// 1. Sort the left and right halves recursively.
+  /// 2. Copy the left half into a scratch buffer and merge it with the right
+  ///    half back into the slice, taking from the left on ties.
+  ///
+  /// The sort is stable: equal elements keep their relative order. The
+  /// scratch buffer is allocated once and reused by every merge.
+  ///
+  /// Time complexity:
+  /// - Always O(n log n).
+  ///
+  /// Space complexity:
+  /// - O(n) for the scratch buffer.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![7, 3, 5, 2];
+  ///
+  /// Sorter::<u32>::merge_sort_in_place(&mut data);
+  /// assert_eq!(data, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn merge_sort_in_place(data: &mut [T]) {
+    merge_sort_by(data, &mut T::cmp);
+  }
+
+  /// Sorts the input vector using introsort.
+  ///
+  /// The vector is sorted in place by [`intro_sort_in_place`](Self::intro_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let result = Sorter::<u32>::intro_sort(vec![7, 3, 5, 2]);
+  /// assert_eq!(result, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn intro_sort(mut data: Vec<T>) -> Vec<T> {
+    Self::intro_sort_in_place(&mut data);
+    data
+  }
+
+  /// Sorts the slice in place using introsort, a hybrid of quick, heap and
+  /// insertion sort.
+  ///
+  /// Steps:
+  /// 1. Partition like [`quick_sort_in_place`](Self::quick_sort_in_place).
+  /// 2. Once the recursion gets deeper than twice log2 of the length, which
+  ///    only happens on adversarial input, heap sort the partition instead.
+  /// 3. Leave partitions of up to 16 elements to insertion sort, which is
+  ///    faster than partitioning at that size.
+  ///
+  /// The sort is not stable.
+  ///
+  /// Time complexity:
+  /// - Always O(n log n), the heap sort fallback rules out quick sort's
+  ///   worst case.
+  ///
+  /// Space complexity:
+  /// - O(log n) for the recursion.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![7, 3, 5, 2];
+  ///
+  /// Sorter::<u32>::intro_sort_in_place(&mut data);
+  /// assert_eq!(data, vec![2, 3, 5, 7]);
+  /// ```
+  ///
+  pub fn intro_sort_in_place(data: &mut [T]) {
+    intro_sort_by(data, &mut T::cmp);
+  }
+
+  /// Sorts the input vector using three-way quick sort.
+  ///
+  /// The vector is sorted in place by
+  /// [`three_way_quick_sort_in_place`](Self::three_way_quick_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let result = Sorter::<u32>::three_way_quick_sort(vec![2, 7, 2, 2, 1]);
+  /// assert_eq!(result, vec![1, 2, 2, 2, 7]);
+  /// ```
+  ///
+  pub fn three_way_quick_sort(mut data: Vec<T>) -> Vec<T> {
+    Self::three_way_quick_sort_in_place(&mut data);
+    data
+  }
+
+  /// Sorts the slice in place using quick sort with three-way (Dutch
+  /// national flag) partitioning.
+  ///
+  /// Steps:
+  /// 1. Pick the median of the first, middle and last elements as the pivot.
+  /// 2. In a single pass, gather the elements less than, equal to and
+  ///    greater than the pivot into three runs.
+  /// 3. Recurse into the smaller and loop on the larger of the outer runs;
+  ///    the middle run is already in place.
+  ///
+  /// Elements equal to the pivot are never looked at again, so inputs with
+  /// many duplicates, like tombstone-heavy keydirs, take linear time per
+  /// level where [`quick_sort_in_place`](Self::quick_sort_in_place) degrades
+  /// to O(n squared). The sort is not stable.
+  ///
+  /// Time complexity:
+  /// - Average case: O(n log k) for k distinct values
+  /// - Worst case: O(n squared)
+  ///
+  /// Space complexity:
+  /// - O(log n) for the recursion.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![0; 100_000];
+  /// data[500] = 1;
+  ///
+  /// Sorter::<u32>::three_way_quick_sort_in_place(&mut data);
+  /// assert_eq!(data[99_999], 1);
+  /// ```
+  ///
+  pub fn three_way_quick_sort_in_place(data: &mut [T]) {
+    three_way_quick_sort_by(data, &mut T::cmp);
+  }
+
+  /// Sorts the slice in place in descending order, using merge sort.
+  ///
+  /// Equal elements keep their relative order, which reversing an
+  /// ascending sort would not preserve.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![2, 7, 5];
+  ///
+  /// Sorter::<u32>::sort_desc(&mut data);
+  /// assert_eq!(data, vec![7, 5, 2]);
+  /// ```
+  ///
+  pub fn sort_desc(data: &mut [T]) {
+    merge_sort_by(data, &mut |a, b| b.cmp(a));
+  }
+
+  /// Checks whether the slice is sorted in ascending order.
+  ///
+  /// Cheap enough for a debug assertion that a run really is ordered
+  /// before it is written out.
+  ///
+  /// Time complexity: O(n)
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// assert!(Sorter::<u32>::is_sorted(&[1, 2, 2, 9]));
+  /// assert!(!Sorter::<u32>::is_sorted(&[1, 3, 2]));
+  /// ```
+  ///
+  pub fn is_sorted(data: &[T]) -> bool {
+    Self::is_sorted_by(data, T::cmp)
+  }
+
+  /// Checks whether the slice is sorted in descending order.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// assert!(Sorter::<u32>::is_sorted_desc(&[9, 2, 2, 1]));
+  /// ```
+  ///
+  pub fn is_sorted_desc(data: &[T]) -> bool {
+    Self::is_sorted_by(data, |a, b| b.cmp(a))
+  }
+
+  /// Finds the element that would be at position `n` if the slice were
+  /// sorted, without sorting it.
+  ///
+  /// This is quickselect: partition like three-way quick sort, then only
+  /// continue into the side that holds position `n`. Afterwards `data[n]` is
+  /// that element, everything before it is no larger and everything after
+  /// it no smaller, like [`slice::select_nth_unstable`]. Handy for a median
+  /// or percentile without paying for a full sort.
+  ///
+  /// Time complexity:
+  /// - Average case: O(n)
+  /// - Worst case: O(n squared)
+  ///
+  /// Returns:
+  /// - A reference to the element at position `n`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `n` is not less than the length of the slice.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut sizes = vec![120, 48, 4096, 64, 512];
+  ///
+  /// let middle = sizes.len() / 2;
+  /// let median = *Sorter::<u32>::select_nth(&mut sizes, middle);
+  /// assert_eq!(median, 120);
+  /// ```
+  ///
+  pub fn select_nth(data: &mut [T], n: usize) -> &T {
+    select_nth_by(data, n, &mut T::cmp)
+  }
+
+  /// Returns the `k` largest elements, largest first.
+  ///
+  /// The elements are copied, then [`select_nth`](Self::select_nth) moves
+  /// the `k` largest to the front and only those are sorted, so this costs
+  /// O(n + k log k) instead of a full O(n log n) sort. Asking for more
+  /// elements than there are returns all of them.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let reads = vec![3, 90, 7, 41, 90, 2];
+  ///
+  /// assert_eq!(Sorter::<u32>::top_k(&reads, 3), vec![90, 90, 41]);
+  /// ```
+  ///
+  pub fn top_k(data: &[T], k: usize) -> Vec<T> {
+    top_k_by(data, k, &mut T::cmp)
+  }
+}
+
+/// Comparator and key driven variants of the sorts, for types that don't
+/// implement `Ord` or need a different order than it gives. `T` only needs
+/// what the algorithm itself needs: merge sorts clone and the parallel one
+/// sends elements to other threads.
+impl<T> Sorter<T> {
+  /// Sorts the slice in place with a comparator function, using merge sort.
+  ///
+  /// This is the general purpose entry point: stable, and O(n log n)
+  /// whatever the input looks like. The `<algorithm>_by` functions pick a
+  /// specific algorithm instead.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data = vec![2, 7, 5];
+  ///
+  /// Sorter::sort_by(&mut data, |a, b| b.cmp(a));
+  /// assert_eq!(data, vec![7, 5, 2]);
+  /// ```
+  ///
+  pub fn sort_by<F>(data: &mut [T], compare: F)
+  where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    Self::merge_sort_by(data, compare);
+  }
+
+  /// Sorts the slice in place by a key extracted from each element, using
+  /// merge sort.
+  ///
+  /// The key function is called twice per comparison, so it should be
+  /// cheap, such as reading a field.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// #[derive(Clone)]
+  /// struct Segment {
+  ///   id: u64,
+  ///   smallest_key: &'static str,
+  /// }
+  ///
+  /// let mut segments = vec![
+  ///   Segment { id: 1, smallest_key: "mango" },
+  ///   Segment { id: 2, smallest_key: "apple" },
+  /// ];
+  ///
+  /// Sorter::sort_by_key(&mut segments, |segment| segment.smallest_key);
+  /// assert_eq!(segments[0].id, 2);
+  /// ```
+  ///
+  pub fn sort_by_key<K, F>(data: &mut [T], key: F)
+  where
+    T: Clone,
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    Self::merge_sort_by_key(data, key);
+  }
+
+  /// [`selection_sort_in_place`](Sorter::selection_sort_in_place) with a comparator function.
+  pub fn selection_sort_by<F>(data: &mut [T], mut compare: F)
+  where
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    selection_sort_by(data, &mut compare);
+  }
+
+  /// [`selection_sort_in_place`](Sorter::selection_sort_in_place)
+  /// by a key extracted from each element.
+  pub fn selection_sort_by_key<K, F>(data: &mut [T], mut key: F)
+  where
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    selection_sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+  }
+
+  /// [`quick_sort_in_place`](Sorter::quick_sort_in_place) with a comparator function.
+  pub fn quick_sort_by<F>(data: &mut [T], mut compare: F)
+  where
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    quick_sort_by(data, &mut compare);
+  }
+
+  /// [`quick_sort_in_place`](Sorter::quick_sort_in_place) by a key extracted from each element.
+  pub fn quick_sort_by_key<K, F>(data: &mut [T], mut key: F)
+  where
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    quick_sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+  }
+
+  /// [`three_way_quick_sort_in_place`](Sorter::three_way_quick_sort_in_place)
+  /// with a comparator function.
+  pub fn three_way_quick_sort_by<F>(data: &mut [T], mut compare: F)
+  where
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    three_way_quick_sort_by(data, &mut compare);
+  }
+
+  /// [`three_way_quick_sort_in_place`](Sorter::three_way_quick_sort_in_place)
+  /// by a key extracted from each element.
+  pub fn three_way_quick_sort_by_key<K, F>(data: &mut [T], mut key: F)
+  where
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    three_way_quick_sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+  }
+
+  /// [`intro_sort_in_place`](Sorter::intro_sort_in_place) with a comparator function.
+  pub fn intro_sort_by<F>(data: &mut [T], mut compare: F)
+  where
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    intro_sort_by(data, &mut compare);
+  }
+
+  /// [`intro_sort_in_place`](Sorter::intro_sort_in_place) by a key extracted from each element.
+  pub fn intro_sort_by_key<K, F>(data: &mut [T], mut key: F)
+  where
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    intro_sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+  }
+
+  /// [`merge_sort_in_place`](Sorter::merge_sort_in_place) with a comparator function.
+  pub fn merge_sort_by<F>(data: &mut [T], mut compare: F)
+  where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    merge_sort_by(data, &mut compare);
+  }
+
+  /// [`merge_sort_in_place`](Sorter::merge_sort_in_place) by a key extracted from each element.
+  pub fn merge_sort_by_key<K, F>(data: &mut [T], mut key: F)
+  where
+    T: Clone,
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    merge_sort_by(data, &mut |a, b| key(a).cmp(&key(b)));
+  }
+
+  /// [`par_merge_sort_in_place`](Sorter::par_merge_sort_in_place) with a comparator
+  /// function, which every thread calls.
+  pub fn par_merge_sort_by<F>(data: &mut [T], threads: usize, compare: F)
+  where
+    T: Clone + Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+  {
+    par_merge_sort_by(data, threads, &compare);
+  }
+
+  /// [`par_merge_sort_in_place`](Sorter::par_merge_sort_in_place) by a key extracted from
+  /// each element, which every thread calls.
+  pub fn par_merge_sort_by_key<K, F>(data: &mut [T], threads: usize, key: F)
+  where
+    T: Clone + Send,
+    K: Ord,
+    F: Fn(&T) -> K + Sync,
+  {
+    par_merge_sort_by(data, threads, &|a: &T, b: &T| key(a).cmp(&key(b)));
+  }
+
+  /// Checks whether the slice is sorted under a comparator function, i.e.
+  /// no element compares `Greater` than the one after it.
+  ///
+  /// Time complexity: O(n)
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let sizes = [0.5, 1.0, 3.5];
+  ///
+  /// assert!(Sorter::is_sorted_by(&sizes, |a: &f64, b| a.total_cmp(b)));
+  /// ```
+  ///
+  pub fn is_sorted_by<F>(data: &[T], mut compare: F) -> bool
+  where
+    F: FnMut(&T, &T) -> Ordering,
+  {
+    data
+      .windows(2)
+      .all(|pair| compare(&pair[0], &pair[1]) != Ordering::Greater)
+  }
+
+  /// Checks whether the slice is sorted by a key extracted from each element.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let entries = [("apple", 3), ("mango", 1)];
+  ///
+  /// assert!(Sorter::is_sorted_by_key(&entries, |&(key, _)| key));
+  /// assert!(!Sorter::is_sorted_by_key(&entries, |&(_, count)| count));
+  /// ```
+  ///
+  pub fn is_sorted_by_key<K, F>(data: &[T], mut key: F) -> bool
+  where
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    Self::is_sorted_by(data, |a, b| key(a).cmp(&key(b)))
+  }
+
+  /// [`top_k`](Sorter::top_k) by a key extracted from each element, e.g. the
+  /// hottest keys by their read count.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let reads = vec![("user:1", 12), ("user:2", 340), ("user:3", 57)];
+  ///
+  /// let hottest = Sorter::top_k_by_key(&reads, 2, |&(_, count)| count);
+  /// assert_eq!(hottest, vec![("user:2", 340), ("user:3", 57)]);
+  /// ```
+  ///
+  pub fn top_k_by_key<K, F>(data: &[T], k: usize, mut key: F) -> Vec<T>
+  where
+    T: Clone,
+    K: Ord,
+    F: FnMut(&T) -> K,
+  {
+    top_k_by(data, k, &mut |a, b| key(a).cmp(&key(b)))
+  }
+}
+
+/// Unsigned integers that [`Sorter::radix_sort`] can sort byte by byte.
+pub trait RadixKey: Copy {
+  /// Number of bytes in the key.
+  const BYTES: usize;
 
-    for i in 0..n {
-      let mut min_idx = i;
+  /// The `index`-th byte of the key, counting from the least significant.
+  fn byte(self, index: usize) -> u8;
+}
+
+macro_rules! radix_key {
+  ($($int:ty),*) => {
+    $(
+      impl RadixKey for $int {
+        const BYTES: usize = std::mem::size_of::<$int>();
 
-      for j in (i + 1)..n {
-        if data[j] < data[min_idx] {
-          min_idx = j;
+        fn byte(self, index: usize) -> u8 {
+          (self >> (index * 8)) as u8
         }
       }
+    )*
+  };
+}
 
-      data.swap(i, min_idx);
-    }
+radix_key!(u8, u16, u32, u64, usize);
 
+impl<T> Sorter<T>
+where
+  T: RadixKey,
+{
+  /// Sorts the input vector of unsigned integers using LSD radix sort.
+  ///
+  /// The vector is sorted in place by [`radix_sort_in_place`](Self::radix_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let offsets: Vec<u64> = vec![4096, 0, 1 << 40, 512];
+  ///
+  /// let result = Sorter::radix_sort(offsets);
+  /// assert_eq!(result, vec![0, 512, 4096, 1 << 40]);
+  /// ```
+  ///
+  pub fn radix_sort(mut data: Vec<T>) -> Vec<T> {
+    Self::radix_sort_in_place(&mut data);
     data
   }
 
-  pub fn quick_sort(mut data: Vec<T>) -> Vec<T> {
+  /// Sorts the slice of unsigned integers in place using LSD radix sort.
+  ///
+  /// Instead of comparing elements, this distributes them into 256 buckets
+  /// by one byte at a time, starting with the least significant. Each pass
+  /// is stable, so after the last one the elements are ordered by every
+  /// byte. Passes over a byte that all elements share are skipped, so small
+  /// values in a wide type cost less.
+  ///
+  /// Time complexity:
+  /// - O(n * b) for b-byte keys, independent of how the input is ordered.
+  ///
+  /// Space complexity:
+  /// - O(n) for the buffer each pass scatters into.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut seqs: Vec<u32> = vec![300, 7, 65_536, 7];
+  ///
+  /// Sorter::radix_sort_in_place(&mut seqs);
+  /// assert_eq!(seqs, vec![7, 7, 300, 65_536]);
+  /// ```
+  ///
+  pub fn radix_sort_in_place(data: &mut [T]) {
     if data.len() < 2 {
-      return data;
+      return;
     }
-    let pivot = data.pop().unwrap();
-    let mut left = vec![];
-    let mut right = vec![];
 
-    for item in data {
-      if item <= pivot {
-        left.push(item);
-      } else {
-        right.push(item);
+    let mut buffer = data.to_vec();
+    // whether the latest pass left its output in `buffer`
+    let mut in_buffer = false;
+
+    for index in 0..T::BYTES {
+      let (src, dst) = match in_buffer {
+        false => (&*data, &mut buffer[..]),
+        true => (&buffer[..], &mut *data),
+      };
+
+      let mut counts = [0usize; 256];
+      for item in src {
+        counts[item.byte(index) as usize] += 1;
       }
+      if counts.contains(&src.len()) {
+        continue;
+      }
+
+      // turn the counts into the first position of each bucket
+      let mut next = 0;
+      for count in counts.iter_mut() {
+        let start = next;
+        next += *count;
+        *count = start;
+      }
+      for &item in src {
+        let bucket = &mut counts[item.byte(index) as usize];
+        dst[*bucket] = item;
+        *bucket += 1;
+      }
+      in_buffer = !in_buffer;
+    }
+
+    if in_buffer {
+      data.copy_from_slice(&buffer);
+    }
+  }
+}
+
+impl<T> Sorter<T>
+where
+  T: Copy + TryInto<usize>,
+{
+  /// Sorts the input vector of small non-negative integers using counting sort.
+  ///
+  /// The vector is sorted in place by [`counting_sort_in_place`](Self::counting_sort_in_place)
+  /// and handed back along with the histogram.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let (sorted, histogram) = Sorter::<u8>::counting_sort(vec![3, 1, 3, 0], 3);
+  /// assert_eq!(sorted, vec![0, 1, 3, 3]);
+  /// assert_eq!(histogram, vec![1, 1, 0, 2]);
+  /// ```
+  ///
+  pub fn counting_sort(mut data: Vec<T>, max: usize) -> (Vec<T>, Vec<usize>) {
+    let histogram = Self::counting_sort_in_place(&mut data, max);
+    (data, histogram)
+  }
+
+  /// Sorts the slice of integers in `0..=max` in place using counting sort,
+  /// and returns how often each value occurs.
+  ///
+  /// Steps:
+  /// 1. Count the occurrences of every value into a histogram of `max + 1`
+  ///    buckets.
+  /// 2. Turn the counts into the position where each value's run starts.
+  /// 3. Copy every element into its run, in input order, so the sort is
+  ///    stable.
+  ///
+  /// The histogram is handed back as well, which makes this a cheap way to
+  /// bucket values (say record sizes) while sorting them.
+  ///
+  /// Time complexity:
+  /// - O(n + max), independent of how the input is ordered.
+  ///
+  /// Space complexity:
+  /// - O(n + max) for a copy of the input and the histogram.
+  ///
+  /// Returns:
+  /// - The histogram: element `v` is how many times the value `v` occurs.
+  ///
+  /// # Panics
+  ///
+  /// Panics if a value is negative or greater than `max`.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut sizes: Vec<u32> = vec![2, 0, 2, 1];
+  ///
+  /// let histogram = Sorter::counting_sort_in_place(&mut sizes, 2);
+  /// assert_eq!(sizes, vec![0, 1, 2, 2]);
+  /// assert_eq!(histogram, vec![1, 1, 2]);
+  /// ```
+  ///
+  pub fn counting_sort_in_place(data: &mut [T], max: usize) -> Vec<usize> {
+    let bucket = |item: T| match item.try_into() {
+      Ok(value) if value <= max => value,
+      _ => panic!("counting sort only takes values in 0..={max}"),
+    };
+
+    let mut histogram = vec![0; max + 1];
+    for &item in data.iter() {
+      histogram[bucket(item)] += 1;
     }
 
-    let mut result = Sorter::quick_sort(left);
-    result.push(pivot);
-    result.extend(Sorter::quick_sort(right));
+    let mut starts = Vec::with_capacity(histogram.len());
+    let mut next = 0;
+    for count in &histogram {
+      starts.push(next);
+      next += count;
+    }
+
+    let input = data.to_vec();
+    for &item in &input {
+      let start = &mut starts[bucket(item)];
+      data[*start] = item;
+      *start += 1;
+    }
 
-    result
+    histogram
   }
+}
 
-  pub fn merge_sort(data: Vec<T>) -> Vec<T> {
-    if data.len() < 2 {
-      return data;
+impl<T> Sorter<T>
+where
+  T: Ord + Clone + Send,
+{
+  /// Sorts the input vector using merge sort spread over several threads.
+  ///
+  /// The vector is sorted in place by [`par_merge_sort_in_place`](Self::par_merge_sort_in_place)
+  /// and handed back.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let data: Vec<u32> = (0..10_000).rev().collect();
+  ///
+  /// let result = Sorter::par_merge_sort(data, 4);
+  /// assert!(result.windows(2).all(|pair| pair[0] <= pair[1]));
+  /// ```
+  ///
+  pub fn par_merge_sort(mut data: Vec<T>, threads: usize) -> Vec<T> {
+    Self::par_merge_sort_in_place(&mut data, threads);
+    data
+  }
+
+  /// Sorts the slice in place using merge sort spread over `threads` threads.
+  ///
+  /// Steps:
+  /// 1. Split the slice into one chunk per thread and merge sort every chunk
+  ///    on its own scoped thread.
+  /// 2. Merge neighbouring runs pairwise, each pair on its own thread, until
+  ///    a single run is left.
+  ///
+  /// Slices shorter than 4096 elements, or `threads` of 1, are sorted with
+  /// [`merge_sort_in_place`](Self::merge_sort_in_place) on the calling
+  /// thread, where spawning would cost more than it saves. A `threads` of 0
+  /// uses one thread per available core. Like the single-threaded version,
+  /// the sort is stable.
+  ///
+  /// Time complexity:
+  /// - O(n log n) work, of which the chunk sorts are divided among the
+  ///   threads.
+  ///
+  /// Space complexity:
+  /// - O(n) for the scratch buffers.
+  ///
+  /// Example:
+  /// ```rust
+  /// use utils::sorter::Sorter;
+  ///
+  /// let mut data: Vec<u64> = (0..10_000).map(|i| (i * 7919) % 10_000).collect();
+  ///
+  /// Sorter::par_merge_sort_in_place(&mut data, 0);
+  /// assert_eq!(data, (0..10_000).collect::<Vec<_>>());
+  /// ```
+  ///
+  pub fn par_merge_sort_in_place(data: &mut [T], threads: usize) {
+    par_merge_sort_by(data, threads, &T::cmp);
+  }
+}
+
+/// Selection sort under `compare`.
+fn selection_sort_by<T, F>(data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let n = data.len();
+
+  for i in 0..n {
+    let mut min_idx = i;
+
+    for j in (i + 1)..n {
+      if compare(&data[j], &data[min_idx]) == Ordering::Less {
+        min_idx = j;
+      }
+    }
+
+    data.swap(i, min_idx);
+  }
+}
+
+/// Quick sort under `compare`, recursing only into the smaller partition.
+fn quick_sort_by<T, F>(mut data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  while data.len() > 1 {
+    let pivot = partition(data, compare);
+    let (left, right) = data.split_at_mut(pivot);
+    let right = &mut right[1..];
+
+    if left.len() < right.len() {
+      quick_sort_by(left, compare);
+      data = right;
+    } else {
+      quick_sort_by(right, compare);
+      data = left;
+    }
+  }
+}
+
+/// Three-way quick sort under `compare`.
+fn three_way_quick_sort_by<T, F>(mut data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  while data.len() > 1 {
+    let (lt, gt) = partition_three_way(data, compare);
+    let (rest, greater) = data.split_at_mut(gt);
+    let less = &mut rest[..lt];
+
+    if less.len() < greater.len() {
+      three_way_quick_sort_by(less, compare);
+      data = greater;
+    } else {
+      three_way_quick_sort_by(greater, compare);
+      data = less;
+    }
+  }
+}
+
+/// Quickselect under `compare`, narrowing `low..high` to the run that
+/// holds position `n`.
+fn select_nth_by<'a, T, F>(data: &'a mut [T], n: usize, compare: &mut F) -> &'a T
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  assert!(
+    n < data.len(),
+    "position {n} is out of bounds for a slice of length {}",
+    data.len()
+  );
+
+  let (mut low, mut high) = (0, data.len());
+  while high - low > 1 {
+    let (lt, gt) = partition_three_way(&mut data[low..high], compare);
+    if n < low + lt {
+      high = low + lt;
+    } else if n >= low + gt {
+      low += gt;
+    } else {
+      break;
+    }
+  }
+
+  &data[n]
+}
+
+/// The `k` largest elements under `compare`, largest first.
+fn top_k_by<T, F>(data: &[T], k: usize, compare: &mut F) -> Vec<T>
+where
+  T: Clone,
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let k = k.min(data.len());
+  if k == 0 {
+    return Vec::new();
+  }
+
+  let mut descending = |a: &T, b: &T| compare(b, a);
+  let mut top = data.to_vec();
+  select_nth_by(&mut top, k - 1, &mut descending);
+  top.truncate(k);
+  merge_sort_by(&mut top, &mut descending);
+  top
+}
+
+/// Splits the slice into elements less than, equal to and greater than the
+/// median of its first, middle and last elements. Returns where the equal
+/// run starts and ends.
+fn partition_three_way<T, F>(data: &mut [T], compare: &mut F) -> (usize, usize)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let last = data.len() - 1;
+  median_of_three_to_end(data, compare);
+  data.swap(0, last);
+
+  // data[..lt] < pivot, data[lt..i] == pivot, data[gt..] > pivot; the pivot
+  // itself always sits at data[lt]
+  let (mut lt, mut i, mut gt) = (0, 1, data.len());
+  while i < gt {
+    match compare(&data[i], &data[lt]) {
+      Ordering::Less => {
+        data.swap(lt, i);
+        lt += 1;
+        i += 1;
+      },
+      Ordering::Greater => {
+        gt -= 1;
+        data.swap(i, gt);
+      },
+      Ordering::Equal => i += 1,
+    }
+  }
+
+  (lt, gt)
+}
+
+/// Moves the median of the first, middle and last elements to the end.
+fn median_of_three_to_end<T, F>(data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let last = data.len() - 1;
+  let mid = last / 2;
+  if compare(&data[mid], &data[0]) == Ordering::Less {
+    data.swap(0, mid);
+  }
+  if compare(&data[last], &data[0]) == Ordering::Less {
+    data.swap(0, last);
+  }
+  if compare(&data[mid], &data[last]) == Ordering::Less {
+    data.swap(mid, last);
+  }
+}
+
+/// Moves the median of the first, middle and last elements to the end,
+/// partitions the slice around it and returns its final position.
+fn partition<T, F>(data: &mut [T], compare: &mut F) -> usize
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let last = data.len() - 1;
+  median_of_three_to_end(data, compare);
+
+  let mut store = 0;
+  for i in 0..last {
+    if compare(&data[i], &data[last]) == Ordering::Less {
+      data.swap(i, store);
+      store += 1;
+    }
+  }
+  data.swap(store, last);
+
+  store
+}
+
+/// Stable merge sort under `compare`.
+fn merge_sort_by<T, F>(data: &mut [T], compare: &mut F)
+where
+  T: Clone,
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let mut scratch = Vec::with_capacity(data.len() / 2);
+  merge_sort_with(data, &mut scratch, compare);
+}
+
+fn merge_sort_with<T, F>(data: &mut [T], scratch: &mut Vec<T>, compare: &mut F)
+where
+  T: Clone,
+  F: FnMut(&T, &T) -> Ordering,
+{
+  if data.len() < 2 {
+    return;
+  }
+
+  let mid = data.len() / 2;
+  merge_sort_with(&mut data[..mid], scratch, compare);
+  merge_sort_with(&mut data[mid..], scratch, compare);
+
+  merge_halves(data, mid, scratch, compare);
+}
+
+/// Merges the sorted runs `data[..mid]` and `data[mid..]`, taking from the
+/// left on ties.
+fn merge_halves<T, F>(data: &mut [T], mid: usize, scratch: &mut Vec<T>, compare: &mut F)
+where
+  T: Clone,
+  F: FnMut(&T, &T) -> Ordering,
+{
+  // the halves are already in order
+  if mid == 0 || mid == data.len() || compare(&data[mid - 1], &data[mid]) != Ordering::Greater {
+    return;
+  }
+
+  scratch.clear();
+  scratch.extend_from_slice(&data[..mid]);
+  let (mut i, mut j, mut k) = (0, mid, 0);
+
+  while i < scratch.len() && j < data.len() {
+    if compare(&data[j], &scratch[i]) == Ordering::Less {
+      data[k] = data[j].clone();
+      j += 1;
+    } else {
+      data[k] = scratch[i].clone();
+      i += 1;
     }
+    k += 1;
+  }
 
-    let mid = data.len() / 2;
-    let left = data[..mid].to_vec();
-    let right = data[mid..].to_vec();
+  // whatever is left of the right half is already in place
+  for item in &scratch[i..] {
+    data[k] = item.clone();
+    k += 1;
+  }
+}
 
-    let left = Sorter::merge_sort(left);
-    let right = Sorter::merge_sort(right);
+/// Slices shorter than this are merge sorted on a single thread.
+const PARALLEL_THRESHOLD: usize = 4096;
 
-    Sorter::merge(left, right)
+/// Parallel merge sort under `compare`, shared by every thread.
+fn par_merge_sort_by<T, F>(data: &mut [T], threads: usize, compare: &F)
+where
+  T: Clone + Send,
+  F: Fn(&T, &T) -> Ordering + Sync,
+{
+  let threads = match threads {
+    0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    n => n,
+  };
+  if threads == 1 || data.len() < PARALLEL_THRESHOLD {
+    merge_sort_by(data, &mut |a, b| compare(a, b));
+    return;
   }
 
-  fn merge(mut left: Vec<T>, mut right: Vec<T>) -> Vec<T> {
-    let mut result = Vec::with_capacity(left.len() + right.len());
-    let i = 0;
-    let j = 0;
+  let mut width = data.len().div_ceil(threads);
+  std::thread::scope(|scope| {
+    for chunk in data.chunks_mut(width) {
+      scope.spawn(move || merge_sort_by(chunk, &mut |a, b| compare(a, b)));
+    }
+  });
 
-    while i < left.len() && j < right.len() {
-      if left[i] <= right[j] {
-        result.push(left.remove(i));
-      } else {
-        result.push(right.remove(j));
+  while width < data.len() {
+    std::thread::scope(|scope| {
+      for pair in data.chunks_mut(2 * width) {
+        scope.spawn(move || {
+          let mut scratch = Vec::with_capacity(width);
+          merge_halves(pair, width.min(pair.len()), &mut scratch, &mut |a, b| {
+            compare(a, b)
+          });
+        });
       }
+    });
+    width *= 2;
+  }
+}
+
+/// Partitions at or below this size are insertion sorted by introsort.
+const INSERTION_SORT_THRESHOLD: usize = 16;
+
+/// Introsort under `compare`.
+fn intro_sort_by<T, F>(data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  let depth_limit = 2 * data.len().max(1).ilog2();
+  intro_sort_with(data, depth_limit, compare);
+}
+
+fn intro_sort_with<T, F>(mut data: &mut [T], mut depth_limit: u32, compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  while data.len() > INSERTION_SORT_THRESHOLD {
+    if depth_limit == 0 {
+      heap_sort_by(data, compare);
+      return;
+    }
+    depth_limit -= 1;
+
+    let pivot = partition(data, compare);
+    let (left, right) = data.split_at_mut(pivot);
+    let right = &mut right[1..];
+
+    if left.len() < right.len() {
+      intro_sort_with(left, depth_limit, compare);
+      data = right;
+    } else {
+      intro_sort_with(right, depth_limit, compare);
+      data = left;
     }
+  }
 
-    result.extend(left);
-    result.extend(right);
+  insertion_sort_by(data, compare);
+}
 
-    result
+/// Insertion sort under `compare`, fast on short or nearly sorted slices.
+fn insertion_sort_by<T, F>(data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  for i in 1..data.len() {
+    let mut j = i;
+    while j > 0 && compare(&data[j], &data[j - 1]) == Ordering::Less {
+      data.swap(j, j - 1);
+      j -= 1;
+    }
+  }
+}
+
+/// Heap sort under `compare`: builds a max-heap, then repeatedly swaps its
+/// root behind the shrinking heap.
+fn heap_sort_by<T, F>(data: &mut [T], compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  for root in (0..data.len() / 2).rev() {
+    sift_down(data, root, compare);
+  }
+  for end in (1..data.len()).rev() {
+    data.swap(0, end);
+    sift_down(&mut data[..end], 0, compare);
+  }
+}
+
+/// Moves `data[root]` down until both its children are no larger.
+fn sift_down<T, F>(data: &mut [T], mut root: usize, compare: &mut F)
+where
+  F: FnMut(&T, &T) -> Ordering,
+{
+  loop {
+    let mut child = 2 * root + 1;
+    if child >= data.len() {
+      return;
+    }
+    if child + 1 < data.len() && compare(&data[child], &data[child + 1]) == Ordering::Less {
+      child += 1;
+    }
+    if compare(&data[root], &data[child]) != Ordering::Less {
+      return;
+    }
+    data.swap(root, child);
+    root = child;
   }
 }
@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod stack_test {
+  use std::rc::Rc;
+
   use crate::stack::Stack;
 
   #[test]
@@ -70,4 +72,32 @@ mod stack_test {
     let as_vec = stack.into_vec();
     assert_eq!(as_vec, vec![1, 2, 3, 4]);
   }
+
+  #[test]
+  fn nodes_are_freed() {
+    let mut stack = Stack::new();
+    let nodes = (0..4)
+      .map(|i| Rc::downgrade(&stack.push(i).unwrap()))
+      .collect::<Vec<_>>();
+    let top = stack.peek().unwrap();
+    assert_eq!(top.borrow().prev().unwrap().borrow().value, 2);
+    drop(top);
+
+    stack.pop();
+    assert!(nodes[3].upgrade().is_none());
+    drop(stack);
+    assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+  }
+
+  #[test]
+  fn clear_frees_a_long_stack() {
+    let mut stack = Stack::new();
+    let bottom = Rc::downgrade(&stack.push(0).unwrap());
+    for i in 1..200_000 {
+      stack.push(i);
+    }
+
+    stack.clear();
+    assert!(bottom.upgrade().is_none());
+  }
 }
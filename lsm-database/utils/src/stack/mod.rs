@@ -2,6 +2,7 @@
 //!
 //! The stack stores nodes in a doubly linked list so pushes and pops stay at
 //! `O(1)` while still allowing bidirectional iteration for debugging and tests.
+//! `prev` pointers are `Weak`, so the nodes are freed with the stack.
 //!
 //! # Example
 //!
@@ -20,16 +21,20 @@
 
 mod __test__;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{
+  cell::RefCell,
+  rc::{Rc, Weak},
+};
 
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
 
 /// Internal node storing the stack value and neighbor pointers.
 pub struct Node<T>
 where
   T: PartialEq,
 {
-  pub prev: Link<T>,
+  pub prev: WeakLink<T>,
   pub value: T,
   pub next: Link<T>,
 }
@@ -49,6 +54,11 @@ where
   fn wrap(self) -> Rc<RefCell<Node<T>>> {
     Rc::new(RefCell::new(self))
   }
+
+  /// Returns the node below this one, or `None` at the bottom.
+  pub fn prev(&self) -> Link<T> {
+    self.prev.as_ref().and_then(Weak::upgrade)
+  }
 }
 
 impl<T> std::fmt::Debug for Node<T>
@@ -117,9 +127,7 @@ where
 
   /// Removes all items, leaving the stack empty.
   pub fn clear(&mut self) {
-    self.head = None;
-    self.tail = None;
-    self.len = 0;
+    *self = Self::default();
   }
 
   /// Pushes a value onto the top of the stack and returns the new node handle.
@@ -128,8 +136,8 @@ where
 
     match self.tail.take() {
       Some(old_tail) => {
+        new.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
         old_tail.borrow_mut().next = Some(new.clone());
-        new.borrow_mut().prev = Some(old_tail);
         self.tail = Some(new.clone());
         if self.head.is_none() {
           self.head = self.tail.clone();
@@ -150,7 +158,7 @@ where
   pub fn pop(&mut self) -> Link<T> {
     let old_tail = self.tail.clone()?;
 
-    let prev = old_tail.borrow_mut().prev.take();
+    let prev = old_tail.borrow_mut().prev.take().and_then(|p| p.upgrade());
     match prev {
       Some(ref new_tail) => {
         new_tail.borrow_mut().next = None;
@@ -219,6 +227,26 @@ where
   }
 }
 
+/// Frees the nodes one at a time. Dropping the head alone would free the
+/// whole chain recursively, one stack frame per node.
+impl<T> Drop for Stack<T>
+where
+  T: PartialEq,
+{
+  fn drop(&mut self) {
+    self.tail = None;
+    let mut cursor = self.head.take();
+
+    while let Some(node_rc) = cursor {
+      // A node still held elsewhere keeps the rest of the chain alive.
+      cursor = match Rc::try_unwrap(node_rc) {
+        Ok(node) => node.into_inner().next,
+        Err(_) => None,
+      };
+    }
+  }
+}
+
 impl<T> std::fmt::Debug for Stack<T>
 where
   T: PartialEq + std::fmt::Debug + Clone,
@@ -244,7 +272,7 @@ where
 
       let node = rc_node.borrow();
 
-      let prev = node.prev.as_ref().map(|p| p.borrow().value.clone());
+      let prev = node.prev().map(|p| p.borrow().value.clone());
       let next = node.next.as_ref().map(|n| n.borrow().value.clone());
 
       writeln!(
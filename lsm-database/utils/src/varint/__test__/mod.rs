@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod varint_test {
+  use crate::varint::{
+    decode_i64, decode_u32, decode_u64, encode_i64, encode_u32, encode_u64, encoded_len,
+    zigzag_decode, zigzag_encode, MAX_LEN_U32, MAX_LEN_U64,
+  };
+
+  fn encoded(value: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode_u64(value, &mut buf);
+    buf
+  }
+
+  #[test]
+  fn known_encodings() {
+    assert_eq!(encoded(0), vec![0x00]);
+    assert_eq!(encoded(1), vec![0x01]);
+    assert_eq!(encoded(127), vec![0x7f]);
+    assert_eq!(encoded(128), vec![0x80, 0x01]);
+    assert_eq!(encoded(300), vec![0xac, 0x02]);
+    assert_eq!(encoded(16_384), vec![0x80, 0x80, 0x01]);
+    assert_eq!(encoded(u64::MAX).len(), MAX_LEN_U64);
+    assert_eq!(*encoded(u64::MAX).last().unwrap(), 0x01);
+  }
+
+  #[test]
+  fn round_trips_at_every_length() {
+    let mut values = vec![0, u64::MAX];
+    for bits in 1..64 {
+      values.extend([(1u64 << bits) - 1, 1u64 << bits]);
+    }
+
+    for value in values {
+      let buf = encoded(value);
+      assert_eq!(buf.len(), encoded_len(value));
+      assert_eq!(decode_u64(&buf), Some((value, buf.len())));
+    }
+  }
+
+  #[test]
+  fn u32_round_trip_and_overflow() {
+    let mut buf = Vec::new();
+    assert_eq!(encode_u32(u32::MAX, &mut buf), MAX_LEN_U32);
+    assert_eq!(decode_u32(&buf), Some((u32::MAX, MAX_LEN_U32)));
+
+    assert_eq!(decode_u32(&encoded(u32::MAX as u64 + 1)), None);
+  }
+
+  #[test]
+  fn decode_rejects_truncated_and_overlong_input() {
+    assert_eq!(decode_u64(&[]), None);
+    assert_eq!(decode_u64(&[0x80]), None);
+    assert_eq!(decode_u64(&[0xff; 11]), None);
+
+    let mut too_big = vec![0xff; 9];
+    too_big.push(0x02);
+    assert_eq!(decode_u64(&too_big), None);
+  }
+
+  #[test]
+  fn decode_stops_at_the_end_of_the_value() {
+    let mut buf = Vec::new();
+    encode_u64(300, &mut buf);
+    encode_u64(5, &mut buf);
+
+    let (first, read) = decode_u64(&buf).unwrap();
+    assert_eq!((first, read), (300, 2));
+    assert_eq!(decode_u64(&buf[read..]), Some((5, 1)));
+  }
+
+  #[test]
+  fn zigzag_keeps_small_values_short() {
+    let expected = [(0, 0), (-1, 1), (1, 2), (-2, 3), (2, 4)];
+    for (signed, unsigned) in expected {
+      assert_eq!(zigzag_encode(signed), unsigned);
+      assert_eq!(zigzag_decode(unsigned), signed);
+    }
+
+    for value in [i64::MIN, -300, -64, 63, 300, i64::MAX] {
+      let mut buf = Vec::new();
+      let len = encode_i64(value, &mut buf);
+      assert_eq!(decode_i64(&buf), Some((value, len)));
+    }
+    assert_eq!(encode_i64(-64, &mut Vec::new()), 1);
+  }
+}
@@ -0,0 +1,116 @@
+//! Variable-length integer encoding (LEB128 varints) and zigzag encoding.
+//!
+//! A varint stores 7 bits of the value per byte, low bits first, and sets
+//! the top bit of every byte but the last. Sizes and other small numbers
+//! take a single byte instead of a fixed 4 or 8, a `u32` takes at most 5
+//! bytes and a `u64` at most 10.
+//!
+//! Signed values go through zigzag encoding first, which maps 0, -1, 1, -2,
+//! 2, ... to 0, 1, 2, 3, 4, ..., so that small negative numbers such as time
+//! deltas stay short too.
+//!
+//! The `encode_*` functions append to a `Vec<u8>` and return how many bytes
+//! they wrote. The `decode_*` functions read from the start of a slice and
+//! return the value and how many bytes it took, or `None` if the slice ends
+//! mid-value or the value doesn't fit the type.
+//!
+//! # Example
+//!
+//! ```rust
+//! use utils::varint::{decode_i64, decode_u64, encode_i64, encode_u64};
+//!
+//! let mut header = Vec::new();
+//! encode_u64(300, &mut header);
+//! encode_i64(-2, &mut header);
+//! assert_eq!(header, vec![0xac, 0x02, 0x03]);
+//!
+//! let (size, read) = decode_u64(&header).unwrap();
+//! let (delta, _) = decode_i64(&header[read..]).unwrap();
+//! assert_eq!((size, delta), (300, -2));
+//! ```
+
+mod __test__;
+
+/// The most bytes a `u32` varint takes.
+pub const MAX_LEN_U32: usize = 5;
+
+/// The most bytes a `u64` varint takes.
+pub const MAX_LEN_U64: usize = 10;
+
+/// Appends `value` as a varint to `buf` and returns the number of bytes
+/// written.
+pub fn encode_u32(value: u32, buf: &mut Vec<u8>) -> usize {
+  encode_u64(value as u64, buf)
+}
+
+/// Appends `value` as a varint to `buf` and returns the number of bytes
+/// written.
+pub fn encode_u64(mut value: u64, buf: &mut Vec<u8>) -> usize {
+  let start = buf.len();
+  while value >= 0x80 {
+    buf.push(value as u8 | 0x80);
+    value >>= 7;
+  }
+  buf.push(value as u8);
+  buf.len() - start
+}
+
+/// Appends `value` zigzag encoded as a varint to `buf` and returns the
+/// number of bytes written.
+pub fn encode_i64(value: i64, buf: &mut Vec<u8>) -> usize {
+  encode_u64(zigzag_encode(value), buf)
+}
+
+/// Reads a varint `u32` from the start of `bytes`, returning it and the
+/// number of bytes it took.
+pub fn decode_u32(bytes: &[u8]) -> Option<(u32, usize)> {
+  let (value, len) = decode(bytes, MAX_LEN_U32)?;
+  Some((u32::try_from(value).ok()?, len))
+}
+
+/// Reads a varint `u64` from the start of `bytes`, returning it and the
+/// number of bytes it took.
+pub fn decode_u64(bytes: &[u8]) -> Option<(u64, usize)> {
+  decode(bytes, MAX_LEN_U64)
+}
+
+/// Reads a zigzag encoded varint `i64` from the start of `bytes`, returning
+/// it and the number of bytes it took.
+pub fn decode_i64(bytes: &[u8]) -> Option<(i64, usize)> {
+  let (value, len) = decode_u64(bytes)?;
+  Some((zigzag_decode(value), len))
+}
+
+/// Returns the number of bytes `value` takes as a varint.
+pub fn encoded_len(value: u64) -> usize {
+  // One byte per started group of 7 significant bits, and one for zero.
+  (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+}
+
+/// Maps signed integers to unsigned ones so that values near zero, of
+/// either sign, map to small numbers.
+pub fn zigzag_encode(value: i64) -> u64 {
+  ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Inverts [`zigzag_encode`].
+pub fn zigzag_decode(value: u64) -> i64 {
+  (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn decode(bytes: &[u8], max_len: usize) -> Option<(u64, usize)> {
+  let mut value = 0u64;
+  for (i, &byte) in bytes.iter().take(max_len).enumerate() {
+    let bits = (byte & 0x7f) as u64;
+    let shift = 7 * i as u32;
+    // The last byte of a u64 may only carry its top bit.
+    if shift == 63 && bits > 1 {
+      return None;
+    }
+    value |= bits << shift;
+    if byte & 0x80 == 0 {
+      return Some((value, i + 1));
+    }
+  }
+  None
+}